@@ -0,0 +1,105 @@
+//! Timings of the two search and serving shortcuts, on synthetic rows: the
+//! gain-bound pruning of split candidates against a search scoring all of
+//! them (keep_runner_up, which turns pruning off), and classify_batch
+//! against classifying one point at a time. Prints a table; there is no
+//! harness to compare runs.
+//!
+//! `cargo bench --bench tree`
+
+// the generated functions take &Vec<DataPoint>
+#![allow(clippy::ptr_arg)]
+
+use decision_leaf::classification_data_layout;
+use decision_leaf::rng::Rng;
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+classification_data_layout!(
+    enum_fields = {shade: u8, shiny: bool},
+    number_fields = {weight: f64, count: u32, length: f32},
+    u8
+);
+
+fn rows(rng: &mut Rng, rows: usize) -> Vec<DataPoint> {
+    (0..rows)
+        .map(|_| {
+            let shade = rng.gen_index(8) as u8;
+            let shiny = rng.gen_index(2) == 0;
+            let weight = (rng.next_f64() * 1000.).round() / 4.;
+            let count = rng.gen_index(40) as u32;
+            let length = rng.normal() as f32;
+            let class = if rng.gen_index(10) == 0 {
+                rng.gen_index(2)
+            } else {
+                ((weight > 120.) as usize + (shade > 5) as usize + (length > 1.) as usize) % 2
+            };
+            DataPoint::new(shade, shiny, weight, count, length, class as u8)
+        })
+        .collect()
+}
+
+// the fastest of `runs` calls of `f`
+fn time<T>(runs: usize, mut f: impl FnMut() -> T) -> Duration {
+    (0..runs)
+        .map(|_| {
+            let start = Instant::now();
+            black_box(f());
+            start.elapsed()
+        })
+        .min()
+        .expect("at least one run")
+}
+
+fn row(name: &str, with: Duration, without: Duration) {
+    println!(
+        "{:<28} {:>10.2?} {:>10.2?} {:>7.2}x",
+        name,
+        with,
+        without,
+        without.as_secs_f64() / with.as_secs_f64()
+    );
+}
+
+fn main() {
+    let mut rng = Rng::new(237);
+    let data = rows(&mut rng, 20_000);
+    let unpruned = TreeConfig {
+        keep_runner_up: true,
+        ..TreeConfig::default()
+    };
+
+    let pruned_stats = split_stats(&data, &TreeConfig::default());
+    let full_stats = split_stats(&data, &unpruned);
+    println!(
+        "root candidates scored: {} pruned, {} unpruned, {} skipped by the bound",
+        pruned_stats.scored, full_stats.scored, pruned_stats.skipped_bound
+    );
+
+    println!(
+        "{:<28} {:>10} {:>10} {:>8}",
+        "", "with", "without", "speedup"
+    );
+    row(
+        "root split search",
+        time(10, || split_stats(&data, &TreeConfig::default())),
+        time(10, || split_stats(&data, &unpruned)),
+    );
+    row(
+        "build_tree_with",
+        time(3, || build_tree_with(&data, &TreeConfig::default())),
+        time(3, || build_tree_with(&data, &unpruned)),
+    );
+
+    let tree = build_tree(&data);
+    let points = rows(&mut rng, 100_000);
+    row(
+        "classify_batch, 100k points",
+        time(10, || classify_batch(&points, &tree)),
+        time(10, || {
+            points
+                .iter()
+                .map(|point| classify(point, &tree))
+                .collect::<Vec<_>>()
+        }),
+    );
+}
@@ -0,0 +1,91 @@
+//! Conformal prediction sets on synthetic data with a known noise level:
+//! train, calibrate on held-out rows, and print the sets of a few points and
+//! their coverage on fresh rows. tests/conformal.rs checks the guarantees.
+//!
+//! `cargo run --example conformal`
+
+// the generated functions take &Vec<DataPoint>
+#![allow(clippy::ptr_arg)]
+
+use decision_leaf::classification_data_layout;
+use decision_leaf::rng::Rng;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Zone {
+    Low,
+    Mid,
+    High,
+}
+
+impl FromStr for Zone {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, String> {
+        match s {
+            "Low" => Ok(Zone::Low),
+            "Mid" => Ok(Zone::Mid),
+            "High" => Ok(Zone::High),
+            _ => Err(format!("unknown zone {}", s)),
+        }
+    }
+}
+
+classification_data_layout!(
+    enum_fields = {},
+    number_fields = {x: f64, y: f64},
+    Zone
+);
+
+// the zone of x + y, relabeled at random for a fifth of the rows
+fn rows(n: usize, rng: &mut Rng) -> Vec<DataPoint> {
+    (0..n)
+        .map(|_| {
+            let (x, y) = (rng.next_f64(), rng.next_f64());
+            let zone = match x + y {
+                s if s < 0.7 => Zone::Low,
+                s if s < 1.3 => Zone::Mid,
+                _ => Zone::High,
+            };
+            let class = if rng.next_f64() < 0.2 {
+                [Zone::Low, Zone::Mid, Zone::High][rng.gen_index(3)]
+            } else {
+                zone
+            };
+            DataPoint::new(x, y, class)
+        })
+        .collect()
+}
+
+fn main() {
+    let mut rng = Rng::new(5);
+    let (train, calib, test) = (
+        rows(2000, &mut rng),
+        rows(2000, &mut rng),
+        rows(5000, &mut rng),
+    );
+    let config = TreeConfig {
+        max_depth: Some(4),
+        min_samples_leaf: 20,
+        ..Default::default()
+    };
+    let tree = build_tree_with(&train, &config).expect("valid config");
+
+    // sets that hold the true class of 90% of the points
+    let conformal = ConformalClassifier::calibrate(&tree, &calib, 0.1).expect("valid alpha");
+    for point in test.iter().take(5) {
+        println!(
+            "({:.2}, {:.2}), a {:?}: {:?}",
+            point.x,
+            point.y,
+            point.class,
+            conformal.predict_set(point)
+        );
+    }
+    let coverage = conformal.coverage(&test);
+    println!(
+        "threshold {:.3}: coverage {:.3}, mean set size {:.2}",
+        conformal.calibration.threshold(),
+        coverage.coverage(),
+        coverage.mean_set_size()
+    );
+}
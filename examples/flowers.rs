@@ -0,0 +1,85 @@
+//! End-to-end run on an iris-style dataset of f32 lengths in cm: load the
+//! CSV, split, train, print, evaluate a tree and a random forest, save and
+//! reload.
+//!
+//! `cargo run --example flowers --features csv,persist`
+
+// the generated functions take &Vec<DataPoint>
+#![allow(clippy::ptr_arg)]
+
+use decision_leaf::classification_data_layout;
+use decision_leaf::ensemble::Resample;
+use decision_leaf::persist::LoadOptions;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Species {
+    Setosa,
+    Versicolor,
+    Virginica,
+}
+
+impl FromStr for Species {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, String> {
+        match s {
+            "Setosa" => Ok(Species::Setosa),
+            "Versicolor" => Ok(Species::Versicolor),
+            "Virginica" => Ok(Species::Virginica),
+            _ => Err(format!("unknown species {}", s)),
+        }
+    }
+}
+
+classification_data_layout!(
+    enum_fields = {},
+    number_fields = {sepal_length: f32, sepal_width: f32, petal_length: f32, petal_width: f32},
+    Species
+);
+
+const FLOWERS: &str = include_str!("data/flowers.csv");
+
+fn main() {
+    let data = load_csv_with(FLOWERS.as_bytes(), &mut LoaderConfig::new()).expect("fixture loads");
+
+    // every fifth row is held out
+    let (test, train): (Vec<_>, Vec<_>) = data
+        .iter()
+        .cloned()
+        .enumerate()
+        .partition(|(i, _)| i % 5 == 0);
+    let train: Vec<DataPoint> = train.into_iter().map(|(_, p)| p).collect();
+    let test: Vec<DataPoint> = test.into_iter().map(|(_, p)| p).collect();
+
+    let config = TreeConfig {
+        max_depth: Some(4),
+        min_samples_leaf: 2,
+        ..Default::default()
+    };
+    let tree = build_tree_with(&train, &config).expect("valid config");
+    tree.print_tree("");
+    run_tests(&test, &tree);
+
+    // a random forest: each tree on 80% of the rows, each node searching two
+    // of the four fields
+    let random = TreeConfig {
+        max_features: Some(2),
+        ..config
+    };
+    let forest =
+        build_forest_with(&train, 25, 3, &random, Resample::Fraction(0.8)).expect("valid forest");
+    let right = test
+        .iter()
+        .filter(|p| forest.predict(p) == Some(p.class))
+        .count();
+    println!(
+        "random forest of 25 trees: {} of {} right",
+        right,
+        test.len()
+    );
+
+    let mut saved = Vec::new();
+    tree.save(&mut saved).expect("write to memory");
+    let reloaded = Node::load(&saved[..], &LoadOptions::new()).expect("saved tree loads");
+    println!("reloaded, fingerprint {:016x}", reloaded.fingerprint());
+}
@@ -0,0 +1,62 @@
+//! A plain harness for the generated `fuzz_roundtrip`: feeds it seeded
+//! random byte strings, as many as the first argument asks (2000 by
+//! default), and `fuzz_roundtrip` panics on the first that breaks an
+//! invariant. tests/fuzz.rs runs a shorter round under `cargo test`; for
+//! coverage-guided fuzzing, the `fuzz` directory runs the same function
+//! under cargo-fuzz.
+//!
+//! `cargo run --example fuzz --features fuzz,persist`
+
+// the generated functions take &Vec<DataPoint>
+#![allow(clippy::ptr_arg)]
+
+use decision_leaf::classification_data_layout;
+use decision_leaf::fuzz::{Arbitrary, Unstructured};
+use decision_leaf::rng::Rng;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Kind {
+    Spam,
+    Ham,
+    Unsure,
+}
+
+impl FromStr for Kind {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, String> {
+        match s {
+            "Spam" => Ok(Kind::Spam),
+            "Ham" => Ok(Kind::Ham),
+            "Unsure" => Ok(Kind::Unsure),
+            _ => Err(format!("unknown kind {}", s)),
+        }
+    }
+}
+
+impl Arbitrary for Kind {
+    fn arbitrary(input: &mut Unstructured) -> Self {
+        [Kind::Spam, Kind::Ham, Kind::Unsure][input.choose(3)]
+    }
+}
+
+// every category of field the layout supports, floats with NaN and an
+// optional field included
+classification_data_layout!(
+    enum_fields = {sender: Kind, flagged: bool, initial: char},
+    number_fields = {links: u16, score: f64, offset: i8, rating: Option<f32>},
+    Kind
+);
+
+fn main() {
+    let runs: usize = std::env::args()
+        .nth(1)
+        .map_or(2000, |arg| arg.parse().expect("a number of runs"));
+    let mut rng = Rng::new(11);
+    for _ in 0..runs {
+        let len = rng.gen_index(1200);
+        let bytes: Vec<u8> = (0..len).map(|_| rng.gen_index(256) as u8).collect();
+        fuzz_roundtrip(&bytes);
+    }
+    println!("{} random inputs passed", runs);
+}
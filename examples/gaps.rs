@@ -0,0 +1,83 @@
+//! Fields with gaps: an `Option<u32>` number field and an `Option<bool>`
+//! enum field, loaded from a CSV with empty and `N/A` cells, trained on
+//! without imputing, and saved and reloaded.
+//!
+//! `cargo run --example gaps --features csv,persist`
+
+// the generated functions take &Vec<DataPoint>
+#![allow(clippy::ptr_arg)]
+
+use decision_leaf::classification_data_layout;
+use decision_leaf::persist::LoadOptions;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Risk {
+    Low,
+    High,
+}
+
+impl FromStr for Risk {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, String> {
+        match s {
+            "Low" => Ok(Risk::Low),
+            "High" => Ok(Risk::High),
+            _ => Err(format!("unknown risk {}", s)),
+        }
+    }
+}
+
+classification_data_layout!(
+    enum_fields = {smoker: Option<bool>},
+    number_fields = {age: u32, income: Option<u32>},
+    Risk
+);
+
+// High below an income of 30 and when the income isn't given; smokers are
+// High either way. Every fourth income and every fifth smoker cell is blank.
+fn csv() -> String {
+    let mut csv = String::from("smoker,age,income,class\n");
+    for i in 0..80u32 {
+        let smoker = match i % 5 {
+            0 => "",
+            1 => "true",
+            _ => "false",
+        };
+        let income = match i % 4 {
+            0 => "N/A".to_owned(),
+            _ => (10 + i * 13 % 50).to_string(),
+        };
+        let high = smoker == "true" || income == "N/A" || income.parse::<u32>().unwrap() < 30;
+        csv.push_str(&format!(
+            "{},{},{},{}\n",
+            smoker,
+            20 + i % 40,
+            income,
+            if high { "High" } else { "Low" }
+        ));
+    }
+    csv
+}
+
+fn main() {
+    // a missing cell of an Option field loads as None
+    let mut config = LoaderConfig::new().missing_markers(&["N/A"]);
+    let data = load_csv_with(csv().as_bytes(), &mut config).expect("fixture loads");
+    let tree = build_tree(&data);
+    tree.print_tree("");
+
+    // None goes down the false branch of every question about its field
+    let unknown = DataPoint::new(Some(false), 30, None, Risk::High);
+    let unsure = DataPoint::new(None, 30, Some(45), Risk::Low);
+    println!(
+        "no income: {:?}, not saying whether they smoke: {:?}",
+        predict(&unknown, &tree),
+        predict(&unsure, &tree)
+    );
+
+    let mut saved = Vec::new();
+    tree.save(&mut saved).expect("write to memory");
+    let reloaded = Node::load(&saved[..], &LoadOptions::new()).expect("saved tree loads");
+    println!("reloaded, fingerprint {:016x}", reloaded.fingerprint());
+}
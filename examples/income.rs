@@ -0,0 +1,131 @@
+//! End-to-end run on an adult-style dataset mixing enum and number fields,
+//! with `yes`/`no` booleans and `?` for missing hours: load the CSV with
+//! adapters and imputation, split, train, print, evaluate, classify a
+//! request of the caller's own type, save and reload.
+//!
+//! `cargo run --example income --features csv,persist`
+
+// the generated functions take &Vec<DataPoint>
+#![allow(clippy::ptr_arg)]
+
+use decision_leaf::classification_data_layout;
+use decision_leaf::impl_has_fields;
+use decision_leaf::persist::LoadOptions;
+use std::str::FromStr;
+
+// FromStr by variant name, as the csv loader and Node::load expect
+macro_rules! labeled_enum {
+    ($name:ident { $($variant:ident),* }) => {
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+        pub enum $name {
+            $($variant),*
+        }
+
+        impl FromStr for $name {
+            type Err = String;
+            fn from_str(s: &str) -> Result<Self, String> {
+                match s {
+                    $(stringify!($variant) => Ok($name::$variant),)*
+                    _ => Err(format!("unknown {} {}", stringify!($name), s)),
+                }
+            }
+        }
+    };
+}
+
+labeled_enum!(Education {
+    HighSchool,
+    Bachelors,
+    Masters,
+    Doctorate
+});
+labeled_enum!(Occupation {
+    Clerical,
+    Sales,
+    Craft,
+    Professional,
+    Managerial,
+    Service
+});
+labeled_enum!(Income { Low, High });
+
+classification_data_layout!(
+    enum_fields = {education: Education, occupation: Occupation, married: bool},
+    number_fields = {age: u32, hours_per_week: u32},
+    Income
+);
+
+// a request as it arrives off the wire, classified without building a DataPoint
+struct Request {
+    id: u64,
+    degree: Education,
+    job: Occupation,
+    spouse: bool,
+    years: u32,
+    weekly_hours: u32,
+}
+
+impl_has_fields!(Request {
+    education: degree,
+    occupation: job,
+    married: spouse,
+    age: years,
+    hours_per_week: weekly_hours,
+});
+
+const INCOME: &str = include_str!("data/income.csv");
+
+fn main() {
+    // a first pass without imputation fits the imputer on the complete rows
+    let complete = load_csv_with(
+        INCOME.as_bytes(),
+        &mut LoaderConfig::new()
+            .missing_markers(&["?"])
+            .bool_markers("married", &["yes"], &["no"])
+            .skip_missing_rows(),
+    )
+    .expect("fixture loads");
+    let imputer = Imputer::fit(&complete).expect("fixture has rows");
+    let mut config = LoaderConfig::new()
+        .missing_markers(&["?"])
+        .bool_markers("married", &["yes"], &["no"])
+        .impute(imputer);
+    let data = load_csv_with(INCOME.as_bytes(), &mut config).expect("fixture loads");
+    for (column, filled) in config.imputed() {
+        println!("imputed {} cells of {}", filled, column);
+    }
+
+    // every fourth row is held out
+    let (test, train): (Vec<_>, Vec<_>) = data
+        .iter()
+        .cloned()
+        .enumerate()
+        .partition(|(i, _)| i % 4 == 0);
+    let train: Vec<DataPoint> = train.into_iter().map(|(_, p)| p).collect();
+    let test: Vec<DataPoint> = test.into_iter().map(|(_, p)| p).collect();
+
+    let config = TreeConfig {
+        max_depth: Some(4),
+        min_samples_leaf: 5,
+        ..Default::default()
+    };
+    let tree = build_tree_with(&train, &config).expect("valid config");
+    tree.print_tree("");
+    run_tests(&test, &tree);
+
+    // classified where it lies, without building a DataPoint
+    let request = Request {
+        id: 7,
+        degree: Education::Masters,
+        job: Occupation::Managerial,
+        spouse: true,
+        years: 45,
+        weekly_hours: 50,
+    };
+    println!("request {}: {:?}", request.id, predict(&request, &tree));
+
+    let mut saved = Vec::new();
+    tree.save(&mut saved).expect("write to memory");
+    let reloaded = Node::load(&saved[..], &LoadOptions::new()).expect("saved tree loads");
+    println!("reloaded, fingerprint {:016x}", reloaded.fingerprint());
+}
@@ -0,0 +1,99 @@
+//! The smallest use of the crate: a layout with every kind of field, an
+//! optional one included, a tree and a prediction. Needs no features, so it
+//! builds under every combination of them; scripts/check-features.sh builds
+//! it under each to catch a gated part of the expansion naming one that is
+//! gated off.
+//!
+//! `cargo run --example minimal --no-default-features`
+
+// the generated functions take &Vec<DataPoint>
+#![allow(clippy::ptr_arg)]
+
+use decision_leaf::classification_data_layout;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Color {
+    Red,
+    Green,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Fruit {
+    Apple,
+    Lime,
+}
+
+impl FromStr for Color {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, String> {
+        match s {
+            "Red" => Ok(Color::Red),
+            "Green" => Ok(Color::Green),
+            _ => Err(format!("unknown color {}", s)),
+        }
+    }
+}
+
+impl FromStr for Fruit {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, String> {
+        match s {
+            "Apple" => Ok(Fruit::Apple),
+            "Lime" => Ok(Fruit::Lime),
+            _ => Err(format!("unknown fruit {}", s)),
+        }
+    }
+}
+
+#[cfg(feature = "fuzz")]
+mod arbitrary {
+    use super::{Color, Fruit};
+    use decision_leaf::fuzz::{Arbitrary, Unstructured};
+
+    impl Arbitrary for Color {
+        fn arbitrary(input: &mut Unstructured) -> Self {
+            [Color::Red, Color::Green][input.choose(2)]
+        }
+    }
+
+    impl Arbitrary for Fruit {
+        fn arbitrary(input: &mut Unstructured) -> Self {
+            [Fruit::Apple, Fruit::Lime][input.choose(2)]
+        }
+    }
+}
+
+classification_data_layout!(
+    enum_fields = {color: Color, origin: String},
+    number_fields = {size: u32, weight: Option<u16>},
+    derived_fields = {half: u32 = |p| p.size / 2},
+    Fruit
+);
+
+fn main() {
+    let data = vec![
+        DataPoint::new(Color::Red, "Spain".to_owned(), 60, Some(150), Fruit::Apple),
+        DataPoint::new(Color::Red, "Chile".to_owned(), 70, None, Fruit::Apple),
+        DataPoint::new(
+            Color::Green,
+            "Spain".to_owned(),
+            65,
+            Some(140),
+            Fruit::Apple,
+        ),
+        DataPoint::new(Color::Green, "Mexico".to_owned(), 30, Some(40), Fruit::Lime),
+        DataPoint::new(Color::Green, "Mexico".to_owned(), 35, None, Fruit::Lime),
+    ];
+    let tree = build_tree(&data);
+    tree.print_tree("");
+    let point = DataPoint::new(Color::Green, "Chile".to_owned(), 62, None, Fruit::Apple);
+    println!("a green one of 62: {:?}", predict(&point, &tree));
+    // an enum field that isn't Copy splits like any other
+    let by_origin = TreeConfig {
+        fields: Some(vec![Field::origin]),
+        ..Default::default()
+    };
+    let tree = build_tree_with(&data, &by_origin).expect("valid config");
+    tree.print_tree("");
+}
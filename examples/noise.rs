@@ -0,0 +1,82 @@
+//! Label noise on clean synthetic data: flip labels at known rates, watch the
+//! cross-validated accuracy fall, and estimate how many labels are wrong.
+//!
+//! `cargo run --example noise`
+
+// the generated functions take &Vec<DataPoint>
+#![allow(clippy::ptr_arg)]
+
+use decision_leaf::classification_data_layout;
+use decision_leaf::rng::Rng;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Zone {
+    Low,
+    Mid,
+    High,
+}
+
+impl FromStr for Zone {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, String> {
+        match s {
+            "Low" => Ok(Zone::Low),
+            "Mid" => Ok(Zone::Mid),
+            "High" => Ok(Zone::High),
+            _ => Err(format!("unknown zone {}", s)),
+        }
+    }
+}
+
+classification_data_layout!(
+    enum_fields = {},
+    number_fields = {x: f64, y: f64},
+    Zone
+);
+
+// the zone of x + y, without noise
+fn rows(n: usize, seed: u64) -> Vec<DataPoint> {
+    let mut rng = Rng::new(seed);
+    (0..n)
+        .map(|_| {
+            let (x, y) = (rng.next_f64(), rng.next_f64());
+            let class = match x + y {
+                s if s < 0.7 => Zone::Low,
+                s if s < 1.3 => Zone::Mid,
+                _ => Zone::High,
+            };
+            DataPoint::new(x, y, class)
+        })
+        .collect()
+}
+
+fn main() {
+    let data = rows(2000, 9);
+    let config = TreeConfig {
+        max_depth: Some(6),
+        min_samples_leaf: 10,
+        max_candidates: 32,
+        ..Default::default()
+    };
+
+    let rates = [0.0, 0.1, 0.2, 0.3, 0.4];
+    let curve = noise_sensitivity_curve(&data, &rates, 5, 2, &config).expect("valid curve");
+    for point in &curve {
+        println!(
+            "rate {:.1}: accuracy {:.3} ± {:.3}, {} training labels flipped",
+            point.rate, point.accuracy_mean, point.accuracy_std, point.flipped
+        );
+    }
+
+    // confident out-of-fold disagreements, before and after flipping a fifth
+    // of the labels
+    let noisy = inject_label_noise(&data, 0.2, 4).expect("valid rate");
+    let clean = estimate_label_noise(&data, 5, 2, &config, 0.7).expect("valid estimate");
+    let estimate = estimate_label_noise(&noisy, 5, 2, &config, 0.7).expect("valid estimate");
+    println!(
+        "estimated noise {:.3} on clean labels, {:.3} on noisy ones",
+        clean.rate(),
+        estimate.rate()
+    );
+}
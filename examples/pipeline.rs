@@ -0,0 +1,113 @@
+//! A Pipeline of imputation and rebalancing in front of a tree, on the
+//! adult-style data with a derived field: fit on raw rows with missing hours,
+//! predict raw points, save and reload.
+//!
+//! `cargo run --example pipeline --features persist`
+
+// the generated functions take &Vec<DataPoint>
+#![allow(clippy::ptr_arg)]
+
+use decision_leaf::classification_data_layout;
+use decision_leaf::persist::LoadOptions;
+use decision_leaf::pipeline::StepSpec;
+use std::str::FromStr;
+
+// FromStr by variant name, as Node::load expects
+macro_rules! labeled_enum {
+    ($name:ident { $($variant:ident),* }) => {
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+        pub enum $name {
+            $($variant),*
+        }
+
+        impl FromStr for $name {
+            type Err = String;
+            fn from_str(s: &str) -> Result<Self, String> {
+                match s {
+                    $(stringify!($variant) => Ok($name::$variant),)*
+                    _ => Err(format!("unknown {} {}", stringify!($name), s)),
+                }
+            }
+        }
+    };
+}
+
+labeled_enum!(Education {
+    HighSchool,
+    Bachelors,
+    Masters,
+    Doctorate
+});
+labeled_enum!(Occupation {
+    Clerical,
+    Sales,
+    Craft,
+    Professional,
+    Managerial,
+    Service
+});
+labeled_enum!(Income { Low, High });
+
+classification_data_layout!(
+    enum_fields = {education: Education, occupation: Occupation, married: bool},
+    number_fields = {age: u32, hours_per_week: u32},
+    derived_fields = {hours_per_decade: u32 = |p| p.hours_per_week * 100 / p.age.max(1)},
+    Income
+);
+
+const INCOME: &str = include_str!("data/income.csv");
+
+// the raw rows, `?` hours left missing
+fn raw_rows() -> Vec<PartialPoint> {
+    INCOME
+        .lines()
+        .skip(1)
+        .map(|line| {
+            let cells: Vec<&str> = line.split(',').collect();
+            PartialPoint {
+                age: cells[0].parse().ok(),
+                education: cells[1].parse().ok(),
+                occupation: cells[2].parse().ok(),
+                married: Some(cells[3] == "yes"),
+                hours_per_week: cells[4].parse().ok(),
+                class: cells[5].parse().expect("fixture classes parse"),
+            }
+        })
+        .collect()
+}
+
+fn main() {
+    let rows = raw_rows();
+    let config = TreeConfig {
+        max_depth: Some(5),
+        min_samples_leaf: 3,
+        ..Default::default()
+    };
+    // imputation runs on every point, rebalancing only in training
+    let steps = [StepSpec::Impute, StepSpec::Rebalance { seed: 3 }];
+    let pipeline = Pipeline::fit(rows.clone(), &steps, &config).expect("valid pipeline");
+    pipeline.tree.print_tree("");
+
+    // a raw point with its hours missing is filled in as in training
+    let raw = rows
+        .iter()
+        .find(|r| r.hours_per_week.is_none())
+        .expect("the fixture has gaps")
+        .clone();
+    let prepared = pipeline.prepare(raw.clone()).expect("imputed");
+    println!(
+        "{} hours a week filled in, predicted {:?}",
+        prepared.hours_per_week,
+        pipeline.predict(raw).expect("imputed")
+    );
+
+    // one saved artifact holds the steps and the tree
+    let mut saved = Vec::new();
+    pipeline.save(&mut saved).expect("write to memory");
+    let reloaded = Pipeline::load(&saved[..], &LoadOptions::new()).expect("saved pipeline loads");
+    println!(
+        "reloaded {} steps, fingerprint {:016x}",
+        reloaded.steps.len(),
+        reloaded.tree.fingerprint()
+    );
+}
@@ -0,0 +1,44 @@
+//! `cargo +nightly fuzz run roundtrip` from the crate root. The layout is
+//! the one examples/fuzz.rs drives with seeded random input.
+
+#![no_main]
+// the generated functions take &Vec<DataPoint>
+#![allow(clippy::ptr_arg)]
+
+use decision_leaf::classification_data_layout;
+use decision_leaf::fuzz::{Arbitrary, Unstructured};
+use libfuzzer_sys::fuzz_target;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Kind {
+    Spam,
+    Ham,
+    Unsure,
+}
+
+impl FromStr for Kind {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, String> {
+        match s {
+            "Spam" => Ok(Kind::Spam),
+            "Ham" => Ok(Kind::Ham),
+            "Unsure" => Ok(Kind::Unsure),
+            _ => Err(format!("unknown kind {}", s)),
+        }
+    }
+}
+
+impl Arbitrary for Kind {
+    fn arbitrary(input: &mut Unstructured) -> Self {
+        [Kind::Spam, Kind::Ham, Kind::Unsure][input.choose(3)]
+    }
+}
+
+classification_data_layout!(
+    enum_fields = {sender: Kind, flagged: bool, initial: char},
+    number_fields = {links: u16, score: f64, offset: i8, rating: Option<f32>},
+    Kind
+);
+
+fuzz_target!(|bytes: &[u8]| fuzz_roundtrip(bytes));
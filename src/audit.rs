@@ -0,0 +1,86 @@
+//! Per-prediction audit records for append-only logs.
+//!
+//! A record serializes to one line of JSON with this shape (version 2):
+//!
+//! ```text
+//! {"version":2,"model_fingerprint":"<16 hex>","schema_fingerprint":"<16 hex>",
+//!  "input":{"<field>":"<value>",...},
+//!  "path":[{"question":"Is size >= 50","answer":true},...],
+//!  "leaf_id":<preorder node id>,"distribution":{"<class>":<count>,...},
+//!  "predicted":"<class>"|null}
+//! ```
+//!
+//! Field values and class labels are their `Debug` representations; the
+//! distribution is ordered by descending count, then label (version 1
+//! ordered it by label only). Any change to this layout bumps
+//! [`AUDIT_RECORD_VERSION`].
+
+use std::io::{self, Write};
+
+use crate::fingerprint::to_hex;
+use crate::labels::json_string;
+
+pub const AUDIT_RECORD_VERSION: u32 = 2;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuditStep {
+    pub question: String,
+    pub answer: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuditRecord {
+    pub version: u32,
+    pub model_fingerprint: u64,
+    pub schema_fingerprint: u64,
+    /// (field name, value) in declaration order.
+    pub input: Vec<(String, String)>,
+    pub path: Vec<AuditStep>,
+    pub leaf_id: usize,
+    /// (class label, count) by descending count, then label.
+    pub distribution: Vec<(String, u64)>,
+    pub predicted: Option<String>,
+}
+
+impl AuditRecord {
+    /// The record as a single line of JSON, without a trailing newline.
+    pub fn to_json_line(&self) -> String {
+        let input: Vec<String> = self
+            .input
+            .iter()
+            .map(|(field, value)| format!("{}:{}", json_string(field), json_string(value)))
+            .collect();
+        let path: Vec<String> = self
+            .path
+            .iter()
+            .map(|step| {
+                format!(
+                    "{{\"question\":{},\"answer\":{}}}",
+                    json_string(&step.question),
+                    step.answer
+                )
+            })
+            .collect();
+        let distribution: Vec<String> = self
+            .distribution
+            .iter()
+            .map(|(class, count)| format!("{}:{}", json_string(class), count))
+            .collect();
+        format!(
+            "{{\"version\":{},\"model_fingerprint\":{},\"schema_fingerprint\":{},\"input\":{{{}}},\"path\":[{}],\"leaf_id\":{},\"distribution\":{{{}}},\"predicted\":{}}}",
+            self.version,
+            json_string(&to_hex(self.model_fingerprint)),
+            json_string(&to_hex(self.schema_fingerprint)),
+            input.join(","),
+            path.join(","),
+            self.leaf_id,
+            distribution.join(","),
+            self.predicted.as_deref().map_or("null".to_owned(), json_string)
+        )
+    }
+
+    /// Appends the record and a newline to `out`.
+    pub fn write_line<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        writeln!(out, "{}", self.to_json_line())
+    }
+}
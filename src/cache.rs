@@ -0,0 +1,181 @@
+//! A least-recently-used prediction cache in front of a [`Model`].
+
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+use crate::serving::Model;
+
+const NONE: usize = usize::MAX;
+
+struct Entry<P, R> {
+    point: P,
+    output: R,
+    prev: usize,
+    next: usize,
+}
+
+/// Entries in a slab linked from most (`head`) to least (`tail`) recently
+/// used, so lookups, promotion and eviction are all O(1).
+struct Lru<P, R> {
+    index: HashMap<P, usize>,
+    entries: Vec<Entry<P, R>>,
+    head: usize,
+    tail: usize,
+}
+
+impl<P: Hash + Eq + Clone, R: Clone> Lru<P, R> {
+    fn new(capacity: usize) -> Self {
+        Self {
+            index: HashMap::with_capacity(capacity),
+            entries: Vec::with_capacity(capacity),
+            head: NONE,
+            tail: NONE,
+        }
+    }
+
+    fn unlink(&mut self, i: usize) {
+        let (prev, next) = (self.entries[i].prev, self.entries[i].next);
+        match prev {
+            NONE => self.head = next,
+            p => self.entries[p].next = next,
+        }
+        match next {
+            NONE => self.tail = prev,
+            n => self.entries[n].prev = prev,
+        }
+    }
+
+    fn push_front(&mut self, i: usize) {
+        self.entries[i].prev = NONE;
+        self.entries[i].next = self.head;
+        if self.head != NONE {
+            self.entries[self.head].prev = i;
+        }
+        self.head = i;
+        if self.tail == NONE {
+            self.tail = i;
+        }
+    }
+
+    fn get(&mut self, point: &P) -> Option<R> {
+        let i = *self.index.get(point)?;
+        self.unlink(i);
+        self.push_front(i);
+        Some(self.entries[i].output.clone())
+    }
+
+    fn insert(&mut self, point: P, output: R, capacity: usize) {
+        if let Some(&i) = self.index.get(&point) {
+            self.entries[i].output = output;
+            self.unlink(i);
+            self.push_front(i);
+            return;
+        }
+        let i = if self.entries.len() < capacity {
+            self.entries.push(Entry {
+                point: point.clone(),
+                output,
+                prev: NONE,
+                next: NONE,
+            });
+            self.entries.len() - 1
+        } else {
+            // reuse the least recently used slot
+            let i = self.tail;
+            self.unlink(i);
+            self.index.remove(&self.entries[i].point);
+            self.entries[i].point = point.clone();
+            self.entries[i].output = output;
+            i
+        };
+        self.index.insert(point, i);
+        self.push_front(i);
+    }
+}
+
+/// What a point is cached under: the values a model reads from it, leaving
+/// out the rest. The generated `DataPoint` implements it with `FeatureKey`,
+/// its fields without the class, so a point predicts the same whatever class
+/// it was labelled with.
+pub trait CacheKey {
+    type Key: Hash + Eq + Clone + Send;
+    fn cache_key(&self) -> Self::Key;
+}
+
+/// Wraps a model with a cache of its last `capacity` distinct points.
+/// Keys are compared in full, so a hash collision can never return another
+/// point's prediction.
+pub struct CachedClassifier<P: CacheKey, M: Model<P>> {
+    model: M,
+    capacity: usize,
+    lru: Mutex<Lru<P::Key, M::Output>>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl<P, M> CachedClassifier<P, M>
+where
+    P: CacheKey,
+    M: Model<P>,
+    M::Output: Clone,
+{
+    /// A capacity of 0 is treated as 1.
+    pub fn new(model: M, capacity: usize) -> Self {
+        let capacity = capacity.max(1);
+        Self {
+            model,
+            capacity,
+            lru: Mutex::new(Lru::new(capacity)),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+
+    pub fn model(&self) -> &M {
+        &self.model
+    }
+
+    pub fn hits(&self) -> u64 {
+        self.hits.load(Ordering::Relaxed)
+    }
+
+    pub fn misses(&self) -> u64 {
+        self.misses.load(Ordering::Relaxed)
+    }
+
+    /// Number of points currently cached.
+    pub fn len(&self) -> usize {
+        self.lru.lock().unwrap().index.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+// the model runs outside the lock, so a slow prediction doesn't block hits
+impl<P, M> Model<P> for CachedClassifier<P, M>
+where
+    P: CacheKey,
+    M: Model<P>,
+    M::Output: Clone,
+{
+    type Output = M::Output;
+
+    fn predict(&self, point: &P) -> M::Output {
+        let key = point.cache_key();
+        if let Some(output) = self.lru.lock().unwrap().get(&key) {
+            self.hits.fetch_add(1, Ordering::Relaxed);
+            return output;
+        }
+        self.misses.fetch_add(1, Ordering::Relaxed);
+        let output = self.model.predict(point);
+        self.lru
+            .lock()
+            .unwrap()
+            .insert(key, output.clone(), self.capacity);
+        output
+    }
+}
@@ -0,0 +1,209 @@
+//! Model cards: a summary of a trained tree for review and compliance,
+//! assembled from the layout, the config, a training summary and an
+//! evaluation. Only `notes` is free text, and it is written as given; the
+//! other text is escaped for the format, and JSON has null for NaN.
+
+use crate::drift::FieldDrift;
+use crate::fingerprint::to_hex;
+use crate::labels::{escape, json_number, json_string, Target};
+
+fn markdown(text: &str) -> String {
+    escape(text, Target::Markdown)
+}
+
+/// A known weakness found by one of the checks.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Limitation {
+    /// The check that raised it, e.g. `drift` or `memorization`.
+    pub source: &'static str,
+    pub field: String,
+    pub detail: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClassMetrics {
+    pub class: String,
+    pub support: usize,
+    pub precision: f32,
+    pub recall: f32,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ModelCard {
+    /// Seconds since the Unix epoch when the card was made.
+    pub created: u64,
+    pub model_fingerprint: u64,
+    pub schema_fingerprint: u64,
+    /// (field, kind and type), in declaration order; the class last.
+    pub schema: Vec<(String, String)>,
+    /// (option, value) of the TreeConfig.
+    pub config: Vec<(String, String)>,
+    pub training_rows: usize,
+    /// (class, training rows) by descending rows, then label.
+    pub class_distribution: Vec<(String, u64)>,
+    pub node_count: usize,
+    pub depth: usize,
+    /// Node::heap_size_estimate of the tree.
+    pub heap_bytes: usize,
+    pub test_rows: usize,
+    pub accuracy: f32,
+    pub class_metrics: Vec<ClassMetrics>,
+    /// (field, share of the total impurity decrease), largest first.
+    pub top_features: Vec<(String, f64)>,
+    pub limitations: Vec<Limitation>,
+    pub notes: Option<String>,
+}
+
+impl ModelCard {
+    /// Adds a limitation for every flagged field of a drift report.
+    pub fn add_drift(&mut self, drift: &[FieldDrift]) {
+        for field in drift.iter().filter(|f| f.flagged) {
+            let mut detail = format!("PSI {:.3}", field.psi);
+            if !field.unseen_values.is_empty() {
+                detail += &format!(", unseen values {}", field.unseen_values.join(", "));
+            }
+            self.limitations.push(Limitation {
+                source: "drift",
+                field: field.field.clone(),
+                detail,
+            });
+        }
+    }
+
+    pub fn to_markdown(&self) -> String {
+        let mut md = format!(
+            "# Model card\n\n- Model: `{}`\n- Schema: `{}`\n- Created: {}\n",
+            to_hex(self.model_fingerprint),
+            to_hex(self.schema_fingerprint),
+            utc_date(self.created)
+        );
+        if let Some(notes) = &self.notes {
+            md += &format!("\n{}\n", notes);
+        }
+        md += "\n## Schema\n\n| Field | Type |\n|---|---|\n";
+        for (field, kind) in &self.schema {
+            md += &format!("| {} | {} |\n", markdown(field), markdown(kind));
+        }
+        md += "\n## Training\n\n";
+        md += &format!(
+            "- Rows: {}\n- Nodes: {}\n- Depth: {}\n- Size estimate: {} bytes\n",
+            self.training_rows, self.node_count, self.depth, self.heap_bytes
+        );
+        for (option, value) in &self.config {
+            md += &format!("- {}: {}\n", markdown(option), markdown(value));
+        }
+        md += "\n| Class | Rows |\n|---|---|\n";
+        for (class, rows) in &self.class_distribution {
+            md += &format!("| {} | {} |\n", markdown(class), rows);
+        }
+        md += &format!(
+            "\n## Evaluation\n\n- Test rows: {}\n- Accuracy: {:.3}\n\n| Class | Support | Precision | Recall |\n|---|---|---|---|\n",
+            self.test_rows, self.accuracy
+        );
+        for m in &self.class_metrics {
+            md += &format!(
+                "| {} | {} | {:.3} | {:.3} |\n",
+                markdown(&m.class),
+                m.support,
+                m.precision,
+                m.recall
+            );
+        }
+        md += "\n## Top features\n\n";
+        for (i, (field, share)) in self.top_features.iter().enumerate() {
+            md += &format!("{}. {} ({:.3})\n", i + 1, markdown(field), share);
+        }
+        md += "\n## Limitations\n\n";
+        if self.limitations.is_empty() {
+            md += "None found.\n";
+        }
+        for l in &self.limitations {
+            md += &format!(
+                "- {} ({}): {}\n",
+                markdown(&l.field),
+                l.source,
+                markdown(&l.detail)
+            );
+        }
+        md
+    }
+
+    pub fn to_json(&self) -> String {
+        let pairs = |pairs: &[(String, String)]| {
+            pairs
+                .iter()
+                .map(|(k, v)| format!("{}:{}", json_string(k), json_string(v)))
+                .collect::<Vec<_>>()
+                .join(",")
+        };
+        let classes: Vec<String> = self
+            .class_distribution
+            .iter()
+            .map(|(class, rows)| format!("{}:{}", json_string(class), rows))
+            .collect();
+        let metrics: Vec<String> = self
+            .class_metrics
+            .iter()
+            .map(|m| {
+                format!(
+                    "{{\"class\":{},\"support\":{},\"precision\":{},\"recall\":{}}}",
+                    json_string(&m.class),
+                    m.support,
+                    json_number(m.precision),
+                    json_number(m.recall)
+                )
+            })
+            .collect();
+        let features: Vec<String> = self
+            .top_features
+            .iter()
+            .map(|(field, share)| format!("{}:{}", json_string(field), json_number(*share)))
+            .collect();
+        let limitations: Vec<String> = self
+            .limitations
+            .iter()
+            .map(|l| {
+                format!(
+                    "{{\"source\":{},\"field\":{},\"detail\":{}}}",
+                    json_string(l.source),
+                    json_string(&l.field),
+                    json_string(&l.detail)
+                )
+            })
+            .collect();
+        format!(
+            "{{\"created\":{},\"model_fingerprint\":{},\"schema_fingerprint\":{},\"schema\":{{{}}},\"config\":{{{}}},\"training_rows\":{},\"class_distribution\":{{{}}},\"node_count\":{},\"depth\":{},\"heap_bytes\":{},\"test_rows\":{},\"accuracy\":{},\"class_metrics\":[{}],\"top_features\":{{{}}},\"limitations\":[{}],\"notes\":{}}}",
+            self.created,
+            json_string(&to_hex(self.model_fingerprint)),
+            json_string(&to_hex(self.schema_fingerprint)),
+            pairs(&self.schema),
+            pairs(&self.config),
+            self.training_rows,
+            classes.join(","),
+            self.node_count,
+            self.depth,
+            self.heap_bytes,
+            self.test_rows,
+            json_number(self.accuracy),
+            metrics.join(","),
+            features.join(","),
+            limitations.join(","),
+            self.notes.as_deref().map_or("null".to_owned(), json_string)
+        )
+    }
+}
+
+/// `YYYY-MM-DD` (UTC) of a Unix timestamp.
+pub fn utc_date(seconds: u64) -> String {
+    // days to civil date, from Howard Hinnant's date algorithms
+    let z = (seconds / 86_400) as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + (month <= 2) as i64;
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
@@ -0,0 +1,102 @@
+//! Split conformal prediction: the nonconformity scores of a held-out
+//! calibration set turn a tree's leaf probabilities into prediction sets
+//! that contain the true class of at least `1 - alpha` of exchangeable rows.
+//!
+//! A row's score is `1 - p`, `p` being the probability its leaf gives its
+//! class; a leaf that never saw the class scores 1. Trees give few distinct
+//! probabilities, so scores tie often. [`Calibration::includes`] keeps every
+//! class tied with the threshold, which only adds coverage.
+//! [`Calibration::includes_randomized`] breaks ties with one uniform draw per
+//! point (the smoothed p-value), which makes the coverage `1 - alpha` on
+//! average at the cost of sets that may be empty.
+
+/// Sorted nonconformity scores of a calibration set and the miscoverage
+/// level they were calibrated for.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Calibration {
+    scores: Vec<f64>,
+    alpha: f64,
+}
+
+impl Calibration {
+    pub fn new(mut scores: Vec<f64>, alpha: f64) -> Self {
+        scores.sort_by(f64::total_cmp);
+        Self { scores, alpha }
+    }
+
+    pub fn alpha(&self) -> f64 {
+        self.alpha
+    }
+
+    pub fn scores(&self) -> &[f64] {
+        &self.scores
+    }
+
+    /// The `ceil((n + 1)(1 - alpha))`-th smallest score; infinite when that
+    /// rank exceeds `n`, i.e. too few calibration rows for this `alpha`, so
+    /// that every class is included.
+    pub fn threshold(&self) -> f64 {
+        let n = self.scores.len();
+        // the epsilon keeps products like 10 * 0.9 from rounding up a rank
+        let rank = ((n + 1) as f64 * (1.0 - self.alpha) - 1e-9).ceil() as usize;
+        match rank {
+            0 => f64::NEG_INFINITY,
+            rank if rank > n => f64::INFINITY,
+            rank => self.scores[rank - 1],
+        }
+    }
+
+    /// A class scoring `score` belongs in the set: `score <= threshold()`.
+    pub fn includes(&self, score: f64) -> bool {
+        score <= self.threshold()
+    }
+
+    /// Randomized inclusion with `u` uniform in `[0, 1)`, shared by all
+    /// classes of a point: the smoothed p-value
+    /// `(greater + u * (equal + 1)) / (n + 1)` is above `alpha`.
+    pub fn includes_randomized(&self, score: f64, u: f64) -> bool {
+        let below = self.scores.partition_point(|&s| s < score);
+        let not_above = self.scores.partition_point(|&s| s <= score);
+        let greater = self.scores.len() - not_above;
+        let equal = not_above - below;
+        let p = (greater as f64 + u * (equal + 1) as f64) / (self.scores.len() + 1) as f64;
+        p > self.alpha
+    }
+}
+
+/// How often prediction sets held the true class.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Coverage {
+    pub rows: usize,
+    pub covered: usize,
+    /// Summed size of the sets.
+    pub set_sizes: usize,
+    pub empty_sets: usize,
+}
+
+impl Coverage {
+    /// Records one set.
+    pub fn add(&mut self, size: usize, covered: bool) {
+        self.rows += 1;
+        self.covered += covered as usize;
+        self.set_sizes += size;
+        self.empty_sets += (size == 0) as usize;
+    }
+
+    /// Share of rows whose set held the true class; 0 without rows.
+    pub fn coverage(&self) -> f64 {
+        if self.rows == 0 {
+            0.0
+        } else {
+            self.covered as f64 / self.rows as f64
+        }
+    }
+
+    pub fn mean_set_size(&self) -> f64 {
+        if self.rows == 0 {
+            0.0
+        } else {
+            self.set_sizes as f64 / self.rows as f64
+        }
+    }
+}
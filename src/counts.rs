@@ -0,0 +1,243 @@
+//! Per-class accumulators shared by leaves, impurity and evaluation code.
+
+use std::collections::hash_map::{self, HashMap};
+use std::fmt::Debug;
+use std::hash::Hash;
+use std::ops::AddAssign;
+
+/// A count or weight type that can be accumulated per class.
+pub trait Weight: Copy + PartialOrd + Default + AddAssign + Debug {
+    fn one() -> Self;
+    fn to_f64(self) -> f64;
+}
+
+macro_rules! impl_weight {
+    ($($t:ty => $one:expr),*) => {
+        $(impl Weight for $t {
+            fn one() -> Self {
+                $one
+            }
+            fn to_f64(self) -> f64 {
+                self as f64
+            }
+        })*
+    };
+}
+
+impl_weight!(u32 => 1, u64 => 1, usize => 1, f32 => 1.0, f64 => 1.0);
+
+/// Compensated (Kahan) sum; exact for integer-valued inputs below 2^53.
+pub fn kahan_sum<I: IntoIterator<Item = f64>>(values: I) -> f64 {
+    let mut sum = 0.0;
+    let mut compensation = 0.0;
+    for value in values {
+        let y = value - compensation;
+        let t = sum + y;
+        compensation = (t - sum) - y;
+        sum = t;
+    }
+    sum
+}
+
+/// How much of each class has been seen. `W = u64` for plain counts, `W = f64`
+/// for weighted rows.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClassCounts<C: Eq + Hash, W = u64> {
+    counts: HashMap<C, W>,
+}
+
+impl<C: Eq + Hash, W> Default for ClassCounts<C, W> {
+    fn default() -> Self {
+        Self {
+            counts: HashMap::new(),
+        }
+    }
+}
+
+impl<C: Eq + Hash + Clone, W: Weight> ClassCounts<C, W> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Empty counts with room for `classes` classes before reallocating.
+    pub fn with_capacity(classes: usize) -> Self {
+        Self {
+            counts: HashMap::with_capacity(classes),
+        }
+    }
+
+    pub fn add(&mut self, class: C, weight: W) {
+        *self.counts.entry(class).or_default() += weight;
+    }
+
+    /// Accumulated weight of `class`, zero if it was never added.
+    pub fn get(&self, class: &C) -> W {
+        self.counts.get(class).copied().unwrap_or_default()
+    }
+
+    /// The summed weight, added in ascending order so float weights give
+    /// the same total however the map is laid out.
+    pub fn total(&self) -> W {
+        let mut weights: Vec<W> = self.counts.values().copied().collect();
+        weights.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+        let mut total = W::default();
+        for w in weights {
+            total += w;
+        }
+        total
+    }
+
+    /// The total as f64, summed with Kahan compensation so large float
+    /// weights don't lose the small ones.
+    pub fn total_f64(&self) -> f64 {
+        kahan_sum(self.sorted_weights())
+    }
+
+    // the weights as f64 in ascending order, so float sums over them don't
+    // depend on the hash order of the map
+    fn sorted_weights(&self) -> Vec<f64> {
+        let mut weights: Vec<f64> = self.counts.values().map(|w| w.to_f64()).collect();
+        weights.sort_by(f64::total_cmp);
+        weights
+    }
+
+    /// Fraction of the total weight belonging to `class`; zero when empty.
+    pub fn proba(&self, class: &C) -> f64 {
+        let total = self.total_f64();
+        if total == 0.0 {
+            0.0
+        } else {
+            self.get(class).to_f64() / total
+        }
+    }
+
+    /// The class with the largest weight. Ties go to the class whose `Debug`
+    /// label sorts first, so the result does not depend on hash order.
+    pub fn argmax(&self) -> Option<&C>
+    where
+        C: Debug,
+    {
+        let mut best: Option<(&C, W)> = None;
+        for (class, &w) in &self.counts {
+            best = match best {
+                Some((b, bw))
+                    if bw > w || (bw == w && format!("{:?}", b) <= format!("{:?}", class)) =>
+                {
+                    Some((b, bw))
+                }
+                _ => Some((class, w)),
+            };
+        }
+        best.map(|(class, _)| class)
+    }
+
+    /// The classes and their weights by descending weight, ties by `Debug`
+    /// label: the order every exporter writes distributions in.
+    pub fn ordered(&self) -> Vec<(&C, W)>
+    where
+        C: Debug,
+    {
+        let mut entries: Vec<(String, &C, W)> = self
+            .counts
+            .iter()
+            .map(|(class, &w)| (format!("{:?}", class), class, w))
+            .collect();
+        entries.sort_by(|a, b| {
+            b.2.partial_cmp(&a.2)
+                .unwrap_or(std::cmp::Ordering::Equal)
+                .then_with(|| a.0.cmp(&b.0))
+        });
+        entries
+            .into_iter()
+            .map(|(_, class, w)| (class, w))
+            .collect()
+    }
+
+    /// Adds every class weight of `other` into `self`.
+    pub fn merge(&mut self, other: &Self) {
+        for (class, &w) in &other.counts {
+            self.add(class.clone(), w);
+        }
+    }
+
+    /// Gini impurity of the distribution; zero when empty.
+    pub fn gini(&self) -> f64 {
+        let total = self.total_f64();
+        if total == 0.0 {
+            return 0.0;
+        }
+        1.0 - kahan_sum(
+            self.sorted_weights()
+                .into_iter()
+                .map(|w| (w / total).powi(2)),
+        )
+    }
+
+    /// Shannon entropy of the distribution in bits; zero when empty.
+    pub fn entropy(&self) -> f64 {
+        let total = self.total_f64();
+        if total == 0.0 {
+            return 0.0;
+        }
+        -kahan_sum(
+            self.sorted_weights()
+                .into_iter()
+                .filter(|&w| w > 0.0)
+                .map(|w| (w / total) * (w / total).log2()),
+        )
+    }
+}
+
+impl<C: Eq + Hash, W> ClassCounts<C, W> {
+    /// Number of distinct classes present.
+    pub fn len(&self) -> usize {
+        self.counts.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.counts.is_empty()
+    }
+
+    /// Releases the map's spare capacity.
+    pub fn shrink_to_fit(&mut self) {
+        self.counts.shrink_to_fit();
+    }
+
+    /// Estimated bytes the map allocates: one `(C, W)` entry plus one
+    /// control byte per slot of capacity. Heap data owned by `C` itself is
+    /// not counted.
+    pub fn heap_size_estimate(&self) -> usize {
+        self.counts.capacity() * (std::mem::size_of::<(C, W)>() + 1)
+    }
+
+    pub fn contains(&self, class: &C) -> bool {
+        self.counts.contains_key(class)
+    }
+
+    pub fn classes(&self) -> hash_map::Keys<'_, C, W> {
+        self.counts.keys()
+    }
+
+    pub fn iter(&self) -> hash_map::Iter<'_, C, W> {
+        self.counts.iter()
+    }
+}
+
+impl<'a, C: Eq + Hash, W> IntoIterator for &'a ClassCounts<C, W> {
+    type Item = (&'a C, &'a W);
+    type IntoIter = hash_map::Iter<'a, C, W>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.counts.iter()
+    }
+}
+
+impl<C: Eq + Hash + Clone, W: Weight> FromIterator<(C, W)> for ClassCounts<C, W> {
+    fn from_iter<I: IntoIterator<Item = (C, W)>>(iter: I) -> Self {
+        let mut counts = Self::new();
+        for (class, w) in iter {
+            counts.add(class, w);
+        }
+        counts
+    }
+}
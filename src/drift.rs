@@ -0,0 +1,197 @@
+//! Field-level drift between training data and a batch of live points.
+
+/// Smallest bin fraction used in PSI, so empty bins don't divide by zero.
+const PSI_FLOOR: f64 = 1e-4;
+/// Number of quantile bins a number field is split into for PSI.
+const NUMBER_BINS: usize = 10;
+
+/// The distribution of one field.
+#[derive(Debug, Clone, PartialEq)]
+pub enum FieldSummary {
+    /// Count per enum value label, ordered by label.
+    Categorical(Vec<(String, usize)>),
+    /// All values, sorted ascending.
+    Numeric(Vec<f64>),
+}
+
+impl FieldSummary {
+    pub fn categorical<I: IntoIterator<Item = String>>(labels: I) -> Self {
+        let mut counts: Vec<(String, usize)> = Vec::new();
+        for label in labels {
+            match counts.iter_mut().find(|(l, _)| *l == label) {
+                Some((_, n)) => *n += 1,
+                None => counts.push((label, 1)),
+            }
+        }
+        counts.sort();
+        FieldSummary::Categorical(counts)
+    }
+
+    pub fn numeric<I: IntoIterator<Item = f64>>(values: I) -> Self {
+        let mut values: Vec<f64> = values.into_iter().collect();
+        values.sort_by(|a, b| a.total_cmp(b));
+        FieldSummary::Numeric(values)
+    }
+
+    /// (mean, min, max) of a number field; None for an enum field or no values.
+    pub fn mean_min_max(&self) -> Option<(f64, f64, f64)> {
+        match self {
+            FieldSummary::Numeric(values) if !values.is_empty() => Some((
+                values.iter().sum::<f64>() / values.len() as f64,
+                values[0],
+                values[values.len() - 1],
+            )),
+            _ => None,
+        }
+    }
+
+    /// Adds the values of `other`, a summary of the same field.
+    pub fn merge(&mut self, other: &FieldSummary) {
+        match (self, other) {
+            (FieldSummary::Categorical(counts), FieldSummary::Categorical(more)) => {
+                for (label, n) in more {
+                    match counts.iter_mut().find(|(l, _)| l == label) {
+                        Some((_, total)) => *total += n,
+                        None => counts.push((label.clone(), *n)),
+                    }
+                }
+                counts.sort();
+            }
+            (FieldSummary::Numeric(values), FieldSummary::Numeric(more)) => {
+                values.extend(more);
+                values.sort_by(|a, b| a.total_cmp(b));
+            }
+            _ => panic!("can't merge summaries of different field kinds"),
+        }
+    }
+}
+
+/// Per-field distributions of a training set, in layout order.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TrainingSummary {
+    pub rows: usize,
+    pub fields: Vec<(String, FieldSummary)>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldDrift {
+    pub field: String,
+    /// Population stability index of the live distribution against training.
+    pub psi: f64,
+    /// Kolmogorov-Smirnov statistic; number fields only.
+    pub ks: Option<f64>,
+    /// Enum values in the live batch that never occurred in training.
+    pub unseen_values: Vec<String>,
+    /// `psi >= threshold` or some value is unseen.
+    pub flagged: bool,
+}
+
+/// Compares `live` against `training` for the field `field`. Both summaries
+/// must be of the same kind.
+pub fn compare(
+    field: &str,
+    training: &FieldSummary,
+    live: &FieldSummary,
+    threshold: f64,
+) -> FieldDrift {
+    let (psi, ks, unseen_values) = match (training, live) {
+        (FieldSummary::Categorical(train), FieldSummary::Categorical(live)) => {
+            let train_total: usize = train.iter().map(|(_, n)| n).sum();
+            let live_total: usize = live.iter().map(|(_, n)| n).sum();
+            let count = |counts: &[(String, usize)], label: &str| {
+                counts
+                    .iter()
+                    .find(|(l, _)| l == label)
+                    .map_or(0, |(_, n)| *n)
+            };
+            let mut labels: Vec<&String> = train.iter().chain(live).map(|(l, _)| l).collect();
+            labels.sort();
+            labels.dedup();
+            let psi = labels
+                .iter()
+                .map(|label| {
+                    psi_term(
+                        fraction(count(train, label), train_total),
+                        fraction(count(live, label), live_total),
+                    )
+                })
+                .sum();
+            let unseen = live
+                .iter()
+                .filter(|(label, _)| count(train, label) == 0)
+                .map(|(label, _)| label.clone())
+                .collect();
+            (psi, None, unseen)
+        }
+        (FieldSummary::Numeric(train), FieldSummary::Numeric(live)) => {
+            (numeric_psi(train, live), Some(ks(train, live)), Vec::new())
+        }
+        _ => panic!("{} is summarized as different field kinds", field),
+    };
+    FieldDrift {
+        field: field.to_owned(),
+        psi,
+        ks,
+        flagged: psi >= threshold || !unseen_values.is_empty(),
+        unseen_values,
+    }
+}
+
+fn fraction(count: usize, total: usize) -> f64 {
+    if total == 0 {
+        0.0
+    } else {
+        count as f64 / total as f64
+    }
+}
+
+fn psi_term(expected: f64, actual: f64) -> f64 {
+    let expected = expected.max(PSI_FLOOR);
+    let actual = actual.max(PSI_FLOOR);
+    (actual - expected) * (actual / expected).ln()
+}
+
+// bins are cut at the training deciles; repeated edges (few distinct values) merge bins
+fn numeric_psi(train: &[f64], live: &[f64]) -> f64 {
+    if train.is_empty() || live.is_empty() {
+        return 0.0;
+    }
+    let mut edges: Vec<f64> = (1..NUMBER_BINS)
+        .map(|i| crate::stats::sorted_quantile(train, i as f64 / NUMBER_BINS as f64))
+        .collect();
+    edges.dedup();
+    let bin = |v: f64| edges.iter().take_while(|&&e| v >= e).count();
+    let mut train_bins = vec![0; edges.len() + 1];
+    let mut live_bins = vec![0; edges.len() + 1];
+    for &v in train {
+        train_bins[bin(v)] += 1;
+    }
+    for &v in live {
+        live_bins[bin(v)] += 1;
+    }
+    train_bins
+        .iter()
+        .zip(&live_bins)
+        .map(|(&t, &l)| psi_term(fraction(t, train.len()), fraction(l, live.len())))
+        .sum()
+}
+
+// largest gap between the two empirical CDFs; both inputs sorted
+fn ks(a: &[f64], b: &[f64]) -> f64 {
+    if a.is_empty() || b.is_empty() {
+        return 0.0;
+    }
+    let (mut i, mut j) = (0, 0);
+    let mut max: f64 = 0.0;
+    while i < a.len() && j < b.len() {
+        let v = a[i].min(b[j]);
+        while i < a.len() && a[i] <= v {
+            i += 1;
+        }
+        while j < b.len() && b[j] <= v {
+            j += 1;
+        }
+        max = max.max((fraction(i, a.len()) - fraction(j, b.len())).abs());
+    }
+    max
+}
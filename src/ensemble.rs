@@ -0,0 +1,135 @@
+//! Out-of-bag bookkeeping for forests grown one tree at a time.
+
+use std::fmt::Debug;
+use std::hash::Hash;
+
+use crate::counts::ClassCounts;
+
+/// How many trees `build_forest_oob` grows.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NTrees {
+    Fixed(usize),
+    /// Up to `max` trees, stopping once the out-of-bag accuracy gained over
+    /// the last `window` trees is below `tolerance`.
+    Auto {
+        max: usize,
+        window: usize,
+        tolerance: f64,
+    },
+}
+
+/// The rows each tree of `build_forest_with` grows on.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Resample {
+    /// A bootstrap resample: as many rows as the data, drawn with replacement.
+    Bootstrap,
+    /// A bootstrap resample of this fraction of the rows (rounded, at least
+    /// one), in `(0, 1]`.
+    Fraction(f64),
+    /// Every row once.
+    None,
+}
+
+/// Out-of-bag accuracy of the forest after each tree.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OobCurve {
+    /// Entry `i` is the accuracy of the first `i + 1` trees on the rows left
+    /// out of at least one of them; None while no row was left out.
+    pub accuracy: Vec<Option<f64>>,
+    /// Growth ended early under `NTrees::Auto`.
+    pub stopped_early: bool,
+}
+
+/// Accumulates each row's votes from the trees it was left out of, so every
+/// tree classifies only its own out-of-bag rows, once.
+#[derive(Debug, Clone)]
+pub struct OobTracker<C: Eq + Hash> {
+    votes: Vec<ClassCounts<C, f64>>,
+    correct: Vec<Option<bool>>,
+    n_correct: usize,
+    n_scored: usize,
+    curve: Vec<Option<f64>>,
+}
+
+impl<C: Eq + Hash + Clone + Debug> OobTracker<C> {
+    pub fn new(rows: usize) -> Self {
+        Self {
+            votes: vec![ClassCounts::new(); rows],
+            correct: vec![None; rows],
+            n_correct: 0,
+            n_scored: 0,
+            curve: Vec::new(),
+        }
+    }
+
+    /// Adds one tree's leaf for each of its out-of-bag rows, as
+    /// (row index, leaf counts, actual class).
+    pub fn add_tree<'a, I>(&mut self, out_of_bag: I)
+    where
+        C: 'a,
+        I: IntoIterator<Item = (usize, &'a ClassCounts<C>, &'a C)>,
+    {
+        for (row, leaf, actual) in out_of_bag {
+            for class in leaf.classes() {
+                self.votes[row].add(class.clone(), leaf.proba(class));
+            }
+            let correct = self.votes[row].argmax() == Some(actual);
+            match self.correct[row].replace(correct) {
+                None => self.n_scored += 1,
+                Some(true) => self.n_correct -= 1,
+                Some(false) => {}
+            }
+            if correct {
+                self.n_correct += 1;
+            }
+        }
+        self.curve.push(if self.n_scored == 0 {
+            None
+        } else {
+            Some(self.n_correct as f64 / self.n_scored as f64)
+        });
+    }
+
+    /// Whether `n_trees` says to stop after the trees added so far.
+    pub fn should_stop(&self, n_trees: NTrees) -> bool {
+        let t = self.curve.len();
+        match n_trees {
+            NTrees::Fixed(n) => t >= n,
+            NTrees::Auto {
+                max,
+                window,
+                tolerance,
+            } => {
+                t >= max
+                    || (t > window
+                        && matches!(
+                            (self.curve[t - 1], self.curve[t - 1 - window]),
+                            (Some(now), Some(before)) if now - before < tolerance
+                        ))
+            }
+        }
+    }
+
+    pub fn into_curve(self, n_trees: NTrees) -> OobCurve {
+        let stopped_early = match n_trees {
+            NTrees::Fixed(_) => false,
+            NTrees::Auto { max, .. } => self.curve.len() < max,
+        };
+        OobCurve {
+            accuracy: self.curve,
+            stopped_early,
+        }
+    }
+}
+
+/// How closely a tree distilled from a forest follows it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FidelityReport {
+    /// Synthetic rows the tree was trained on.
+    pub synthetic_rows: usize,
+    /// Fraction of fresh synthetic rows, not trained on, where the tree and
+    /// the forest predict the same class.
+    pub agreement: f64,
+    /// The same on the original data.
+    pub data_agreement: f64,
+}
@@ -0,0 +1,70 @@
+//! Errors returned by the generated functions.
+
+use std::fmt::{self, Debug, Display};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DecisionLeafError {
+    /// An argument or config value outside its valid range.
+    InvalidParameter {
+        name: &'static str,
+        value: String,
+        expected: &'static str,
+    },
+    /// A field of the wrong kind for the operation, e.g. an enum field where a
+    /// number field is required.
+    InvalidField {
+        name: &'static str,
+        field: String,
+        expected: &'static str,
+    },
+    /// Something the operation needs was not recorded, e.g. in a model saved
+    /// by an older version.
+    Unavailable {
+        name: &'static str,
+        reason: &'static str,
+    },
+}
+
+impl Display for DecisionLeafError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidParameter {
+                name,
+                value,
+                expected,
+            } => write!(
+                f,
+                "invalid `{}`: got {}, expected {}",
+                name, value, expected
+            ),
+            Self::InvalidField {
+                name,
+                field,
+                expected,
+            } => write!(f, "invalid `{}`: {} is not {}", name, field, expected),
+            Self::Unavailable { name, reason } => {
+                write!(f, "`{}` is unavailable: {}", name, reason)
+            }
+        }
+    }
+}
+
+impl std::error::Error for DecisionLeafError {}
+
+/// `Err(InvalidParameter)` naming `name` and `value` unless `valid`.
+pub fn ensure<T: Debug>(
+    valid: bool,
+    name: &'static str,
+    value: T,
+    expected: &'static str,
+) -> Result<(), DecisionLeafError> {
+    if valid {
+        Ok(())
+    } else {
+        Err(DecisionLeafError::InvalidParameter {
+            name,
+            value: format!("{:?}", value),
+            expected,
+        })
+    }
+}
@@ -0,0 +1,434 @@
+//! Evaluation reports computed from (actual, predicted) pairs.
+
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::hash::Hash;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct EvaluationReport<C: Eq + Hash> {
+    pub total: usize,
+    pub correct: usize,
+    /// `correct / total`, or 0 for an empty test set.
+    pub accuracy: f32,
+    /// Number of points per (actual, predicted) pair. Points that reached an
+    /// empty leaf have no prediction and are counted as incorrect only.
+    pub confusion: HashMap<(C, C), usize>,
+}
+
+impl<C: Eq + Hash + Clone + Debug> EvaluationReport<C> {
+    pub fn from_predictions<I: IntoIterator<Item = (C, Option<C>)>>(pairs: I) -> Self {
+        let mut total = 0;
+        let mut correct = 0;
+        let mut confusion: HashMap<(C, C), usize> = HashMap::new();
+        for (actual, predicted) in pairs {
+            total += 1;
+            if let Some(predicted) = predicted {
+                if predicted == actual {
+                    correct += 1;
+                }
+                *confusion.entry((actual, predicted)).or_insert(0) += 1;
+            }
+        }
+        Self {
+            total,
+            correct,
+            accuracy: if total == 0 {
+                0.0
+            } else {
+                correct as f32 / total as f32
+            },
+            confusion,
+        }
+    }
+
+    pub fn incorrect(&self) -> usize {
+        self.total - self.correct
+    }
+
+    /// Every class appearing as actual or predicted, ordered by label.
+    pub fn classes(&self) -> Vec<C> {
+        let mut classes: Vec<C> = Vec::new();
+        for (actual, predicted) in self.confusion.keys() {
+            for class in [actual, predicted] {
+                if !classes.contains(class) {
+                    classes.push(class.clone());
+                }
+            }
+        }
+        classes.sort_by_key(|c| format!("{:?}", c));
+        classes
+    }
+
+    /// Fraction of points predicted as `class` that really are; 0 if it was never predicted.
+    pub fn precision(&self, class: &C) -> f32 {
+        let predicted: usize = self
+            .confusion
+            .iter()
+            .filter(|((_, p), _)| p == class)
+            .map(|(_, &n)| n)
+            .sum();
+        let hits = self
+            .confusion
+            .get(&(class.clone(), class.clone()))
+            .copied()
+            .unwrap_or(0);
+        if predicted == 0 {
+            0.0
+        } else {
+            hits as f32 / predicted as f32
+        }
+    }
+
+    /// Fraction of points of `class` predicted as such; 0 if it never occurs.
+    pub fn recall(&self, class: &C) -> f32 {
+        let actual: usize = self
+            .confusion
+            .iter()
+            .filter(|((a, _), _)| a == class)
+            .map(|(_, &n)| n)
+            .sum();
+        let hits = self
+            .confusion
+            .get(&(class.clone(), class.clone()))
+            .copied()
+            .unwrap_or(0);
+        if actual == 0 {
+            0.0
+        } else {
+            hits as f32 / actual as f32
+        }
+    }
+
+    /// Adds the counts of `other` into `self` and recomputes the accuracy.
+    pub fn merge(&mut self, other: &Self) {
+        self.total += other.total;
+        self.correct += other.correct;
+        for (pair, &n) in &other.confusion {
+            *self.confusion.entry(pair.clone()).or_insert(0) += n;
+        }
+        self.accuracy = if self.total == 0 {
+            0.0
+        } else {
+            self.correct as f32 / self.total as f32
+        };
+    }
+}
+
+/// Evaluation on repeated class-balanced subsamples of a test set.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BalancedEvaluation<C: Eq + Hash> {
+    /// All repeats pooled: confusion matrices summed, accuracy over all draws.
+    pub report: EvaluationReport<C>,
+    pub repeats: usize,
+    /// Rows drawn from every class in each repeat (the smallest class size).
+    pub per_class_size: usize,
+    pub accuracy_mean: f32,
+    pub accuracy_std: f32,
+    /// (class, mean recall, recall std) ordered by label.
+    pub recall: Vec<(C, f32, f32)>,
+    /// Classes with too few test rows for the balanced numbers to mean much.
+    pub warnings: Vec<String>,
+}
+
+/// Mean and sample standard deviation; the deviation is 0 for fewer than two values.
+pub fn mean_std(values: &[f32]) -> (f32, f32) {
+    if values.is_empty() {
+        return (0.0, 0.0);
+    }
+    let n = values.len() as f32;
+    let mean = values.iter().sum::<f32>() / n;
+    if values.len() < 2 {
+        return (mean, 0.0);
+    }
+    let variance = values.iter().map(|v| (v - mean).powi(2)).sum::<f32>() / (n - 1.0);
+    (mean, variance.sqrt())
+}
+
+/// Classes with fewer test rows than this get a warning in [`BalancedEvaluation`].
+pub const MIN_BALANCED_CLASS_COUNT: usize = 10;
+
+/// Draws `n_repeats` subsamples of `test` holding the same number of rows of
+/// every class (as many as the smallest class has), evaluates each with
+/// `predict` and combines them. Deterministic for a given `seed`.
+pub fn balanced_evaluation<P, C>(
+    test: &[P],
+    class_of: impl Fn(&P) -> C,
+    predict: impl Fn(&P) -> Option<C>,
+    seed: u64,
+    n_repeats: usize,
+) -> BalancedEvaluation<C>
+where
+    C: Eq + Hash + Clone + Debug,
+{
+    let mut by_class: HashMap<C, Vec<usize>> = HashMap::new();
+    for (i, point) in test.iter().enumerate() {
+        by_class.entry(class_of(point)).or_default().push(i);
+    }
+    let mut groups: Vec<(C, Vec<usize>)> = by_class.into_iter().collect();
+    groups.sort_by_key(|(c, _)| format!("{:?}", c));
+    let per_class_size = groups.iter().map(|(_, rows)| rows.len()).min().unwrap_or(0);
+    let warnings = groups
+        .iter()
+        .filter(|(_, rows)| rows.len() < MIN_BALANCED_CLASS_COUNT)
+        .map(|(c, rows)| {
+            format!(
+                "class {:?} has only {} test rows (minimum {})",
+                c,
+                rows.len(),
+                MIN_BALANCED_CLASS_COUNT
+            )
+        })
+        .collect();
+
+    let mut rng = crate::rng::Rng::new(seed);
+    let mut report = EvaluationReport::from_predictions(std::iter::empty());
+    let mut accuracies: Vec<f32> = Vec::with_capacity(n_repeats);
+    let mut recalls: Vec<Vec<f32>> = vec![Vec::with_capacity(n_repeats); groups.len()];
+    for _ in 0..n_repeats {
+        let mut pairs: Vec<(C, Option<C>)> = Vec::with_capacity(per_class_size * groups.len());
+        for (class, rows) in &groups {
+            // partial Fisher-Yates: the first per_class_size entries are the sample
+            let mut rows = rows.clone();
+            for i in 0..per_class_size {
+                let j = i + rng.gen_index(rows.len() - i);
+                rows.swap(i, j);
+                pairs.push((class.clone(), predict(&test[rows[i]])));
+            }
+        }
+        let repeat = EvaluationReport::from_predictions(pairs);
+        accuracies.push(repeat.accuracy);
+        for (k, (class, _)) in groups.iter().enumerate() {
+            recalls[k].push(repeat.recall(class));
+        }
+        report.merge(&repeat);
+    }
+    let (accuracy_mean, accuracy_std) = mean_std(&accuracies);
+    BalancedEvaluation {
+        report,
+        repeats: n_repeats,
+        per_class_size,
+        accuracy_mean,
+        accuracy_std,
+        recall: groups
+            .into_iter()
+            .zip(recalls)
+            .map(|((class, _), values)| {
+                let (mean, std) = mean_std(&values);
+                (class, mean, std)
+            })
+            .collect(),
+        warnings,
+    }
+}
+
+/// Display settings for [`EvaluationReport::render`]. They only change what is
+/// printed; the metrics are always computed from the full matrix.
+#[derive(Debug, Clone, Default)]
+pub struct ReportOptions {
+    /// Predicted classes shown per confusion row, the most frequent first; all when None.
+    pub top_k: Option<usize>,
+    /// Actual and predicted classes with fewer test points than this are
+    /// shown together as `other`.
+    pub min_support: usize,
+    pub number_format: crate::NumberFormat,
+}
+
+impl<C: Eq + Hash + Clone + Debug> EvaluationReport<C> {
+    /// Number of test points of `class` that got a prediction.
+    pub fn support(&self, class: &C) -> usize {
+        self.confusion
+            .iter()
+            .filter(|((a, _), _)| a == class)
+            .map(|(_, &n)| n)
+            .sum()
+    }
+
+    /// Accuracy line followed by one confusion row per actual class:
+    /// `Apple (12): Apple 10, Pear 2`.
+    pub fn render(&self, options: &ReportOptions) -> String {
+        const OTHER: &str = "other";
+        let label = |c: &C| crate::labels::label(c, crate::labels::Target::Text);
+        let shown = |c: &C| {
+            if self.support(c) >= options.min_support {
+                label(c)
+            } else {
+                OTHER.to_owned()
+            }
+        };
+        // (row label, support, (column label, count)); `other` sorts after the real classes
+        type Row = (String, usize, Vec<(String, usize)>);
+        let mut rows: Vec<Row> = Vec::new();
+        for ((actual, predicted), &n) in &self.confusion {
+            let (row, column) = (shown(actual), shown(predicted));
+            let index = match rows.iter().position(|(r, _, _)| *r == row) {
+                Some(index) => index,
+                None => {
+                    rows.push((row, 0, Vec::new()));
+                    rows.len() - 1
+                }
+            };
+            let (_, support, cells) = &mut rows[index];
+            *support += n;
+            match cells.iter_mut().find(|(c, _)| *c == column) {
+                Some((_, count)) => *count += n,
+                None => cells.push((column, n)),
+            }
+        }
+        rows.sort_by(|a, b| (a.0 == OTHER, &a.0).cmp(&(b.0 == OTHER, &b.0)));
+
+        let format = &options.number_format;
+        let mut text = format!(
+            "accuracy: {} ({}/{})\n",
+            format.percent(self.accuracy as f64),
+            format.localize(&self.correct.to_string()),
+            format.localize(&self.total.to_string())
+        );
+        for (row, support, mut cells) in rows {
+            cells.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+            let hidden = options.top_k.map_or(0, |k| cells.len().saturating_sub(k));
+            let hidden_count: usize = cells[cells.len() - hidden..].iter().map(|(_, n)| n).sum();
+            cells.truncate(cells.len() - hidden);
+            let mut line: Vec<String> = cells
+                .iter()
+                .map(|(column, n)| format!("{} {}", column, format.localize(&n.to_string())))
+                .collect();
+            if hidden > 0 {
+                line.push(format!(
+                    "+{} more {}",
+                    hidden,
+                    format.localize(&hidden_count.to_string())
+                ));
+            }
+            text += &format!(
+                "{} ({}): {}\n",
+                row,
+                format.localize(&support.to_string()),
+                line.join(", ")
+            );
+        }
+        text
+    }
+
+    /// One line per class in [`classes`](Self::classes) order:
+    /// `Apple: precision 83.3%, recall 90.9%`.
+    pub fn render_classes(&self, format: &crate::NumberFormat) -> String {
+        self.classes()
+            .iter()
+            .map(|class| {
+                format!(
+                    "{}: precision {}, recall {}\n",
+                    crate::labels::label(class, crate::labels::Target::Text),
+                    format.percent(self.precision(class) as f64),
+                    format.percent(self.recall(class) as f64)
+                )
+            })
+            .collect()
+    }
+}
+
+/// [`render`](EvaluationReport::render) with the default options followed by
+/// [`render_classes`](EvaluationReport::render_classes): the report
+/// `run_tests` prints.
+impl<C: Eq + Hash + Clone + Debug> std::fmt::Display for EvaluationReport<C> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let options = ReportOptions::default();
+        write!(
+            f,
+            "{}{}",
+            self.render(&options),
+            self.render_classes(&options.number_format)
+        )
+    }
+}
+
+/// Segments with fewer test rows than this are marked low-confidence.
+pub const MIN_SEGMENT_ROWS: usize = 30;
+
+/// An [`EvaluationReport`] for every segment of a test set and for all of it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SegmentedEvaluation<K: Eq + Hash, C: Eq + Hash> {
+    pub overall: EvaluationReport<C>,
+    pub segments: HashMap<K, EvaluationReport<C>>,
+}
+
+impl<K: Eq + Hash + Clone + Debug, C: Eq + Hash + Clone + Debug> SegmentedEvaluation<K, C> {
+    /// Segments with fewer than [`MIN_SEGMENT_ROWS`] rows, ordered by label.
+    pub fn low_confidence(&self) -> Vec<K> {
+        let mut keys: Vec<K> = self
+            .segments
+            .iter()
+            .filter(|(_, report)| report.total < MIN_SEGMENT_ROWS)
+            .map(|(key, _)| key.clone())
+            .collect();
+        keys.sort_by_key(|k| format!("{:?}", k));
+        keys
+    }
+
+    /// One line per segment, largest first, under an `overall` line:
+    /// segment, rows, accuracy, and a `low confidence` marker.
+    pub fn render(&self, format: &crate::NumberFormat) -> String {
+        let mut rows: Vec<(String, &EvaluationReport<C>)> = self
+            .segments
+            .iter()
+            .map(|(key, report)| {
+                (
+                    crate::labels::label(key, crate::labels::Target::Text),
+                    report,
+                )
+            })
+            .collect();
+        rows.sort_by(|a, b| b.1.total.cmp(&a.1.total).then_with(|| a.0.cmp(&b.0)));
+        rows.insert(0, ("overall".to_owned(), &self.overall));
+        let width = rows
+            .iter()
+            .map(|(label, _)| label.chars().count())
+            .max()
+            .unwrap_or(0);
+        let mut text = format!("{:<width$}  {:>8}  {:>8}\n", "segment", "rows", "accuracy");
+        for (i, (label, report)) in rows.iter().enumerate() {
+            let flag = if i > 0 && report.total < MIN_SEGMENT_ROWS {
+                "  low confidence"
+            } else {
+                ""
+            };
+            text += &format!(
+                "{:<width$}  {:>8}  {:>8}{}\n",
+                label,
+                format.localize(&report.total.to_string()),
+                format.percent(report.accuracy as f64),
+                flag
+            );
+        }
+        text
+    }
+}
+
+/// Evaluates `test` as a whole and separately for every value of `segment`.
+pub fn evaluate_by_segment<P, K, C>(
+    test: &[P],
+    segment: impl Fn(&P) -> K,
+    class_of: impl Fn(&P) -> C,
+    predict: impl Fn(&P) -> Option<C>,
+) -> SegmentedEvaluation<K, C>
+where
+    K: Eq + Hash + Clone + Debug,
+    C: Eq + Hash + Clone + Debug,
+{
+    let mut pairs: HashMap<K, Vec<(C, Option<C>)>> = HashMap::new();
+    for point in test {
+        pairs
+            .entry(segment(point))
+            .or_default()
+            .push((class_of(point), predict(point)));
+    }
+    let mut overall = EvaluationReport::from_predictions(std::iter::empty());
+    let segments = pairs
+        .into_iter()
+        .map(|(key, pairs)| {
+            let report = EvaluationReport::from_predictions(pairs);
+            overall.merge(&report);
+            (key, report)
+        })
+        .collect();
+    SegmentedEvaluation { overall, segments }
+}
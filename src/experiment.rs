@@ -0,0 +1,250 @@
+//! Reproducible experiments: one config evaluated on one dataset under an
+//! evaluation protocol, recorded with everything needed to repeat it.
+//!
+//! Rows are assigned to folds with the seeded [`Rng`], so identical data,
+//! config, seed and protocol give identical results. Timings are kept apart
+//! from the rest and left out of [`ExperimentResult::to_json`].
+//!
+//! `nested_cv` estimates the accuracy of tuning a config over a
+//! [`ParamGrid`] by running the tuning inside every outer fold.
+
+use std::time::Duration;
+
+use crate::fingerprint::to_hex;
+use crate::labels::json_string;
+use crate::rng::Rng;
+
+/// How the data is split into training and test rows.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Protocol {
+    /// One split holding out `test_fraction` of the rows, drawn at random.
+    Holdout { test_fraction: f64 },
+    /// `k` folds of shuffled rows, each tested once on a tree grown on the others.
+    KFold { k: usize },
+    /// Like `KFold`, with every class spread evenly over the folds.
+    StratifiedKFold { k: usize },
+}
+
+impl Protocol {
+    pub fn name(&self) -> String {
+        match self {
+            Protocol::Holdout { test_fraction } => format!("holdout({})", test_fraction),
+            Protocol::KFold { k } => format!("kfold({})", k),
+            Protocol::StratifiedKFold { k } => format!("stratified_kfold({})", k),
+        }
+    }
+}
+
+/// (training rows, test rows) of every fold, as indices into the data.
+/// `labels` holds each row's class label and is only used for
+/// `StratifiedKFold`. Test rows are listed in data order.
+pub fn split_folds(
+    labels: &[String],
+    protocol: Protocol,
+    seed: u64,
+) -> Vec<(Vec<usize>, Vec<usize>)> {
+    let n = labels.len();
+    let mut rng = Rng::new(seed);
+    let mut shuffle = |rows: &mut Vec<usize>| {
+        for i in (1..rows.len()).rev() {
+            rows.swap(i, rng.gen_index(i + 1));
+        }
+    };
+    // fold of every row
+    let mut fold = vec![0; n];
+    let k = match protocol {
+        Protocol::Holdout { test_fraction } => {
+            let mut rows: Vec<usize> = (0..n).collect();
+            shuffle(&mut rows);
+            let n_test = ((n as f64 * test_fraction).round() as usize).clamp(1, n - 1);
+            for &row in &rows[n_test..] {
+                fold[row] = 1;
+            }
+            // only the first fold is tested
+            return vec![folds_of(&fold, 0)];
+        }
+        Protocol::KFold { k } => {
+            let mut rows: Vec<usize> = (0..n).collect();
+            shuffle(&mut rows);
+            for (i, &row) in rows.iter().enumerate() {
+                fold[row] = i % k;
+            }
+            k
+        }
+        Protocol::StratifiedKFold { k } => {
+            let mut classes: Vec<&String> = labels.iter().collect();
+            classes.sort();
+            classes.dedup();
+            // continue where the last class stopped so fold sizes stay even
+            let mut next = 0;
+            for class in classes {
+                let mut rows: Vec<usize> = (0..n).filter(|&i| labels[i] == *class).collect();
+                shuffle(&mut rows);
+                for row in rows {
+                    fold[row] = next % k;
+                    next += 1;
+                }
+            }
+            k
+        }
+    };
+    (0..k).map(|f| folds_of(&fold, f)).collect()
+}
+
+fn folds_of(fold: &[usize], test: usize) -> (Vec<usize>, Vec<usize>) {
+    (0..fold.len()).partition(|&i| fold[i] != test)
+}
+
+/// One training and testing round.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FoldResult {
+    pub train_rows: usize,
+    pub test_rows: usize,
+    pub correct: usize,
+    pub accuracy: f32,
+    /// (actual, predicted, rows) ordered by label.
+    pub confusion: Vec<(String, String, usize)>,
+    pub model_fingerprint: u64,
+}
+
+/// Wall-clock time spent, excluded from the serialized result.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Timings {
+    /// Training and testing time of each fold.
+    pub folds: Vec<Duration>,
+    /// Training the final tree on all rows.
+    pub final_model: Duration,
+    pub total: Duration,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExperimentResult {
+    /// Fingerprint of the data's rows, in order.
+    pub data_hash: u64,
+    pub rows: usize,
+    pub schema_fingerprint: u64,
+    /// (option, value) of the TreeConfig, as in the model card.
+    pub config: Vec<(String, String)>,
+    pub seed: u64,
+    pub protocol: Protocol,
+    pub folds: Vec<FoldResult>,
+    pub accuracy_mean: f32,
+    /// Sample standard deviation over the folds; 0 for a holdout.
+    pub accuracy_std: f32,
+    /// Fingerprint of the tree grown on all rows.
+    pub model_fingerprint: u64,
+    pub timings: Timings,
+}
+
+impl ExperimentResult {
+    /// One line of JSON with everything but the timings, so identical
+    /// experiments serialize to identical bytes.
+    pub fn to_json(&self) -> String {
+        let config: Vec<String> = self
+            .config
+            .iter()
+            .map(|(k, v)| format!("{}:{}", json_string(k), json_string(v)))
+            .collect();
+        let folds: Vec<String> = self
+            .folds
+            .iter()
+            .map(|f| {
+                let confusion: Vec<String> = f
+                    .confusion
+                    .iter()
+                    .map(|(actual, predicted, n)| {
+                        format!("[{},{},{}]", json_string(actual), json_string(predicted), n)
+                    })
+                    .collect();
+                format!(
+                    "{{\"train_rows\":{},\"test_rows\":{},\"correct\":{},\"accuracy\":{},\"confusion\":[{}],\"model_fingerprint\":{}}}",
+                    f.train_rows,
+                    f.test_rows,
+                    f.correct,
+                    f.accuracy,
+                    confusion.join(","),
+                    json_string(&to_hex(f.model_fingerprint))
+                )
+            })
+            .collect();
+        format!(
+            "{{\"data_hash\":{},\"rows\":{},\"schema_fingerprint\":{},\"config\":{{{}}},\"seed\":{},\"protocol\":{},\"folds\":[{}],\"accuracy_mean\":{},\"accuracy_std\":{},\"model_fingerprint\":{}}}",
+            json_string(&to_hex(self.data_hash)),
+            self.rows,
+            json_string(&to_hex(self.schema_fingerprint)),
+            config.join(","),
+            self.seed,
+            json_string(&self.protocol.name()),
+            folds.join(","),
+            self.accuracy_mean,
+            self.accuracy_std,
+            json_string(&to_hex(self.model_fingerprint))
+        )
+    }
+}
+
+/// The configs `nested_cv` chooses from: every combination of the listed
+/// values, an empty list keeping the base config's value.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ParamGrid {
+    pub max_depth: Vec<Option<usize>>,
+    pub min_samples_leaf: Vec<usize>,
+}
+
+/// One outer fold of a nested cross-validation.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OuterFold {
+    /// (option, value) of the config the inner folds chose.
+    pub config: Vec<(String, String)>,
+    /// Its mean accuracy over the inner folds.
+    pub inner_accuracy: f32,
+    /// Its accuracy on the outer fold's test rows.
+    pub accuracy: f32,
+    pub test_rows: usize,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct NestedCvReport {
+    pub folds: Vec<OuterFold>,
+    /// Configs in the grid.
+    pub candidates: usize,
+    /// Mean and sample standard deviation of the outer accuracies: the
+    /// estimate for the tuned model.
+    pub accuracy_mean: f32,
+    pub accuracy_std: f32,
+    /// The best mean outer accuracy of any single config, what tuning on
+    /// the same folds the result is reported on would claim.
+    pub naive_accuracy: f32,
+    /// Distinct configs chosen across the outer folds.
+    pub distinct_configs: usize,
+    pub warnings: Vec<String>,
+}
+
+impl NestedCvReport {
+    pub fn new(folds: Vec<OuterFold>, candidates: usize, naive_accuracy: f32) -> Self {
+        let accuracies: Vec<f32> = folds.iter().map(|f| f.accuracy).collect();
+        let (accuracy_mean, accuracy_std) = crate::eval::mean_std(&accuracies);
+        let mut configs: Vec<&Vec<(String, String)>> = folds.iter().map(|f| &f.config).collect();
+        configs.sort();
+        configs.dedup();
+        let distinct_configs = configs.len();
+        let mut warnings = Vec::new();
+        // a stable choice repeats in most folds
+        if folds.len() > 2 && distinct_configs * 2 > folds.len() {
+            warnings.push(format!(
+                "{} different configs chosen in {} outer folds; the tuning is unstable",
+                distinct_configs,
+                folds.len()
+            ));
+        }
+        Self {
+            folds,
+            candidates,
+            accuracy_mean,
+            accuracy_std,
+            naive_accuracy,
+            distinct_configs,
+            warnings,
+        }
+    }
+}
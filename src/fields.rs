@@ -0,0 +1,228 @@
+//! Bounds on the layout's types, checked once per field at the top of the
+//! generated code so a type in the wrong category first gets an error naming
+//! the field, ahead of the pages of them from inside the expansion. The
+//! bounds are everything the expansion needs of the type; tests/ui has a
+//! layout per kind of bad field.
+
+use std::fmt::Debug;
+use std::hash::Hash;
+use std::marker::PhantomData;
+use std::str::FromStr;
+
+use crate::Number;
+
+/// `Name` is a marker type named after the field, so it shows up in the message.
+#[diagnostic::on_unimplemented(
+    message = "enum field `{Name}` has type `{Self}`, which isn't Eq + Hash + Clone + Debug + Send + Sync",
+    label = "can't be an enum field"
+)]
+pub trait EnumField<Name> {}
+
+impl<T: Eq + Hash + Clone + Debug + Send + Sync, Name> EnumField<Name> for T {}
+
+#[diagnostic::on_unimplemented(
+    message = "number field `{Name}` has type `{Self}`, which isn't a Number that is also Debug + Send + Sync",
+    label = "can't be a number field",
+    note = "number fields take the integer and float types and Options of them; enums and strings go in enum_fields"
+)]
+pub trait NumberField<Name> {}
+
+impl<T: Number + Debug + Send + Sync, Name> NumberField<Name> for T {}
+
+#[diagnostic::on_unimplemented(
+    message = "class type `{Self}` isn't Eq + Hash + Clone + Debug + Send + Sync",
+    label = "can't be the class"
+)]
+pub trait ClassType {}
+
+impl<T: Eq + Hash + Clone + Debug + Send + Sync> ClassType for T {}
+
+/// Reads field values back from text (CSV cells, saved trees) for any
+/// field type that can be: with `FromStr`, also after stripping the
+/// quotes `Debug` puts around chars and strings, for arrays of such types
+/// from their `Debug` form `[a, b, c]`, and for `Option`s of them from
+/// `None`, an empty cell, `Some(x)` or plain `x`. Which applies is decided
+/// per concrete type by method resolution, see `__parse_field!`. Types with
+/// none of these can't be read at all; `__readable_field!` tells them apart
+/// so the loaders fail up front naming the field, instead of reporting each
+/// of its values as invalid.
+pub struct Parser<T>(PhantomData<T>);
+
+impl<T> Parser<T> {
+    #[allow(clippy::new_without_default)]
+    pub fn new() -> Self {
+        Self(PhantomData)
+    }
+}
+
+pub trait ParseFromStr<T> {
+    fn parse_text(&self, text: &str) -> Option<T>;
+    fn readable(&self) -> bool {
+        true
+    }
+}
+
+impl<T: FromStr> ParseFromStr<T> for &&Parser<T> {
+    fn parse_text(&self, text: &str) -> Option<T> {
+        from_str(text)
+    }
+}
+
+pub trait ParseArray<T> {
+    fn parse_text(&self, text: &str) -> Option<T>;
+    fn readable(&self) -> bool {
+        true
+    }
+}
+
+impl<E: FromStr, const N: usize> ParseArray<[E; N]> for &Parser<[E; N]> {
+    fn parse_text(&self, text: &str) -> Option<[E; N]> {
+        let text = text.trim();
+        let inner = text
+            .strip_prefix('[')
+            .and_then(|t| t.strip_suffix(']'))
+            .unwrap_or(text);
+        let values: Vec<E> = if inner.trim().is_empty() {
+            Vec::new()
+        } else {
+            inner
+                .split(',')
+                .map(|v| from_str(v.trim()))
+                .collect::<Option<_>>()?
+        };
+        values.try_into().ok()
+    }
+}
+
+pub trait ParseOption<T> {
+    fn parse_text(&self, text: &str) -> Option<T>;
+    fn readable(&self) -> bool {
+        true
+    }
+}
+
+impl<E: FromStr> ParseOption<Option<E>> for &Parser<Option<E>> {
+    fn parse_text(&self, text: &str) -> Option<Option<E>> {
+        let text = text.trim();
+        if text.is_empty() || text == "None" {
+            return Some(None);
+        }
+        let inner = text
+            .strip_prefix("Some(")
+            .and_then(|t| t.strip_suffix(')'))
+            .unwrap_or(text);
+        from_str(inner.trim()).map(Some)
+    }
+}
+
+pub trait ParseNone<T> {
+    fn parse_text(&self, text: &str) -> Option<T>;
+    fn readable(&self) -> bool;
+}
+
+impl<T> ParseNone<T> for Parser<T> {
+    fn parse_text(&self, _: &str) -> Option<T> {
+        None
+    }
+    fn readable(&self) -> bool {
+        false
+    }
+}
+
+// A double-quoted text is unquoted first: it's how Debug writes a string,
+// and String's FromStr would keep the quotes.
+fn from_str<T: FromStr>(text: &str) -> Option<T> {
+    if text.starts_with('"') {
+        if let Some(value) = unquote(text).and_then(|inner| inner.parse().ok()) {
+            return Some(value);
+        }
+    }
+    text.parse().ok().or_else(|| unquote(text)?.parse().ok())
+}
+
+/// The text inside the quotes of a `Debug`-printed char or string, with its
+/// escapes resolved.
+fn unquote(text: &str) -> Option<String> {
+    let quote = text.chars().next().filter(|c| *c == '\'' || *c == '"')?;
+    let inner = text[1..].strip_suffix(quote)?;
+    let mut out = String::with_capacity(inner.len());
+    let mut chars = inner.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        out.push(match chars.next()? {
+            'n' => '\n',
+            't' => '\t',
+            'r' => '\r',
+            '0' => '\0',
+            'u' => {
+                let code: String = chars.by_ref().skip(1).take_while(|c| *c != '}').collect();
+                char::from_u32(u32::from_str_radix(&code, 16).ok()?)?
+            }
+            c => c,
+        });
+    }
+    Some(out)
+}
+
+/// The value a missing CSV cell stands for: `None` for `Option` fields,
+/// which hold it, and no value for every other type.
+pub trait MissingOption<T> {
+    fn missing_value(&self) -> Option<T>;
+}
+
+impl<E> MissingOption<Option<E>> for &Parser<Option<E>> {
+    fn missing_value(&self) -> Option<Option<E>> {
+        Some(None)
+    }
+}
+
+pub trait MissingNone<T> {
+    fn missing_value(&self) -> Option<T>;
+}
+
+impl<T> MissingNone<T> for Parser<T> {
+    fn missing_value(&self) -> Option<T> {
+        None
+    }
+}
+
+/// A `$ty` from `text` through `fields::Parser`, None if it doesn't parse.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __parse_field {
+    ($ty:ty, $text:expr) => {{
+        #[allow(unused_imports)]
+        use $crate::fields::{
+            ParseArray as _, ParseFromStr as _, ParseNone as _, ParseOption as _,
+        };
+        (&&&$crate::fields::Parser::<$ty>::new()).parse_text($text)
+    }};
+}
+
+/// Whether `__parse_field!` can read a `$ty` at all: false for the types
+/// without `FromStr` that only `ParseNone` takes.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __readable_field {
+    ($ty:ty) => {{
+        #[allow(unused_imports)]
+        use $crate::fields::{
+            ParseArray as _, ParseFromStr as _, ParseNone as _, ParseOption as _,
+        };
+        (&&&$crate::fields::Parser::<$ty>::new()).readable()
+    }};
+}
+
+/// What a missing cell of a `$ty` field loads as, see `fields::MissingOption`.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __missing_field {
+    ($ty:ty) => {{
+        #[allow(unused_imports)]
+        use $crate::fields::{MissingNone as _, MissingOption as _};
+        (&&$crate::fields::Parser::<$ty>::new()).missing_value()
+    }};
+}
@@ -0,0 +1,20 @@
+//! Stable 64-bit fingerprints. `std`'s default hasher is randomly seeded per
+//! process, so anything that is persisted or compared across runs uses this.
+
+const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+const PRIME: u64 = 0x0100_0000_01b3;
+
+/// FNV-1a hash of `bytes`.
+pub fn fnv1a(bytes: &[u8]) -> u64 {
+    let mut hash = OFFSET_BASIS;
+    for &b in bytes {
+        hash ^= b as u64;
+        hash = hash.wrapping_mul(PRIME);
+    }
+    hash
+}
+
+/// Fixed-width lowercase hex form used wherever a fingerprint is written out.
+pub fn to_hex(fingerprint: u64) -> String {
+    format!("{:016x}", fingerprint)
+}
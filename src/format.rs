@@ -0,0 +1,74 @@
+//! Number formatting for human-facing output (tree text, test reports).
+
+/// How numbers are written in human-facing output. The default is the
+/// historical en-US style: `.` decimal point, no grouping, whole percents.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct NumberFormat {
+    /// Decimal places of percentages.
+    pub decimals: usize,
+    /// Inserted between groups of three integer digits, e.g. `Some(',')` or `Some('.')`.
+    pub thousands_separator: Option<char>,
+    /// Write `0,5` instead of `0.5`.
+    pub decimal_comma: bool,
+}
+
+impl NumberFormat {
+    /// German/French style: `1.234,5`.
+    pub fn european() -> Self {
+        Self {
+            decimals: 1,
+            thousands_separator: Some('.'),
+            decimal_comma: true,
+        }
+    }
+
+    /// `fraction` as a percentage with `decimals` places, e.g. `66%` for 2/3.
+    /// Truncated rather than rounded, so the shares of a leaf never add up to
+    /// more than 100%.
+    pub fn percent(&self, fraction: f64) -> String {
+        let scale = 10_f64.powi(self.decimals as i32);
+        // round away float noise first: 0.29 * 100.0 is 28.999999999999996
+        let value = ((fraction * 100.0 * scale * 1e6).round() / 1e6).trunc() / scale;
+        format!(
+            "{}%",
+            self.localize(&format!("{:.*}", self.decimals, value))
+        )
+    }
+
+    /// `value` with `decimals` places.
+    pub fn number(&self, value: f64) -> String {
+        self.localize(&format!("{:.*}", self.decimals, value))
+    }
+
+    /// Rewrites a number already formatted with Rust's `Display`/`Debug`
+    /// (`-1234.5`, `7`) with this format's separators, keeping its digits.
+    /// Anything that is not such a number is returned unchanged.
+    pub fn localize(&self, text: &str) -> String {
+        let (sign, digits) = match text.strip_prefix('-') {
+            Some(rest) => ("-", rest),
+            None => ("", text),
+        };
+        let (integer, fraction) = match digits.split_once('.') {
+            Some((i, f)) => (i, Some(f)),
+            None => (digits, None),
+        };
+        let is_digits = |s: &str| !s.is_empty() && s.bytes().all(|b| b.is_ascii_digit());
+        if !is_digits(integer) || fraction.is_some_and(|f| !is_digits(f)) {
+            return text.to_owned();
+        }
+        let mut out = sign.to_owned();
+        for (i, c) in integer.chars().enumerate() {
+            if i > 0 && (integer.len() - i) % 3 == 0 {
+                if let Some(separator) = self.thousands_separator {
+                    out.push(separator);
+                }
+            }
+            out.push(c);
+        }
+        if let Some(fraction) = fraction {
+            out.push(if self.decimal_comma { ',' } else { '.' });
+            out.push_str(fraction);
+        }
+        out
+    }
+}
@@ -0,0 +1,45 @@
+//! Subtrees kept as they are when a tree is refit.
+
+/// A set of frozen subtrees, each given by its path from the root
+/// (`true` = true branch). Everything below a frozen path is frozen too.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Frozen {
+    paths: Vec<Vec<bool>>,
+    /// Refit recounts the classes in frozen leaves from the new data;
+    /// when false frozen subtrees are copied unchanged.
+    pub update_counts: bool,
+}
+
+impl Frozen {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn freeze(&mut self, path: &[bool]) {
+        if !self.is_frozen(path) {
+            self.paths.retain(|p| !p.starts_with(path));
+            self.paths.push(path.to_vec());
+        }
+    }
+
+    pub fn paths(&self) -> &[Vec<bool>] {
+        &self.paths
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.paths.is_empty()
+    }
+
+    /// `path` is a frozen subtree or lies inside one.
+    pub fn is_frozen(&self, path: &[bool]) -> bool {
+        self.paths.iter().any(|p| path.starts_with(p))
+    }
+
+    /// Some frozen subtree lies strictly below `path`, so the node at `path`
+    /// must keep its question for the frozen part to stay reachable.
+    pub fn contains_below(&self, path: &[bool]) -> bool {
+        self.paths
+            .iter()
+            .any(|p| p.len() > path.len() && p.starts_with(path))
+    }
+}
@@ -0,0 +1,155 @@
+//! Decoding fuzzer input into field values, for the generated
+//! `fuzz_roundtrip`. Any byte string decodes: reads past the end give zeros,
+//! so a fuzzer's shrunk inputs stay meaningful and the same bytes always give
+//! the same dataset.
+//!
+//! `fuzz_roundtrip` is callable once the field types and the class implement
+//! [`Arbitrary`]; a layout over types that don't still compiles with the
+//! feature on, it just can't call it. [`Arbitrary`] is implemented here for
+//! the primitive types, `bool`, `char`, `String`, and arrays and `Option`s of
+//! those. A user enum usually picks a variant by a byte:
+//!
+//! ```ignore
+//! impl Arbitrary for Fruit {
+//!     fn arbitrary(input: &mut Unstructured) -> Self {
+//!         [Fruit::Apple, Fruit::Lime, Fruit::Pear][input.choose(3)]
+//!     }
+//! }
+//! ```
+
+/// Most rows `fuzz_roundtrip` decodes: enough that `classify_batch` also
+/// routes a node at a time, few enough that each run stays fast.
+pub const MAX_ROWS: usize = 128;
+
+/// Bytes being decoded, consumed from the front.
+#[derive(Debug, Clone)]
+pub struct Unstructured<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Unstructured<'a> {
+    pub fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes }
+    }
+
+    /// The next byte, 0 once the input is used up.
+    pub fn byte(&mut self) -> u8 {
+        match self.bytes.split_first() {
+            Some((&first, rest)) => {
+                self.bytes = rest;
+                first
+            }
+            None => 0,
+        }
+    }
+
+    /// The next `N` bytes, zero-padded.
+    pub fn bytes<const N: usize>(&mut self) -> [u8; N] {
+        let mut out = [0; N];
+        for b in out.iter_mut() {
+            *b = self.byte();
+        }
+        out
+    }
+
+    /// An index below `n`, from one byte; 0 when `n` is 0.
+    pub fn choose(&mut self, n: usize) -> usize {
+        if n == 0 {
+            0
+        } else {
+            self.byte() as usize % n
+        }
+    }
+
+    /// A value in `low..=high`, from one byte.
+    pub fn in_range(&mut self, low: usize, high: usize) -> usize {
+        low + self.choose(high.saturating_sub(low) + 1)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bytes.is_empty()
+    }
+}
+
+/// A value decoded from fuzzer bytes.
+#[diagnostic::on_unimplemented(
+    message = "`{Self}` doesn't implement decision_leaf::fuzz::Arbitrary",
+    note = "fuzz_roundtrip decodes the layout's field types and class from fuzzer input through this trait"
+)]
+pub trait Arbitrary: Sized {
+    fn arbitrary(input: &mut Unstructured) -> Self;
+}
+
+// A tag byte picks, three times in four, a small value, so rows share values
+// and trees split, and otherwise one spanning the whole type.
+macro_rules! impl_integer {
+    ($($t:ty),*) => {
+        $(impl Arbitrary for $t {
+            fn arbitrary(input: &mut Unstructured) -> Self {
+                if input.byte() & 3 != 0 {
+                    (input.byte() % 8) as $t
+                } else {
+                    <$t>::from_le_bytes(input.bytes())
+                }
+            }
+        })*
+    };
+}
+
+impl_integer!(u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize);
+
+// The whole-type values are raw bit patterns: NaN, infinities, -0.0 and
+// subnormals included.
+macro_rules! impl_float {
+    ($($t:ty),*) => {
+        $(impl Arbitrary for $t {
+            fn arbitrary(input: &mut Unstructured) -> Self {
+                if input.byte() & 3 != 0 {
+                    (input.byte() % 8) as $t / 2.0
+                } else {
+                    <$t>::from_le_bytes(input.bytes())
+                }
+            }
+        })*
+    };
+}
+
+impl_float!(f32, f64);
+
+impl Arbitrary for bool {
+    fn arbitrary(input: &mut Unstructured) -> Self {
+        input.byte() & 1 == 1
+    }
+}
+
+impl Arbitrary for char {
+    fn arbitrary(input: &mut Unstructured) -> Self {
+        if input.byte() & 3 != 0 {
+            (b'a' + input.byte() % 8) as char
+        } else {
+            char::from_u32(u32::from_le_bytes(input.bytes())).unwrap_or(char::REPLACEMENT_CHARACTER)
+        }
+    }
+}
+
+// up to three chars, so rows share values
+impl Arbitrary for String {
+    fn arbitrary(input: &mut Unstructured) -> Self {
+        (0..input.choose(4))
+            .map(|_| char::arbitrary(input))
+            .collect()
+    }
+}
+
+// None one time in four
+impl<T: Arbitrary> Arbitrary for Option<T> {
+    fn arbitrary(input: &mut Unstructured) -> Self {
+        (input.byte() & 3 != 0).then(|| T::arbitrary(input))
+    }
+}
+
+impl<T: Arbitrary, const N: usize> Arbitrary for [T; N] {
+    fn arbitrary(input: &mut Unstructured) -> Self {
+        std::array::from_fn(|_| T::arbitrary(input))
+    }
+}
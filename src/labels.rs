@@ -0,0 +1,135 @@
+//! Label generation for every exporter. Field names, enum variants and class
+//! labels come from `Debug` and can contain anything (quotes, generics, tuple
+//! payloads), so each output format escapes them here rather than inline.
+
+use std::fmt::Debug;
+
+/// Output format a label is written into.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Target {
+    /// Plain terminal/text output, no escaping.
+    Text,
+    /// Inside a double-quoted Graphviz DOT string.
+    Dot,
+    /// XML text or attribute content (SVG, PMML).
+    Xml,
+    /// HTML text or attribute content.
+    Html,
+    /// Inside a double-quoted mermaid node label, e.g. `A["..."]`.
+    Mermaid,
+    /// Inside a double-quoted JSON string.
+    Json,
+    /// A Markdown table cell or list item.
+    Markdown,
+}
+
+/// The `Debug` representation of `value`, escaped for `target`.
+pub fn label<T: Debug + ?Sized>(value: &T, target: Target) -> String {
+    escape(&format!("{:?}", value), target)
+}
+
+pub fn escape(text: &str, target: Target) -> String {
+    match target {
+        Target::Text => text.to_owned(),
+        Target::Dot => escape_dot(text),
+        Target::Xml => escape_xml(text),
+        Target::Html => escape_html(text),
+        Target::Mermaid => escape_mermaid(text),
+        Target::Json => escape_json(text),
+        Target::Markdown => escape_markdown(text),
+    }
+}
+
+// a JSON string literal of `text`, quotes included
+#[cfg(any(feature = "export", feature = "metrics"))]
+pub(crate) fn json_string(text: &str) -> String {
+    format!("\"{}\"", escape(text, Target::Json))
+}
+
+// JSON has no NaN or infinity, so those are written as null
+#[cfg(all(feature = "export", feature = "metrics"))]
+pub(crate) fn json_number<T: Into<f64> + std::fmt::Display + Copy>(value: T) -> String {
+    if value.into().is_finite() {
+        value.to_string()
+    } else {
+        "null".to_owned()
+    }
+}
+
+fn escape_dot(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+fn escape_xml(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&apos;"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+fn escape_html(text: &str) -> String {
+    // &apos; is not an HTML4 entity, so quotes use the numeric form
+    escape_xml(text).replace("&apos;", "&#39;")
+}
+
+fn escape_mermaid(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '"' => out.push_str("#quot;"),
+            '#' => out.push_str("#35;"),
+            '<' => out.push_str("#lt;"),
+            '>' => out.push_str("#gt;"),
+            '\n' => out.push_str("<br/>"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+fn escape_json(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+fn escape_markdown(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '|' => out.push_str("\\|"),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("<br>"),
+            '\r' => {}
+            c => out.push(c),
+        }
+    }
+    out
+}
@@ -0,0 +1,55 @@
+//! What the training rows reaching each leaf looked like, class by class,
+//! for debugging a leaf that misbehaves.
+
+use crate::drift::FieldSummary;
+
+/// The training rows of one class in a leaf.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClassProfile {
+    pub class: String,
+    pub rows: usize,
+    /// Summary of each field over these rows, in layout order.
+    pub fields: Vec<(String, FieldSummary)>,
+}
+
+/// The training rows reaching one leaf, split by class.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LeafProfile {
+    /// Preorder index of the leaf, as in `Node::paths`.
+    pub leaf: usize,
+    /// Ordered by class label.
+    pub classes: Vec<ClassProfile>,
+}
+
+impl LeafProfile {
+    pub fn rows(&self) -> usize {
+        self.classes.iter().map(|c| c.rows).sum()
+    }
+
+    /// For every class, one line per field: mean, min and max of a number
+    /// field, or the count of each value of an enum field.
+    pub fn render(&self) -> String {
+        let mut out = format!("leaf {}: {} rows\n", self.leaf, self.rows());
+        for class in &self.classes {
+            out += &format!("  {} ({} rows)\n", class.class, class.rows);
+            let width = class.fields.iter().map(|(f, _)| f.len()).max().unwrap_or(0);
+            for (field, summary) in &class.fields {
+                let text = match summary {
+                    FieldSummary::Categorical(counts) => counts
+                        .iter()
+                        .map(|(value, n)| format!("{} {}", value, n))
+                        .collect::<Vec<_>>()
+                        .join(", "),
+                    FieldSummary::Numeric(_) => match summary.mean_min_max() {
+                        Some((mean, min, max)) => {
+                            format!("mean {:.3}, min {}, max {}", mean, min, max)
+                        }
+                        None => "no values".to_owned(),
+                    },
+                };
+                out += &format!("    {:<width$}  {}\n", field, text, width = width);
+            }
+        }
+        out
+    }
+}
@@ -1,25 +1,264 @@
+#[cfg(feature = "export")]
+pub mod audit;
+#[cfg(feature = "serving")]
+pub mod cache;
+#[cfg(all(feature = "export", feature = "metrics"))]
+pub mod card;
+#[cfg(feature = "metrics")]
+pub mod conformal;
+pub mod counts;
+#[cfg(feature = "metrics")]
+pub mod drift;
+#[cfg(feature = "ensemble")]
+pub mod ensemble;
+pub mod error;
+#[cfg(feature = "metrics")]
+pub mod eval;
+#[cfg(feature = "metrics")]
+pub mod experiment;
+#[doc(hidden)]
+pub mod fields;
+pub mod fingerprint;
+pub mod format;
+pub mod freeze;
+#[cfg(feature = "fuzz")]
+pub mod fuzz;
+pub mod labels;
+#[cfg(feature = "metrics")]
+pub mod leaf;
+#[cfg(feature = "csv")]
+pub mod load;
+#[cfg(feature = "metrics")]
+pub mod noise;
+pub mod number;
+#[cfg(feature = "persist")]
+pub mod persist;
+pub mod pipeline;
+#[cfg(feature = "export")]
+pub mod regions;
+pub mod render;
+pub mod rng;
+#[cfg(feature = "serving")]
+pub mod serving;
+#[cfg(feature = "metrics")]
+pub mod snap;
+pub mod split;
+#[cfg(feature = "ensemble")]
+pub mod stability;
+pub mod stats;
+#[cfg(test)]
+mod tests;
+pub mod timing;
+pub mod trace;
+
+pub use counts::ClassCounts;
+pub use error::DecisionLeafError;
+pub use format::NumberFormat;
+pub use number::Number;
+#[cfg(feature = "export")]
+pub use regions::Grid;
+
 /// Creates the functions needed to create and test a decision tree based on the layout of your data.
 ///
 ///Params:
 /// (
-/// enum_fields = {fieldname: EnumType, fieldname2: EnumType2 ...}, // enums that are comparable using ==
-/// number_fields = {fieldname: NumberType, fieldname2: NumberType2 ...}, // fields that are comparable using >=
+/// enum_fields = {fieldname: EnumType, fieldname2: EnumType2 ...}, // Eq + Hash + Clone + Debug types (enums, bool, char, [u8; 3], ...) compared using ==
+/// number_fields = {fieldname: NumberType, fieldname2: NumberType2 ...}, // integer or float fields compared using >=; a NaN answers false, as does None for an Option<NumberType> field
+/// derived_fields = {fieldname: NumberType = |p| ..., ...}, // optional, number fields computed from the others
 /// class // The enum that we're trying to classify
 /// )
 ///
+/// All field types and the class must be Send + Sync, which makes the generated types Send + Sync too.
+///
+/// Enum fields are printed and exported with Debug. Loading a CSV or a saved tree reads them and the
+/// class with FromStr, also accepting the quoted Debug form of chars and strings, or as `[a, b, c]`
+/// for arrays of FromStr types. CSV columns of other types need a LoaderConfig::custom adapter or
+/// map_enum, and a saved tree can't be loaded at all; both fail up front naming the field.
+///
+/// Fields with gaps take an Option type, e.g. `income: Option<u32>` or `smoker: Option<bool>`, and
+/// train without imputing. A missing number is never a threshold and goes down the false branch of
+/// every question about its field, in training and classification alike, so the split search
+/// accounts for where those rows land; a missing enum value is a value of its own. In CSVs, `None`,
+/// an empty cell or a LoaderConfig::missing_markers cell loads as None.
+///
+/// The default features `ensemble`, `export` and `metrics` generate the items marked with them
+/// below; a crate that only trains and classifies can turn them off to expand less code.
+///
+/// Exports (printed and saved trees, audit records, model cards) list fields in declaration order
+/// (Field::ALL) and class distributions by descending count, then label (ClassCounts::ordered), so
+/// the same tree always exports to the same bytes.
+///
 ///Generates:
 /// struct DataPoint // structure for your data
 ///
+/// impl DataPoint::new // construct from the enum, number and class values, computing derived fields
+///
+/// impl DataPoint::compute_derived // recompute derived fields after editing a point
+///
 /// enum Node // tree node
 ///
+/// enum Question // the question of a decision node, with Question::evaluate (and evaluate_partial) for any HasFields point
+///
+/// trait HasFields // field access shared by DataPoint and PartialPoint, and implementable for other structs (impl_has_fields!)
+///
+/// mod field_types // a type alias per field, named after it
+///
 /// fn build_tree // build tree from training data
 ///
-/// fn run_tests // testing the tree
+/// fn build_tree_with // build tree with a TreeConfig (max_depth, min_samples_leaf, min_samples_split, min_gain, min_side_fraction, fields, forced_fields, keep_runner_up, max_candidates, midpoint_thresholds, max_features, feature_seed, criterion, dedupe)
+///
+/// fn build_tree_weighted // build_tree_with on rows weighted by whole-number counts, e.g. dedupe_weighted's; the tree the expanded rows would grow
+///
+/// fn build_trained // build_tree_with, returning a TrainedTree that keeps the TrainingProfile (class counts, observed values) of the data
+///
+/// fn build_forest // soft-voting Forest of trees grown on bootstrap resamples; with TreeConfig::max_features, a random forest; only with the `ensemble` feature
+///
+/// impl Forest::classify // each class's count of tree votes for a point, the majority vote being its argmax; only with the `ensemble` feature
+///
+/// fn build_forest_with // build_forest with an ensemble::Resample: a fraction of the rows per tree, or Resample::None growing every tree on all rows; only with the `ensemble` feature
+///
+/// fn build_forest_oob // the same, with the out-of-bag accuracy after each tree and optional early stopping; only with the `ensemble` feature
+///
+/// fn build_feature_bagged // soft-voting Forest of trees that each split on a random subset of the fields; only with the `ensemble` feature
+///
+/// fn distill // one tree trained on synthetic rows labeled by a Forest, with its fidelity to the forest; only with the `ensemble` feature
+///
+/// fn stability_analysis // how consistently trees grown on bootstrap resamples choose the same splits; only with the `ensemble` feature
+///
+/// fn build_tree_forced // build_tree_with, reporting where TreeConfig::forced_fields chose the split or fell through (split::ForcingReport)
+///
+/// fn build_tree_timed // build_tree_with, with the time spent per depth and per field (timing::BuildTimings)
+///
+/// fn build_tree_traced // build_tree_with, sending a trace::BuildTraceEvent per node to a sink; only with the `trace` feature
+///
+/// fn refit // regrow a tree on new data, keeping the freeze::Frozen subtrees
+///
+/// fn suggest_config // heuristic TreeConfig for a dataset's size and class balance
+///
+/// fn split_stats // counters from the root split search (candidates, cache hits, skips)
+///
+/// fn robustness_report // nodes whose question barely beat the runner-up kept with TreeConfig::keep_runner_up
+///
+/// fn split_pvalues // permutation-test p-value of every split, by preorder node id; only with the `metrics` feature
+///
+/// fn dedupe_weighted // collapse identical rows into unique rows plus multiplicities
+///
+/// fn memorization_report // flag high-cardinality fields whose splits only memorize rows; only with the `metrics` feature
+///
+/// fn run_experiment // holdout or (stratified) k-fold evaluation of a config, recorded with the data hash and seed (experiment::ExperimentResult); only with the `metrics` feature
 ///
-/// fn classify // classify a new datapoint
+/// fn inject_label_noise // a copy of the data with labels flipped to random other classes at a rate; only with the `metrics` feature
+///
+/// fn noise_sensitivity_curve // k-fold accuracy with training labels flipped at each of several rates (noise::NoisePoint); only with the `metrics` feature
+///
+/// fn estimate_label_noise // rows whose out-of-fold prediction confidently disagrees with their label (noise::NoiseEstimate); only with the `metrics` feature
+///
+/// fn nested_cv // nested cross-validation of tuning a TreeConfig over an experiment::ParamGrid; only with the `metrics` feature
+///
+/// fn run_tests // print evaluate's report for test data (accuracy, confusion, per-class precision and recall); only with the `metrics` feature
+///
+/// fn run_tests_with // same, with percentages written in a format::NumberFormat; only with the `metrics` feature
+///
+/// fn evaluate // accuracy and confusion matrix on test data (eval::EvaluationReport, with per-class precision and recall, displaying as run_tests prints it); only with the `metrics` feature
+///
+/// fn balanced_evaluation // the same, averaged over class-balanced subsamples of the test data; only with the `metrics` feature
+///
+/// fn evaluate_by_segment // evaluate per value of a key computed from each row, plus overall; only with the `metrics` feature
+///
+/// fn training_summary // per-field value counts and number distributions of the training data; only with the `metrics` feature
+///
+/// fn field_drift // PSI/KS drift of each field in a live batch against a training_summary; only with the `metrics` feature
+///
+/// fn load_csv // read DataPoints from a CSV file, columns matched to fields by header name; only with the `csv` feature
+///
+/// fn load_csv_with // read DataPoints from CSV, with per-column LoaderConfig adapters; only with the `csv` feature
+///
+/// fn write_csv // write DataPoints as CSV that load_csv_with reads back; only with the `csv` feature
+///
+/// struct Imputer // per-field fill-in values (mode or lower median) for PartialPoint rows and LoaderConfig::impute; save/load with the `persist` feature
+///
+/// struct ConformalClassifier // split conformal prediction sets over a tree, calibrated on held-out rows (conformal::Calibration), with their coverage; only with the `metrics` feature
+///
+/// struct Pipeline // preprocessing steps (pipeline::StepSpec) fitted in front of a tree, replayed by Pipeline::predict; save/load with the `persist` feature
+///
+/// fn feature_importance // each field's share of the tree's impurity decrease
+///
+/// fn model_card // card::ModelCard (Markdown/JSON) from the tree, config, training summary and evaluation; only with the `export` and `metrics` features
+///
+/// fn add_memorization_flags // record memorization_report findings as model card limitations; only with the `export` and `metrics` features
+///
+/// fn audit_record // loggable record of one prediction (audit::AuditRecord); only with the `export` feature
+///
+/// fn schema_fingerprint // stable hash of the layout
+///
+/// fn classify // leaf counts for a new datapoint, or any HasFields point
+///
+/// fn predict // majority class for a new datapoint, or any HasFields point
+///
+/// fn classify_batch // leaf counts for many datapoints at once, routing large batches a node at a time
+///
+/// fn classify_with_uncertainty // credible intervals for the class probabilities of a new datapoint
+///
+/// fn classify_with_uncertainty_mc // same, estimated by seeded Monte Carlo sampling
+///
+/// fn decision_regions // predicted class over a grid of two number fields, other fields held fixed; only with the `export` feature
+///
+/// fn decision_regions_svg // render such a grid as an SVG heatmap with the training points on top; only with the `export` feature
 ///
 /// impl Node::print_tree // show the tree
 ///
+/// impl Node::write_tree // show the tree with render::RenderOptions (box drawing, color, number format)
+///
+/// impl Node::to_dot, Node::write_dot // Graphviz DOT of the tree, leaves labeled with their class counts; only with the `export` feature
+///
+/// impl Node::depth // longest root-to-leaf path
+///
+/// impl Node::fingerprint // stable hash of the tree
+///
+/// impl Node::paths // iterate over root-to-leaf paths with their conditions and leaf statistics
+///
+/// impl Node::subtree // the node at a path of true/false answers
+///
+/// impl Node::classes // every class seen in the tree's leaves
+///
+/// impl Node::graft // replace the subtree at a path with another, outside freeze::Frozen subtrees
+///
+/// impl Node::leaf_profiles // per-class field summaries of the training rows reaching each leaf (leaf::LeafProfile); only with the `metrics` feature
+///
+/// impl Node::leaf_woe // weight of evidence of each leaf for a binary class, with smoothing for empty cells; only with the `ensemble` feature
+///
+/// impl Node::scorecard, Forest::scorecard // Scorecard giving points the summed weight of evidence of their leaves; only with the `ensemble` feature
+///
+/// impl Node::with_alternative // what-if: swap in a node's runner-up split and regrow below it
+///
+/// impl Node::snap_thresholds // round thresholds to a SnapPolicy's steps where training accuracy allows (snap::SnapReport); only with the `metrics` feature
+///
+/// impl Node::what_if_threshold // rows switching branches, leaf shifts and accuracy change if a threshold moved (snap::WhatIfReport); only with the `metrics` feature
+///
+/// impl Node::apply_what_if // the tree with a threshold moved and the switching rows' counts moved between leaves; only with the `metrics` feature
+///
+/// impl Node::canonicalize_thresholds // merge thresholds of a field within a tolerance where training routing is unchanged (snap::CanonicalReport); only with the `metrics` feature
+///
+/// impl Node::distinct_questions // every question the tree asks, once
+///
+/// impl Node::shrink_to_fit // release the spare capacity of the leaves' class counts
+///
+/// impl Node::heap_size_estimate // estimated bytes the tree occupies (nodes, leaf counts, runner-ups)
+///
+/// impl Node::save // write the tree as text (persist format); only with the `persist` feature
+///
+/// impl Node::load // read a saved tree, mapping renamed fields and enum values with persist::LoadOptions; only with the `persist` feature
+///
+/// fn save_tree, load_tree // Node::save to and Node::load from a file path, in the persist text format (there is no serde support; loading needs FromStr on the field types and class); only with the `persist` feature
+///
+/// impl TrainedTree::save, TrainedTree::load // the same with the training profile; only with the `persist` feature
+///
+/// impl serving::Model<DataPoint> for Node // only with the `serving` feature
+///
+/// struct FeatureKey // a DataPoint's fields without the class, which cache::CachedClassifier keys on; only with the `serving` feature
+///
+/// fn fuzz_roundtrip // decode arbitrary bytes into a small dataset and config, train, and panic if classification or the persist round trip breaks an invariant; only with the `fuzz` feature, and callable when the field types and the class implement fuzz::Arbitrary
+///
 ///Example:
 /// enum Color {
 ///  Red
@@ -45,40 +284,171 @@
 #[macro_export]
 macro_rules! classification_data_layout {
     (enum_fields = { $($field_name:ident : $field_type:ty),*}, number_fields = { $($number_field_name:ident : $number_field_type:ty),* } ,$class:ty) => {
+        $crate::classification_data_layout!(
+            @layout
+            enum_fields = { $($field_name : $field_type),* },
+            number_fields = { $($number_field_name : $number_field_type),* },
+            input_number_fields = { $($number_field_name : $number_field_type),* },
+            derived_fields = {},
+            $class
+        );
+    };
+    (enum_fields = { $($field_name:ident : $field_type:ty),*}, number_fields = { $($number_field_name:ident : $number_field_type:ty),* }, derived_fields = { $($derived_name:ident : $derived_type:ty = $derived_fn:expr),* $(,)? }, $class:ty) => {
+        // derived fields are split on like any other number field
+        $crate::classification_data_layout!(
+            @layout
+            enum_fields = { $($field_name : $field_type),* },
+            number_fields = { $($number_field_name : $number_field_type,)* $($derived_name : $derived_type,)* },
+            input_number_fields = { $($number_field_name : $number_field_type),* },
+            derived_fields = { $($derived_name : $derived_type = $derived_fn),* },
+            $class
+        );
+    };
+    (@layout enum_fields = { $($field_name:ident : $field_type:ty),* }, number_fields = { $($number_field_name:ident : $number_field_type:ty),* $(,)? }, input_number_fields = { $($input_name:ident : $input_type:ty),* }, derived_fields = { $($derived_name:ident : $derived_type:ty = $derived_fn:expr),* }, $class:ty) => {
 
         use std::collections::{HashMap, HashSet};
 
-        #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+        // Checks the field types' bounds first, so a type in the wrong category is reported
+        // by field name (see fields.rs). A where clause is checked with the item signatures,
+        // ahead of the derives and function bodies below that would fail on the same type.
+        #[allow(dead_code, non_camel_case_types)]
+        const _: () = {
+            mod names {
+                $(pub struct $field_name;)*
+                $(pub struct $number_field_name;)*
+            }
+            struct Check where
+                $($field_type: $crate::fields::EnumField<names::$field_name>,)*
+                $($number_field_type: $crate::fields::NumberField<names::$number_field_name>,)*
+                $class: $crate::fields::ClassType;
+        };
+
+        #[derive(Debug, Clone)]
         pub struct DataPoint {
             $($field_name : $field_type ,)*
             $($number_field_name : $number_field_type ,)*
             class: $class,
         }
 
-        #[derive(Debug)]
-        enum Field {
+        // number fields compare with Number::same, so float fields keep DataPoint Eq + Hash
+        impl PartialEq for DataPoint {
+            fn eq(&self, other: &Self) -> bool {
+                $(self.$field_name == other.$field_name &&)*
+                $($crate::Number::same(self.$number_field_name, other.$number_field_name) &&)*
+                self.class == other.class
+            }
+        }
+
+        impl Eq for DataPoint {}
+
+        impl std::hash::Hash for DataPoint {
+            fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+                $(self.$field_name.hash(state);)*
+                $($crate::Number::hash_value(self.$number_field_name, state);)*
+                self.class.hash(state);
+            }
+        }
+
+        impl DataPoint {
+            #[allow(clippy::too_many_arguments)]
+            pub fn new($($field_name: $field_type,)* $($input_name: $input_type,)* class: $class) -> Self {
+                let mut point = Self {
+                    $($field_name,)*
+                    $($input_name,)*
+                    $($derived_name: Default::default(),)*
+                    class,
+                };
+                point.compute_derived();
+                point
+            }
+            pub fn compute_derived(&mut self) {
+                $(self.$derived_name = {
+                    let derive: fn(&DataPoint) -> $derived_type = $derived_fn;
+                    derive(self)
+                };)*
+            }
+        }
+
+        #[allow(non_camel_case_types)]
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+        pub enum Field {
             $($field_name,)*
             $($number_field_name,)*
         }
 
-        #[derive(Debug, Clone, PartialEq, Eq, Hash)]
-        enum Question {
-            $($field_name($field_type),)*
+        impl Field {
+            // every field, in declaration order
+            pub const ALL: &'static [Field] = &[$(Field::$field_name,)* $(Field::$number_field_name),*];
+            pub fn name(&self) -> &'static str {
+                match self {
+                    $(Field::$field_name => stringify!($field_name),)*
+                    $(Field::$number_field_name => stringify!($number_field_name),)*
+                }
+            }
+        }
+
+        #[allow(non_camel_case_types)]
+        #[derive(Debug, Clone)]
+        pub enum Question {
+            // the value is one of these, sorted by label; usually a single value
+            $($field_name(Vec<$field_type>),)*
             $($number_field_name($number_field_type),)*
         }
 
+        impl PartialEq for Question {
+            fn eq(&self, other: &Self) -> bool {
+                match (self, other) {
+                    $((Question::$field_name(a), Question::$field_name(b)) => a == b,)*
+                    $((Question::$number_field_name(a), Question::$number_field_name(b)) => $crate::Number::same(*a, *b),)*
+                    #[allow(unreachable_patterns)]
+                    _ => false,
+                }
+            }
+        }
+
+        impl Eq for Question {}
+
+        impl std::hash::Hash for Question {
+            fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+                self.field().hash(state);
+                match self {
+                    $(Question::$field_name(x) => x.hash(state),)*
+                    $(Question::$number_field_name(x) => $crate::Number::hash_value(*x, state),)*
+                }
+            }
+        }
+
         #[derive(Clone)]
         pub enum Node {
-            Leaf(HashMap<$class, i32>),
+            Leaf($crate::ClassCounts<$class>),
             Decision {
                 question: Question,
                 true_branch: Box<Node>,
                 false_branch: Box<Node>,
+                // the second-best split, kept when TreeConfig::keep_runner_up is set
+                runner_up: Option<Box<RunnerUp>>,
             },
         }
 
+        #[derive(Clone)]
+        pub struct RunnerUp {
+            question: Question,
+            pub gain: f64,
+            // gain of the question the node asks
+            pub best_gain: f64,
+        }
+
+        impl RunnerUp {
+            pub fn field(&self) -> Field {
+                self.question.field()
+            }
+            pub fn question_text(&self) -> String {
+                question_text(&self.question)
+            }
+        }
+
         impl Node {
-            fn new_leaf(data: &Vec<DataPoint>) -> Self {
+            fn new_leaf(data: &[Row]) -> Self {
                 Self::Leaf(class_counts(data))
             }
             fn new_decision_node(q: Question, true_branch: Node, false_branch: Node) -> Self {
@@ -86,23 +456,21 @@ macro_rules! classification_data_layout {
                     question: q,
                     true_branch: Box::new(true_branch),
                     false_branch: Box::new(false_branch),
+                    runner_up: None,
                 }
             }
             pub fn print_tree(&self, indent: &str) {
                 match self {
                     Self::Leaf(x) => {
-                        print_leaf(x, indent);
+                        print_leaf(x, indent, &$crate::NumberFormat::default());
                     }
                     Self::Decision {
                         question,
                         true_branch,
                         false_branch,
+                        ..
                     } => {
-                        match question {
-                            $(Question::$field_name(x) => println!("{}Is {:?} == {:?}", indent, Field::$field_name, x),)*
-                            $(Question::$number_field_name(x) => println!("{}Is {:?} >= {:?}", indent, Field::$number_field_name, x),)*
-                            _ => print!("what")
-                        }
+                        println!("{}{}", indent, question_text(question));
                         println!("{}--> True:", indent);
                         true_branch.print_tree(&("  ".to_owned() + indent));
                         println!("{}--> False;", indent);
@@ -110,145 +478,4039 @@ macro_rules! classification_data_layout {
                     }
                 }
             }
+            pub fn write_tree<W: std::io::Write>(&self, out: &mut W, options: &$crate::render::RenderOptions) -> std::io::Result<()> {
+                let classes = class_universe(self);
+                match options.style {
+                    $crate::render::TreeStyle::Indent => write_indented(self, out, "", &mut Vec::new(), &mut 0, &classes, options),
+                    $crate::render::TreeStyle::BoxDrawing => {
+                        writeln!(out, "{}", node_text(self, &[], 0, &classes, options))?;
+                        write_boxed(self, out, "", &mut Vec::new(), &mut 1, &classes, options)
+                    }
+                }
+            }
+            // every class occurring in some leaf, ordered by label; leaves
+            // themselves only store the classes they saw
+            pub fn classes(&self) -> Vec<$class> {
+                class_universe(self)
+            }
+            // the node reached by following `path` from here (true = true branch)
+            pub fn subtree(&self, path: &[bool]) -> Option<&Node> {
+                match (path.split_first(), self) {
+                    (None, _) => Some(self),
+                    (Some((&answer, rest)), Self::Decision { true_branch, false_branch, .. }) => {
+                        if answer { true_branch } else { false_branch }.subtree(rest)
+                    }
+                    (Some(_), Self::Leaf(_)) => None,
+                }
+            }
+            // every root-to-leaf path in preorder (true branch first), without recursion
+            pub fn paths(&self) -> Paths<'_> {
+                Paths {
+                    stack: vec![(self, Vec::new())],
+                    next_id: 0,
+                }
+            }
+            // longest root-to-leaf path in questions; 0 for a single leaf
+            pub fn depth(&self) -> usize {
+                match self {
+                    Self::Leaf(_) => 0,
+                    Self::Decision {
+                        true_branch,
+                        false_branch,
+                        ..
+                    } => 1 + true_branch.depth().max(false_branch.depth()),
+                }
+            }
+            pub fn node_count(&self) -> usize {
+                match self {
+                    Self::Leaf(_) => 1,
+                    Self::Decision {
+                        true_branch,
+                        false_branch,
+                        ..
+                    } => 1 + true_branch.node_count() + false_branch.node_count(),
+                }
+            }
+            // releases the spare capacity of every leaf's counts; trees are built with
+            // room to grow and keep it otherwise
+            pub fn shrink_to_fit(&mut self) {
+                match self {
+                    Self::Leaf(counts) => counts.shrink_to_fit(),
+                    Self::Decision {
+                        true_branch,
+                        false_branch,
+                        ..
+                    } => {
+                        true_branch.shrink_to_fit();
+                        false_branch.shrink_to_fit();
+                    }
+                }
+            }
+            // estimated bytes of the whole tree: size_of::<Node>() for every node (the
+            // root included), the allocated capacity of each leaf's counts
+            // (ClassCounts::heap_size_estimate) and size_of::<RunnerUp>() for each kept
+            // runner-up. Allocator overhead and heap data owned by field values aren't counted.
+            pub fn heap_size_estimate(&self) -> usize {
+                std::mem::size_of::<Node>() + match self {
+                    Self::Leaf(counts) => counts.heap_size_estimate(),
+                    Self::Decision {
+                        true_branch,
+                        false_branch,
+                        runner_up,
+                        ..
+                    } => {
+                        runner_up.as_ref().map_or(0, |_| std::mem::size_of::<RunnerUp>())
+                            + true_branch.heap_size_estimate()
+                            + false_branch.heap_size_estimate()
+                    }
+                }
+            }
+            // stable across runs: hashes the questions and the leaf counts in label order
+            pub fn fingerprint(&self) -> u64 {
+                fn encode(node: &Node, text: &mut String) {
+                    match node {
+                        Node::Leaf(x) => {
+                            text.push('L');
+                            for (label, count) in sorted_counts(x) {
+                                text.push_str(&format!("{}={};", label, count));
+                            }
+                            text.push('|');
+                        }
+                        Node::Decision {
+                            question,
+                            true_branch,
+                            false_branch,
+                            ..
+                        } => {
+                            text.push('D');
+                            text.push_str(&question_text(question));
+                            text.push('|');
+                            encode(true_branch, text);
+                            encode(false_branch, text);
+                        }
+                    }
+                }
+                let mut text = String::new();
+                encode(self, &mut text);
+                $crate::fingerprint::fnv1a(text.as_bytes())
+            }
+            // like write_tree to stdout, but colors only when stdout is a terminal
+            pub fn print_tree_with(&self, options: &$crate::render::RenderOptions) {
+                use std::io::IsTerminal;
+                let mut options = options.clone();
+                options.color &= std::io::stdout().is_terminal();
+                self.write_tree(&mut std::io::stdout().lock(), &options)
+                    .expect("failed to write tree to stdout");
+            }
+        }
+
+        // one step of a path: the question asked and the answer taken
+        #[derive(Clone)]
+        pub struct Condition {
+            question: Question,
+            pub answer: bool,
+        }
+
+        impl Condition {
+            pub fn field(&self) -> Field {
+                self.question.field()
+            }
+            pub fn question_text(&self) -> String {
+                question_text(&self.question)
+            }
+            // `point` answers the question the way this path does
+            pub fn holds(&self, point: &DataPoint) -> bool {
+                self.question.evaluate(point) == self.answer
+            }
+        }
+
+        pub struct PathInfo<'a> {
+            pub conditions: Vec<Condition>,
+            // preorder index of the leaf, as in audit_record
+            pub leaf_id: usize,
+            pub distribution: &'a $crate::ClassCounts<$class>,
+            pub n_samples: u64,
+            pub depth: usize,
+            pub impurity: f64,
+        }
+
+        pub struct Paths<'a> {
+            stack: Vec<(&'a Node, Vec<Condition>)>,
+            // nodes pop in preorder, so this counts them to the preorder id of the next one
+            next_id: usize,
+        }
+
+        impl<'a> Iterator for Paths<'a> {
+            type Item = PathInfo<'a>;
+            fn next(&mut self) -> Option<PathInfo<'a>> {
+                while let Some((node, conditions)) = self.stack.pop() {
+                    let id = self.next_id;
+                    self.next_id += 1;
+                    match node {
+                        Node::Leaf(x) => {
+                            return Some(PathInfo {
+                                depth: conditions.len(),
+                                conditions,
+                                leaf_id: id,
+                                distribution: x,
+                                n_samples: x.total(),
+                                impurity: x.gini(),
+                            });
+                        }
+                        Node::Decision { question, true_branch, false_branch, .. } => {
+                            let step = |answer| {
+                                let mut steps = conditions.clone();
+                                steps.push(Condition { question: question.clone(), answer });
+                                steps
+                            };
+                            self.stack.push((false_branch, step(false)));
+                            self.stack.push((true_branch, step(true)));
+                        }
+                    }
+                }
+                None
+            }
+        }
+
+        // the default format leaves thresholds as Debug prints them, which fingerprint relies on
+        fn question_text(q: &Question) -> String {
+            question_text_with(q, &$crate::NumberFormat::default())
+        }
+        fn question_text_with(q: &Question, format: &$crate::NumberFormat) -> String {
+            match q {
+                $(Question::$field_name(x) if x.len() == 1 => format!("Is {} == {}", $crate::labels::label(&Field::$field_name, $crate::labels::Target::Text), $crate::labels::label(&x[0], $crate::labels::Target::Text)),
+                Question::$field_name(x) => format!(
+                    "Is {} in {{{}}}",
+                    $crate::labels::label(&Field::$field_name, $crate::labels::Target::Text),
+                    x.iter().map(|v| $crate::labels::label(v, $crate::labels::Target::Text)).collect::<Vec<_>>().join(", ")
+                ),)*
+                $(Question::$number_field_name(x) => format!("Is {} >= {}", $crate::labels::label(&Field::$number_field_name, $crate::labels::Target::Text), format.localize(&$crate::labels::label(x, $crate::labels::Target::Text))),)*
+            }
+        }
+        // class labels take the color of the leaf's majority class, percentages are dimmed
+        fn leaf_text(x: &$crate::ClassCounts<$class>, classes: &[$class], color: bool, format: &$crate::NumberFormat) -> String {
+            let index = x
+                .argmax()
+                .and_then(|majority| classes.iter().position(|c| c == majority))
+                .unwrap_or(0);
+            let mut text = String::new();
+            for (label, _) in x.ordered() {
+                let percent = format.percent(x.proba(label));
+                text += &format!(
+                    "{}: {}, ",
+                    $crate::render::paint_class(&$crate::labels::label(label, $crate::labels::Target::Text), index, color),
+                    $crate::render::paint_dim(&percent, color)
+                );
+            }
+            text
+        }
+        // (label, count) ordered by label, for hashing
+        fn sorted_counts(x: &$crate::ClassCounts<$class>) -> Vec<(String, u64)> {
+            let mut counts: Vec<(String, u64)> = x
+                .iter()
+                .map(|(label, &count)| ($crate::labels::label(label, $crate::labels::Target::Text), count))
+                .collect();
+            counts.sort();
+            counts
+        }
+        // (label, count) in export order, ClassCounts::ordered
+        fn ordered_counts(x: &$crate::ClassCounts<$class>) -> Vec<(String, u64)> {
+            x.ordered()
+                .into_iter()
+                .map(|(label, count)| ($crate::labels::label(label, $crate::labels::Target::Text), count))
+                .collect()
+        }
+        // `path` leads from the root to `node`, `id` is its preorder index; the roots of
+        // frozen subtrees and split p-values are marked
+        fn node_text(node: &Node, path: &[bool], id: usize, classes: &[$class], options: &$crate::render::RenderOptions) -> String {
+            let mut text = match node {
+                Node::Leaf(x) => leaf_text(x, classes, options.color, &options.number_format),
+                Node::Decision { question, .. } => $crate::render::paint_question(&question_text_with(question, &options.number_format), options.color),
+            };
+            if let Some(&pvalue) = options.split_pvalues.get(&id) {
+                text += &$crate::render::paint_dim(&$crate::render::pvalue_note(pvalue, &options.number_format), options.color);
+            }
+            if options.frozen.paths().iter().any(|p| p == path) {
+                text += &$crate::render::paint_dim(" [frozen]", options.color);
+            }
+            text
+        }
+        // `next_id` is the preorder id of `node`, and past its subtree on return
+        fn write_indented<W: std::io::Write>(node: &Node, out: &mut W, indent: &str, path: &mut Vec<bool>, next_id: &mut usize, classes: &[$class], options: &$crate::render::RenderOptions) -> std::io::Result<()> {
+            writeln!(out, "{}{}", indent, node_text(node, path, *next_id, classes, options))?;
+            *next_id += 1;
+            if let Node::Decision { true_branch, false_branch, .. } = node {
+                let child_indent = "  ".to_owned() + indent;
+                writeln!(out, "{}--> True:", indent)?;
+                path.push(true);
+                write_indented(true_branch, out, &child_indent, path, next_id, classes, options)?;
+                path.pop();
+                writeln!(out, "{}--> False;", indent)?;
+                path.push(false);
+                write_indented(false_branch, out, &child_indent, path, next_id, classes, options)?;
+                path.pop();
+            }
+            Ok(())
+        }
+        // the branches of `node`, whose own line is written; `next_id` is the preorder id of
+        // its true branch, and past its subtree on return
+        fn write_boxed<W: std::io::Write>(node: &Node, out: &mut W, prefix: &str, path: &mut Vec<bool>, next_id: &mut usize, classes: &[$class], options: &$crate::render::RenderOptions) -> std::io::Result<()> {
+            if let Node::Decision { true_branch, false_branch, .. } = node {
+                path.push(true);
+                writeln!(out, "{}├── True: {}", prefix, node_text(true_branch, path, *next_id, classes, options))?;
+                *next_id += 1;
+                write_boxed(true_branch, out, &format!("{}│   ", prefix), path, next_id, classes, options)?;
+                path.pop();
+                path.push(false);
+                writeln!(out, "{}└── False: {}", prefix, node_text(false_branch, path, *next_id, classes, options))?;
+                *next_id += 1;
+                write_boxed(false_branch, out, &format!("{}    ", prefix), path, next_id, classes, options)?;
+                path.pop();
+            }
+            Ok(())
         }
 
-        fn print_leaf(x: &HashMap<$class, i32>, indent: &str) {
-            let total = x.values().sum::<i32>() as f32;
+        fn print_leaf(x: &$crate::ClassCounts<$class>, indent: &str, format: &$crate::NumberFormat) {
             print!("{}", indent);
-            for label in x.keys() {
+            for (label, _) in x.ordered() {
                 print!(
-                    "{:?}: {}%, ",
-                    label,
-                    (x[label] as f32 / total * 100_f32) as i32
+                    "{}: {}, ",
+                    $crate::labels::label(label, $crate::labels::Target::Text),
+                    format.percent(x.proba(label))
                 );
             }
             print!("\n");
         }
 
 
-        fn check(q: &Question, val: &DataPoint) -> bool {
-            match q {
-                $(Question::$field_name(x) => {
-                     *x == val.$field_name
-                },)*
-                $(Question::$number_field_name(x) => {
-                    val.$number_field_name >= *x
-                }),*
+        // Read access to the fields of the point types, so questions treat them alike.
+        // None when the point doesn't know the field, which is also what a method left out
+        // of an impl answers. Other structs holding the values implement it too, usually with
+        // impl_has_fields!, to be classified in place.
+        pub trait HasFields {
+            $(fn $field_name(&self) -> Option<&$field_type> {
+                None
+            })*
+            $(fn $number_field_name(&self) -> Option<&$number_field_type> {
+                None
+            })*
+        }
+
+        // so classify and predict still take a point behind two references, as they did when
+        // they took &DataPoint
+        impl<T: HasFields + ?Sized> HasFields for &T {
+            $(fn $field_name(&self) -> Option<&$field_type> {
+                (**self).$field_name()
+            })*
+            $(fn $number_field_name(&self) -> Option<&$number_field_type> {
+                (**self).$number_field_name()
+            })*
+        }
+
+        // the type of each field by its name, for impl_has_fields!
+        #[allow(non_camel_case_types)]
+        pub mod field_types {
+            #[allow(unused_imports)]
+            use super::*;
+            $(pub type $field_name = $field_type;)*
+            $(pub type $number_field_name = $number_field_type;)*
+        }
+
+        impl HasFields for DataPoint {
+            $(fn $field_name(&self) -> Option<&$field_type> {
+                Some(&self.$field_name)
+            })*
+            $(fn $number_field_name(&self) -> Option<&$number_field_type> {
+                Some(&self.$number_field_name)
+            })*
+        }
+
+        // derived fields are unknown until the point is complete, see PartialPoint::into_point
+        impl HasFields for PartialPoint {
+            $(fn $field_name(&self) -> Option<&$field_type> {
+                self.$field_name.as_ref()
+            })*
+            $(fn $input_name(&self) -> Option<&$input_type> {
+                self.$input_name.as_ref()
+            })*
+            $(fn $derived_name(&self) -> Option<&$derived_type> {
+                None
+            })*
+        }
+
+        impl Question {
+            // The answer for `point`: enum questions ask whether the value is one of theirs,
+            // number questions whether it is at least the threshold. Panics if `point`
+            // doesn't know the field; use evaluate_partial for such points.
+            pub fn evaluate(&self, point: &impl HasFields) -> bool {
+                self.evaluate_partial(point)
+                    .unwrap_or_else(|| panic!("the point has no value for {:?}", self.field()))
+            }
+            // the answer, or None if `point` doesn't know the field asked about
+            pub fn evaluate_partial(&self, point: &impl HasFields) -> Option<bool> {
+                match self {
+                    $(Question::$field_name(x) => point.$field_name().map(|v| x.contains(v)),)*
+                    $(Question::$number_field_name(x) => point.$number_field_name().map(|v| v >= x),)*
+                }
             }
         }
 
-        fn unique_questions(data: &Vec<DataPoint>, t: Field) -> Vec<Question> {
+        fn unique_questions(data: &[Row], t: Field) -> Vec<Question> {
             let mut set: HashSet<Question> = HashSet::new();
 
             for point in data {
                 match t {
-                    $(Field::$field_name => set.insert(Question::$field_name(point.$field_name)),)*
+                    $(Field::$field_name => set.insert(Question::$field_name(vec![point.$field_name.clone()])),)*
                     $(Field::$number_field_name => set.insert(Question::$number_field_name(point.$number_field_name)),)*
-                    _ => panic!("weird")
                 };
             }
-            let result: Vec<Question> = set.into_iter().collect();
+            // in a fixed order, so ties between candidates break the same way every build
+            let mut result: Vec<Question> = set.into_iter().collect();
+            result.sort_by_key(|question| format!("{:?}", question));
             result
         }
-        fn class_counts(data: &Vec<DataPoint>) -> HashMap<$class, i32> {
-            let mut map: HashMap<$class, i32> = HashMap::new();
-            for point in data {
-                let count = map.entry(point.class.clone()).or_insert(0);
-                *count += 1;
+        // The questions find_best_field_split tries on `field`: one per distinct value, except
+        // for a number field with more than `config.max_candidates` distinct values among the
+        // rows. That one gets the values at `max_candidates` random quantiles of the rows
+        // instead, drawn with a seed from the field and row count so a build repeats. With
+        // config.midpoint_thresholds each value v becomes the midpoint between v and the
+        // distinct value below it.
+        fn candidate_questions(data: &[Row], field: Field, config: &TreeConfig, stats: &mut $crate::split::SplitStats) -> Vec<Question> {
+            match field {
+                $(Field::$field_name => unique_questions(data, field),)*
+                $(Field::$number_field_name => {
+                    // NaNs answer every question with false, so they can't be thresholds
+                    let mut rows: Vec<($number_field_type, u64)> = data.iter().map(|row| (row.$number_field_name, row.1)).filter(|(v, _)| !$crate::Number::is_nan(*v)).collect();
+                    rows.sort_by(|a, b| a.0.partial_cmp(&b.0).expect("NaNs are filtered out"));
+                    let mut all: Vec<$number_field_type> = rows.iter().map(|&(v, _)| v).collect();
+                    all.dedup();
+                    let mut values: Vec<$number_field_type>;
+                    if all.len() > config.max_candidates {
+                        stats.sampled_fields += 1;
+                        let mut rng = $crate::rng::Rng::new($crate::fingerprint::fnv1a(field.name().as_bytes()) ^ total_weight(data) as u64);
+                        // a row of weight w spans w positions, as w copies of it would
+                        let positions = rows.iter().map(|&(_, weight)| weight as usize).sum();
+                        let mut picked: Vec<usize> = (0..config.max_candidates).map(|_| rng.gen_index(positions)).collect();
+                        picked.sort_unstable();
+                        let (mut row, mut end) = (0, rows[0].1 as usize);
+                        values = picked
+                            .into_iter()
+                            .map(|position| {
+                                while position >= end {
+                                    row += 1;
+                                    end += rows[row].1 as usize;
+                                }
+                                rows[row].0
+                            })
+                            .collect();
+                        values.dedup();
+                    } else {
+                        values = all.clone();
+                    }
+                    if config.midpoint_thresholds {
+                        for value in values.iter_mut() {
+                            let below = all.partition_point(|v| v < value);
+                            if below > 0 {
+                                *value = $crate::Number::threshold_between(all[below - 1], *value);
+                            }
+                        }
+                    }
+                    values.into_iter().map(Question::$number_field_name).collect()
+                })*
             }
-            map
         }
-        fn partition(q: &Question, data: &Vec<DataPoint>) -> (Vec<DataPoint>, Vec<DataPoint>) {
-            let mut false_points: Vec<DataPoint> = Vec::new();
-            let mut true_points: Vec<DataPoint> = Vec::new();
+        // For two classes the best subset split of an enum field is a prefix of its values
+        // ordered by the rate of one class (Breiman et al., 1984), so `questions` (one per
+        // value) become the k - 1 prefix sets. Any other number of classes, or a number
+        // field, leaves them as they are.
+        fn ordered_set_questions(data: &[Row], questions: Vec<Question>, classes: &[$class]) -> Vec<Question> {
+            if classes.len() != 2 || !questions.first().map_or(false, Question::is_enum) {
+                return questions;
+            }
+            let positive = classes.iter().min_by_key(|c| format!("{:?}", c)).unwrap();
+            let mut rated: Vec<(f64, String, Question)> = questions
+                .into_iter()
+                .map(|question| {
+                    let (mut rows, mut hits) = (0, 0);
+                    for row in data.iter().filter(|row| question.evaluate(row.0)) {
+                        rows += row.1;
+                        hits += (row.class == *positive) as u64 * row.1;
+                    }
+                    (hits as f64 / rows as f64, question_text(&question), question)
+                })
+                .collect();
+            rated.sort_by(|a, b| a.0.total_cmp(&b.0).then_with(|| a.1.cmp(&b.1)));
+            (1..rated.len())
+                .map(|k| merge_questions(rated[..k].iter().map(|(_, _, question)| question)))
+                .collect()
+        }
+        // one question asking for any of the values of several questions on the same enum field
+        fn merge_questions<'a>(mut questions: impl Iterator<Item = &'a Question>) -> Question {
+            match questions.next() {
+                $(Some(Question::$field_name(first)) => {
+                    let mut values = first.clone();
+                    for question in questions {
+                        match question {
+                            Question::$field_name(more) => values.extend(more.iter().cloned()),
+                            _ => panic!("can't merge questions on different fields"),
+                        }
+                    }
+                    values.sort_by_key(|v| format!("{:?}", v));
+                    values.dedup();
+                    Question::$field_name(values)
+                },)*
+                _ => panic!("only questions on enum fields can be merged"),
+            }
+        }
+        // The tree-building internals work on borrowed rows, so splitting a node copies
+        // pointers rather than points. A row stands for `.1` copies of its point: 1 unless
+        // grown on weights or with config.dedupe, and every count the internals take is a sum
+        // of them. A tuple struct, so no field of its own shadows one of the point's.
+        #[derive(Clone, Copy)]
+        struct Row<'a>(&'a DataPoint, u64);
+        impl std::ops::Deref for Row<'_> {
+            type Target = DataPoint;
+            fn deref(&self) -> &DataPoint {
+                self.0
+            }
+        }
+        fn row_refs(data: &[DataPoint]) -> Vec<Row<'_>> {
+            data.iter().map(|point| Row(point, 1)).collect()
+        }
+        fn total_weight(data: &[Row]) -> usize {
+            data.iter().map(|row| row.1 as usize).sum()
+        }
+        fn class_counts(data: &[Row]) -> $crate::ClassCounts<$class> {
+            data.iter().map(|row| (row.class.clone(), row.1)).collect()
+        }
+        fn partition<'a>(q: &Question, data: &[Row<'a>]) -> (Vec<Row<'a>>, Vec<Row<'a>>) {
+            let mut false_points: Vec<Row> = Vec::new();
+            let mut true_points: Vec<Row> = Vec::new();
 
-            for point in data {
-                if check(&q, point) {
-                    true_points.push(point.clone());
+            for &point in data {
+                if q.evaluate(point.0) {
+                    true_points.push(point);
                 } else {
-                    false_points.push(point.clone());
+                    false_points.push(point);
                 }
             }
             return (true_points, false_points);
         }
 
-        fn gini(data: &Vec<DataPoint>) -> f32 {
-            let counts = class_counts(data);
-            let mut impurity = 1_f32;
-            for label in counts.keys() {
-                let prop_of_label = counts[label] as f32 / data.len() as f32;
-                impurity -= prop_of_label.powi(2);
-            }
-            impurity
+        fn gini(data: &[Row]) -> f64 {
+            class_counts(data).gini()
         }
 
-        fn info_gain(left: &Vec<DataPoint>, right: &Vec<DataPoint>, cur_uncertainty: f32) -> f32 {
-            let p: f32 = left.len() as f32 / (left.len() + right.len()) as f32;
-            return cur_uncertainty - p * gini(left) - (1_f32 - p) * gini(right);
+        fn info_gain(left: &[Row], right: &[Row], cur_uncertainty: f64) -> f64 {
+            let p: f64 = total_weight(left) as f64 / (total_weight(left) + total_weight(right)) as f64;
+            return cur_uncertainty - p * gini(left) - (1_f64 - p) * gini(right);
+        }
+        // Per-node state of the split search. The gain of a candidate only depends on the
+        // class counts of its true side, so candidates producing the same counts (common
+        // for enum fields on skewed data) are scored once.
+        struct SplitSearch {
+            criterion: $crate::split::SplitCriterion,
+            parent: $crate::ClassCounts<$class>,
+            classes: Vec<$class>,
+            current_uncertainty: f64,
+            cache: HashMap<Vec<u64>, f64>,
+            stats: $crate::split::SplitStats,
+            // the two best candidates so far, best first; only with keep_runner_up
+            top: Vec<(f64, Question)>,
+            // best gain scored so far over all fields, and `2 - gini - 2 * p_min` for the
+            // parent's class proportions (see gain_bound; Gini only)
+            best: f64,
+            bound_factor: f64,
+            // whether can_skip may skip; off only to check it against the full search
+            prune: bool,
+            // (field, time spent searching it), when the build is timed
+            field_times: Option<Vec<(&'static str, std::time::Duration)>>,
         }
-        fn find_best_split(data: &Vec<DataPoint>) -> (f32, Option<Question>) {
-            let mut best_gain: f32 = 0.;
-            let mut best_question: Option<Question> = None;
-            let current_uncertainty = gini(data);
-
-            for s in [$(Field::$field_name,)* $(Field::$number_field_name),*] {
-                let questions: Vec<Question> = unique_questions(data, s);
-
-                for question in questions {
-                    let (true_data, false_data) = partition(&question, data);
-
-                    if true_data.len() == 0 || false_data.len() == 0 {
-                        continue;
-                    }
 
-                    let gain = info_gain(&true_data, &false_data, current_uncertainty);
-                    if gain >= best_gain {
-                        best_gain = gain;
-                        best_question = Some(question.clone());
-                    }
+        impl SplitSearch {
+            fn new(data: &[Row], criterion: $crate::split::SplitCriterion) -> Self {
+                let parent = class_counts(data);
+                let classes: Vec<$class> = parent.classes().cloned().collect();
+                let current_uncertainty = criterion.impurity(&parent);
+                let p_min = classes.iter().map(|c| parent.proba(c)).fold(1_f64, f64::min);
+                let bound_factor = 2. - parent.gini() - 2. * p_min;
+                Self {
+                    criterion,
+                    parent,
+                    classes,
+                    current_uncertainty,
+                    cache: HashMap::new(),
+                    stats: $crate::split::SplitStats::default(),
+                    top: Vec::new(),
+                    best: 0.,
+                    bound_factor,
+                    prune: true,
+                    field_times: None,
                 }
             }
-            (best_gain, best_question)
-        }
-
-        pub fn build_tree(data: &Vec<DataPoint>) -> Node {
-            let (gain, question) = find_best_split(&data);
-
-            if gain == 0.0 {
-                return Node::new_leaf(&data);
+            // Upper bound on the gain of any partition with `true_len` rows on the true side.
+            // With parent proportions p and true/false side proportions t/f, the gini gain is
+            // exactly (n_t / n_f) * |t - p|^2 = (n_f / n_t) * |f - p|^2, and |t - p|^2 is at
+            // most its value at the corner of the class with the smallest p, which is
+            // bound_factor. It is also at most the parent's impurity, the only bound used for
+            // the entropy criterion.
+            fn gain_bound(&self, true_len: usize) -> f64 {
+                if self.criterion != $crate::split::SplitCriterion::Gini {
+                    return self.current_uncertainty;
+                }
+                let total = self.parent.total() as usize;
+                let (small, large) = (true_len.min(total - true_len), true_len.max(total - true_len));
+                (self.bound_factor * small as f64 / large as f64).min(self.current_uncertainty)
+            }
+            // whether a candidate can be skipped without changing the chosen split: it can't
+            // reach the best gain so far, with room for rounding so exact ties are still scored
+            fn can_skip(&self, true_len: usize) -> bool {
+                self.prune && self.gain_bound(true_len) * (1. + 1e-9) + 1e-12 < self.best
+            }
+            // ties go to the later candidate, as in search_split; a question offered again
+            // replaces its entry, so the best and the runner-up always differ
+            fn offer(&mut self, gain: f64, question: &Question) {
+                self.top.retain(|(_, kept)| kept != question);
+                if self.top.first().map_or(true, |(best, _)| gain >= *best) {
+                    self.top.insert(0, (gain, question.clone()));
+                } else if self.top.get(1).map_or(true, |(second, _)| gain >= *second) {
+                    self.top.insert(1, (gain, question.clone()));
+                }
+                self.top.truncate(2);
+            }
+            // forgets the candidates offered so far, keeping the stats and the score cache, so a
+            // search falling through from the forced fields ranks only its own candidates
+            fn restart(&mut self) {
+                self.top.clear();
+                self.best = 0.;
+            }
+            fn score(&mut self, true_counts: &$crate::ClassCounts<$class>) -> f64 {
+                let key: Vec<u64> = self.classes.iter().map(|c| true_counts.get(c)).collect();
+                if let Some(&gain) = self.cache.get(&key) {
+                    self.stats.cache_hits += 1;
+                    return gain;
+                }
+                self.stats.scored += 1;
+                let false_counts: $crate::ClassCounts<$class> = self
+                    .classes
+                    .iter()
+                    .map(|c| (c.clone(), self.parent.get(c) - true_counts.get(c)))
+                    .collect();
+                let true_len = true_counts.total();
+                let p: f64 = true_len as f64 / self.parent.total() as f64;
+                let gain = self.current_uncertainty
+                    - p * self.criterion.impurity(true_counts)
+                    - (1_f64 - p) * self.criterion.impurity(&false_counts);
+                self.cache.insert(key, gain);
+                gain
+            }
+        }
+
+        fn search_split(data: &[Row], config: &TreeConfig, search: &mut SplitSearch) -> (f64, Option<Question>) {
+            let mut best_gain: f64 = 0.;
+            let mut best_question: Option<Question> = None;
+
+            for &s in Field::ALL {
+                if config.fields.as_ref().map_or(false, |fields| !fields.contains(&s)) {
+                    continue;
+                }
+                let started = search.field_times.is_some().then(std::time::Instant::now);
+                let (gain, question) = find_best_field_split(data, s, search, config);
+                if let (Some(started), Some(times)) = (started, search.field_times.as_mut()) {
+                    times.push((s.name(), started.elapsed()));
+                }
+                if question.is_some() && gain >= best_gain {
+                    best_gain = gain;
+                    best_question = question;
+                }
+            }
+            (best_gain, best_question)
+        }
+        fn find_best_field_split(data: &[Row], field: Field, search: &mut SplitSearch, config: &TreeConfig) -> (f64, Option<Question>) {
+            let mut best_gain: f64 = 0.;
+            let mut best_question: Option<Question> = None;
+            let questions: Vec<Question> = candidate_questions(data, field, config, &mut search.stats);
+            if questions.len() < 2 {
+                search.stats.constant_fields += 1;
+                return (best_gain, best_question);
+            }
+            let questions = ordered_set_questions(data, questions, &search.classes);
+            let counts = true_side_counts(data, &questions, &search.classes);
+
+            for (question, true_counts) in questions.into_iter().zip(counts) {
+                search.stats.candidates += 1;
+                let true_len = true_counts.total() as usize;
+                let false_len = search.parent.total() as usize - true_len;
+
+                if true_len == 0 || false_len == 0 {
+                    search.stats.skipped_empty += 1;
+                    continue;
+                }
+                if true_len < config.min_samples_leaf || false_len < config.min_samples_leaf {
+                    search.stats.skipped_min_samples += 1;
+                    continue;
+                }
+                if (true_len.min(false_len) as f32) < config.min_side_fraction * (true_len + false_len) as f32 {
+                    search.stats.skipped_min_fraction += 1;
+                    continue;
+                }
+
+                // the runner-up needs every gain
+                if !config.keep_runner_up && search.can_skip(true_len) {
+                    search.stats.skipped_bound += 1;
+                    continue;
+                }
+
+                let gain = search.score(&true_counts);
+                search.best = search.best.max(gain);
+                if config.keep_runner_up {
+                    search.offer(gain, &question);
+                }
+                if gain >= best_gain {
+                    best_gain = gain;
+                    best_question = Some(question.clone());
+                }
+            }
+            (best_gain, best_question)
+        }
+        // The class counts of the rows each question answers true for, all questions being
+        // on one field. An enum question's are summed from the counts of its values. Number
+        // questions come in ascending order of threshold (see candidate_questions), so one
+        // sweep over the rows sorted by value counts them all: a row leaves the true side
+        // once the threshold passes its value.
+        fn true_side_counts(data: &[Row], questions: &[Question], classes: &[$class]) -> Vec<$crate::ClassCounts<$class>> {
+            let index = |class: &$class| classes.iter().position(|c| c == class).expect("the search holds every class of its rows");
+            let collect = |counts: &[u64]| -> $crate::ClassCounts<$class> {
+                classes.iter().zip(counts).filter(|(_, &n)| n > 0).map(|(c, &n)| (c.clone(), n)).collect()
+            };
+            match questions.first().map(Question::field) {
+                $(Some(Field::$field_name) => {
+                    let mut by_value: HashMap<&$field_type, Vec<u64>> = HashMap::new();
+                    for row in data {
+                        by_value.entry(&row.0.$field_name).or_insert_with(|| vec![0; classes.len()])[index(&row.class)] += row.1;
+                    }
+                    questions
+                        .iter()
+                        .map(|question| {
+                            let Question::$field_name(values) = question else {
+                                unreachable!("a search's questions share a field")
+                            };
+                            let mut counts = vec![0; classes.len()];
+                            for value in values {
+                                for (total, n) in counts.iter_mut().zip(by_value.get(value).into_iter().flatten()) {
+                                    *total += n;
+                                }
+                            }
+                            collect(&counts)
+                        })
+                        .collect()
+                },)*
+                $(Some(Field::$number_field_name) => {
+                    // NaNs answer every question with false
+                    let mut rows: Vec<($number_field_type, usize, u64)> = data
+                        .iter()
+                        .filter(|row| !$crate::Number::is_nan(row.$number_field_name))
+                        .map(|row| (row.$number_field_name, index(&row.class), row.1))
+                        .collect();
+                    rows.sort_by(|a, b| a.0.partial_cmp(&b.0).expect("NaNs are filtered out"));
+                    let mut counts = vec![0; classes.len()];
+                    for &(_, class, weight) in &rows {
+                        counts[class] += weight;
+                    }
+                    let mut below = 0;
+                    questions
+                        .iter()
+                        .map(|question| {
+                            let Question::$number_field_name(threshold) = question else {
+                                unreachable!("a search's questions share a field")
+                            };
+                            while below < rows.len() && rows[below].0 < *threshold {
+                                counts[rows[below].1] -= rows[below].2;
+                                below += 1;
+                            }
+                            collect(&counts)
+                        })
+                        .collect()
+                },)*
+                None => Vec::new(),
+            }
+        }
+        // counters from searching the root split of `data`
+        pub fn split_stats(data: &Vec<DataPoint>, config: &TreeConfig) -> $crate::split::SplitStats {
+            let rows = row_refs(data);
+            let mut search = SplitSearch::new(&rows, config.criterion);
+            search_split(&rows, config, &mut search);
+            search.stats
+        }
+
+        impl Question {
+            pub fn field(&self) -> Field {
+                match self {
+                    $(Question::$field_name(_) => Field::$field_name,)*
+                    $(Question::$number_field_name(_) => Field::$number_field_name,)*
+                }
+            }
+            fn is_enum(&self) -> bool {
+                match self {
+                    $(Question::$field_name(_) => true,)*
+                    $(Question::$number_field_name(_) => false,)*
+                }
+            }
+        }
+
+        $crate::__metrics! {
+            #[derive(Debug, Clone)]
+            pub struct MemorizationFlag {
+                pub field: Field,
+                pub distinct_fraction: f32,
+                pub splits: usize,
+                // splits whose gain a shuffled copy of the column matches on average
+                pub failed_splits: usize,
+                pub flagged: bool,
+            }
+
+            fn copy_field(dst: &mut DataPoint, src: &DataPoint, field: Field) {
+                match field {
+                    $(Field::$field_name => dst.$field_name = src.$field_name.clone(),)*
+                    $(Field::$number_field_name => dst.$number_field_name = src.$number_field_name.clone(),)*
+                }
+            }
+            // Fields with at least `distinct_fraction * n` distinct values get every split that
+            // uses them re-checked: the column is shuffled within the node's rows `permutations`
+            // times and if the best gain on the shuffled column matches the real split's gain on
+            // average, the split is memorizing rows rather than finding structure.
+            pub fn memorization_report(data: &Vec<DataPoint>, tree: &Node, distinct_fraction: f32, permutations: usize, seed: u64) -> Result<Vec<MemorizationFlag>, $crate::DecisionLeafError> {
+                $crate::error::ensure((0.0..=1.0).contains(&distinct_fraction), "distinct_fraction", distinct_fraction, "a fraction between 0 and 1")?;
+                $crate::error::ensure(permutations > 0, "permutations", permutations, "at least 1")?;
+
+                fn visit(node: &Node, rows: &[Row], suspects: &[Field], permutations: usize, rng: &mut $crate::rng::Rng, stats: &mut HashMap<Field, (usize, usize)>) {
+                    let Node::Decision { question, true_branch, false_branch, .. } = node else {
+                        return;
+                    };
+                    let (true_rows, false_rows) = partition(question, rows);
+                    let field = question.field();
+                    if suspects.contains(&field) && !true_rows.is_empty() && !false_rows.is_empty() {
+                        let current_uncertainty = gini(rows);
+                        let gain = info_gain(&true_rows, &false_rows, current_uncertainty);
+                        let mut permuted_gain = 0_f64;
+                        for _ in 0..permutations {
+                            let mut order: Vec<usize> = (0..rows.len()).collect();
+                            for i in (1..order.len()).rev() {
+                                order.swap(i, rng.gen_index(i + 1));
+                            }
+                            let mut shuffled: Vec<DataPoint> = rows.iter().map(|row| row.0.clone()).collect();
+                            for (point, &from) in shuffled.iter_mut().zip(&order) {
+                                copy_field(point, rows[from].0, field);
+                            }
+                            let shuffled = row_refs(&shuffled);
+                            let mut search = SplitSearch::new(&shuffled, $crate::split::SplitCriterion::Gini);
+                            permuted_gain += find_best_field_split(&shuffled, field, &mut search, &TreeConfig::default()).0;
+                        }
+                        let entry = stats.entry(field).or_insert((0, 0));
+                        entry.0 += 1;
+                        if permuted_gain / permutations as f64 >= gain {
+                            entry.1 += 1;
+                        }
+                    }
+                    visit(true_branch, &true_rows, suspects, permutations, rng, stats);
+                    visit(false_branch, &false_rows, suspects, permutations, rng, stats);
+                }
+
+                let rows = row_refs(data);
+                let n = data.len().max(1) as f32;
+                let mut fractions: Vec<(Field, f32)> = Vec::new();
+                for &field in Field::ALL {
+                    let fraction = unique_questions(&rows, field).len() as f32 / n;
+                    if fraction >= distinct_fraction {
+                        fractions.push((field, fraction));
+                    }
+                }
+                let suspects: Vec<Field> = fractions.iter().map(|(field, _)| *field).collect();
+                let mut rng = $crate::rng::Rng::new(seed);
+                let mut stats: HashMap<Field, (usize, usize)> = HashMap::new();
+                visit(tree, &rows, &suspects, permutations, &mut rng, &mut stats);
+
+                Ok(fractions
+                    .into_iter()
+                    .filter_map(|(field, distinct_fraction)| {
+                        let &(splits, failed_splits) = stats.get(&field)?;
+                        Some(MemorizationFlag {
+                            field,
+                            distinct_fraction,
+                            splits,
+                            failed_splits,
+                            flagged: failed_splits > 0,
+                        })
+                    })
+                    .collect())
+            }
+
+            // For every decision node (by preorder id, root 0) the fraction of `permutations`
+            // shufflings of the class labels among the node's rows whose best split gains at
+            // least as much as the node's own question. High values mark splits noise could fit.
+            pub fn split_pvalues(tree: &Node, data: &Vec<DataPoint>, permutations: usize, seed: u64) -> Result<HashMap<usize, f32>, $crate::DecisionLeafError> {
+                $crate::error::ensure(permutations > 0, "permutations", permutations, "at least 1")?;
+
+                fn visit(node: &Node, next_id: &mut usize, rows: &[Row], permutations: usize, rng: &mut $crate::rng::Rng, pvalues: &mut HashMap<usize, f32>) {
+                    let id = *next_id;
+                    *next_id += 1;
+                    let Node::Decision { question, true_branch, false_branch, .. } = node else {
+                        return;
+                    };
+                    let (true_rows, false_rows) = partition(question, rows);
+                    let pvalue = if true_rows.is_empty() || false_rows.is_empty() {
+                        1_f32
+                    } else {
+                        let gain = info_gain(&true_rows, &false_rows, gini(rows));
+                        let mut shuffled: Vec<DataPoint> = rows.iter().map(|row| row.0.clone()).collect();
+                        let mut beaten = 0;
+                        for _ in 0..permutations {
+                            for i in (1..shuffled.len()).rev() {
+                                let j = rng.gen_index(i + 1);
+                                let class = shuffled[j].class.clone();
+                                shuffled[j].class = std::mem::replace(&mut shuffled[i].class, class);
+                            }
+                            let shuffled = row_refs(&shuffled);
+                            let mut search = SplitSearch::new(&shuffled, $crate::split::SplitCriterion::Gini);
+                            if search_split(&shuffled, &TreeConfig::default(), &mut search).0 >= gain {
+                                beaten += 1;
+                            }
+                        }
+                        beaten as f32 / permutations as f32
+                    };
+                    pvalues.insert(id, pvalue);
+                    visit(true_branch, next_id, &true_rows, permutations, rng, pvalues);
+                    visit(false_branch, next_id, &false_rows, permutations, rng, pvalues);
+                }
+
+                let mut rng = $crate::rng::Rng::new(seed);
+                let mut pvalues = HashMap::new();
+                visit(tree, &mut 0, &row_refs(data), permutations, &mut rng, &mut pvalues);
+                Ok(pvalues)
+            }
+        }
+
+        // unique rows in first-seen order, with how often each one occurred as its weight;
+        // build_tree_weighted grows on them the tree `data` would grow
+        pub fn dedupe_weighted(data: &Vec<DataPoint>) -> (Vec<DataPoint>, Vec<f32>) {
+            let rows = collapse(data);
+            (rows.iter().map(|row| row.0.clone()).collect(), rows.iter().map(|row| row.1 as f32).collect())
+        }
+        fn collapse(data: &[DataPoint]) -> Vec<Row<'_>> {
+            let mut seen: HashMap<&DataPoint, usize> = HashMap::new();
+            let mut rows: Vec<Row> = Vec::new();
+            for point in data {
+                match seen.get(point) {
+                    Some(&i) => rows[i].1 += 1,
+                    None => {
+                        seen.insert(point, rows.len());
+                        rows.push(Row(point, 1));
+                    }
+                }
+            }
+            rows
+        }
+        // the rows a build grows on: collapsed with config.dedupe, else one per point
+        fn training_rows<'a>(data: &'a [DataPoint], config: &TreeConfig) -> Vec<Row<'a>> {
+            if config.dedupe {
+                collapse(data)
+            } else {
+                row_refs(data)
+            }
+        }
+
+        #[derive(Debug, Clone)]
+        pub struct TreeConfig {
+            // depth at which every node becomes a leaf, the root being depth 0
+            pub max_depth: Option<usize>,
+            // splits leaving fewer rows than this on either side are not considered
+            pub min_samples_leaf: usize,
+            // only these fields are split on; all fields when None
+            pub fields: Option<Vec<Field>>,
+            // store the second-best split of every decision node (Node::Decision::runner_up)
+            pub keep_runner_up: bool,
+            // splits leaving less than this fraction of the node's rows on either side are not
+            // considered, on top of min_samples_leaf; keeps large nodes from splitting off slivers
+            pub min_side_fraction: f32,
+            // number fields with more distinct values than this at a node are searched on a
+            // sample of that many values (split::SplitStats::sampled_fields counts them), which
+            // bounds the work spent on columns like ids or timestamps
+            pub max_candidates: usize,
+            // nodes with fewer rows than this become leaves without a split search
+            pub min_samples_split: usize,
+            // nodes whose best split gains less than this become leaves
+            pub min_gain: f32,
+            // (field, depth): nodes shallower than `depth` with no ancestor splitting on `field`
+            // search only the fields forced there, and all fields if none of those gives a
+            // valid split (split::SplitStats::forced / forced_fell_through record which)
+            pub forced_fields: Vec<(Field, usize)>,
+            // number thresholds sit halfway between the node's consecutive distinct values
+            // instead of on the upper one (Number::threshold_between; integer fields round up, so they
+            // stay integers). Training rows split the same either way; values never seen in
+            // training fall on the side nearer to them
+            pub midpoint_thresholds: bool,
+            // every node searches only this many fields (of config.fields, or all), drawn at
+            // random: the per-split feature sampling of random forests. All of them when None
+            pub max_features: Option<usize>,
+            // seeds the max_features draws; a node passes its children seeds drawn from its own
+            pub feature_seed: u64,
+            // the impurity splits decrease, Gini by default; min_gain is in its units
+            pub criterion: $crate::split::SplitCriterion,
+            // identical rows are collapsed into one row weighted by their count before growing,
+            // as build_tree_weighted does with dedupe_weighted's output: the same tree, with the
+            // split searches' work proportional to the distinct rows
+            pub dedupe: bool,
+        }
+
+        impl Default for TreeConfig {
+            fn default() -> Self {
+                Self {
+                    max_depth: None,
+                    min_samples_leaf: 1,
+                    fields: None,
+                    keep_runner_up: false,
+                    min_side_fraction: 0.0,
+                    max_candidates: 10_000,
+                    min_samples_split: 2,
+                    min_gain: 0.0,
+                    forced_fields: Vec::new(),
+                    midpoint_thresholds: false,
+                    max_features: None,
+                    feature_seed: 0,
+                    criterion: $crate::split::SplitCriterion::Gini,
+                    dedupe: false,
+                }
+            }
+        }
+
+        impl TreeConfig {
+            pub fn validate(&self) -> Result<(), $crate::DecisionLeafError> {
+                $crate::error::ensure(self.min_samples_leaf > 0, "min_samples_leaf", self.min_samples_leaf, "at least 1")?;
+                $crate::error::ensure((0.0..=0.5).contains(&self.min_side_fraction), "min_side_fraction", self.min_side_fraction, "a fraction between 0 and 0.5")?;
+                $crate::error::ensure(self.max_candidates >= 2, "max_candidates", self.max_candidates, "at least 2")?;
+                $crate::error::ensure(self.min_samples_split >= 2, "min_samples_split", self.min_samples_split, "at least 2")?;
+                $crate::error::ensure(self.min_gain.is_finite() && self.min_gain >= 0.0, "min_gain", self.min_gain, "at least 0 and finite")?;
+                $crate::error::ensure(self.forced_fields.iter().all(|&(_, depth)| depth > 0), "forced_fields", &self.forced_fields, "depths of at least 1")?;
+                $crate::error::ensure(self.max_features.map_or(true, |k| k > 0), "max_features", self.max_features, "None or at least 1")?;
+                $crate::error::ensure(self.fields.as_ref().map_or(true, |fields| !fields.is_empty()), "fields", &self.fields, "None or at least one field")
+            }
+        }
+
+        #[derive(Debug, Clone)]
+        pub struct ConfigSuggestion {
+            pub config: TreeConfig,
+            // share of the rarest class in the data
+            pub minority_fraction: f32,
+            // the rarest class is below 10% of the rows; consider balancing class weights
+            pub imbalanced: bool,
+        }
+
+        // A starting point, not an optimum: depth about log2(n) + 1, leaves of at least
+        // n / 1000 rows, and a flag when the class distribution is heavily skewed.
+        pub fn suggest_config(data: &Vec<DataPoint>) -> ConfigSuggestion {
+            let n = data.len();
+            let counts = class_counts(&row_refs(data));
+            let minority = counts.iter().map(|(_, &c)| c).min().unwrap_or(0);
+            let minority_fraction = if n == 0 { 0_f32 } else { minority as f32 / n as f32 };
+            ConfigSuggestion {
+                config: TreeConfig {
+                    max_depth: Some((n.max(2) as f64).log2().ceil() as usize + 1),
+                    min_samples_leaf: (n / 1000).max(1),
+                    ..TreeConfig::default()
+                },
+                minority_fraction,
+                imbalanced: counts.len() > 1 && minority_fraction < 0.1,
+            }
+        }
+
+        pub fn build_tree(data: &Vec<DataPoint>) -> Node {
+            grow_tree(data, &TreeConfig::default(), 0)
+        }
+        pub fn build_tree_with(data: &Vec<DataPoint>, config: &TreeConfig) -> Result<Node, $crate::DecisionLeafError> {
+            config.validate()?;
+            Ok(grow_tree(data, config, 0))
+        }
+        fn grow_tree(data: &Vec<DataPoint>, config: &TreeConfig, depth: usize) -> Node {
+            grow_observed(&training_rows(data, config), config, depth, &mut ())
+        }
+        // build_tree_with where row i stands for weights[i] copies of data[i], e.g. the output
+        // of dedupe_weighted: the tree grown on the copies, counts included, with the work of
+        // the distinct rows. Weights are whole numbers; rows of weight 0 are left out.
+        pub fn build_tree_weighted(data: &Vec<DataPoint>, weights: &[f32], config: &TreeConfig) -> Result<Node, $crate::DecisionLeafError> {
+            config.validate()?;
+            $crate::error::ensure(weights.len() == data.len(), "weights", weights.len(), "one weight per row")?;
+            for &weight in weights {
+                $crate::error::ensure(weight >= 0.0 && weight.fract() == 0.0, "weights", weight, "whole numbers of at least 0")?;
+            }
+            let rows: Vec<Row> = data
+                .iter()
+                .zip(weights)
+                .filter(|(_, &weight)| weight > 0.0)
+                .map(|(point, &weight)| Row(point, weight as u64))
+                .collect();
+            Ok(grow_observed(&rows, config, 0, &mut ()))
+        }
+        fn grow_observed<O: $crate::trace::BuildObserver<Question>>(data: &[Row], config: &TreeConfig, depth: usize, observer: &mut O) -> Node {
+            let started = observer.start();
+            let rows = total_weight(data);
+            if config.max_depth.map_or(false, |max_depth| depth >= max_depth) || rows < config.min_samples_split {
+                observer.node(started, depth, rows, None, 0.0, &$crate::split::SplitStats::default());
+                return Node::new_leaf(data);
+            }
+
+            let mut search = SplitSearch::new(data, config.criterion);
+            if observer.times_fields() {
+                search.field_times = Some(Vec::new());
+            }
+            let valid = |gain: f64| gain > 0.0 && gain >= config.min_gain as f64;
+            let forced: Vec<Field> = config.forced_fields.iter().filter(|&&(_, max_depth)| depth < max_depth).map(|&(field, _)| field).collect();
+            let (mut gain, mut question) = (0.0, None);
+            if !forced.is_empty() {
+                (gain, question) = search_split(data, &TreeConfig { fields: Some(forced.clone()), ..config.clone() }, &mut search);
+                if valid(gain) {
+                    search.stats.forced += 1;
+                } else {
+                    search.stats.forced_fell_through += 1;
+                }
+            }
+            let mut rng = $crate::rng::Rng::new(config.feature_seed);
+            if !valid(gain) {
+                search.restart();
+                match config.max_features {
+                    Some(k) => {
+                        let mut fields = config.fields.clone().unwrap_or_else(|| Field::ALL.to_vec());
+                        for i in 0..k.min(fields.len()) {
+                            let j = i + rng.gen_index(fields.len() - i);
+                            fields.swap(i, j);
+                        }
+                        fields.truncate(k);
+                        (gain, question) = search_split(data, &TreeConfig { fields: Some(fields), ..config.clone() }, &mut search);
+                    }
+                    None => (gain, question) = search_split(data, config, &mut search),
+                }
+            }
+            if let Some(times) = &search.field_times {
+                observer.fields(times);
+            }
+
+            if !valid(gain) {
+                observer.node(started, depth, rows, None, gain, &search.stats);
+                return Node::new_leaf(data);
+            }
+
+            let question = question.unwrap();
+            observer.node(started, depth, rows, Some(&question), gain, &search.stats);
+
+            let (true_rows, false_rows) = partition(&question, data);
+
+            // a split on a forced field satisfies its constraint below
+            let satisfied;
+            let config = if config.forced_fields.iter().any(|&(field, _)| field == question.field()) {
+                satisfied = TreeConfig {
+                    forced_fields: config.forced_fields.iter().filter(|&&(field, _)| field != question.field()).cloned().collect(),
+                    ..config.clone()
+                };
+                &satisfied
+            } else {
+                config
+            };
+            let (true_branch, false_branch) = if config.max_features.is_some() {
+                let (true_seed, false_seed) = (rng.next_u64(), rng.next_u64());
+                (
+                    grow_observed(&true_rows, &TreeConfig { feature_seed: true_seed, ..config.clone() }, depth + 1, observer),
+                    grow_observed(&false_rows, &TreeConfig { feature_seed: false_seed, ..config.clone() }, depth + 1, observer),
+                )
+            } else {
+                (grow_observed(&true_rows, config, depth + 1, observer), grow_observed(&false_rows, config, depth + 1, observer))
+            };
+
+            let runner_up = search.top.iter().find(|(_, runner_up)| *runner_up != question).map(|(runner_up_gain, runner_up)| {
+                Box::new(RunnerUp {
+                    question: runner_up.clone(),
+                    gain: *runner_up_gain,
+                    best_gain: gain,
+                })
+            });
+            return Node::Decision {
+                question,
+                true_branch: Box::new(true_branch),
+                false_branch: Box::new(false_branch),
+                runner_up,
+            };
+        }
+        // build_tree_with, listing the nodes where config.forced_fields chose the split and those
+        // where it fell through to all fields
+        pub fn build_tree_forced(data: &Vec<DataPoint>, config: &TreeConfig) -> Result<(Node, $crate::split::ForcingReport), $crate::DecisionLeafError> {
+            config.validate()?;
+            let mut report = $crate::split::ForcingReport::default();
+            let tree = grow_observed(&training_rows(data, config), config, 0, &mut report);
+            Ok((tree, report))
+        }
+        // build_tree_with, timing every depth and the split search of every field
+        pub fn build_tree_timed(data: &Vec<DataPoint>, config: &TreeConfig) -> Result<(Node, $crate::timing::BuildTimings), $crate::DecisionLeafError> {
+            config.validate()?;
+            let mut timer = $crate::timing::Timer::default();
+            let tree = grow_observed(&training_rows(data, config), config, 0, &mut timer);
+            Ok((tree, timer.finish()))
+        }
+        $crate::__trace! {
+            struct Tracer<'a> {
+                sink: &'a mut dyn $crate::trace::TraceSink,
+                next_id: usize,
+            }
+
+            impl $crate::trace::BuildObserver<Question> for Tracer<'_> {
+                type Started = std::time::Instant;
+                fn start(&mut self) -> std::time::Instant {
+                    std::time::Instant::now()
+                }
+                fn node(&mut self, started: std::time::Instant, depth: usize, rows: usize, question: Option<&Question>, gain: f64, stats: &$crate::split::SplitStats) {
+                    self.sink.event($crate::trace::BuildTraceEvent {
+                        node_id: self.next_id,
+                        depth,
+                        rows,
+                        field: question.map(|q| $crate::labels::label(&q.field(), $crate::labels::Target::Text)),
+                        question: question.map(question_text),
+                        gain: gain as f32,
+                        stats: stats.clone(),
+                        elapsed: started.elapsed(),
+                    });
+                    self.next_id += 1;
+                }
+            }
+
+            // build_tree_with, reporting every node to `sink`
+            pub fn build_tree_traced(data: &Vec<DataPoint>, config: &TreeConfig, sink: &mut dyn $crate::trace::TraceSink) -> Result<Node, $crate::DecisionLeafError> {
+                config.validate()?;
+                Ok(grow_observed(&training_rows(data, config), config, 0, &mut Tracer { sink, next_id: 0 }))
+            }
+        }
+
+        // Regrows `tree` on `data` except for the subtrees in `frozen`, which keep their
+        // questions (and their leaf counts unless frozen.update_counts). Nodes above a
+        // frozen subtree keep their questions as well so it stays reachable.
+        pub fn refit(tree: &Node, data: &Vec<DataPoint>, config: &TreeConfig, frozen: &$crate::freeze::Frozen) -> Result<Node, $crate::DecisionLeafError> {
+            config.validate()?;
+            for path in frozen.paths() {
+                $crate::error::ensure(tree.subtree(path).is_some(), "frozen", path, "paths of nodes in the tree")?;
+            }
+            Ok(refit_node(tree, &row_refs(data), config, frozen, &mut Vec::new()))
+        }
+        fn refit_node(node: &Node, data: &[Row], config: &TreeConfig, frozen: &$crate::freeze::Frozen, path: &mut Vec<bool>) -> Node {
+            if frozen.is_frozen(path) {
+                return if frozen.update_counts { recount(node, data) } else { node.clone() };
+            }
+            match node {
+                Node::Decision { question, true_branch, false_branch, .. } if frozen.contains_below(path) => {
+                    let (true_rows, false_rows) = partition(question, data);
+                    path.push(true);
+                    let true_branch = refit_node(true_branch, &true_rows, config, frozen, path);
+                    path.pop();
+                    path.push(false);
+                    let false_branch = refit_node(false_branch, &false_rows, config, frozen, path);
+                    path.pop();
+                    Node::new_decision_node(question.clone(), true_branch, false_branch)
+                }
+                _ => grow_observed(data, config, path.len(), &mut ()),
+            }
+        }
+        // same questions, leaf counts taken from the rows of `data` reaching each leaf
+        fn recount(node: &Node, data: &[Row]) -> Node {
+            match node {
+                Node::Leaf(_) => Node::new_leaf(data),
+                Node::Decision { question, true_branch, false_branch, .. } => {
+                    let (true_rows, false_rows) = partition(question, data);
+                    Node::new_decision_node(question.clone(), recount(true_branch, &true_rows), recount(false_branch, &false_rows))
+                }
+            }
+        }
+
+        // the nodes of `tree` with a runner-up (see TreeConfig::keep_runner_up); nodes whose
+        // gain is within `tie_margin` (relative, e.g. 0.05) of it are near ties
+        pub fn robustness_report(tree: &Node, tie_margin: f64) -> Result<$crate::split::RobustnessReport, $crate::DecisionLeafError> {
+            $crate::error::ensure(tie_margin >= 0.0, "tie_margin", tie_margin, "at least 0")?;
+            fn visit(node: &Node, next_id: &mut usize, alternatives: &mut Vec<$crate::split::Alternative>) {
+                let id = *next_id;
+                *next_id += 1;
+                if let Node::Decision { question, true_branch, false_branch, runner_up } = node {
+                    if let Some(r) = runner_up {
+                        alternatives.push($crate::split::Alternative {
+                            node: id,
+                            question: question_text(question),
+                            gain: r.best_gain,
+                            runner_up: r.question_text(),
+                            runner_up_gain: r.gain,
+                        });
+                    }
+                    visit(true_branch, next_id, alternatives);
+                    visit(false_branch, next_id, alternatives);
+                }
+            }
+            let mut alternatives = Vec::new();
+            visit(tree, &mut 0, &mut alternatives);
+            Ok($crate::split::RobustnessReport { alternatives, tie_margin })
+        }
+
+        impl Node {
+            // What-if: the tree with node `node_id` asking its runner-up instead, and both
+            // branches below it regrown from the rows of `data` reaching it with `config`.
+            // The rest of the tree is unchanged.
+            pub fn with_alternative(&self, node_id: usize, data: &Vec<DataPoint>, config: &TreeConfig) -> Result<Node, $crate::DecisionLeafError> {
+                config.validate()?;
+                fn swap(node: &Node, id: usize, sizes: &[usize], target: usize, rows: &[Row], config: &TreeConfig, depth: usize) -> Option<Node> {
+                    let Node::Decision { question, true_branch, false_branch, runner_up } = node else {
+                        return None;
+                    };
+                    if id == target {
+                        let alternative = runner_up.as_ref()?.question.clone();
+                        let (true_rows, false_rows) = partition(&alternative, rows);
+                        return Some(Node::new_decision_node(
+                            alternative,
+                            grow_observed(&true_rows, config, depth + 1, &mut ()),
+                            grow_observed(&false_rows, config, depth + 1, &mut ()),
+                        ));
+                    }
+                    let false_id = id + 1 + sizes[id + 1];
+                    let (true_rows, false_rows) = partition(question, rows);
+                    let (true_branch, false_branch) = if target < false_id {
+                        (swap(true_branch, id + 1, sizes, target, &true_rows, config, depth + 1)?, (**false_branch).clone())
+                    } else {
+                        ((**true_branch).clone(), swap(false_branch, false_id, sizes, target, &false_rows, config, depth + 1)?)
+                    };
+                    Some(Node::Decision {
+                        question: question.clone(),
+                        true_branch: Box::new(true_branch),
+                        false_branch: Box::new(false_branch),
+                        runner_up: runner_up.clone(),
+                    })
+                }
+                swap(self, 0, &subtree_sizes(self), node_id, &row_refs(data), config, 0).ok_or_else(|| $crate::DecisionLeafError::InvalidParameter {
+                    name: "node_id",
+                    value: node_id.to_string(),
+                    expected: "a decision node with a runner-up",
+                })
+            }
+        }
+
+        impl Node {
+            // A copy with the node at `path` replaced by `replacement`. The path must lead to
+            // a node outside `frozen` with no frozen subtree below it, every leaf of the
+            // replacement must have counts and every enum question at least one value.
+            // Preorder ids after the grafted node shift with its size.
+            pub fn graft(&self, path: &[bool], replacement: Node, frozen: &$crate::freeze::Frozen) -> Result<Node, $crate::DecisionLeafError> {
+                $crate::error::ensure(self.subtree(path).is_some(), "path", path, "the path of a node in the tree")?;
+                $crate::error::ensure(!frozen.is_frozen(path) && !frozen.contains_below(path), "path", path, "a node outside the frozen subtrees")?;
+                fn valid(node: &Node) -> bool {
+                    match node {
+                        Node::Leaf(x) => x.total() > 0,
+                        Node::Decision { question, true_branch, false_branch, .. } => {
+                            let asks_something = match question {
+                                $(Question::$field_name(values) => !values.is_empty(),)*
+                                $(Question::$number_field_name(_) => true,)*
+                            };
+                            asks_something && valid(true_branch) && valid(false_branch)
+                        }
+                    }
+                }
+                $crate::error::ensure(valid(&replacement), "replacement", replacement.node_count(), "a subtree with non-empty leaves and enum questions")?;
+                fn replace(node: &Node, path: &[bool], replacement: Node) -> Node {
+                    let Some((&answer, rest)) = path.split_first() else {
+                        return replacement;
+                    };
+                    let Node::Decision { question, true_branch, false_branch, runner_up } = node else {
+                        unreachable!("path checked above");
+                    };
+                    let (true_branch, false_branch) = if answer {
+                        (replace(true_branch, rest, replacement), (**false_branch).clone())
+                    } else {
+                        ((**true_branch).clone(), replace(false_branch, rest, replacement))
+                    };
+                    Node::Decision {
+                        question: question.clone(),
+                        true_branch: Box::new(true_branch),
+                        false_branch: Box::new(false_branch),
+                        runner_up: runner_up.clone(),
+                    }
+                }
+                Ok(replace(self, path, replacement))
+            }
+        }
+
+        // the size of every subtree by the preorder id of its root, so walks down one path
+        // step over a true branch without counting it
+        fn subtree_sizes(tree: &Node) -> Vec<usize> {
+            fn visit(node: &Node, sizes: &mut Vec<usize>) -> usize {
+                let id = sizes.len();
+                sizes.push(1);
+                if let Node::Decision { true_branch, false_branch, .. } = node {
+                    sizes[id] += visit(true_branch, sizes) + visit(false_branch, sizes);
+                }
+                sizes[id]
+            }
+            let mut sizes = Vec::new();
+            visit(tree, &mut sizes);
+            sizes
+        }
+
+        // preorder index of the leaf `point` reaches; `sizes` are the tree's subtree_sizes
+        fn leaf_id(point: &DataPoint, tree: &Node, sizes: &[usize]) -> usize {
+            let mut node = tree;
+            let mut id = 0;
+            while let Node::Decision { question, true_branch, false_branch, .. } = node {
+                if question.evaluate(point) {
+                    id += 1;
+                    node = true_branch;
+                } else {
+                    id += 1 + sizes[id + 1];
+                    node = false_branch;
+                }
+            }
+            id
+        }
+
+        $crate::__metrics! {
+            impl Node {
+                // The rows of `data` reaching each leaf, by leaf id, summarized per class as
+                // training_summary summarizes a whole dataset. Leaves no row reaches are left out.
+                pub fn leaf_profiles(&self, data: &Vec<DataPoint>) -> HashMap<usize, $crate::leaf::LeafProfile> {
+                    let sizes = subtree_sizes(self);
+                    let mut groups: HashMap<usize, HashMap<String, Vec<DataPoint>>> = HashMap::new();
+                    for point in data {
+                        groups
+                            .entry(leaf_id(point, self, &sizes))
+                            .or_default()
+                            .entry($crate::labels::label(&point.class, $crate::labels::Target::Text))
+                            .or_default()
+                            .push(point.clone());
+                    }
+                    groups
+                        .into_iter()
+                        .map(|(leaf, classes)| {
+                            let mut classes: Vec<$crate::leaf::ClassProfile> = classes
+                                .into_iter()
+                                .map(|(class, rows)| $crate::leaf::ClassProfile {
+                                    class,
+                                    rows: rows.len(),
+                                    fields: field_summaries(&rows),
+                                })
+                                .collect();
+                            classes.sort_by(|a, b| a.class.cmp(&b.class));
+                            (leaf, $crate::leaf::LeafProfile { leaf, classes })
+                        })
+                        .collect()
+                }
+            }
+
+            // Rounding of thresholds for Node::snap_thresholds: each listed number field is
+            // snapped to the nearest multiple of its step (1000.0, 0.1, ...). A snap is kept only
+            // if the accuracy of the node's subtree on the training rows reaching it drops by at
+            // most `tolerance` (a fraction, e.g. 0.01).
+            #[derive(Debug, Clone, PartialEq)]
+            pub struct SnapPolicy {
+                pub steps: Vec<(Field, f64)>,
+                pub tolerance: f64,
+            }
+
+            // rows of `rows` whose leaf in `node` predicts their class
+            fn correct_count(node: &Node, rows: &[Row]) -> usize {
+                rows.iter().filter(|p| classify(p.0, node).argmax() == Some(&p.class)).count()
+            }
+
+            // `next_id` is the preorder id of `node`, and past its subtree on return
+            fn snap_node(node: &Node, rows: &[Row], policy: &SnapPolicy, next_id: &mut usize, changes: &mut Vec<$crate::snap::SnapChange>) -> Node {
+                let id = *next_id;
+                *next_id += 1;
+                let Node::Decision { question, true_branch, false_branch, .. } = node else {
+                    return node.clone();
+                };
+                let mut question = question.clone();
+                let snapped = match &question {
+                    $(Question::$number_field_name(v) => policy.steps.iter().find(|(f, _)| *f == Field::$number_field_name).and_then(|(_, step)| {
+                        let from = $crate::Number::to_f64(*v);
+                        let to = <$number_field_type as $crate::Number>::from_f64((from / step).round() * step);
+                        (to != *v).then(|| (Question::$number_field_name(to), from, $crate::Number::to_f64(to)))
+                    }),)*
+                    $(Question::$field_name(_) => None,)*
+                };
+                if let Some((candidate, from, to)) = snapped {
+                    let accuracy = |q: &Question| {
+                        let (t, f) = partition(q, rows);
+                        let correct = correct_count(true_branch, &t) + correct_count(false_branch, &f);
+                        if rows.is_empty() { 0.0 } else { correct as f64 / rows.len() as f64 }
+                    };
+                    let accuracy_delta = accuracy(&candidate) - accuracy(&question);
+                    let accepted = -accuracy_delta <= policy.tolerance;
+                    changes.push($crate::snap::SnapChange {
+                        node: id,
+                        field: format!("{:?}", question.field()),
+                        from,
+                        to,
+                        rows: rows.len(),
+                        accuracy_delta,
+                        accepted,
+                    });
+                    if accepted {
+                        question = candidate;
+                    }
+                }
+                let (true_rows, false_rows) = partition(&question, rows);
+                let snapped_true = snap_node(true_branch, &true_rows, policy, next_id, changes);
+                let snapped_false = snap_node(false_branch, &false_rows, policy, next_id, changes);
+                Node::new_decision_node(question, snapped_true, snapped_false)
+            }
+
+            impl Node {
+                // A copy with thresholds rounded by `policy`, checked against the training `data`,
+                // and a report of every threshold the policy would change. Leaf counts are kept
+                // as trained. Snaps are decided top-down, each on the already snapped tree above.
+                pub fn snap_thresholds(&self, data: &Vec<DataPoint>, policy: &SnapPolicy) -> Result<(Node, $crate::snap::SnapReport), $crate::DecisionLeafError> {
+                    $crate::error::ensure(policy.tolerance >= 0.0, "tolerance", policy.tolerance, "at least 0")?;
+                    for &(field, step) in &policy.steps {
+                        ensure_number_field("steps", field)?;
+                        $crate::error::ensure(step.is_finite() && step > 0.0, "steps", step, "positive and finite")?;
+                    }
+                    let mut report = $crate::snap::SnapReport::default();
+                    let tree = snap_node(self, &row_refs(data), policy, &mut 0, &mut report.changes);
+                    Ok((tree, report))
+                }
+            }
+
+            // a copy of `node` (preorder id `id`, `sizes` the subtree_sizes of the whole tree)
+            // with the question of decision node `target` replaced by `ask`; None if `target` is
+            // a leaf, missing, or `ask` declines
+            fn with_question_at(node: &Node, id: usize, sizes: &[usize], target: usize, ask: &dyn Fn(&Question) -> Option<Question>) -> Option<Node> {
+                let Node::Decision { question, true_branch, false_branch, runner_up } = node else {
+                    return None;
+                };
+                let false_id = id + 1 + sizes[id + 1];
+                let (question, true_branch, false_branch) = if id == target {
+                    (ask(question)?, (**true_branch).clone(), (**false_branch).clone())
+                } else if target < false_id {
+                    (question.clone(), with_question_at(true_branch, id + 1, sizes, target, ask)?, (**false_branch).clone())
+                } else {
+                    (question.clone(), (**true_branch).clone(), with_question_at(false_branch, false_id, sizes, target, ask)?)
+                };
+                Some(Node::Decision {
+                    question,
+                    true_branch: Box::new(true_branch),
+                    false_branch: Box::new(false_branch),
+                    runner_up: runner_up.clone(),
+                })
+            }
+
+            // a copy of `node` with every leaf's counts replaced by `counts(leaf id, counts)`;
+            // `next_id` is the preorder id of `node`, and past its subtree on return
+            fn map_leaves(node: &Node, next_id: &mut usize, counts: &mut dyn FnMut(usize, &$crate::ClassCounts<$class>) -> $crate::ClassCounts<$class>) -> Node {
+                let id = *next_id;
+                *next_id += 1;
+                match node {
+                    Node::Leaf(x) => Node::Leaf(counts(id, x)),
+                    Node::Decision { question, true_branch, false_branch, runner_up } => {
+                        let true_branch = map_leaves(true_branch, next_id, counts);
+                        let false_branch = map_leaves(false_branch, next_id, counts);
+                        Node::Decision {
+                            question: question.clone(),
+                            true_branch: Box::new(true_branch),
+                            false_branch: Box::new(false_branch),
+                            runner_up: runner_up.clone(),
+                        }
+                    }
+                }
+            }
+
+            // what moving the threshold of a node to `to` changes for `data`: the moved tree, the
+            // indices of the rows answering differently, and for each the leaf it left and joined
+            struct ThresholdMove {
+                moved: Node,
+                from: f64,
+                to: f64,
+                field: Field,
+                reaching: Vec<usize>,
+                switched: Vec<(usize, usize, usize)>,
+            }
+
+            impl Node {
+                fn move_threshold(&self, node_id: usize, to: f64, data: &Vec<DataPoint>) -> Result<ThresholdMove, $crate::DecisionLeafError> {
+                    $crate::error::ensure(to.is_finite(), "new_threshold", to, "a finite number")?;
+                    let invalid = || $crate::DecisionLeafError::InvalidParameter {
+                        name: "node_id",
+                        value: node_id.to_string(),
+                        expected: "a decision node asking about a number field",
+                    };
+                    let ask = |question: &Question| match question {
+                        $(Question::$number_field_name(_) => Some(Question::$number_field_name(<$number_field_type as $crate::Number>::from_f64(to))),)*
+                        $(Question::$field_name(_) => None,)*
+                    };
+                    // the moved tree has the same shape, so these are its sizes too
+                    let sizes = subtree_sizes(self);
+                    let moved = with_question_at(self, 0, &sizes, node_id, &ask).ok_or_else(invalid)?;
+                    let mut path = Vec::new();
+                    let mut node = self;
+                    let mut id = 0;
+                    while id != node_id {
+                        let Node::Decision { true_branch, false_branch, .. } = node else {
+                            return Err(invalid());
+                        };
+                        let false_id = id + 1 + sizes[id + 1];
+                        let answer = node_id < false_id;
+                        path.push(answer);
+                        (node, id) = if answer { (&**true_branch, id + 1) } else { (&**false_branch, false_id) };
+                    }
+                    let Node::Decision { question, .. } = node else {
+                        return Err(invalid());
+                    };
+                    let new_question = ask(question).ok_or_else(invalid)?;
+                    let threshold = |q: &Question| match q {
+                        $(Question::$number_field_name(v) => $crate::Number::to_f64(*v),)*
+                        $(Question::$field_name(_) => f64::NAN,)*
+                    };
+                    // only rows reaching the node are routed, and only those answering the moved
+                    // question differently through both trees
+                    let reaches = |point: &DataPoint| {
+                        let mut node = self;
+                        path.iter().all(|&answer| {
+                            let Node::Decision { question, true_branch, false_branch, .. } = node else {
+                                return false;
+                            };
+                            node = if answer { true_branch } else { false_branch };
+                            question.evaluate(point) == answer
+                        })
+                    };
+                    let reaching: Vec<usize> = (0..data.len()).filter(|&i| reaches(&data[i])).collect();
+                    let switched = reaching
+                        .iter()
+                        .filter(|&&i| question.evaluate(&data[i]) != new_question.evaluate(&data[i]))
+                        .map(|&i| (i, leaf_id(&data[i], self, &sizes), leaf_id(&data[i], &moved, &sizes)))
+                        .collect();
+                    Ok(ThresholdMove {
+                        moved,
+                        from: threshold(question),
+                        to: threshold(&new_question),
+                        field: question.field(),
+                        reaching,
+                        switched,
+                    })
+                }
+
+                // What-if for a manual threshold: which rows of `data` reaching number node
+                // `node_id` would switch branches if it asked `>= new_threshold`, how the rows of
+                // `data` in the leaves below would shift, and the accuracy on `data` before and
+                // after (leaf counts as trained). The tree is not changed; see apply_what_if.
+                pub fn what_if_threshold(&self, node_id: usize, new_threshold: f64, data: &Vec<DataPoint>) -> Result<$crate::snap::WhatIfReport, $crate::DecisionLeafError> {
+                    let change = self.move_threshold(node_id, new_threshold, data)?;
+                    let mut affected: Vec<usize> = change.switched.iter().flat_map(|&(_, from, to)| [from, to]).collect();
+                    affected.sort();
+                    affected.dedup();
+                    let mut before: HashMap<usize, $crate::ClassCounts<$class>> = affected.iter().map(|&leaf| (leaf, $crate::ClassCounts::new())).collect();
+                    let sizes = subtree_sizes(self);
+                    for &i in &change.reaching {
+                        if let Some(counts) = before.get_mut(&leaf_id(&data[i], self, &sizes)) {
+                            counts.add(data[i].class.clone(), 1);
+                        }
+                    }
+                    let mut after = before.clone();
+                    let (mut correct_before, mut correct_after) = (0, 0);
+                    for &(i, from, to) in &change.switched {
+                        let point = &data[i];
+                        let mut out = $crate::ClassCounts::new();
+                        out.add(point.class.clone(), 1);
+                        after.insert(from, subtract_counts(&after[&from], &out));
+                        after.get_mut(&to).expect("affected").add(point.class.clone(), 1);
+                        correct_before += (classify(point, self).argmax() == Some(&point.class)) as usize;
+                        correct_after += (classify(point, &change.moved).argmax() == Some(&point.class)) as usize;
+                    }
+                    let correct = correct_count(self, &row_refs(data));
+                    let accuracy = |correct: usize| if data.is_empty() { 0.0 } else { correct as f64 / data.len() as f64 };
+                    Ok($crate::snap::WhatIfReport {
+                        node: node_id,
+                        field: format!("{:?}", change.field),
+                        from: change.from,
+                        to: change.to,
+                        rows: change.reaching.len(),
+                        switched: change.switched.iter().map(|&(i, _, _)| i).collect(),
+                        leaves: affected
+                            .into_iter()
+                            .map(|leaf| $crate::snap::LeafShift {
+                                leaf,
+                                before: ordered_counts(&before[&leaf]),
+                                after: ordered_counts(&after[&leaf]),
+                            })
+                            .collect(),
+                        accuracy_before: accuracy(correct),
+                        accuracy_after: accuracy(correct + correct_after - correct_before),
+                    })
+                }
+
+                // The tree with node `node_id` asking `>= new_threshold`: the rows of `data` (the
+                // training rows) that switch branches leave the counts of their old leaf, never
+                // below 0, and join their new one. Other leaves keep their counts.
+                pub fn apply_what_if(&self, node_id: usize, new_threshold: f64, data: &Vec<DataPoint>) -> Result<Node, $crate::DecisionLeafError> {
+                    let change = self.move_threshold(node_id, new_threshold, data)?;
+                    let mut left: HashMap<usize, $crate::ClassCounts<$class>> = HashMap::new();
+                    let mut joined: HashMap<usize, $crate::ClassCounts<$class>> = HashMap::new();
+                    for &(i, from, to) in &change.switched {
+                        left.entry(from).or_default().add(data[i].class.clone(), 1);
+                        joined.entry(to).or_default().add(data[i].class.clone(), 1);
+                    }
+                    Ok(map_leaves(&change.moved, &mut 0, &mut |id, counts| {
+                        let mut counts = left.get(&id).map_or_else(|| counts.clone(), |out| subtract_counts(counts, out));
+                        if let Some(joined) = joined.get(&id) {
+                            counts.merge(joined);
+                        }
+                        counts
+                    }))
+                }
+            }
+
+            // `counts` less `out` per class, never below 0; classes left at 0 are dropped
+            fn subtract_counts(counts: &$crate::ClassCounts<$class>, out: &$crate::ClassCounts<$class>) -> $crate::ClassCounts<$class> {
+                let mut rest = $crate::ClassCounts::new();
+                for (class, &n) in counts {
+                    let n = n.saturating_sub(out.get(class));
+                    if n > 0 {
+                        rest.add(class.clone(), n);
+                    }
+                }
+                rest
+            }
+
+            fn count_questions(node: &Node, uses: &mut HashMap<Question, usize>) {
+                if let Node::Decision { question, true_branch, false_branch, .. } = node {
+                    *uses.entry(question.clone()).or_default() += 1;
+                    count_questions(true_branch, uses);
+                    count_questions(false_branch, uses);
+                }
+            }
+
+            // The representative of every number question in `node` with a different one. Each
+            // field's thresholds are clustered from the lowest up, a cluster holding the values
+            // within `tolerance` of its first; the most used threshold (the lowest on ties)
+            // represents it.
+            fn canonical_questions(node: &Node, tolerance: f64) -> HashMap<Question, Question> {
+                let mut uses = HashMap::new();
+                count_questions(node, &mut uses);
+                let mut thresholds: Vec<(Field, f64, usize, Question)> = uses
+                    .into_iter()
+                    .filter_map(|(question, n)| {
+                        let value = match &question {
+                            $(Question::$number_field_name(v) => Some($crate::Number::to_f64(*v)),)*
+                            $(Question::$field_name(_) => None,)*
+                        };
+                        value.map(|value| (question.field(), value, n, question))
+                    })
+                    .collect();
+                thresholds.sort_by(|a, b| a.0.name().cmp(b.0.name()).then(a.1.total_cmp(&b.1)));
+                let mut canonical = HashMap::new();
+                let mut start = 0;
+                while start < thresholds.len() {
+                    let (field, low) = (thresholds[start].0, thresholds[start].1);
+                    let len = thresholds[start..].iter().take_while(|t| t.0 == field && t.1 - low <= tolerance).count();
+                    let cluster = &thresholds[start..start + len];
+                    let representative = &cluster.iter().max_by(|a, b| a.2.cmp(&b.2).then(b.1.total_cmp(&a.1))).expect("clusters are not empty").3;
+                    for (_, _, _, question) in cluster {
+                        if question != representative {
+                            canonical.insert(question.clone(), representative.clone());
+                        }
+                    }
+                    start += len;
+                }
+                canonical
+            }
+
+            // `next_id` is the preorder id of `node`, and past its subtree on return
+            fn canonicalize_node(node: &Node, rows: &[Row], canonical: &HashMap<Question, Question>, next_id: &mut usize, merges: &mut Vec<$crate::snap::ThresholdMerge>) -> Node {
+                let id = *next_id;
+                *next_id += 1;
+                let Node::Decision { question, true_branch, false_branch, runner_up } = node else {
+                    return node.clone();
+                };
+                let mut question = question.clone();
+                if let Some(candidate) = canonical.get(&question) {
+                    let value = |q: &Question| match q {
+                        $(Question::$number_field_name(v) => $crate::Number::to_f64(*v),)*
+                        $(Question::$field_name(_) => unreachable!("only number questions are merged"),)*
+                    };
+                    let accepted = rows.iter().all(|p| question.evaluate(p.0) == candidate.evaluate(p.0));
+                    merges.push($crate::snap::ThresholdMerge {
+                        node: id,
+                        field: format!("{:?}", question.field()),
+                        from: value(&question),
+                        to: value(candidate),
+                        accepted,
+                    });
+                    if accepted {
+                        question = candidate.clone();
+                    }
+                }
+                let (true_rows, false_rows) = partition(&question, rows);
+                let true_branch = canonicalize_node(true_branch, &true_rows, canonical, next_id, merges);
+                let false_branch = canonicalize_node(false_branch, &false_rows, canonical, next_id, merges);
+                Node::Decision {
+                    question,
+                    true_branch: Box::new(true_branch),
+                    false_branch: Box::new(false_branch),
+                    runner_up: runner_up.clone(),
+                }
+            }
+
+            impl Node {
+                // A copy where thresholds of a field within `tolerance` of each other are replaced
+                // by one representative, so the tree asks fewer distinct questions. A threshold is
+                // only replaced if every training row reaching its node stays on the same side, so
+                // the training data is routed exactly as before.
+                pub fn canonicalize_thresholds(&self, data: &Vec<DataPoint>, tolerance: f64) -> Result<(Node, $crate::snap::CanonicalReport), $crate::DecisionLeafError> {
+                    $crate::error::ensure(tolerance.is_finite() && tolerance >= 0.0, "tolerance", tolerance, "at least 0 and finite")?;
+                    let canonical = canonical_questions(self, tolerance);
+                    let mut merges = Vec::new();
+                    let tree = canonicalize_node(self, &row_refs(data), &canonical, &mut 0, &mut merges);
+                    let report = $crate::snap::CanonicalReport {
+                        merges,
+                        questions_before: self.distinct_questions().len(),
+                        questions_after: tree.distinct_questions().len(),
+                    };
+                    Ok((tree, report))
+                }
+            }
+        }
+
+        impl Node {
+            // Every question the tree asks once, in preorder of first use: the table a format
+            // sharing identical questions between nodes would index into.
+            pub fn distinct_questions(&self) -> Vec<&Question> {
+                fn collect<'a>(node: &'a Node, seen: &mut HashSet<&'a Question>, out: &mut Vec<&'a Question>) {
+                    if let Node::Decision { question, true_branch, false_branch, .. } = node {
+                        if seen.insert(question) {
+                            out.push(question);
+                        }
+                        collect(true_branch, seen, out);
+                        collect(false_branch, seen, out);
+                    }
+                }
+                let mut out = Vec::new();
+                collect(self, &mut HashSet::new(), &mut out);
+                out
+            }
+        }
+
+        $crate::__csv! {
+            // a parsed value for one column, returned by LoaderConfig::custom parsers
+            #[allow(non_camel_case_types)]
+            #[derive(Debug, Clone, PartialEq)]
+            pub enum FieldValue {
+                $($field_name($field_type),)*
+                $($input_name($input_type),)*
+                class($class),
+            }
+
+            type CustomParser = Box<dyn FnMut(&str) -> Result<FieldValue, String>>;
+
+            // Per-column adapters applied to the raw text before it is parsed with FromStr. Enum
+            // columns of types without FromStr (other than arrays of FromStr types) need `custom`.
+            // Columns are named like the fields, the class column is `class`.
+            #[derive(Default)]
+            pub struct LoaderConfig {
+                enum_maps: HashMap<String, Vec<(String, Box<dyn std::any::Any>)>>,
+                missing_markers: Vec<String>,
+                bool_markers: HashMap<String, (Vec<String>, Vec<String>)>,
+                custom: HashMap<String, CustomParser>,
+                thousands_separator: Option<char>,
+                skip_missing_rows: bool,
+                imputer: Option<Imputer>,
+                imputed: HashMap<&'static str, usize>,
+            }
+
+            impl LoaderConfig {
+                pub fn new() -> Self {
+                    Self::default()
+                }
+                // labels for enum values, e.g. ("lime-green", Color::LimeGreen); the type must
+                // be the column's type
+                pub fn map_enum<T: Clone + 'static>(mut self, column: &str, labels: &[(&str, T)]) -> Self {
+                    let map = self.enum_maps.entry(column.to_owned()).or_default();
+                    for (label, value) in labels {
+                        map.push((label.to_string(), Box::new(value.clone())));
+                    }
+                    self
+                }
+                // cell texts meaning "no value", e.g. &["N/A", ""]
+                pub fn missing_markers(mut self, markers: &[&str]) -> Self {
+                    self.missing_markers.extend(markers.iter().map(|m| m.to_string()));
+                    self
+                }
+                // texts read as true and false; number columns get 1 and 0
+                pub fn bool_markers(mut self, column: &str, truthy: &[&str], falsy: &[&str]) -> Self {
+                    self.bool_markers.insert(
+                        column.to_owned(),
+                        (truthy.iter().map(|m| m.to_string()).collect(), falsy.iter().map(|m| m.to_string()).collect()),
+                    );
+                    self
+                }
+                // parses every non-missing cell of `column`, replacing all other adapters
+                pub fn custom(mut self, column: &str, parser: impl FnMut(&str) -> Result<FieldValue, String> + 'static) -> Self {
+                    self.custom.insert(column.to_owned(), Box::new(parser));
+                    self
+                }
+                // removed from number cells before parsing, e.g. ',' for 1,234
+                pub fn thousands_separator(mut self, separator: char) -> Self {
+                    self.thousands_separator = Some(separator);
+                    self
+                }
+                // drop rows with a missing value instead of failing
+                pub fn skip_missing_rows(mut self) -> Self {
+                    self.skip_missing_rows = true;
+                    self
+                }
+                // fill missing field values from `imputer`; a missing class still fails
+                // (or skips the row)
+                pub fn impute(mut self, imputer: Imputer) -> Self {
+                    self.imputer = Some(imputer);
+                    self
+                }
+                // (column, values filled in) of the last load, for columns with any
+                pub fn imputed(&self) -> Vec<(&'static str, usize)> {
+                    CSV_COLUMNS.iter().filter_map(|c| self.imputed.get(c).map(|n| (*c, *n))).collect()
+                }
+            }
+
+            const CSV_COLUMNS: &[&str] = &[$(stringify!($field_name),)* $(stringify!($input_name),)* "class"];
+
+            // None for a missing marker, unless the field is an Option that holds it as its None
+            #[allow(clippy::too_many_arguments)]
+            fn parse_cell<T: Clone + 'static>(column: &str, raw: &str, line: usize, config: &mut LoaderConfig, is_number: bool, parse: fn(&str) -> Option<T>, extract: fn(FieldValue) -> Option<T>, missing: Option<T>) -> Result<Option<T>, $crate::load::LoadError> {
+                let raw = raw.trim();
+                let error = |message: String| $crate::load::LoadError::Parse {
+                    line,
+                    column: column.to_owned(),
+                    value: raw.to_owned(),
+                    message,
+                };
+                if config.missing_markers.iter().any(|m| m == raw) {
+                    return Ok(missing);
+                }
+                if let Some(parser) = config.custom.get_mut(column) {
+                    let value = parser(raw).map_err(error)?;
+                    return extract(value).map(Some).ok_or_else(|| error("custom parser returned a value for another column".to_owned()));
+                }
+                if let Some((_, value)) = config.enum_maps.get(column).and_then(|map| map.iter().find(|(label, _)| label == raw)) {
+                    return value.downcast_ref::<T>().cloned().map(Some).ok_or_else(|| error(format!("is mapped to a value that is not a {}", std::any::type_name::<T>())));
+                }
+                let mut text = raw.to_owned();
+                if let Some((truthy, falsy)) = config.bool_markers.get(column) {
+                    if truthy.iter().any(|m| *m == text) {
+                        text = if is_number { "1" } else { "true" }.to_owned();
+                    } else if falsy.iter().any(|m| *m == text) {
+                        text = if is_number { "0" } else { "false" }.to_owned();
+                    }
+                }
+                if let (true, Some(separator)) = (is_number, config.thousands_separator) {
+                    text.retain(|c| c != separator);
+                }
+                parse(&text).map(Some).ok_or_else(|| error(format!("is not a valid {}", std::any::type_name::<T>())))
+            }
+
+            // Reads a CSV with a header row into DataPoints. Enum fields and the class are
+            // parsed with FromStr (see `__parse_field!`) unless an adapter in `config` handles the cell.
+            pub fn load_csv_with<R: std::io::BufRead>(reader: R, config: &mut LoaderConfig) -> Result<Vec<DataPoint>, $crate::load::LoadError> {
+                for column in config.enum_maps.keys().chain(config.bool_markers.keys()).chain(config.custom.keys()) {
+                    if !CSV_COLUMNS.contains(&column.as_str()) {
+                        return Err($crate::load::LoadError::UnknownColumn { column: column.clone() });
+                    }
+                }
+                // every cell of a column no FromStr or adapter reads would fail
+                for (column, type_name, readable) in [
+                    $((stringify!($field_name), stringify!($field_type), $crate::__readable_field!($field_type)),)*
+                    $((stringify!($input_name), stringify!($input_type), $crate::__readable_field!($input_type)),)*
+                    ("class", stringify!($class), $crate::__readable_field!($class)),
+                ] {
+                    if !readable && !config.custom.contains_key(column) && !config.enum_maps.contains_key(column) {
+                        return Err($crate::load::LoadError::Unreadable { column: column.to_owned(), type_name: type_name.to_owned() });
+                    }
+                }
+                config.imputed.clear();
+                let mut lines = reader.lines();
+                let header = match lines.next() {
+                    Some(header) => header?,
+                    None => return Err($crate::load::LoadError::MissingColumn { column: CSV_COLUMNS[0].to_owned() }),
+                };
+                let header = $crate::load::split_record(&header).map_err(|message| $crate::load::LoadError::Parse {
+                    line: 1,
+                    column: String::new(),
+                    value: header.clone(),
+                    message: message.to_owned(),
+                })?;
+                let index = |column: &str| {
+                    header.iter().position(|h| h.trim() == column).ok_or_else(|| $crate::load::LoadError::MissingColumn { column: column.to_owned() })
+                };
+                $(let $field_name = index(stringify!($field_name))?;)*
+                $(let $input_name = index(stringify!($input_name))?;)*
+                let class = index("class")?;
+
+                let mut points = Vec::new();
+                for (i, text) in lines.enumerate() {
+                    let line = i + 2;
+                    let text = text?;
+                    if text.trim().is_empty() {
+                        continue;
+                    }
+                    let record = $crate::load::split_record(&text).map_err(|message| $crate::load::LoadError::Parse {
+                        line,
+                        column: String::new(),
+                        value: text.clone(),
+                        message: message.to_owned(),
+                    })?;
+                    let cell = |column: &str, i: usize| {
+                        record.get(i).map(String::as_str).ok_or_else(|| $crate::load::LoadError::MissingValue { line, column: column.to_owned() })
+                    };
+                    $(let mut $field_name = parse_cell(stringify!($field_name), cell(stringify!($field_name), $field_name)?, line, config, false, |text| $crate::__parse_field!($field_type, text), |v| match v {
+                        FieldValue::$field_name(x) => Some(x),
+                        _ => None,
+                    }, $crate::__missing_field!($field_type))?;)*
+                    $(let mut $input_name = parse_cell(stringify!($input_name), cell(stringify!($input_name), $input_name)?, line, config, true, |text| $crate::__parse_field!($input_type, text), |v| match v {
+                        FieldValue::$input_name(x) => Some(x),
+                        _ => None,
+                    }, $crate::__missing_field!($input_type))?;)*
+                    let class = parse_cell("class", cell("class", class)?, line, config, false, |text| $crate::__parse_field!($class, text), |v| match v {
+                        FieldValue::class(x) => Some(x),
+                        _ => None,
+                    }, None)?;
+                    if let Some(imputer) = &config.imputer {
+                        $(if $field_name.is_none() {
+                            $field_name = Some(imputer.$field_name.clone());
+                            *config.imputed.entry(stringify!($field_name)).or_default() += 1;
+                        })*
+                        $(if $input_name.is_none() {
+                            $input_name = Some(imputer.$input_name);
+                            *config.imputed.entry(stringify!($input_name)).or_default() += 1;
+                        })*
+                    }
+                    let missing = [$((stringify!($field_name), $field_name.is_none()),)* $((stringify!($input_name), $input_name.is_none()),)* ("class", class.is_none())];
+                    if let Some((column, _)) = missing.iter().find(|(_, missing)| *missing) {
+                        if config.skip_missing_rows {
+                            continue;
+                        }
+                        return Err($crate::load::LoadError::MissingValue { line, column: column.to_string() });
+                    }
+                    points.push(DataPoint::new($($field_name.unwrap(),)* $($input_name.unwrap(),)* class.unwrap()));
+                }
+                Ok(points)
+            }
+
+            // load_csv_with on the file at `path` with a default LoaderConfig: columns are found
+            // by header name, in any order, and values parsed with FromStr
+            pub fn load_csv(path: &std::path::Path) -> Result<Vec<DataPoint>, $crate::load::LoadError> {
+                let file = std::fs::File::open(path)?;
+                load_csv_with(std::io::BufReader::new(file), &mut LoaderConfig::new())
+            }
+
+            // Writes `data` as CSV that load_csv_with reads back with a default LoaderConfig: a
+            // header of the input fields and class, then a record per point with its values in
+            // their Debug form. Derived fields are left out, loading computes them again.
+            pub fn write_csv<W: std::io::Write>(data: &[DataPoint], out: &mut W) -> std::io::Result<()> {
+                let header: Vec<String> = CSV_COLUMNS.iter().map(|c| c.to_string()).collect();
+                $crate::load::write_record(out, &header)?;
+                for point in data {
+                    let record = vec![
+                        $(format!("{:?}", point.$field_name),)*
+                        $(format!("{:?}", point.$input_name),)*
+                        format!("{:?}", point.class),
+                    ];
+                    $crate::load::write_record(out, &record)?;
+                }
+                Ok(())
+            }
+        }
+
+        $crate::__persist! {
+            impl Node {
+                // writes the tree in the persist text format, preceded by the layout's fields so
+                // load can tell renamed or retyped fields apart
+                pub fn save<W: std::io::Write>(&self, out: &mut W) -> std::io::Result<()> {
+                    save_with(self, None, out)
+                }
+
+                // reads a tree written by save. Fields and enum values renamed since can be
+                // mapped with `options`; fields the layout lacks or has with another type, and
+                // values that no longer parse, fail with every offending node listed
+                pub fn load<R: std::io::BufRead>(reader: R, options: &$crate::persist::LoadOptions) -> Result<Node, $crate::persist::PersistError> {
+                    load_saved(reader, options).map(|(node, _)| node)
+                }
+            }
+
+            // Node::save to a file, created or truncated
+            pub fn save_tree(tree: &Node, path: &std::path::Path) -> std::io::Result<()> {
+                use std::io::Write;
+                let mut out = std::io::BufWriter::new(std::fs::File::create(path)?);
+                tree.save(&mut out)?;
+                out.flush()
+            }
+
+            // Node::load from a file, without renames; a file saved under another layout
+            // fails with PersistError::Schema
+            pub fn load_tree(path: &std::path::Path) -> Result<Node, $crate::persist::PersistError> {
+                let file = std::fs::File::open(path)?;
+                Node::load(std::io::BufReader::new(file), &$crate::persist::LoadOptions::new())
+            }
+
+            fn save_with<W: std::io::Write>(tree: &Node, profile: Option<&TrainingProfile>, out: &mut W) -> std::io::Result<()> {
+                use $crate::persist::escape;
+                writeln!(out, "{}", $crate::persist::HEADER)?;
+                $(writeln!(out, "field\t{}\tenum\t{}", stringify!($field_name), escape(stringify!($field_type)))?;)*
+                $(writeln!(out, "field\t{}\tnumber\t{}", stringify!($number_field_name), escape(stringify!($number_field_type)))?;)*
+                writeln!(out, "class\t{}", escape(stringify!($class)))?;
+                if let Some(profile) = profile {
+                    profile.write(out)?;
+                }
+                let mut stack = vec![tree];
+                while let Some(node) = stack.pop() {
+                    match node {
+                        Node::Leaf(x) => {
+                            write!(out, "leaf")?;
+                            for (class, n) in x.ordered() {
+                                let label = format!("{:?}", class);
+                                write!(out, "\t{}\t{}", escape(&label), n)?;
+                            }
+                        }
+                        Node::Decision {
+                            question,
+                            true_branch,
+                            false_branch,
+                            ..
+                        } => {
+                            write!(out, "split\t{:?}", question.field())?;
+                            match question {
+                                $(Question::$field_name(values) => {
+                                    for v in values {
+                                        write!(out, "\t{}", escape(&format!("{:?}", v)))?;
+                                    }
+                                })*
+                                $(Question::$number_field_name(v) => write!(out, "\t{}", escape(&format!("{:?}", v)))?,)*
+                            }
+                            stack.push(false_branch);
+                            stack.push(true_branch);
+                        }
+                    }
+                    writeln!(out)?;
+                }
+                Ok(())
+            }
+
+            // the first field (or the class) whose saved values can't be read back
+            fn ensure_readable() -> Result<(), $crate::persist::PersistError> {
+                for (field, type_name, readable) in [
+                    $((stringify!($field_name), stringify!($field_type), $crate::__readable_field!($field_type)),)*
+                    $((stringify!($input_name), stringify!($input_type), $crate::__readable_field!($input_type)),)*
+                    ("class", stringify!($class), $crate::__readable_field!($class)),
+                ] {
+                    if !readable {
+                        return Err($crate::persist::PersistError::Unreadable { field: field.to_owned(), type_name: type_name.to_owned() });
+                    }
+                }
+                Ok(())
+            }
+
+            // the tree of a saved file, and its training profile lines (see persist::PROFILE_KINDS)
+            fn load_saved<R: std::io::BufRead>(reader: R, options: &$crate::persist::LoadOptions) -> Result<(Node, Vec<(usize, Vec<String>)>), $crate::persist::PersistError> {
+                use $crate::persist::{NodeProblem, PersistError};
+                ensure_readable()?;
+                let mut lines = Vec::new();
+                for (i, text) in reader.lines().enumerate() {
+                    let text = text?;
+                    if !text.is_empty() {
+                        let tokens = $crate::persist::split_line(&text).map_err(|message| PersistError::Format { line: i + 1, message: message.to_owned() })?;
+                        lines.push((i + 1, tokens));
+                    }
+                }
+                if lines.first().is_none_or(|(_, tokens)| tokens[..] != [$crate::persist::HEADER]) {
+                    return Err(PersistError::Format { line: 1, message: "not a saved decision-leaf tree".to_owned() });
+                }
+                // (kind, type) of every saved field, by its current name
+                let mut fields: HashMap<String, (String, String)> = HashMap::new();
+                let mut pos = 1;
+                while let Some((line, tokens)) = lines.get(pos).filter(|(_, t)| t[0] == "field" || t[0] == "class") {
+                    match &tokens[..] {
+                        [_, name, kind, ty] if tokens[0] == "field" => {
+                            fields.insert(options.field_name(name).to_owned(), (kind.clone(), ty.clone()));
+                        }
+                        [_, _] if tokens[0] == "class" => {}
+                        _ => return Err(PersistError::Format { line: *line, message: "malformed field line".to_owned() }),
+                    }
+                    pos += 1;
+                }
+
+                let mut profile = Vec::new();
+                while let Some(entry) = lines.get(pos).filter(|(_, t)| $crate::persist::PROFILE_KINDS.contains(&t[0].as_str())) {
+                    profile.push(entry.clone());
+                    pos += 1;
+                }
+
+                fn read_question(name: &str, saved: Option<&(String, String)>, values: &[String], options: &$crate::persist::LoadOptions) -> Result<Question, String> {
+                    let (kind, ty) = match saved {
+                        Some((kind, ty)) => (kind.as_str(), ty.as_str()),
+                        None => return Err(format!("field `{}` is missing from the saved fields", name)),
+                    };
+                    $(if name == stringify!($field_name) {
+                        if (kind, ty) != ("enum", stringify!($field_type)) {
+                            return Err(format!("field `{}` was {} {}, is enum {}", name, kind, ty, stringify!($field_type)));
+                        }
+                        return values
+                            .iter()
+                            .map(|v| $crate::__parse_field!($field_type, options.enum_value(name, v)).ok_or_else(|| format!("{:?} is not a {}", v, stringify!($field_type))))
+                            .collect::<Result<Vec<_>, _>>()
+                            .map(Question::$field_name);
+                    })*
+                    $(if name == stringify!($number_field_name) {
+                        if (kind, ty) != ("number", stringify!($number_field_type)) {
+                            return Err(format!("field `{}` was {} {}, is number {}", name, kind, ty, stringify!($number_field_type)));
+                        }
+                        return match values {
+                            [v] => $crate::__parse_field!($number_field_type, v).map(Question::$number_field_name).ok_or_else(|| format!("{:?} is not a {}", v, stringify!($number_field_type))),
+                            _ => Err(format!("field `{}` needs one threshold, got {}", name, values.len())),
+                        };
+                    })*
+                    let _ = (values, options);
+                    Err(format!("field `{}` is not in the layout", name))
+                }
+
+                // None if the node or one below it has a problem; reading goes on so
+                // every problem is found
+                fn read(lines: &[(usize, Vec<String>)], pos: &mut usize, next_id: &mut usize, fields: &HashMap<String, (String, String)>, options: &$crate::persist::LoadOptions, problems: &mut Vec<NodeProblem>) -> Result<Option<Node>, PersistError> {
+                    let id = *next_id;
+                    *next_id += 1;
+                    let (line, tokens) = match lines.get(*pos) {
+                        Some((line, tokens)) => (*line, tokens),
+                        None => return Err(PersistError::Format { line: lines.last().map_or(1, |(line, _)| line + 1), message: "the tree ends early".to_owned() }),
+                    };
+                    *pos += 1;
+                    let format_error = |message: &str| PersistError::Format { line, message: message.to_owned() };
+                    match tokens[0].as_str() {
+                        "leaf" if tokens.len() % 2 == 1 => {
+                            let mut counts = $crate::ClassCounts::new();
+                            let mut valid = true;
+                            for pair in tokens[1..].chunks(2) {
+                                let n: u64 = pair[1].parse().map_err(|_| format_error("count is not a number"))?;
+                                match $crate::__parse_field!($class, options.enum_value("class", &pair[0])) {
+                                    Some(class) => counts.add(class, n),
+                                    None => {
+                                        problems.push(NodeProblem { node: id, message: format!("class {:?} is not a {}", pair[0], stringify!($class)) });
+                                        valid = false;
+                                    }
+                                }
+                            }
+                            Ok(valid.then_some(Node::Leaf(counts)))
+                        }
+                        "split" if tokens.len() >= 3 => {
+                            let name = options.field_name(&tokens[1]);
+                            let question = read_question(name, fields.get(name), &tokens[2..], options)
+                                .map_err(|message| problems.push(NodeProblem { node: id, message }))
+                                .ok();
+                            let true_branch = read(lines, pos, next_id, fields, options, problems)?;
+                            let false_branch = read(lines, pos, next_id, fields, options, problems)?;
+                            Ok(match (question, true_branch, false_branch) {
+                                (Some(q), Some(t), Some(f)) => Some(Node::new_decision_node(q, t, f)),
+                                _ => None,
+                            })
+                        }
+                        _ => Err(format_error("expected a split or leaf line")),
+                    }
+                }
+
+                let mut problems = Vec::new();
+                let root = read(&lines, &mut pos, &mut 0, &fields, options, &mut problems)?;
+                if let Some((line, _)) = lines.get(pos) {
+                    return Err(PersistError::Format { line: *line, message: "text after the tree".to_owned() });
+                }
+                root.map(|root| (root, profile)).ok_or(PersistError::Schema(problems))
+            }
+
+            impl TrainingProfile {
+                // the profile lines of the persist format, written between the class line and
+                // the tree
+                fn write<W: std::io::Write>(&self, out: &mut W) -> std::io::Result<()> {
+                    use $crate::persist::escape;
+                    writeln!(out, "rows\t{}", self.rows)?;
+                    for (label, n) in ordered_counts(&self.class_counts) {
+                        writeln!(out, "prior\t{}\t{}", escape(&label), n)?;
+                    }
+                    if let Some(observed) = &self.observed {
+                        $(write!(out, "observed\t{}", stringify!($field_name))?;
+                        for v in &observed.$field_name {
+                            write!(out, "\t{}", escape(&format!("{:?}", v)))?;
+                        }
+                        writeln!(out)?;)*
+                        $(writeln!(
+                            out,
+                            "range\t{}\t{}\t{}",
+                            stringify!($number_field_name),
+                            escape(&format!("{:?}", observed.$number_field_name.0)),
+                            escape(&format!("{:?}", observed.$number_field_name.1))
+                        )?;)*
+                    }
+                    Ok(())
+                }
+
+                // None when the file has no profile lines; the observed values stay
+                // unavailable unless every field of the layout has its line
+                fn read(lines: &[(usize, Vec<String>)], options: &$crate::persist::LoadOptions) -> Result<Option<TrainingProfile>, $crate::persist::PersistError> {
+                    use $crate::persist::PersistError;
+                    let mut rows = None;
+                    let mut class_counts = $crate::ClassCounts::new();
+                    $(let mut $field_name: Option<Vec<$field_type>> = None;)*
+                    $(let mut $number_field_name: Option<($number_field_type, $number_field_type)> = None;)*
+                    for (line, tokens) in lines {
+                        let format_error = |message: String| PersistError::Format { line: *line, message };
+                        let kind = tokens[0].as_str();
+                        let field = tokens.get(1).map_or("", |name| options.field_name(name));
+                        match (kind, &tokens[1..]) {
+                            ("rows", [n]) => rows = Some(n.parse().map_err(|_| format_error("row count is not a number".to_owned()))?),
+                            ("prior", [label, n]) => {
+                                let n: u64 = n.parse().map_err(|_| format_error("count is not a number".to_owned()))?;
+                                let class = $crate::__parse_field!($class, options.enum_value("class", label)).ok_or_else(|| format_error(format!("class {:?} is not a {}", label, stringify!($class))))?;
+                                class_counts.add(class, n);
+                            }
+                            $(("observed", [_, values @ ..]) if field == stringify!($field_name) => {
+                                $field_name = Some(values
+                                    .iter()
+                                    .map(|v| $crate::__parse_field!($field_type, options.enum_value(field, v)).ok_or_else(|| format_error(format!("{:?} is not a {}", v, stringify!($field_type)))))
+                                    .collect::<Result<_, _>>()?);
+                            })*
+                            $(("range", [_, low, high]) if field == stringify!($number_field_name) => {
+                                let parse = |v: &String| $crate::__parse_field!($number_field_type, v).ok_or_else(|| format_error(format!("{:?} is not a {}", v, stringify!($number_field_type))));
+                                $number_field_name = Some((parse(low)?, parse(high)?));
+                            })*
+                            // fields no longer in the layout
+                            ("observed", [_, ..]) | ("range", [_, _, _]) => {}
+                            _ => return Err(format_error(format!("malformed {} line", kind))),
+                        }
+                    }
+                    let Some(rows) = rows else {
+                        return match lines.first() {
+                            Some((line, _)) => Err(PersistError::Format { line: *line, message: "profile without a rows line".to_owned() }),
+                            None => Ok(None),
+                        };
+                    };
+                    let observed = (|| Some(ObservedValues {
+                        $($field_name: $field_name?,)*
+                        $($number_field_name: $number_field_name?,)*
+                    }))();
+                    Ok(Some(TrainingProfile { rows, class_counts, observed }))
+                }
+            }
+
+            impl TrainedTree {
+                // Node::save with the training profile between the fields and the tree;
+                // Node::load reads the result as well
+                pub fn save<W: std::io::Write>(&self, out: &mut W) -> std::io::Result<()> {
+                    save_with(&self.tree, Some(&self.profile), out)
+                }
+
+                // reads a tree saved by TrainedTree::save or Node::save; for the latter the
+                // profile is recovered from the leaves, without observed values
+                pub fn load<R: std::io::BufRead>(reader: R, options: &$crate::persist::LoadOptions) -> Result<TrainedTree, $crate::persist::PersistError> {
+                    let (tree, lines) = load_saved(reader, options)?;
+                    let profile = match TrainingProfile::read(&lines, options)? {
+                        Some(profile) => profile,
+                        None => TrainingProfile::from_tree(&tree),
+                    };
+                    Ok(TrainedTree { tree, profile })
+                }
+            }
+
+            impl Imputer {
+                // writes the fill-in values so serving can impute exactly like training
+                pub fn save<W: std::io::Write>(&self, out: &mut W) -> std::io::Result<()> {
+                    use $crate::persist::escape;
+                    writeln!(out, "{}", $crate::persist::IMPUTER_HEADER)?;
+                    $(writeln!(out, "value\t{}\t{}", stringify!($field_name), escape(&format!("{:?}", self.$field_name)))?;)*
+                    $(writeln!(out, "value\t{}\t{}", stringify!($input_name), escape(&format!("{:?}", self.$input_name)))?;)*
+                    Ok(())
+                }
+
+                // reads values written by save, with the same renaming options as Node::load
+                pub fn load<R: std::io::BufRead>(reader: R, options: &$crate::persist::LoadOptions) -> Result<Imputer, $crate::persist::PersistError> {
+                    use $crate::persist::PersistError;
+                    ensure_readable()?;
+                    let mut values: HashMap<String, (usize, String)> = HashMap::new();
+                    let mut header = false;
+                    for (i, text) in reader.lines().enumerate() {
+                        let text = text?;
+                        let format_error = |message: &str| PersistError::Format { line: i + 1, message: message.to_owned() };
+                        if text.is_empty() {
+                            continue;
+                        }
+                        if !header {
+                            if text != $crate::persist::IMPUTER_HEADER {
+                                return Err(format_error("not a saved decision-leaf imputer"));
+                            }
+                            header = true;
+                            continue;
+                        }
+                        match &$crate::persist::split_line(&text).map_err(format_error)?[..] {
+                            [kind, field, value] if kind == "value" => {
+                                let field = options.field_name(field).to_owned();
+                                let value = options.enum_value(&field, value).to_owned();
+                                values.insert(field, (i + 1, value));
+                            }
+                            _ => return Err(format_error("malformed value line")),
+                        }
+                    }
+                    fn parse<T>(values: &HashMap<String, (usize, String)>, field: &str, parse: fn(&str) -> Option<T>) -> Result<T, PersistError> {
+                        match values.get(field) {
+                            Some((line, value)) => parse(value).ok_or_else(|| PersistError::Format {
+                                line: *line,
+                                message: format!("{:?} is not a valid {}", value, std::any::type_name::<T>()),
+                            }),
+                            None => Err(PersistError::Format { line: 1, message: format!("no value for field `{}`", field) }),
+                        }
+                    }
+                    Ok(Imputer {
+                        $($field_name: parse(&values, stringify!($field_name), |text| $crate::__parse_field!($field_type, text))?,)*
+                        $($input_name: parse(&values, stringify!($input_name), |text| $crate::__parse_field!($input_type, text))?,)*
+                    })
+                }
+            }
+
+            impl Pipeline {
+                // the steps and the tree in one file, each nested file after a line giving
+                // its length (persist::PIPELINE_HEADER)
+                pub fn save<W: std::io::Write>(&self, out: &mut W) -> std::io::Result<()> {
+                    fn nested<W: std::io::Write>(out: &mut W, line: &str, text: Vec<u8>) -> std::io::Result<()> {
+                        let text = String::from_utf8(text).expect("saved files are text");
+                        writeln!(out, "{}\t{}", line, text.lines().count())?;
+                        out.write_all(text.as_bytes())
+                    }
+                    writeln!(out, "{}", $crate::persist::PIPELINE_HEADER)?;
+                    for step in &self.steps {
+                        match step {
+                            FittedStep::Impute(imputer) => {
+                                let mut text = Vec::new();
+                                imputer.save(&mut text)?;
+                                nested(out, "step\timpute", text)?;
+                            }
+                            FittedStep::Rebalance { seed } => writeln!(out, "step\trebalance\t{}", seed)?,
+                        }
+                    }
+                    let mut text = Vec::new();
+                    self.tree.save(&mut text)?;
+                    nested(out, "tree", text)
+                }
+
+                pub fn load<R: std::io::BufRead>(reader: R, options: &$crate::persist::LoadOptions) -> Result<Pipeline, $crate::persist::PersistError> {
+                    use $crate::persist::PersistError;
+                    let lines = reader.lines().collect::<Result<Vec<String>, _>>()?;
+                    let format_error = |line: usize, message: &str| PersistError::Format { line, message: message.to_owned() };
+                    if lines.first().map(String::as_str) != Some($crate::persist::PIPELINE_HEADER) {
+                        return Err(format_error(1, "not a saved decision-leaf pipeline"));
+                    }
+                    // the `n` lines after line `at` (0-based) as one text, and `n`
+                    let nested = |at: usize, n: &str| -> Result<(String, usize), PersistError> {
+                        let n: usize = n.parse().map_err(|_| format_error(at + 1, "line count is not a number"))?;
+                        let body = lines.get(at + 1..at + 1 + n).ok_or_else(|| format_error(at + 1, "file ends inside a nested file"))?;
+                        Ok((body.join("\n"), n))
+                    };
+                    let mut steps = Vec::new();
+                    let mut at = 1;
+                    while at < lines.len() {
+                        let tokens = $crate::persist::split_line(&lines[at]).map_err(|message| format_error(at + 1, message))?;
+                        let tokens: Vec<&str> = tokens.iter().map(String::as_str).collect();
+                        match tokens[..] {
+                            ["step", "impute", n] => {
+                                let (text, n) = nested(at, n)?;
+                                steps.push(FittedStep::Impute(Imputer::load(text.as_bytes(), options).map_err(|e| e.offset(at + 1))?));
+                                at += 1 + n;
+                            }
+                            ["step", "rebalance", seed] => {
+                                let seed = seed.parse().map_err(|_| format_error(at + 1, "seed is not a number"))?;
+                                steps.push(FittedStep::Rebalance { seed });
+                                at += 1;
+                            }
+                            ["tree", n] => {
+                                let (text, _) = nested(at, n)?;
+                                let tree = Node::load(text.as_bytes(), options).map_err(|e| e.offset(at + 1))?;
+                                return Ok(Pipeline { steps, tree });
+                            }
+                            _ => return Err(format_error(at + 1, "malformed pipeline line")),
+                        }
+                    }
+                    Err(format_error(lines.len(), "pipeline without a tree"))
+                }
+            }
+        }
+
+        $crate::__ensemble! {
+            // trees combined by soft voting
+            #[derive(Clone)]
+            pub struct Forest {
+                pub trees: Vec<Node>,
+            }
+
+            impl Forest {
+                // mean of the trees' leaf distributions for `point`
+                pub fn vote(&self, point: &DataPoint) -> $crate::ClassCounts<$class, f64> {
+                    let mut votes = $crate::ClassCounts::new();
+                    for tree in &self.trees {
+                        let leaf = classify(point, tree);
+                        for label in leaf.classes() {
+                            votes.add(label.clone(), leaf.proba(label) / self.trees.len() as f64);
+                        }
+                    }
+                    votes
+                }
+                pub fn predict(&self, point: &DataPoint) -> Option<$class> {
+                    self.vote(point).argmax().cloned()
+                }
+                // Hard votes: how many trees predict each class for `point`. Its argmax is the
+                // majority vote, which can differ from predict's soft vote.
+                pub fn classify(&self, point: &DataPoint) -> $crate::ClassCounts<$class> {
+                    let mut votes = $crate::ClassCounts::new();
+                    for tree in &self.trees {
+                        votes.add(predict(point, tree), 1);
+                    }
+                    votes
+                }
+            }
+
+            impl Node {
+                // Weight of evidence of every leaf, by preorder id: ln((leaf positives / all
+                // positives) / (leaf negatives / all negatives)), counting the training rows stored
+                // in the leaves. `smoothing` is added to both counts of every leaf so leaves
+                // without rows of a class get a finite score; with 0 they score +-infinity.
+                // Only for trees of exactly two classes, `positive` being one of them and the other
+                // negative.
+                pub fn leaf_woe(&self, positive: &$class, smoothing: f64) -> Result<HashMap<usize, f32>, $crate::DecisionLeafError> {
+                    $crate::error::ensure(smoothing.is_finite() && smoothing >= 0.0, "smoothing", smoothing, "at least 0 and finite")?;
+                    let classes = self.classes();
+                    $crate::error::ensure(classes.contains(positive), "positive", positive, "one of the tree's classes")?;
+                    $crate::error::ensure(classes.len() == 2, "tree", classes, "exactly two classes")?;
+                    let cells: Vec<(usize, f64, f64)> = self
+                        .paths()
+                        .map(|path| {
+                            let positives = path.distribution.get(positive) as f64;
+                            let negatives = path.distribution.total() as f64 - positives;
+                            (path.leaf_id, positives + smoothing, negatives + smoothing)
+                        })
+                        .collect();
+                    let total_positives: f64 = cells.iter().map(|c| c.1).sum();
+                    let total_negatives: f64 = cells.iter().map(|c| c.2).sum();
+                    Ok(cells
+                        .into_iter()
+                        .map(|(leaf, positives, negatives)| {
+                            (leaf, $crate::stats::weight_of_evidence(positives, negatives, total_positives, total_negatives) as f32)
+                        })
+                        .collect())
+                }
+                // a Scorecard of this tree's leaf_woe
+                pub fn scorecard(&self, positive: &$class, smoothing: f64) -> Result<Scorecard, $crate::DecisionLeafError> {
+                    Ok(Scorecard { trees: vec![(self.clone(), subtree_sizes(self), self.leaf_woe(positive, smoothing)?)] })
+                }
+            }
+
+            impl Forest {
+                // a Scorecard adding up the leaf_woe of every tree
+                pub fn scorecard(&self, positive: &$class, smoothing: f64) -> Result<Scorecard, $crate::DecisionLeafError> {
+                    let trees = self
+                        .trees
+                        .iter()
+                        .map(|tree| Ok((tree.clone(), subtree_sizes(tree), tree.leaf_woe(positive, smoothing)?)))
+                        .collect::<Result<_, $crate::DecisionLeafError>>()?;
+                    Ok(Scorecard { trees })
+                }
+            }
+
+            // Log-odds scores for points: the weight of evidence of the leaf reached in each tree,
+            // summed over the trees.
+            #[derive(Clone)]
+            pub struct Scorecard {
+                // each tree with its subtree_sizes and leaf_woe
+                trees: Vec<(Node, Vec<usize>, HashMap<usize, f32>)>,
+            }
+
+            impl Scorecard {
+                pub fn score(&self, point: &DataPoint) -> f32 {
+                    self.trees.iter().map(|(tree, sizes, woe)| woe[&leaf_id(point, tree, sizes)]).sum()
+                }
+                // leaf_woe of every tree, in order
+                pub fn leaf_woe(&self) -> impl Iterator<Item = &HashMap<usize, f32>> {
+                    self.trees.iter().map(|(_, _, woe)| woe)
+                }
+            }
+        }
+
+        $crate::__metrics! {
+            // Split conformal prediction sets from a tree (conformal module): calibrated on rows the
+            // tree wasn't trained on, a set holds the true class of at least 1 - alpha of the
+            // points drawn like them.
+            #[derive(Clone)]
+            pub struct ConformalClassifier {
+                tree: Node,
+                // the tree's classes and the calibration rows', ordered by label
+                classes: Vec<$class>,
+                pub calibration: $crate::conformal::Calibration,
+            }
+
+            impl ConformalClassifier {
+                // scores every row of `calib_data` 1 - the probability its leaf gives its class;
+                // alpha is the allowed miscoverage, strictly between 0 and 1
+                pub fn calibrate(tree: &Node, calib_data: &Vec<DataPoint>, alpha: f64) -> Result<Self, $crate::DecisionLeafError> {
+                    $crate::error::ensure(alpha > 0.0 && alpha < 1.0, "alpha", alpha, "strictly between 0 and 1")?;
+                    $crate::error::ensure(!calib_data.is_empty(), "calib_data", calib_data.len(), "at least one row")?;
+                    let scores = calib_data.iter().map(|p| 1.0 - classify(p, tree).proba(&p.class)).collect();
+                    let mut classes = tree.classes();
+                    for point in calib_data {
+                        if !classes.contains(&point.class) {
+                            classes.push(point.class.clone());
+                        }
+                    }
+                    classes.sort_by_key(|c| format!("{:?}", c));
+                    Ok(Self {
+                        tree: tree.clone(),
+                        classes,
+                        calibration: $crate::conformal::Calibration::new(scores, alpha),
+                    })
+                }
+                // the classes scoring at most the calibrated threshold, ties included, in label order
+                pub fn predict_set(&self, point: &DataPoint) -> Vec<$class> {
+                    let leaf = classify(point, &self.tree);
+                    self.classes.iter().filter(|c| self.calibration.includes(1.0 - leaf.proba(c))).cloned().collect()
+                }
+                // predict_set deciding ties with one draw from `rng`; possibly empty
+                pub fn predict_set_randomized(&self, point: &DataPoint, rng: &mut $crate::rng::Rng) -> Vec<$class> {
+                    let leaf = classify(point, &self.tree);
+                    let u = rng.next_f64();
+                    self.classes.iter().filter(|c| self.calibration.includes_randomized(1.0 - leaf.proba(c), u)).cloned().collect()
+                }
+                // how often predict_set holds the class of the rows of `test_data`
+                pub fn coverage(&self, test_data: &Vec<DataPoint>) -> $crate::conformal::Coverage {
+                    let mut coverage = $crate::conformal::Coverage::default();
+                    for point in test_data {
+                        let set = self.predict_set(point);
+                        coverage.add(set.len(), set.contains(&point.class));
+                    }
+                    coverage
+                }
+                // coverage of predict_set_randomized, drawing from Rng::new(seed)
+                pub fn coverage_randomized(&self, test_data: &Vec<DataPoint>, seed: u64) -> $crate::conformal::Coverage {
+                    let mut rng = $crate::rng::Rng::new(seed);
+                    let mut coverage = $crate::conformal::Coverage::default();
+                    for point in test_data {
+                        let set = self.predict_set_randomized(point, &mut rng);
+                        coverage.add(set.len(), set.contains(&point.class));
+                    }
+                    coverage
+                }
+            }
+        }
+
+        // The generated types only hold values of the layout's types, so they are Send + Sync
+        // when those are. Checked here so a change breaking that fails to compile instead of
+        // surprising a multithreaded user.
+        const _: fn() = || {
+            fn assert_send_sync<T: Send + Sync>() {}
+            assert_send_sync::<DataPoint>();
+            assert_send_sync::<Field>();
+            assert_send_sync::<Question>();
+            assert_send_sync::<Node>();
+            assert_send_sync::<Pipeline>();
+        };
+        $crate::__ensemble! {
+            const _: fn() = || {
+                fn assert_send_sync<T: Send + Sync>() {}
+                assert_send_sync::<Forest>();
+                assert_send_sync::<Scorecard>();
+            };
+        }
+        $crate::__metrics! {
+            const _: fn() = || {
+                fn assert_send_sync<T: Send + Sync>() {}
+                assert_send_sync::<ConformalClassifier>();
+            };
+        }
+
+        $crate::__ensemble! {
+            // Bagging: every tree grows with `config` on its own bootstrap resample of `data`.
+            pub fn build_forest(data: &Vec<DataPoint>, n_trees: usize, seed: u64, config: &TreeConfig) -> Result<Forest, $crate::DecisionLeafError> {
+                build_forest_oob(data, $crate::ensemble::NTrees::Fixed(n_trees), seed, config).map(|(forest, _)| forest)
+            }
+
+            // build_forest, resampling the rows as `resample` says. Resample::None grows every
+            // tree on all of `data`, so each is the tree build_tree_with would grow.
+            pub fn build_forest_with(data: &Vec<DataPoint>, n_trees: usize, seed: u64, config: &TreeConfig, resample: $crate::ensemble::Resample) -> Result<Forest, $crate::DecisionLeafError> {
+                match resample {
+                    $crate::ensemble::Resample::Bootstrap => build_forest(data, n_trees, seed, config),
+                    $crate::ensemble::Resample::Fraction(fraction) => {
+                        $crate::error::ensure(fraction > 0.0 && fraction <= 1.0, "fraction", fraction, "above 0 and at most 1")?;
+                        let rows = ((data.len() as f64 * fraction).round() as usize).max(1);
+                        grow_bagged(data, $crate::ensemble::NTrees::Fixed(n_trees), seed, config, rows).map(|(forest, _)| forest)
+                    }
+                    $crate::ensemble::Resample::None => {
+                        config.validate()?;
+                        $crate::error::ensure(n_trees > 0, "n_trees", n_trees, "at least 1")?;
+                        Ok(Forest { trees: (0..n_trees).map(|t| grow_tree(data, &tree_config(config, t), 0)).collect() })
+                    }
+                }
+            }
+
+            // the config of the forest's tree `t`: its own max_features draws
+            fn tree_config(config: &TreeConfig, t: usize) -> TreeConfig {
+                TreeConfig { feature_seed: config.feature_seed.wrapping_add(t as u64), ..config.clone() }
+            }
+
+            // build_forest, with the out-of-bag accuracy after every tree. Each tree classifies
+            // only the rows left out of its resample, once; NTrees::Auto stops at the plateau.
+            pub fn build_forest_oob(data: &Vec<DataPoint>, n_trees: $crate::ensemble::NTrees, seed: u64, config: &TreeConfig) -> Result<(Forest, $crate::ensemble::OobCurve), $crate::DecisionLeafError> {
+                grow_bagged(data, n_trees, seed, config, data.len())
+            }
+
+            // build_forest_oob with resamples of `rows` rows
+            fn grow_bagged(data: &Vec<DataPoint>, n_trees: $crate::ensemble::NTrees, seed: u64, config: &TreeConfig, rows: usize) -> Result<(Forest, $crate::ensemble::OobCurve), $crate::DecisionLeafError> {
+                config.validate()?;
+                $crate::error::ensure(!data.is_empty(), "data", data.len(), "at least one row")?;
+                match n_trees {
+                    $crate::ensemble::NTrees::Fixed(n) => $crate::error::ensure(n > 0, "n_trees", n, "at least 1")?,
+                    $crate::ensemble::NTrees::Auto { max, window, tolerance } => {
+                        $crate::error::ensure(max > 0, "max", max, "at least 1")?;
+                        $crate::error::ensure(window > 0, "window", window, "at least 1")?;
+                        $crate::error::ensure(tolerance >= 0.0, "tolerance", tolerance, "at least 0")?;
+                    }
+                }
+                let mut rng = $crate::rng::Rng::new(seed);
+                let mut tracker = $crate::ensemble::OobTracker::new(data.len());
+                let mut trees = Vec::new();
+                while !tracker.should_stop(n_trees) {
+                    let mut in_bag = vec![false; data.len()];
+                    let sample: Vec<Row> = (0..rows)
+                        .map(|_| {
+                            let i = rng.gen_index(data.len());
+                            in_bag[i] = true;
+                            Row(&data[i], 1)
+                        })
+                        .collect();
+                    let tree = grow_observed(&sample, &tree_config(config, trees.len()), 0, &mut ());
+                    tracker.add_tree(data.iter().enumerate().filter(|(i, _)| !in_bag[*i]).map(|(i, p)| (i, classify(p, &tree), &p.class)));
+                    trees.push(tree);
+                }
+                Ok((Forest { trees }, tracker.into_curve(n_trees)))
+            }
+
+            // Every tree sees all rows but only `fields_per_tree` fields drawn at random (from
+            // config.fields if set), fixed for the whole tree.
+            pub fn build_feature_bagged(data: &Vec<DataPoint>, n_trees: usize, fields_per_tree: usize, seed: u64, config: &TreeConfig) -> Result<Forest, $crate::DecisionLeafError> {
+                config.validate()?;
+                let all_fields = config.fields.clone().unwrap_or_else(|| Field::ALL.to_vec());
+                $crate::error::ensure(n_trees > 0, "n_trees", n_trees, "at least 1")?;
+                $crate::error::ensure(fields_per_tree > 0 && fields_per_tree <= all_fields.len(), "fields_per_tree", fields_per_tree, "between 1 and the number of fields")?;
+                let mut rng = $crate::rng::Rng::new(seed);
+                let mut trees = Vec::with_capacity(n_trees);
+                for _ in 0..n_trees {
+                    let mut fields = all_fields.clone();
+                    for i in 0..fields_per_tree {
+                        let j = i + rng.gen_index(fields.len() - i);
+                        fields.swap(i, j);
+                    }
+                    fields.truncate(fields_per_tree);
+                    let tree_config = TreeConfig {
+                        fields: Some(fields),
+                        ..config.clone()
+                    };
+                    trees.push(grow_tree(data, &tree_config, 0));
+                }
+                Ok(Forest { trees })
             }
 
-            let question = question.unwrap();
-
-            let (true_rows, false_rows) = partition(&question, &data);
+            // Trains one tree with `config` to mimic `forest`. Synthetic rows are training rows
+            // with each input field swapped, with probability 1/2, for the same field of another
+            // random row, labeled with the forest's prediction. A row is kept with probability
+            // equal to the forest's confidence in it, so uncertain regions weigh less. Fidelity
+            // is measured on fresh synthetic rows and on `data`.
+            pub fn distill(forest: &Forest, data: &Vec<DataPoint>, config: &TreeConfig, seed: u64) -> Result<(Node, $crate::ensemble::FidelityReport), $crate::DecisionLeafError> {
+                config.validate()?;
+                $crate::error::ensure(!forest.trees.is_empty(), "forest", forest.trees.len(), "at least one tree")?;
+                $crate::error::ensure(!data.is_empty(), "data", data.len(), "at least one row")?;
+                const SYNTHETIC_PER_ROW: usize = 20;
+                const HELD_OUT_PER_ROW: usize = 5;
+
+                let mut rng = $crate::rng::Rng::new(seed);
+                let synthesize = |rng: &mut $crate::rng::Rng| -> ($crate::ClassCounts<$class, f64>, DataPoint) {
+                    let mut point = data[rng.gen_index(data.len())].clone();
+                    $(if rng.next_f64() < 0.5 {
+                        point.$field_name = data[rng.gen_index(data.len())].$field_name.clone();
+                    })*
+                    $(if rng.next_f64() < 0.5 {
+                        point.$input_name = data[rng.gen_index(data.len())].$input_name.clone();
+                    })*
+                    point.compute_derived();
+                    (forest.vote(&point), point)
+                };
+                let mut synthetic: Vec<DataPoint> = Vec::new();
+                for _ in 0..data.len() * SYNTHETIC_PER_ROW {
+                    let (votes, mut point) = synthesize(&mut rng);
+                    let Some(label) = votes.argmax().cloned() else {
+                        continue;
+                    };
+                    if rng.next_f64() < votes.proba(&label) {
+                        point.class = label;
+                        synthetic.push(point);
+                    }
+                }
+                let tree = grow_tree(&synthetic, config, 0);
 
-            let true_branch = build_tree(&true_rows);
-            let false_branch = build_tree(&false_rows);
+                let agrees = |point: &DataPoint| classify(point, &tree).argmax() == forest.vote(point).argmax();
+                let held_out = data.len() * HELD_OUT_PER_ROW;
+                let agreement = (0..held_out).filter(|_| agrees(&synthesize(&mut rng).1)).count() as f64 / held_out as f64;
+                let data_agreement = data.iter().filter(|p| agrees(p)).count() as f64 / data.len() as f64;
+                Ok((tree, $crate::ensemble::FidelityReport {
+                    synthetic_rows: synthetic.len(),
+                    agreement,
+                    data_agreement,
+                }))
+            }
 
-            return Node::new_decision_node(question.clone(), true_branch, false_branch);
+            // Grows `b` trees with `config` on bootstrap resamples of `data` and reports how
+            // consistently they pick the same fields (and thresholds) at each depth.
+            pub fn stability_analysis(data: &Vec<DataPoint>, b: usize, seed: u64, config: &TreeConfig) -> Result<$crate::stability::StabilityReport, $crate::DecisionLeafError> {
+                config.validate()?;
+                $crate::error::ensure(b >= 2, "b", b, "at least 2")?;
+                $crate::error::ensure(!data.is_empty(), "data", data.len(), "at least one row")?;
+                fn collect(node: &Node, depth: usize, splits: &mut Vec<(usize, String, Option<f64>)>) {
+                    if let Node::Decision { question, true_branch, false_branch, .. } = node {
+                        let threshold = match question {
+                            $(Question::$field_name(_) => None,)*
+                            $(Question::$number_field_name(v) => Some($crate::Number::to_f64(*v)),)*
+                        };
+                        splits.push((depth, format!("{:?}", question.field()), threshold));
+                        collect(true_branch, depth + 1, splits);
+                        collect(false_branch, depth + 1, splits);
+                    }
+                }
+                let mut rng = $crate::rng::Rng::new(seed);
+                let mut trees = Vec::with_capacity(b);
+                for _ in 0..b {
+                    let sample: Vec<Row> = (0..data.len()).map(|_| Row(&data[rng.gen_index(data.len())], 1)).collect();
+                    let tree = grow_observed(&sample, config, 0, &mut ());
+                    let mut splits = Vec::new();
+                    collect(&tree, 0, &mut splits);
+                    trees.push($crate::stability::TreeSplits {
+                        fingerprint: tree.fingerprint(),
+                        splits,
+                    });
+                }
+                Ok($crate::stability::analyze(&trees))
+            }
         }
-        pub fn classify(point: &DataPoint, node: Node) -> HashMap<$class, i32> {
+
+        // The counts of the leaf `point` reaches in `node`. Any HasFields point works, so
+        // values held in another struct are classified without copying them into a DataPoint;
+        // panics if the point doesn't know a field the path asks about.
+        pub fn classify<'a>(point: &impl HasFields, node: &'a Node) -> &'a $crate::ClassCounts<$class> {
             match node {
                 Node::Leaf(x) => x,
                 Node::Decision {
                     question,
                     true_branch,
                     false_branch,
+                    ..
                 } => {
-                    if check(&question, point) {
-                        return classify(point, *true_branch);
+                    if question.evaluate(point) {
+                        classify(point, true_branch)
                     } else {
-                        classify(point, *false_branch)
+                        classify(point, false_branch)
+                    }
+                }
+            }
+        }
+        // The majority class of the leaf `point` reaches. Ties go to the class whose Debug
+        // label sorts first, as in ClassCounts::argmax. Panics on a leaf without counts,
+        // which training never produces.
+        pub fn predict(point: &impl HasFields, tree: &Node) -> $class {
+            classify(point, tree).argmax().cloned().expect("leaves hold at least one row")
+        }
+        // The leaf counts `tree` gives each of `points`, in input order. Large batches are
+        // routed a node at a time: the indices of the points reaching a decision node are
+        // partitioned in place between its branches, as training partitions rows, so each
+        // node's question is looked up once per batch instead of once per point.
+        pub fn classify_batch<'a>(points: &[DataPoint], tree: &'a Node) -> Vec<&'a $crate::ClassCounts<$class>> {
+            // below this, walking the tree per point is as fast
+            const MIN_BATCH_ROUTING: usize = 64;
+            if points.len() < MIN_BATCH_ROUTING {
+                return points.iter().map(|point| classify(point, tree)).collect();
+            }
+            fn route<'a>(node: &'a Node, points: &[DataPoint], indices: &mut [usize], out: &mut [Option<&'a $crate::ClassCounts<$class>>]) {
+                match node {
+                    Node::Leaf(x) => {
+                        for &i in indices.iter() {
+                            out[i] = Some(x);
+                        }
+                    }
+                    Node::Decision { question, true_branch, false_branch, .. } => {
+                        let mut split = 0;
+                        for j in 0..indices.len() {
+                            if question.evaluate(&points[indices[j]]) {
+                                indices.swap(split, j);
+                                split += 1;
+                            }
+                        }
+                        let (true_indices, false_indices) = indices.split_at_mut(split);
+                        route(true_branch, points, true_indices, out);
+                        route(false_branch, points, false_indices, out);
+                    }
+                }
+            }
+            let mut indices: Vec<usize> = (0..points.len()).collect();
+            let mut out = vec![None; points.len()];
+            route(tree, points, &mut indices, &mut out);
+            out.into_iter().map(|leaf| leaf.expect("every point reaches a leaf")).collect()
+        }
+        // every class seen in any leaf, ordered by label so sampling is reproducible
+        fn class_universe(node: &Node) -> Vec<$class> {
+            fn collect(node: &Node, set: &mut HashSet<$class>) {
+                match node {
+                    Node::Leaf(x) => set.extend(x.classes().cloned()),
+                    Node::Decision {
+                        true_branch,
+                        false_branch,
+                        ..
+                    } => {
+                        collect(true_branch, set);
+                        collect(false_branch, set);
+                    }
+                }
+            }
+            let mut set: HashSet<$class> = HashSet::new();
+            collect(node, &mut set);
+            let mut classes: Vec<$class> = set.into_iter().collect();
+            classes.sort_by_key(|c| format!("{:?}", c));
+            classes
+        }
+        // (posterior mean, lower, upper) under a Dirichlet(1 + counts) posterior over the tree's classes
+        pub fn classify_with_uncertainty(point: &DataPoint, tree: &Node, level: f32) -> Result<HashMap<$class, (f32, f32, f32)>, $crate::DecisionLeafError> {
+            $crate::error::ensure(level > 0.0 && level < 1.0, "level", level, "a credible level strictly between 0 and 1")?;
+            let counts = classify(point, tree);
+            let classes = class_universe(tree);
+            let alpha_total = (classes.len() as u64 + counts.total()) as f64;
+            let mut result: HashMap<$class, (f32, f32, f32)> = HashMap::new();
+            for label in classes {
+                let alpha = 1.0 + counts.get(&label) as f64;
+                let (estimate, lower, upper) =
+                    $crate::stats::dirichlet_marginal_interval(alpha, alpha_total, level as f64);
+                result.insert(label, (estimate as f32, lower as f32, upper as f32));
+            }
+            Ok(result)
+        }
+        pub fn classify_with_uncertainty_mc(point: &DataPoint, tree: &Node, level: f32, samples: usize, seed: u64) -> Result<HashMap<$class, (f32, f32, f32)>, $crate::DecisionLeafError> {
+            $crate::error::ensure(level > 0.0 && level < 1.0, "level", level, "a credible level strictly between 0 and 1")?;
+            $crate::error::ensure(samples > 0, "samples", samples, "at least 1")?;
+            let counts = classify(point, tree);
+            let classes = class_universe(tree);
+            let alphas: Vec<f64> = classes
+                .iter()
+                .map(|label| 1.0 + counts.get(label) as f64)
+                .collect();
+            let mut rng = $crate::rng::Rng::new(seed);
+            let mut draws: Vec<Vec<f64>> = vec![Vec::with_capacity(samples); classes.len()];
+            for _ in 0..samples {
+                let gammas: Vec<f64> = alphas.iter().map(|&a| rng.gamma(a)).collect();
+                let total: f64 = gammas.iter().sum();
+                for (k, g) in gammas.iter().enumerate() {
+                    draws[k].push(g / total);
+                }
+            }
+            let tail = (1.0 - level as f64) / 2.0;
+            let mut result: HashMap<$class, (f32, f32, f32)> = HashMap::new();
+            for (label, mut draw) in classes.into_iter().zip(draws) {
+                draw.sort_by(|a, b| a.partial_cmp(b).unwrap());
+                let mean = draw.iter().sum::<f64>() / samples as f64;
+                let lower = $crate::stats::sorted_quantile(&draw, tail);
+                let upper = $crate::stats::sorted_quantile(&draw, 1.0 - tail);
+                result.insert(label, (mean as f32, lower as f32, upper as f32));
+            }
+            Ok(result)
+        }
+        fn ensure_number_field(name: &'static str, field: Field) -> Result<(), $crate::DecisionLeafError> {
+            match field {
+                $(Field::$field_name => Err($crate::DecisionLeafError::InvalidField {
+                    name,
+                    field: format!("{:?}", field),
+                    expected: "a number field",
+                }),)*
+                $(Field::$number_field_name => Ok(()),)*
+            }
+        }
+        $crate::__export! {
+            fn number_value(point: &DataPoint, field: &Field) -> Option<f64> {
+                match field {
+                    $(Field::$field_name => None,)*
+                    $(Field::$number_field_name => Some($crate::Number::to_f64(point.$number_field_name)),)*
+                }
+            }
+            fn ensure_range(name: &'static str, range: (f64, f64)) -> Result<(), $crate::DecisionLeafError> {
+                $crate::error::ensure(range.0.is_finite() && range.1.is_finite() && range.0 <= range.1, name, range, "a finite (low, high) pair with low <= high")
+            }
+            fn set_number(point: &mut DataPoint, field: &Field, value: f64) {
+                match field {
+                    $(Field::$field_name => panic!("{:?} is not a number field", field),)*
+                    $(Field::$number_field_name => point.$number_field_name = $crate::Number::from_f64(value),)*
+                }
+            }
+            // field_x and field_y must be number fields; all other fields are taken from `fixed`
+            pub fn decision_regions(tree: &Node, field_x: Field, field_y: Field, x_range: (f64, f64), y_range: (f64, f64), resolution: usize, fixed: &DataPoint) -> Result<$crate::Grid<$class>, $crate::DecisionLeafError> {
+                ensure_number_field("field_x", field_x)?;
+                ensure_number_field("field_y", field_y)?;
+                ensure_range("x_range", x_range)?;
+                ensure_range("y_range", y_range)?;
+                $crate::error::ensure(resolution > 0, "resolution", resolution, "at least 1")?;
+                let x_values = $crate::regions::linspace(x_range, resolution);
+                let y_values = $crate::regions::linspace(y_range, resolution);
+                let mut point = fixed.clone();
+                let mut cells: Vec<$class> = Vec::with_capacity(x_values.len() * y_values.len());
+                for &y in &y_values {
+                    set_number(&mut point, &field_y, y);
+                    for &x in &x_values {
+                        set_number(&mut point, &field_x, x);
+                        cells.push(classify(&point, tree).argmax().expect("tree has an empty leaf").clone());
+                    }
+                }
+                Ok($crate::Grid {
+                    x_values,
+                    y_values,
+                    cells,
+                })
+            }
+            pub fn decision_regions_svg(grid: &$crate::Grid<$class>, field_x: Field, field_y: Field, training: &Vec<DataPoint>) -> Result<String, $crate::DecisionLeafError> {
+                ensure_number_field("field_x", field_x)?;
+                ensure_number_field("field_y", field_y)?;
+                let points: Vec<(f64, f64, $class)> = training
+                    .iter()
+                    .filter_map(|p| Some((number_value(p, &field_x)?, number_value(p, &field_y)?, p.class.clone())))
+                    .collect();
+                Ok($crate::regions::render_svg(grid, &points))
+            }
+        }
+        $crate::__serving! {
+            impl $crate::serving::Model<DataPoint> for Node {
+                type Output = $crate::ClassCounts<$class>;
+                fn predict(&self, point: &DataPoint) -> Self::Output {
+                    classify(point, self).clone()
+                }
+            }
+            // Compared and hashed like DataPoint, less the class: the tree never reads it, so
+            // a cached prediction is reused for the same fields under any label. Derived
+            // fields are left out too, they follow from the others.
+            #[derive(Debug, Clone)]
+            pub struct FeatureKey {
+                $($field_name: $field_type,)*
+                $($input_name: $input_type,)*
+            }
+            impl PartialEq for FeatureKey {
+                fn eq(&self, other: &Self) -> bool {
+                    $(self.$field_name == other.$field_name &&)*
+                    $($crate::Number::same(self.$input_name, other.$input_name) &&)*
+                    true
+                }
+            }
+            impl Eq for FeatureKey {}
+            impl std::hash::Hash for FeatureKey {
+                fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+                    $(self.$field_name.hash(state);)*
+                    $($crate::Number::hash_value(self.$input_name, state);)*
+                }
+            }
+            impl $crate::cache::CacheKey for DataPoint {
+                type Key = FeatureKey;
+                fn cache_key(&self) -> FeatureKey {
+                    FeatureKey {
+                        $($field_name: self.$field_name.clone(),)*
+                        $($input_name: self.$input_name,)*
+                    }
+                }
+            }
+            const _: fn() = || {
+                fn assert_send_sync<T: Send + Sync>() {}
+                assert_send_sync::<DataPoint>();
+                assert_send_sync::<Node>();
+            };
+        }
+        // hash of the declared fields, their types and the class type
+        pub fn schema_fingerprint() -> u64 {
+            let schema = concat!(
+                $("enum ", stringify!($field_name), ": ", stringify!($field_type), ";",)*
+                $("number ", stringify!($number_field_name), ": ", stringify!($number_field_type), ";",)*
+                "class: ", stringify!($class)
+            );
+            $crate::fingerprint::fnv1a(schema.as_bytes())
+        }
+        $crate::__export! {
+            impl Node {
+                // Graphviz DOT of the tree, for `dot -Tsvg`: decision nodes are boxes asking
+                // "field >= value", "field == value" or "field in {a, b}", with a solid true edge
+                // and a dashed false edge, and leaves list their class counts largest first.
+                // Nodes are named by preorder id, so n3 is the leaf audit_record calls 3.
+                pub fn to_dot(&self) -> String {
+                    use $crate::labels::{label, Target};
+                    fn ask(q: &Question) -> String {
+                        match q {
+                            $(Question::$field_name(x) if x.len() == 1 => format!("{} == {}", label(&Field::$field_name, Target::Dot), label(&x[0], Target::Dot)),
+                            Question::$field_name(x) => format!(
+                                "{} in {{{}}}",
+                                label(&Field::$field_name, Target::Dot),
+                                x.iter().map(|v| label(v, Target::Dot)).collect::<Vec<_>>().join(", ")
+                            ),)*
+                            $(Question::$number_field_name(x) => format!("{} >= {}", label(&Field::$number_field_name, Target::Dot), label(x, Target::Dot)),)*
+                        }
+                    }
+                    // `sizes` are the tree's subtree_sizes
+                    fn visit(node: &Node, id: usize, sizes: &[usize], out: &mut String) {
+                        match node {
+                            Node::Leaf(counts) => {
+                                let format = $crate::NumberFormat::default();
+                                let lines: Vec<String> = counts
+                                    .ordered()
+                                    .into_iter()
+                                    .map(|(class, n)| format!("{}: {} ({})", label(class, Target::Dot), n, format.percent(counts.proba(class))))
+                                    .collect();
+                                let text = if lines.is_empty() { "empty".to_owned() } else { lines.join("\\n") };
+                                out.push_str(&format!("    n{} [shape=ellipse, label=\"{}\"];\n", id, text));
+                            }
+                            Node::Decision { question, true_branch, false_branch, .. } => {
+                                let false_id = id + 1 + sizes[id + 1];
+                                out.push_str(&format!("    n{} [shape=box, label=\"{}\"];\n", id, ask(question)));
+                                out.push_str(&format!("    n{} -> n{} [label=\"true\"];\n", id, id + 1));
+                                out.push_str(&format!("    n{} -> n{} [label=\"false\", style=dashed];\n", id, false_id));
+                                visit(true_branch, id + 1, sizes, out);
+                                visit(false_branch, false_id, sizes, out);
+                            }
+                        }
+                    }
+                    let mut out = String::from("digraph tree {\n    node [fontname=\"Helvetica\"];\n");
+                    visit(self, 0, &subtree_sizes(self), &mut out);
+                    out.push_str("}\n");
+                    out
+                }
+                // to_dot, written to `path`
+                pub fn write_dot(&self, path: &std::path::Path) -> std::io::Result<()> {
+                    std::fs::write(path, self.to_dot())
+                }
+            }
+            // everything needed to log and later replay one prediction; leaf_id is the
+            // leaf's preorder index (root 0, true branch before false branch)
+            pub fn audit_record(point: &DataPoint, tree: &Node) -> $crate::audit::AuditRecord {
+                let sizes = subtree_sizes(tree);
+                let mut path: Vec<$crate::audit::AuditStep> = Vec::new();
+                let mut node = tree;
+                let mut id = 0;
+                let counts = loop {
+                    match node {
+                        Node::Leaf(x) => break x,
+                        Node::Decision {
+                            question,
+                            true_branch,
+                            false_branch,
+                            ..
+                        } => {
+                            let answer = question.evaluate(point);
+                            path.push($crate::audit::AuditStep {
+                                question: question_text(question),
+                                answer,
+                            });
+                            if answer {
+                                id += 1;
+                                node = true_branch;
+                            } else {
+                                id += 1 + sizes[id + 1];
+                                node = false_branch;
+                            }
+                        }
+                    }
+                };
+                $crate::audit::AuditRecord {
+                    version: $crate::audit::AUDIT_RECORD_VERSION,
+                    model_fingerprint: tree.fingerprint(),
+                    schema_fingerprint: schema_fingerprint(),
+                    input: vec![
+                        $((stringify!($field_name).to_owned(), format!("{:?}", point.$field_name)),)*
+                        $((stringify!($number_field_name).to_owned(), format!("{:?}", point.$number_field_name)),)*
+                    ],
+                    path,
+                    leaf_id: id,
+                    distribution: ordered_counts(counts),
+                    predicted: counts
+                        .argmax()
+                        .map(|label| $crate::labels::label(label, $crate::labels::Target::Text)),
+                }
+            }
+        }
+        $crate::__metrics! {
+            fn field_summaries(data: &Vec<DataPoint>) -> Vec<(String, $crate::drift::FieldSummary)> {
+                vec![
+                    $((stringify!($field_name).to_owned(), $crate::drift::FieldSummary::categorical(
+                        data.iter().map(|p| $crate::labels::label(&p.$field_name, $crate::labels::Target::Text)),
+                    )),)*
+                    $((stringify!($number_field_name).to_owned(), $crate::drift::FieldSummary::numeric(
+                        data.iter().map(|p| $crate::Number::to_f64(p.$number_field_name)),
+                    )),)*
+                ]
+            }
+            // per-field distributions to keep alongside the tree for field_drift
+            pub fn training_summary(data: &Vec<DataPoint>) -> $crate::drift::TrainingSummary {
+                $crate::drift::TrainingSummary {
+                    rows: data.len(),
+                    fields: field_summaries(data),
+                }
+            }
+            // PSI per field (plus KS for number fields) of `live` against the training summary;
+            // the class of the live points is ignored
+            pub fn field_drift(summary: &$crate::drift::TrainingSummary, live: &Vec<DataPoint>, threshold: f64) -> Result<Vec<$crate::drift::FieldDrift>, $crate::DecisionLeafError> {
+                $crate::error::ensure(!live.is_empty(), "live", live.len(), "at least one point")?;
+                $crate::error::ensure(threshold.is_finite() && threshold > 0.0, "threshold", threshold, "a positive number")?;
+                let live = field_summaries(live);
+                $crate::error::ensure(summary.fields.len() == live.len(), "summary", summary.fields.len(), "a training_summary of this layout")?;
+                Ok(summary
+                    .fields
+                    .iter()
+                    .zip(&live)
+                    .map(|((name, training), (_, live))| $crate::drift::compare(name, training, live, threshold))
+                    .collect())
+            }
+        }
+        // A row whose declared fields may be missing, for Imputer::transform
+        #[derive(Debug, Clone, PartialEq)]
+        pub struct PartialPoint {
+            $(pub $field_name: Option<$field_type>,)*
+            $(pub $input_name: Option<$input_type>,)*
+            pub class: $class,
+        }
+
+        impl PartialPoint {
+            // the DataPoint, with derived fields computed; None while a field is missing
+            pub fn into_point(self) -> Option<DataPoint> {
+                Some(DataPoint::new($(self.$field_name?,)* $(self.$input_name?,)* self.class))
+            }
+        }
+
+        impl From<&DataPoint> for PartialPoint {
+            fn from(point: &DataPoint) -> Self {
+                PartialPoint {
+                    $($field_name: Some(point.$field_name.clone()),)*
+                    $($input_name: Some(point.$input_name),)*
+                    class: point.class.clone(),
+                }
+            }
+        }
+
+        // Fill-in values for missing fields: the most common value of each enum field
+        // (ties to the smallest label) and the lower median of each number field. The
+        // fields are public, so any of them can be replaced by a constant.
+        #[derive(Debug, Clone, PartialEq)]
+        pub struct Imputer {
+            $(pub $field_name: $field_type,)*
+            $(pub $input_name: $input_type,)*
+        }
+
+        impl Imputer {
+            pub fn fit(data: &Vec<DataPoint>) -> Result<Imputer, $crate::DecisionLeafError> {
+                $crate::error::ensure(!data.is_empty(), "data", data.len(), "at least one row")?;
+                Self::fit_partial(&data.iter().map(PartialPoint::from).collect::<Vec<_>>())
+            }
+
+            // the same from rows with missing values, using the values that are there
+            pub fn fit_partial(rows: &[PartialPoint]) -> Result<Imputer, $crate::DecisionLeafError> {
+                let missing = |field: &'static str| $crate::DecisionLeafError::Unavailable { name: field, reason: "no row has a value for it" };
+                Ok(Imputer {
+                    $($field_name: {
+                        let mut counts: Vec<(String, usize, &$field_type)> = Vec::new();
+                        for value in rows.iter().filter_map(|row| row.$field_name.as_ref()) {
+                            let label = format!("{:?}", value);
+                            match counts.iter_mut().find(|(l, _, _)| *l == label) {
+                                Some((_, n, _)) => *n += 1,
+                                None => counts.push((label, 1, value)),
+                            }
+                        }
+                        counts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+                        counts.first().ok_or_else(|| missing(stringify!($field_name)))?.2.clone()
+                    },)*
+                    $($input_name: {
+                        let mut values: Vec<$input_type> = rows.iter().filter_map(|row| row.$input_name).collect();
+                        $crate::error::ensure(!values.is_empty(), "rows", rows.len(), "a value of every field")
+                            .map_err(|_| missing(stringify!($input_name)))?;
+                        // NaNs sort last
+                        values.sort_by(|a, b| a.partial_cmp(b).unwrap_or_else(|| $crate::Number::is_nan(*a).cmp(&$crate::Number::is_nan(*b))));
+                        values[(values.len() - 1) / 2]
+                    },)*
+                })
+            }
+
+            // fills every missing field of `row` and returns how many were filled
+            pub fn transform(&self, row: &mut PartialPoint) -> usize {
+                let mut filled = 0;
+                $(if row.$field_name.is_none() {
+                    row.$field_name = Some(self.$field_name.clone());
+                    filled += 1;
+                })*
+                $(if row.$input_name.is_none() {
+                    row.$input_name = Some(self.$input_name);
+                    filled += 1;
+                })*
+                filled
+            }
+        }
+
+        // A fitted preprocessing step of a Pipeline.
+        pub trait Step {
+            fn stage(&self) -> $crate::pipeline::Stage;
+            // TrainAndServe steps map rows one to one; Train steps may add and drop rows
+            fn transform(&self, rows: Vec<PartialPoint>) -> Vec<PartialPoint>;
+        }
+
+        impl Step for Imputer {
+            fn stage(&self) -> $crate::pipeline::Stage {
+                $crate::pipeline::Stage::TrainAndServe
+            }
+            fn transform(&self, mut rows: Vec<PartialPoint>) -> Vec<PartialPoint> {
+                for row in &mut rows {
+                    Imputer::transform(self, row);
+                }
+                rows
+            }
+        }
+
+        // a pipeline::StepSpec fitted on the rows reaching it
+        #[derive(Debug, Clone, PartialEq)]
+        pub enum FittedStep {
+            Impute(Imputer),
+            Rebalance { seed: u64 },
+        }
+
+        impl FittedStep {
+            pub fn fit(spec: $crate::pipeline::StepSpec, rows: &[PartialPoint]) -> Result<FittedStep, $crate::DecisionLeafError> {
+                Ok(match spec {
+                    $crate::pipeline::StepSpec::Impute => FittedStep::Impute(Imputer::fit_partial(rows)?),
+                    $crate::pipeline::StepSpec::Rebalance { seed } => FittedStep::Rebalance { seed },
+                })
+            }
+        }
+
+        impl Step for FittedStep {
+            fn stage(&self) -> $crate::pipeline::Stage {
+                match self {
+                    FittedStep::Impute(imputer) => imputer.stage(),
+                    FittedStep::Rebalance { .. } => $crate::pipeline::Stage::Train,
+                }
+            }
+            fn transform(&self, mut rows: Vec<PartialPoint>) -> Vec<PartialPoint> {
+                match self {
+                    FittedStep::Impute(imputer) => Step::transform(imputer, rows),
+                    FittedStep::Rebalance { seed } => {
+                        // the rows of each class, classes ordered by label so draws repeat
+                        let mut classes: Vec<(String, Vec<usize>)> = Vec::new();
+                        for (i, row) in rows.iter().enumerate() {
+                            let label = format!("{:?}", row.class);
+                            match classes.iter_mut().find(|(l, _)| *l == label) {
+                                Some((_, members)) => members.push(i),
+                                None => classes.push((label, vec![i])),
+                            }
+                        }
+                        classes.sort_by(|a, b| a.0.cmp(&b.0));
+                        let target = classes.iter().map(|(_, members)| members.len()).max().unwrap_or(0);
+                        let mut rng = $crate::rng::Rng::new(*seed);
+                        for (_, members) in &classes {
+                            for _ in members.len()..target {
+                                let row = rows[members[rng.gen_index(members.len())]].clone();
+                                rows.push(row);
+                            }
+                        }
+                        rows
+                    }
+                }
+            }
+        }
+
+        // Preprocessing steps and the tree grown on their output, so predicting replays the
+        // steps fitted in training (see pipeline).
+        #[derive(Clone)]
+        pub struct Pipeline {
+            pub steps: Vec<FittedStep>,
+            pub tree: Node,
+        }
+
+        impl Pipeline {
+            // Fits `steps` in order on `rows`, each on the output of the ones before, and
+            // grows the tree with `config` on the result, which must have no missing values.
+            pub fn fit(rows: Vec<PartialPoint>, steps: &[$crate::pipeline::StepSpec], config: &TreeConfig) -> Result<Pipeline, $crate::DecisionLeafError> {
+                config.validate()?;
+                $crate::error::ensure(!rows.is_empty(), "rows", rows.len(), "at least one row")?;
+                let mut rows = rows;
+                let mut fitted = Vec::new();
+                for &spec in steps {
+                    let step = FittedStep::fit(spec, &rows)?;
+                    rows = step.transform(rows);
+                    fitted.push(step);
+                }
+                let data: Vec<DataPoint> = rows
+                    .into_iter()
+                    .map(PartialPoint::into_point)
+                    .collect::<Option<_>>()
+                    .ok_or($crate::DecisionLeafError::Unavailable { name: "rows", reason: "a value is still missing after the steps" })?;
+                Ok(Pipeline { steps: fitted, tree: grow_tree(&data, config, 0) })
+            }
+
+            // `raw` after the TrainAndServe steps, as the tree sees it
+            pub fn prepare(&self, raw: PartialPoint) -> Result<DataPoint, $crate::DecisionLeafError> {
+                let mut rows = vec![raw];
+                for step in self.steps.iter().filter(|step| step.stage() == $crate::pipeline::Stage::TrainAndServe) {
+                    rows = step.transform(rows);
+                }
+                rows.pop()
+                    .and_then(PartialPoint::into_point)
+                    .ok_or($crate::DecisionLeafError::Unavailable { name: "point", reason: "a value is still missing after the steps" })
+            }
+
+            // the tree's prediction for `raw`; its class is ignored
+            pub fn predict(&self, raw: PartialPoint) -> Result<$class, $crate::DecisionLeafError> {
+                Ok(predict(&self.prepare(raw)?, &self.tree))
+            }
+        }
+
+        // What the training data looked like, kept with a tree for features that compare
+        // against it at inference time.
+        #[derive(Debug, Clone)]
+        pub struct TrainingProfile {
+            pub rows: u64,
+            // rows per class
+            pub class_counts: $crate::ClassCounts<$class>,
+            // None for profiles recovered from a tree's leaves, see observed()
+            observed: Option<ObservedValues>,
+        }
+
+        // The values each field took in training: every value of an enum field, ordered by
+        // label, and the (lowest, highest) value of a number field.
+        #[derive(Debug, Clone, PartialEq)]
+        pub struct ObservedValues {
+            $(pub $field_name: Vec<$field_type>,)*
+            $(pub $number_field_name: ($number_field_type, $number_field_type),)*
+        }
+
+        impl TrainingProfile {
+            pub fn fit(data: &Vec<DataPoint>) -> Result<TrainingProfile, $crate::DecisionLeafError> {
+                $crate::error::ensure(!data.is_empty(), "data", data.len(), "at least one row")?;
+                Ok(TrainingProfile {
+                    rows: data.len() as u64,
+                    class_counts: class_counts(&row_refs(data)),
+                    observed: Some(ObservedValues {
+                        $($field_name: {
+                            let mut values: Vec<$field_type> = Vec::new();
+                            for point in data {
+                                if !values.contains(&point.$field_name) {
+                                    values.push(point.$field_name.clone());
+                                }
+                            }
+                            values.sort_by_key(|v| format!("{:?}", v));
+                            values
+                        },)*
+                        $($number_field_name: {
+                            let mut range = (data[0].$number_field_name, data[0].$number_field_name);
+                            for point in data {
+                                if point.$number_field_name < range.0 || $crate::Number::is_nan(range.0) {
+                                    range.0 = point.$number_field_name;
+                                }
+                                if point.$number_field_name > range.1 || $crate::Number::is_nan(range.1) {
+                                    range.1 = point.$number_field_name;
+                                }
+                            }
+                            range
+                        },)*
+                    }),
+                })
+            }
+            // the class counts and rows summed over the leaves of `tree`; the values the
+            // fields took are lost
+            pub fn from_tree(tree: &Node) -> TrainingProfile {
+                let mut class_counts = $crate::ClassCounts::new();
+                for path in tree.paths() {
+                    class_counts.merge(path.distribution);
+                }
+                TrainingProfile {
+                    rows: class_counts.total(),
+                    class_counts,
+                    observed: None,
+                }
+            }
+            // share of the training rows in each class, ordered by label
+            pub fn priors(&self) -> Vec<($class, f64)> {
+                let mut priors: Vec<($class, f64)> = self.class_counts.classes().map(|c| (c.clone(), self.class_counts.proba(c))).collect();
+                priors.sort_by_key(|(c, _)| format!("{:?}", c));
+                priors
+            }
+            pub fn observed(&self) -> Result<&ObservedValues, $crate::DecisionLeafError> {
+                self.observed.as_ref().ok_or($crate::DecisionLeafError::Unavailable {
+                    name: "observed",
+                    reason: "the profile was recovered from a tree saved without one",
+                })
+            }
+        }
+
+        // A tree with the profile of the data it was trained on.
+        #[derive(Clone)]
+        pub struct TrainedTree {
+            pub tree: Node,
+            pub profile: TrainingProfile,
+        }
+
+        pub fn build_trained(data: &Vec<DataPoint>, config: &TreeConfig) -> Result<TrainedTree, $crate::DecisionLeafError> {
+            config.validate()?;
+            let profile = TrainingProfile::fit(data)?;
+            Ok(TrainedTree {
+                tree: grow_tree(data, config, 0),
+                profile,
+            })
+        }
+
+        // Each field's share of the tree's total impurity decrease, where a split on `field`
+        // decreases it by n * gini(node) - n_true * gini(true) - n_false * gini(false),
+        // counted from the leaves. Fields the tree never splits on are left out.
+        pub fn feature_importance(tree: &Node) -> Vec<(Field, f64)> {
+            fn visit(node: &Node, decrease: &mut HashMap<Field, f64>) -> $crate::ClassCounts<$class> {
+                match node {
+                    Node::Leaf(x) => x.clone(),
+                    Node::Decision { question, true_branch, false_branch, .. } => {
+                        let true_counts = visit(true_branch, decrease);
+                        let false_counts = visit(false_branch, decrease);
+                        let mut counts = true_counts.clone();
+                        counts.merge(&false_counts);
+                        let weighted = |c: &$crate::ClassCounts<$class>| c.total() as f64 * c.gini();
+                        *decrease.entry(question.field()).or_insert(0.0) += weighted(&counts) - weighted(&true_counts) - weighted(&false_counts);
+                        counts
+                    }
+                }
+            }
+            let mut decrease = HashMap::new();
+            visit(tree, &mut decrease);
+            let total: f64 = Field::ALL.iter().filter_map(|field| decrease.get(field)).sum();
+            let mut shares: Vec<(Field, f64)> = decrease
+                .into_iter()
+                .map(|(field, d)| (field, if total > 0.0 { d / total } else { 0.0 }))
+                .collect();
+            shares.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| format!("{:?}", a.0).cmp(&format!("{:?}", b.0))));
+            shares
+        }
+
+        $crate::__export! {
+            $crate::__metrics! {
+                // model card of `tree`, trained with `config` on the data of `summary` and
+                // tested with `evaluation`; created now
+                pub fn model_card(tree: &Node, config: &TreeConfig, summary: &$crate::drift::TrainingSummary, evaluation: &EvaluationReport, notes: Option<String>) -> $crate::card::ModelCard {
+                    let label = |value: &dyn std::fmt::Debug| $crate::labels::label(value, $crate::labels::Target::Text);
+                    let mut class_distribution = $crate::ClassCounts::new();
+                    for path in tree.paths() {
+                        class_distribution.merge(path.distribution);
+                    }
+                    $crate::card::ModelCard {
+                        created: std::time::SystemTime::now()
+                            .duration_since(std::time::UNIX_EPOCH)
+                            .map_or(0, |d| d.as_secs()),
+                        model_fingerprint: tree.fingerprint(),
+                        schema_fingerprint: schema_fingerprint(),
+                        schema: vec![
+                            $((stringify!($field_name).to_owned(), concat!("enum ", stringify!($field_type)).to_owned()),)*
+                            $((stringify!($input_name).to_owned(), concat!("number ", stringify!($input_type)).to_owned()),)*
+                            $((stringify!($derived_name).to_owned(), concat!("derived ", stringify!($derived_type)).to_owned()),)*
+                            ("class".to_owned(), concat!("class ", stringify!($class)).to_owned()),
+                        ],
+                        config: config_options(config),
+                        training_rows: summary.rows,
+                        class_distribution: ordered_counts(&class_distribution),
+                        node_count: tree.node_count(),
+                        heap_bytes: tree.heap_size_estimate(),
+                        depth: tree.depth(),
+                        test_rows: evaluation.total,
+                        accuracy: evaluation.accuracy,
+                        class_metrics: evaluation
+                            .classes()
+                            .iter()
+                            .map(|class| $crate::card::ClassMetrics {
+                                class: label(class),
+                                support: evaluation.support(class),
+                                precision: evaluation.precision(class),
+                                recall: evaluation.recall(class),
+                            })
+                            .collect(),
+                        top_features: feature_importance(tree)
+                            .into_iter()
+                            .map(|(field, share)| (label(&field), share))
+                            .collect(),
+                        limitations: Vec::new(),
+                        notes,
+                    }
+                }
+                // a limitation on `card` for every flagged field of a memorization report
+                pub fn add_memorization_flags(card: &mut $crate::card::ModelCard, flags: &[MemorizationFlag]) {
+                    for flag in flags.iter().filter(|flag| flag.flagged) {
+                        card.limitations.push($crate::card::Limitation {
+                            source: "memorization",
+                            field: $crate::labels::label(&flag.field, $crate::labels::Target::Text),
+                            detail: format!("{} of {} splits no better than a shuffled column", flag.failed_splits, flag.splits),
+                        });
+                    }
+                }
+            }
+        }
+        $crate::__metrics! {
+            pub type EvaluationReport = $crate::eval::EvaluationReport<$class>;
+
+            // (option, value) of the options recorded in model cards and experiments
+            fn config_options(config: &TreeConfig) -> Vec<(String, String)> {
+                let label = |value: &dyn std::fmt::Debug| $crate::labels::label(value, $crate::labels::Target::Text);
+                vec![
+                    ("max_depth".to_owned(), label(&config.max_depth)),
+                    ("min_samples_leaf".to_owned(), label(&config.min_samples_leaf)),
+                    ("min_side_fraction".to_owned(), label(&config.min_side_fraction)),
+                    ("max_candidates".to_owned(), label(&config.max_candidates)),
+                    ("min_samples_split".to_owned(), label(&config.min_samples_split)),
+                    ("min_gain".to_owned(), label(&config.min_gain)),
+                    ("forced_fields".to_owned(), label(&config.forced_fields)),
+                    ("midpoint_thresholds".to_owned(), label(&config.midpoint_thresholds)),
+                    ("max_features".to_owned(), label(&config.max_features)),
+                    ("feature_seed".to_owned(), label(&config.feature_seed)),
+                    ("criterion".to_owned(), label(&config.criterion)),
+                    ("dedupe".to_owned(), label(&config.dedupe)),
+                    ("fields".to_owned(), label(&config.fields)),
+                ]
+            }
+
+            // the predicted class is the majority of the reached leaf, see ClassCounts::argmax
+            pub fn evaluate(test_data: &Vec<DataPoint>, tree: &Node) -> EvaluationReport {
+                $crate::eval::EvaluationReport::from_predictions(
+                    test_data
+                        .iter()
+                        .map(|point| (point.class.clone(), classify(point, tree).argmax().cloned())),
+                )
+            }
+            // accuracy on `n_repeats` subsamples holding equally many rows of every class
+            pub fn balanced_evaluation(test_data: &Vec<DataPoint>, tree: &Node, seed: u64, n_repeats: usize) -> Result<$crate::eval::BalancedEvaluation<$class>, $crate::DecisionLeafError> {
+                $crate::error::ensure(n_repeats > 0, "n_repeats", n_repeats, "at least 1")?;
+                Ok($crate::eval::balanced_evaluation(
+                    test_data,
+                    |point| point.class.clone(),
+                    |point| classify(point, tree).argmax().cloned(),
+                    seed,
+                    n_repeats,
+                ))
+            }
+            // evaluate for every value of `segment` (e.g. a country field) and for all of test_data
+            pub fn evaluate_by_segment<K: Eq + std::hash::Hash + Clone + std::fmt::Debug>(test_data: &Vec<DataPoint>, tree: &Node, segment: impl Fn(&DataPoint) -> K) -> $crate::eval::SegmentedEvaluation<K, $class> {
+                $crate::eval::evaluate_by_segment(
+                    test_data,
+                    segment,
+                    |point| point.class.clone(),
+                    |point| classify(point, tree).argmax().cloned(),
+                )
+            }
+            // grows a tree with `config` on the `train` rows of `data` and tests it on the `test` rows
+            fn run_fold(data: &[DataPoint], train: &[usize], test: &[usize], config: &TreeConfig) -> $crate::experiment::FoldResult {
+                let label = |value: &dyn std::fmt::Debug| $crate::labels::label(value, $crate::labels::Target::Text);
+                let train: Vec<DataPoint> = train.iter().map(|&i| data[i].clone()).collect();
+                let test: Vec<DataPoint> = test.iter().map(|&i| data[i].clone()).collect();
+                let tree = grow_tree(&train, config, 0);
+                let report = evaluate(&test, &tree);
+                let mut confusion: Vec<(String, String, usize)> = report
+                    .confusion
+                    .iter()
+                    .map(|((actual, predicted), &n)| (label(actual), label(predicted), n))
+                    .collect();
+                confusion.sort();
+                $crate::experiment::FoldResult {
+                    train_rows: train.len(),
+                    test_rows: test.len(),
+                    correct: report.correct,
+                    accuracy: report.accuracy,
+                    confusion,
+                    model_fingerprint: tree.fingerprint(),
+                }
+            }
+            fn ensure_protocol(protocol: $crate::experiment::Protocol, rows: usize) -> Result<(), $crate::DecisionLeafError> {
+                match protocol {
+                    $crate::experiment::Protocol::Holdout { test_fraction } => {
+                        $crate::error::ensure(test_fraction > 0.0 && test_fraction < 1.0, "test_fraction", test_fraction, "a fraction strictly between 0 and 1")
+                    }
+                    $crate::experiment::Protocol::KFold { k } | $crate::experiment::Protocol::StratifiedKFold { k } => {
+                        $crate::error::ensure(k >= 2 && k <= rows, "k", k, "between 2 and the number of rows")
+                    }
+                }
+            }
+            fn class_labels(data: &[DataPoint]) -> Vec<String> {
+                data.iter().map(|p| $crate::labels::label(&p.class, $crate::labels::Target::Text)).collect()
+            }
+            // Grows trees with `config` on the training rows of every fold of `protocol`, tests
+            // them on the rest, and grows a final tree on all of `data`. The folds are drawn
+            // with `seed`, so the result only depends on the inputs (timings aside).
+            pub fn run_experiment(data: &Vec<DataPoint>, config: &TreeConfig, seed: u64, protocol: $crate::experiment::Protocol) -> Result<$crate::experiment::ExperimentResult, $crate::DecisionLeafError> {
+                config.validate()?;
+                $crate::error::ensure(data.len() >= 2, "data", data.len(), "at least two rows")?;
+                ensure_protocol(protocol, data.len())?;
+                let started = std::time::Instant::now();
+                let mut timings = $crate::experiment::Timings::default();
+                let mut folds = Vec::new();
+                for (train_rows, test_rows) in $crate::experiment::split_folds(&class_labels(data), protocol, seed) {
+                    let fold_started = std::time::Instant::now();
+                    folds.push(run_fold(data, &train_rows, &test_rows, config));
+                    timings.folds.push(fold_started.elapsed());
+                }
+                let final_started = std::time::Instant::now();
+                let model_fingerprint = grow_tree(data, config, 0).fingerprint();
+                timings.final_model = final_started.elapsed();
+                let accuracies: Vec<f32> = folds.iter().map(|f| f.accuracy).collect();
+                let (accuracy_mean, accuracy_std) = $crate::eval::mean_std(&accuracies);
+                let rows: Vec<String> = data.iter().map(|p| format!("{:?}", p)).collect();
+                timings.total = started.elapsed();
+                Ok($crate::experiment::ExperimentResult {
+                    data_hash: $crate::fingerprint::fnv1a(rows.join("\n").as_bytes()),
+                    rows: data.len(),
+                    schema_fingerprint: schema_fingerprint(),
+                    config: config_options(config),
+                    seed,
+                    protocol,
+                    folds,
+                    accuracy_mean,
+                    accuracy_std,
+                    model_fingerprint,
+                    timings,
+                })
+            }
+            // Nested cross-validation of tuning `base` over `grid`: every outer fold picks the
+            // grid config with the best `inner_k`-fold accuracy on its training rows and tests
+            // it on its own test rows, so the estimate covers the tuning too. Folds are drawn
+            // with `seed` (the inner ones with seeds derived from it), and ties go to the
+            // earlier config of the grid.
+            pub fn nested_cv(data: &Vec<DataPoint>, grid: &$crate::experiment::ParamGrid, outer_k: usize, inner_k: usize, seed: u64, base: &TreeConfig) -> Result<$crate::experiment::NestedCvReport, $crate::DecisionLeafError> {
+                base.validate()?;
+                ensure_protocol($crate::experiment::Protocol::KFold { k: outer_k }, data.len())?;
+                $crate::error::ensure(inner_k >= 2 && inner_k * outer_k <= data.len() * (outer_k - 1), "inner_k", inner_k, "between 2 and the rows of an outer training fold")?;
+                let mut configs = Vec::new();
+                for max_depth in grid.max_depth.iter().copied().map(Some).chain(grid.max_depth.is_empty().then_some(None)) {
+                    for min_samples_leaf in grid.min_samples_leaf.iter().copied().map(Some).chain(grid.min_samples_leaf.is_empty().then_some(None)) {
+                        let config = TreeConfig {
+                            max_depth: max_depth.unwrap_or(base.max_depth),
+                            min_samples_leaf: min_samples_leaf.unwrap_or(base.min_samples_leaf),
+                            ..base.clone()
+                        };
+                        config.validate()?;
+                        configs.push(config);
+                    }
+                }
+                let outer = $crate::experiment::split_folds(&class_labels(data), $crate::experiment::Protocol::KFold { k: outer_k }, seed);
+                let mut folds = Vec::new();
+                // outer accuracy of every config on every fold, for the naive estimate
+                let mut outer_accuracy = vec![Vec::new(); configs.len()];
+                for (f, (train_rows, test_rows)) in outer.iter().enumerate() {
+                    let train: Vec<DataPoint> = train_rows.iter().map(|&i| data[i].clone()).collect();
+                    let inner = $crate::experiment::split_folds(&class_labels(&train), $crate::experiment::Protocol::KFold { k: inner_k }, seed.wrapping_add(1 + f as u64));
+                    let mut best: Option<(usize, f32)> = None;
+                    for (c, config) in configs.iter().enumerate() {
+                        let inner_accuracy = inner
+                            .iter()
+                            .map(|(inner_train, inner_test)| run_fold(&train, inner_train, inner_test, config).accuracy)
+                            .sum::<f32>()
+                            / inner.len() as f32;
+                        if best.map_or(true, |(_, accuracy)| inner_accuracy > accuracy) {
+                            best = Some((c, inner_accuracy));
+                        }
+                        outer_accuracy[c].push(run_fold(data, train_rows, test_rows, config).accuracy);
+                    }
+                    let (chosen, inner_accuracy) = best.expect("the grid has at least one config");
+                    folds.push($crate::experiment::OuterFold {
+                        config: config_options(&configs[chosen]),
+                        inner_accuracy,
+                        accuracy: outer_accuracy[chosen][f],
+                        test_rows: test_rows.len(),
+                    });
+                }
+                let naive_accuracy = outer_accuracy
+                    .iter()
+                    .map(|accuracies| $crate::eval::mean_std(accuracies).0)
+                    .fold(0_f32, f32::max);
+                Ok($crate::experiment::NestedCvReport::new(folds, configs.len(), naive_accuracy))
+            }
+            // A copy of `data` with each label flipped, with probability `rate`, to one of the
+            // other classes of `data` drawn uniformly. Every row draws the same two numbers from
+            // Rng::new(seed) whatever the rate, so raising the rate only adds flips.
+            pub fn inject_label_noise(data: &Vec<DataPoint>, rate: f64, seed: u64) -> Result<Vec<DataPoint>, $crate::DecisionLeafError> {
+                $crate::error::ensure((0.0..=1.0).contains(&rate), "rate", rate, "a probability between 0 and 1")?;
+                let mut classes: Vec<$class> = Vec::new();
+                for point in data {
+                    if !classes.contains(&point.class) {
+                        classes.push(point.class.clone());
+                    }
+                }
+                classes.sort_by_key(|c| format!("{:?}", c));
+                $crate::error::ensure(classes.len() >= 2 || data.is_empty(), "data", classes.len(), "at least two classes")?;
+                let mut rng = $crate::rng::Rng::new(seed);
+                Ok(data
+                    .iter()
+                    .map(|point| {
+                        let (flip, other) = (rng.next_f64(), rng.gen_index(classes.len().max(2) - 1));
+                        let mut point = point.clone();
+                        if flip < rate {
+                            let others: Vec<&$class> = classes.iter().filter(|c| **c != point.class).collect();
+                            point.class = others[other].clone();
+                        }
+                        point
+                    })
+                    .collect())
+            }
+            // k-fold accuracy at every rate of `rates`: each fold's training rows get
+            // inject_label_noise(rate, seed), its test rows keep their labels. The folds are the
+            // same for every rate, drawn with `seed`.
+            pub fn noise_sensitivity_curve(data: &Vec<DataPoint>, rates: &[f64], k: usize, seed: u64, config: &TreeConfig) -> Result<Vec<$crate::noise::NoisePoint>, $crate::DecisionLeafError> {
+                config.validate()?;
+                ensure_protocol($crate::experiment::Protocol::KFold { k }, data.len())?;
+                let folds = $crate::experiment::split_folds(&class_labels(data), $crate::experiment::Protocol::KFold { k }, seed);
+                rates
+                    .iter()
+                    .map(|&rate| {
+                        let noisy = inject_label_noise(data, rate, seed)?;
+                        let mut flipped = 0;
+                        let accuracies: Vec<f32> = folds
+                            .iter()
+                            .map(|(train_rows, test_rows)| {
+                                let train: Vec<DataPoint> = train_rows.iter().map(|&i| noisy[i].clone()).collect();
+                                flipped += train_rows.iter().filter(|&&i| noisy[i].class != data[i].class).count();
+                                let test: Vec<DataPoint> = test_rows.iter().map(|&i| data[i].clone()).collect();
+                                evaluate(&test, &grow_tree(&train, config, 0)).accuracy
+                            })
+                            .collect();
+                        let (accuracy_mean, accuracy_std) = $crate::eval::mean_std(&accuracies);
+                        Ok($crate::noise::NoisePoint { rate, accuracy_mean, accuracy_std, flipped })
+                    })
+                    .collect()
+            }
+            // Rows of `data` whose leaf, in the tree grown with `config` on the other folds of a
+            // k-fold split drawn with `seed`, gives some other class a probability of at least
+            // `confidence` (above 0.5, so at most one class can).
+            pub fn estimate_label_noise(data: &Vec<DataPoint>, k: usize, seed: u64, config: &TreeConfig, confidence: f64) -> Result<$crate::noise::NoiseEstimate, $crate::DecisionLeafError> {
+                config.validate()?;
+                ensure_protocol($crate::experiment::Protocol::KFold { k }, data.len())?;
+                $crate::error::ensure(confidence > 0.5 && confidence <= 1.0, "confidence", confidence, "above 0.5 and at most 1")?;
+                let mut suspects = Vec::new();
+                for (train_rows, test_rows) in $crate::experiment::split_folds(&class_labels(data), $crate::experiment::Protocol::KFold { k }, seed) {
+                    let train: Vec<DataPoint> = train_rows.iter().map(|&i| data[i].clone()).collect();
+                    let tree = grow_tree(&train, config, 0);
+                    for i in test_rows {
+                        let leaf = classify(&data[i], &tree);
+                        if leaf.classes().any(|c| *c != data[i].class && leaf.proba(c) >= confidence) {
+                            suspects.push(i);
+                        }
+                    }
+                }
+                suspects.sort();
+                Ok($crate::noise::NoiseEstimate { rows: data.len(), suspects, confidence })
+            }
+        }
+        $crate::__fuzz! {
+            // Decodes `bytes` into at most fuzz::MAX_ROWS rows and a bounded TreeConfig, trains,
+            // and panics if an invariant breaks: the leaves count every row once, each training
+            // row reaches a leaf that counted its class, batch and single classification agree,
+            // config.dedupe doesn't change the tree, and with `persist` the saved tree loads back and saves to the same text. Any input
+            // is valid, so cargo-fuzz or a proptest harness can drive it directly.
+            //
+            // Only callable when the field types and the class implement Arbitrary. The bounds
+            // are higher-ranked so an unmet one is an error at the call instead of here: turning
+            // the feature on must not break a layout over types that never get fuzzed.
+            pub fn fuzz_roundtrip(bytes: &[u8])
+            where
+                $(for<'a> $field_type: $crate::fuzz::Arbitrary,)*
+                $(for<'a> $input_type: $crate::fuzz::Arbitrary,)*
+                for<'a> $class: $crate::fuzz::Arbitrary,
+            {
+                use $crate::fuzz::Arbitrary;
+                let mut input = $crate::fuzz::Unstructured::new(bytes);
+                let config = TreeConfig {
+                    max_depth: Some(input.in_range(0, 8)),
+                    min_samples_leaf: input.in_range(1, 4),
+                    min_samples_split: input.in_range(2, 8),
+                    keep_runner_up: bool::arbitrary(&mut input),
+                    max_candidates: input.in_range(2, 32),
+                    midpoint_thresholds: bool::arbitrary(&mut input),
+                    max_features: bool::arbitrary(&mut input).then(|| input.in_range(1, Field::ALL.len())),
+                    feature_seed: input.byte() as u64,
+                    criterion: [$crate::split::SplitCriterion::Gini, $crate::split::SplitCriterion::Entropy][input.choose(2)],
+                    dedupe: bool::arbitrary(&mut input),
+                    ..Default::default()
+                };
+                let rows = input.choose($crate::fuzz::MAX_ROWS + 1);
+                let data: Vec<DataPoint> = (0..rows)
+                    .map(|_| {
+                        DataPoint::new(
+                            $(<$field_type as Arbitrary>::arbitrary(&mut input),)*
+                            $(<$input_type as Arbitrary>::arbitrary(&mut input),)*
+                            <$class as Arbitrary>::arbitrary(&mut input),
+                        )
+                    })
+                    .collect();
+                let tree = build_tree_with(&data, &config).expect("a bounded config is valid");
+                let toggled = TreeConfig { dedupe: !config.dedupe, ..config.clone() };
+                let other = build_tree_with(&data, &toggled).expect("a bounded config is valid");
+                assert_eq!(other.fingerprint(), tree.fingerprint(), "collapsing duplicate rows grows the same tree");
+
+                fn counted(node: &Node) -> u64 {
+                    match node {
+                        Node::Leaf(counts) => counts.total(),
+                        Node::Decision { true_branch, false_branch, .. } => counted(true_branch) + counted(false_branch),
                     }
                 }
+                assert_eq!(counted(&tree), data.len() as u64, "the leaves count every row once");
+                for (point, leaf) in data.iter().zip(classify_batch(&data, &tree)) {
+                    let single = classify(point, &tree);
+                    assert!(std::ptr::eq(single, leaf), "classify_batch and classify reach the same leaf");
+                    assert!(single.get(&point.class) > 0, "a training row's leaf counted its class");
+                    assert!(single.contains(&predict(point, &tree)), "the prediction is one of the leaf's classes");
+                }
+
+                $crate::__persist! {
+                    let mut saved = Vec::new();
+                    tree.save(&mut saved).expect("writing to a Vec");
+                    let loaded = Node::load(saved.as_slice(), &$crate::persist::LoadOptions::default())
+                        .unwrap_or_else(|e| panic!("a saved tree loads back: {}", e));
+                    let mut resaved = Vec::new();
+                    loaded.save(&mut resaved).expect("writing to a Vec");
+                    assert_eq!(String::from_utf8_lossy(&saved), String::from_utf8_lossy(&resaved), "a loaded tree saves to the same text");
+                }
             }
         }
-        pub fn run_tests(test_data: &Vec<DataPoint>, tree: &Node){
-            println!("\nTests:");
-            for point in test_data {
-                print!("Actual: {:?}. Predicted: ", point.class);
-                print_leaf(&classify(&point, tree.clone()), "");
+        $crate::__metrics! {
+            pub fn run_tests(test_data: &Vec<DataPoint>, tree: &Node){
+                run_tests_with(test_data, tree, &$crate::NumberFormat::default());
             }
+            // prints evaluate's report: accuracy, confusion rows and per-class precision and recall
+            pub fn run_tests_with(test_data: &Vec<DataPoint>, tree: &Node, format: &$crate::NumberFormat){
+                let report = evaluate(test_data, tree);
+                let options = $crate::eval::ReportOptions { number_format: format.clone(), ..Default::default() };
+                println!("\nTests:");
+                print!("{}", report.render(&options));
+                print!("{}", report.render_classes(format));
+            }
+        }
+    };
+}
+
+/// Implements the layout's `HasFields` for a struct of your own by naming, for each layout
+/// field it holds, the member holding it (a name, or an index for tuple structs); `classify`
+/// and `predict` then read it in place. Used where the layout is in scope. Fields left out
+/// answer None, and a tree asking about one panics, so map every field the tree splits on,
+/// derived fields included.
+///
+///Example:
+/// struct Request {
+///  paint: Color,
+///  grams: u32,
+///  id: u64,
+/// }
+///
+/// impl_has_fields!(Request { color: paint, size: grams });
+///
+/// let fruit = predict(&request, &tree);
+#[macro_export]
+macro_rules! impl_has_fields {
+    ($type:ty { $($field:ident : $member:tt),* $(,)? }) => {
+        impl HasFields for $type {
+            $(fn $field(&self) -> Option<&field_types::$field> {
+                Some(&self.$member)
+            })*
         }
     };
 }
+
+// Optional parts of the expansion are wrapped in these, so they follow the
+// features of decision-leaf rather than those of the crate invoking the macro.
+#[cfg(feature = "serving")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __serving {
+    ($($t:tt)*) => { $($t)* };
+}
+#[cfg(not(feature = "serving"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __serving {
+    ($($t:tt)*) => {};
+}
+#[cfg(feature = "csv")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __csv {
+    ($($t:tt)*) => { $($t)* };
+}
+#[cfg(not(feature = "csv"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __csv {
+    ($($t:tt)*) => {};
+}
+#[cfg(feature = "persist")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __persist {
+    ($($t:tt)*) => { $($t)* };
+}
+#[cfg(not(feature = "persist"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __persist {
+    ($($t:tt)*) => {};
+}
+#[cfg(feature = "ensemble")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __ensemble {
+    ($($t:tt)*) => { $($t)* };
+}
+#[cfg(not(feature = "ensemble"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __ensemble {
+    ($($t:tt)*) => {};
+}
+#[cfg(feature = "export")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __export {
+    ($($t:tt)*) => { $($t)* };
+}
+#[cfg(not(feature = "export"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __export {
+    ($($t:tt)*) => {};
+}
+#[cfg(feature = "metrics")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __metrics {
+    ($($t:tt)*) => { $($t)* };
+}
+#[cfg(not(feature = "metrics"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __metrics {
+    ($($t:tt)*) => {};
+}
+#[cfg(feature = "fuzz")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __fuzz {
+    ($($t:tt)*) => { $($t)* };
+}
+#[cfg(not(feature = "fuzz"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __fuzz {
+    ($($t:tt)*) => {};
+}
+#[cfg(feature = "trace")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __trace {
+    ($($t:tt)*) => { $($t)* };
+}
+#[cfg(not(feature = "trace"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __trace {
+    ($($t:tt)*) => {};
+}
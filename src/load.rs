@@ -0,0 +1,134 @@
+//! CSV parsing shared by the generated loaders.
+
+use std::fmt::{self, Display};
+
+#[derive(Debug)]
+pub enum LoadError {
+    Io(std::io::Error),
+    /// A column the layout needs is not in the header.
+    MissingColumn {
+        column: String,
+    },
+    /// A loader option names a column the layout doesn't have.
+    UnknownColumn {
+        column: String,
+    },
+    /// A missing marker in a row while missing rows aren't skipped.
+    MissingValue {
+        line: usize,
+        column: String,
+    },
+    Parse {
+        line: usize,
+        column: String,
+        value: String,
+        message: String,
+    },
+    /// A column of a type with no `FromStr` and no adapter to read it.
+    Unreadable {
+        column: String,
+        type_name: String,
+    },
+}
+
+impl Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "{}", e),
+            Self::MissingColumn { column } => write!(f, "no column `{}` in the header", column),
+            Self::UnknownColumn { column } => {
+                write!(f, "loader option for unknown column `{}`", column)
+            }
+            Self::MissingValue { line, column } => {
+                write!(f, "line {}, column `{}`: missing value", line, column)
+            }
+            Self::Parse {
+                line,
+                column,
+                value,
+                message,
+            } => write!(
+                f,
+                "line {}, column `{}`: {:?} {}",
+                line, column, value, message
+            ),
+            Self::Unreadable { column, type_name } => write!(
+                f,
+                "column `{}` is a {}, which has no FromStr; read it with LoaderConfig::custom or map_enum",
+                column, type_name
+            ),
+        }
+    }
+}
+
+impl std::error::Error for LoadError {}
+
+impl From<std::io::Error> for LoadError {
+    fn from(e: std::io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+/// Writes one CSV line that [`split_record`] splits back into `cells`:
+/// cells holding a comma or a quote are quoted, quotes inside doubled. A
+/// line break in a cell is an `InvalidData` error, since records are read
+/// a line at a time.
+pub fn write_record<W: std::io::Write>(out: &mut W, cells: &[String]) -> std::io::Result<()> {
+    let mut line = String::new();
+    for (i, cell) in cells.iter().enumerate() {
+        if cell.contains(['\n', '\r']) {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("line break in CSV cell {:?}", cell),
+            ));
+        }
+        if i > 0 {
+            line.push(',');
+        }
+        if cell.contains([',', '"']) {
+            line.push('"');
+            line.push_str(&cell.replace('"', "\"\""));
+            line.push('"');
+        } else {
+            line.push_str(cell);
+        }
+    }
+    writeln!(out, "{}", line)
+}
+
+/// Splits one CSV line on commas. Fields may be double-quoted, with `""`
+/// for a quote inside; quoted fields can't span lines.
+pub fn split_record(line: &str) -> Result<Vec<String>, &'static str> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut chars = line.chars().peekable();
+    let mut quoted = false;
+    while let Some(c) = chars.next() {
+        match (c, quoted) {
+            ('"', false) if field.trim().is_empty() => {
+                field.clear();
+                quoted = true;
+            }
+            ('"', true) if chars.peek() == Some(&'"') => {
+                chars.next();
+                field.push('"');
+            }
+            ('"', true) => {
+                quoted = false;
+                // only whitespace may follow the closing quote
+                while chars.peek().is_some_and(|c| *c != ',') {
+                    if !chars.next().is_some_and(char::is_whitespace) {
+                        return Err("text after a closing quote");
+                    }
+                }
+            }
+            (',', false) => fields.push(std::mem::take(&mut field)),
+            (c, _) => field.push(c),
+        }
+    }
+    if quoted {
+        return Err("unterminated quote");
+    }
+    fields.push(field);
+    Ok(fields)
+}
@@ -0,0 +1,42 @@
+//! Sensitivity to label noise: how cross-validated accuracy falls as labels
+//! are flipped at random, and an estimate of how many labels of a dataset
+//! are already wrong.
+//!
+//! `inject_label_noise` draws the same numbers for a row whatever the rate,
+//! so the rows flipped at one rate are flipped at every higher rate too, to
+//! the same class. Curves over rates then differ only by the added flips.
+
+/// Cross-validated accuracy with labels flipped at `rate`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NoisePoint {
+    pub rate: f64,
+    /// Mean and sample standard deviation over the folds, scored against the
+    /// original labels of the test rows.
+    pub accuracy_mean: f32,
+    pub accuracy_std: f32,
+    /// Training labels flipped over all folds' training rows.
+    pub flipped: usize,
+}
+
+/// Rows whose out-of-fold prediction confidently disagrees with their label.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NoiseEstimate {
+    pub rows: usize,
+    /// Indices of the suspect rows, in data order.
+    pub suspects: Vec<usize>,
+    /// Probability the tree trained without a row had to give another class
+    /// for the row to count as a suspect.
+    pub confidence: f64,
+}
+
+impl NoiseEstimate {
+    /// Share of rows that are suspects; a lower bound on the noise rate,
+    /// since flips into a class the model already confuses go unseen.
+    pub fn rate(&self) -> f64 {
+        if self.rows == 0 {
+            0.0
+        } else {
+            self.suspects.len() as f64 / self.rows as f64
+        }
+    }
+}
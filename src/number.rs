@@ -0,0 +1,197 @@
+//! Conversion between number field types and `f64`, for features that need to
+//! do arithmetic on thresholds (grids, quantiles, ...), and the equality the
+//! generated types use for them.
+
+use std::hash::{Hash, Hasher};
+
+/// A type usable in `number_fields`. Implemented for all primitive numbers
+/// and for `Option`s of them, whose `None` is a missing value.
+pub trait Number: Copy + PartialOrd {
+    fn to_f64(self) -> f64;
+    /// Converts back, rounding/saturating the way an `as` cast does.
+    fn from_f64(value: f64) -> Self;
+    /// Equality for `DataPoint` and `Question`, which are `Eq + Hash`: by
+    /// value for integers, by bit pattern for floats, so a NaN equals itself
+    /// and `-0.0` differs from `0.0`.
+    fn same(self, other: Self) -> bool;
+    /// Hashing consistent with `same`.
+    fn hash_value<H: Hasher>(self, state: &mut H);
+    /// A threshold between `low < high` that `>=` answers like `high` for
+    /// every value of the type outside `(low, high)`, so it splits rows the
+    /// way `high` does. Integers take the midpoint rounded up, which stays
+    /// in the type and can't overflow; floats take the midpoint itself, or
+    /// `high` where it rounds onto `low` or overflows.
+    fn threshold_between(low: Self, high: Self) -> Self;
+    /// True for a NaN, which no threshold is split on and which answers
+    /// every question about its field with false.
+    fn is_nan(self) -> bool {
+        self.partial_cmp(&self).is_none()
+    }
+}
+
+// ceil((low + high) / 2) as low + ceil((high - low) / 2), the difference
+// taken unsigned so it fits for signed types too
+macro_rules! unsigned_midpoint {
+    ($low:ident, $high:ident) => {
+        $low + ($high - $low).div_ceil(2)
+    };
+}
+
+macro_rules! signed_midpoint {
+    ($low:ident, $high:ident) => {
+        $low.wrapping_add_unsigned($high.abs_diff($low).div_ceil(2))
+    };
+}
+
+macro_rules! impl_number {
+    ($midpoint:ident: $($t:ty),*) => {
+        $(impl Number for $t {
+            fn to_f64(self) -> f64 {
+                self as f64
+            }
+            fn from_f64(value: f64) -> Self {
+                value as $t
+            }
+            fn same(self, other: Self) -> bool {
+                self == other
+            }
+            fn hash_value<H: Hasher>(self, state: &mut H) {
+                self.hash(state);
+            }
+            fn threshold_between(low: Self, high: Self) -> Self {
+                $midpoint!(low, high)
+            }
+        })*
+    };
+}
+
+macro_rules! impl_float {
+    ($($t:ty),*) => {
+        $(impl Number for $t {
+            fn to_f64(self) -> f64 {
+                self as f64
+            }
+            fn from_f64(value: f64) -> Self {
+                value as $t
+            }
+            fn same(self, other: Self) -> bool {
+                self.to_bits() == other.to_bits()
+            }
+            fn hash_value<H: Hasher>(self, state: &mut H) {
+                self.to_bits().hash(state);
+            }
+            fn threshold_between(low: Self, high: Self) -> Self {
+                // halved first so the sum of two large values stays finite
+                let mid = low / 2.0 + high / 2.0;
+                if mid > low && mid <= high {
+                    mid
+                } else {
+                    high
+                }
+            }
+        })*
+    };
+}
+
+impl_number!(unsigned_midpoint: u8, u16, u32, u64, u128, usize);
+impl_number!(signed_midpoint: i8, i16, i32, i64, i128, isize);
+impl_float!(f32, f64);
+
+// None sorts below every value, so `None >= threshold` is false: a missing
+// value answers every question about its field with false and goes down the
+// false branch, in training as in classification, the way a NaN does.
+impl<N: Number> Number for Option<N> {
+    fn to_f64(self) -> f64 {
+        self.map_or(f64::NAN, N::to_f64)
+    }
+    fn from_f64(value: f64) -> Self {
+        (!value.is_nan()).then(|| N::from_f64(value))
+    }
+    fn same(self, other: Self) -> bool {
+        match (self, other) {
+            (Some(a), Some(b)) => a.same(b),
+            (a, b) => a.is_none() && b.is_none(),
+        }
+    }
+    fn hash_value<H: Hasher>(self, state: &mut H) {
+        self.is_some().hash(state);
+        if let Some(value) = self {
+            value.hash_value(state);
+        }
+    }
+    fn threshold_between(low: Self, high: Self) -> Self {
+        match (low, high) {
+            (Some(low), Some(high)) => Some(N::threshold_between(low, high)),
+            _ => high,
+        }
+    }
+    fn is_nan(self) -> bool {
+        self.is_none_or(N::is_nan)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // every pair of an 8-bit type: an integer value is >= the fractional
+    // midpoint exactly when it is >= the rounded-up one
+    #[test]
+    fn integer_midpoints_split_like_the_fractional_ones() {
+        fn check<N: Number + TryFrom<i32>>(min: i32, max: i32) {
+            let value = |v: i32| N::try_from(v).ok().unwrap();
+            for low in min..max {
+                for high in low + 1..=max {
+                    let threshold = N::threshold_between(value(low), value(high));
+                    let fractional = (low as f64 + high as f64) / 2.0;
+                    for v in min..=max {
+                        assert_eq!(
+                            value(v) >= threshold,
+                            v as f64 >= fractional,
+                            "{} between {} and {}",
+                            v,
+                            low,
+                            high
+                        );
+                    }
+                }
+            }
+        }
+        check::<u8>(0, 255);
+        check::<i8>(-128, 127);
+    }
+
+    #[test]
+    fn integer_midpoints_dont_overflow_at_the_ends() {
+        assert_eq!(u8::threshold_between(0, u8::MAX), 128);
+        assert_eq!(u8::threshold_between(254, u8::MAX), u8::MAX);
+        assert_eq!(i8::threshold_between(i8::MIN, i8::MAX), 0);
+        assert_eq!(i64::threshold_between(i64::MIN, i64::MAX), 0);
+        assert_eq!(i64::threshold_between(i64::MIN, i64::MIN + 1), i64::MIN + 1);
+        assert_eq!(u128::threshold_between(u128::MAX - 1, u128::MAX), u128::MAX);
+        assert_eq!(u64::threshold_between(0, u64::MAX), 1 << 63);
+        assert_eq!(i32::threshold_between(-3, 0), -1);
+        assert_eq!(isize::threshold_between(-4, -1), -2);
+    }
+
+    #[test]
+    fn float_midpoints_stay_above_low() {
+        assert_eq!(f64::threshold_between(1.0, 2.0), 1.5);
+        assert_eq!(f64::threshold_between(-f64::MAX, f64::MAX), 0.0);
+        assert_eq!(
+            f64::threshold_between(f64::MAX / 2.0, f64::MAX),
+            f64::MAX * 0.75
+        );
+        // no float lies between neighbours, so high it is
+        let next = f64::from_bits(1.0f64.to_bits() + 1);
+        assert_eq!(f64::threshold_between(1.0, next), next);
+        assert_eq!(f32::threshold_between(f32::NEG_INFINITY, 0.0), 0.0);
+        assert_eq!(f32::threshold_between(0.0, f32::INFINITY), f32::INFINITY);
+    }
+
+    #[test]
+    fn missing_values_take_the_higher_side() {
+        assert_eq!(Option::threshold_between(Some(1u32), Some(4)), Some(3));
+        assert_eq!(Option::threshold_between(None, Some(4u32)), Some(4));
+    }
+}
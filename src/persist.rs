@@ -0,0 +1,196 @@
+//! A line-based text format for saving trees, shared by the generated
+//! `Node::save`/`Node::load`.
+//!
+//! The first line is [`HEADER`], then one `field` line per layout field and
+//! a `class` line. A `TrainedTree` adds its training profile next: a `rows`
+//! line, a `prior` line per class with its count (by descending count, then
+//! label), an `observed` line with
+//! the values of each enum field and a `range` line with the lowest and
+//! highest value of each number field. The nodes follow in preorder:
+//! `split` lines (the field, then one value for a number field or the set
+//! for an enum field) and `leaf` lines (alternating class and count, by
+//! descending count, then label). Tokens
+//! are tab separated. Values are written with `Debug` and read back with
+//! `FromStr`, which also accepts quoted `char` and string values; arrays of
+//! `FromStr` types are read element by element. Loading a layout with a field
+//! or class type that has no `FromStr` fails with
+//! [`PersistError::Unreadable`].
+//!
+//! Every header ends in the version of its format, and a loader only reads
+//! files starting with its own header. The version goes up when a change
+//! would let an older loader misread a file; lines an older loader rejects
+//! as malformed, like the training profile, are added without a new
+//! version. Changes to the layout need none: the `field` lines record the
+//! layout the tree was saved under, a loader matches them to its own by
+//! name and type, and [`LoadOptions`] renames fields and enum values saved
+//! under older names.
+//!
+//! This format is the crate's only serialization: there is no `serde`
+//! feature. The crate has no dependencies, and deriving serde's traits on
+//! the generated types would make every layout's types implement them too.
+//! Trees go through `Node::save`/`Node::load` (or `save_tree`/`load_tree`),
+//! and `DataPoint`s through `write_csv`/`load_csv` with the `csv` feature.
+
+use std::fmt::{self, Display};
+
+pub const HEADER: &str = "decision-leaf tree 1";
+/// Kinds of the training profile lines, which `Node::load` skips.
+pub const PROFILE_KINDS: &[&str] = &["rows", "prior", "observed", "range"];
+/// First line of a saved `Imputer`, which then has one `value` line per field.
+pub const IMPUTER_HEADER: &str = "decision-leaf imputer 1";
+/// First line of a saved `Pipeline`. A `step` line per step follows, with
+/// the step's kind and, for an imputer, the number of saved imputer lines
+/// after it; then a `tree` line with the number of saved tree lines after it.
+pub const PIPELINE_HEADER: &str = "decision-leaf pipeline 1";
+
+/// Adjustments for loading a tree saved under an older layout.
+#[derive(Debug, Clone, Default)]
+pub struct LoadOptions {
+    renames: Vec<(String, String)>,
+    enum_values: Vec<(String, String, String)>,
+}
+
+impl LoadOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Reads the saved field `old` as the layout's field `new`.
+    pub fn rename_field(mut self, old: &str, new: &str) -> Self {
+        self.renames.push((old.to_owned(), new.to_owned()));
+        self
+    }
+
+    /// Reads the label `old` of enum field `field` (its current name, `class`
+    /// for the class) as `new`.
+    pub fn map_enum_value(mut self, field: &str, old: &str, new: &str) -> Self {
+        self.enum_values
+            .push((field.to_owned(), old.to_owned(), new.to_owned()));
+        self
+    }
+
+    /// The current name of the saved field `saved`.
+    pub fn field_name<'a>(&'a self, saved: &'a str) -> &'a str {
+        self.renames
+            .iter()
+            .find(|(old, _)| old == saved)
+            .map_or(saved, |(_, new)| new)
+    }
+
+    /// The label to parse for the saved label `label` of `field`.
+    pub fn enum_value<'a>(&'a self, field: &str, label: &'a str) -> &'a str {
+        self.enum_values
+            .iter()
+            .find(|(f, old, _)| f == field && old == label)
+            .map_or(label, |(_, _, new)| new)
+    }
+}
+
+/// A saved node that can't be read into the current layout.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NodeProblem {
+    /// Preorder id, as in `Node::paths`.
+    pub node: usize,
+    pub message: String,
+}
+
+#[derive(Debug)]
+pub enum PersistError {
+    Io(std::io::Error),
+    /// The text is not in the tree format.
+    Format {
+        line: usize,
+        message: String,
+    },
+    /// The tree is well-formed but uses fields or values the layout doesn't
+    /// have (or has with another type); every offending node is listed.
+    Schema(Vec<NodeProblem>),
+    /// A layout field (or the class, as `class`) of a type with no `FromStr`,
+    /// whose saved values can't be read back.
+    Unreadable {
+        field: String,
+        type_name: String,
+    },
+}
+
+impl Display for PersistError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "{}", e),
+            Self::Format { line, message } => write!(f, "line {}: {}", line, message),
+            Self::Schema(problems) => {
+                write!(f, "tree doesn't match the layout:")?;
+                for p in problems {
+                    write!(f, "\n  node {}: {}", p.node, p.message)?;
+                }
+                Ok(())
+            }
+            Self::Unreadable { field, type_name } => write!(
+                f,
+                "field `{}` is a {}, which has no FromStr to read saved values with",
+                field, type_name
+            ),
+        }
+    }
+}
+
+impl std::error::Error for PersistError {}
+
+impl PersistError {
+    /// The error with its line number moved down by `lines`, for a file
+    /// saved inside another one.
+    pub fn offset(self, lines: usize) -> Self {
+        match self {
+            Self::Format { line, message } => Self::Format {
+                line: line + lines,
+                message,
+            },
+            other => other,
+        }
+    }
+}
+
+impl From<std::io::Error> for PersistError {
+    fn from(e: std::io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+/// Escapes tabs, newlines and backslashes so `token` stays one token.
+pub fn escape(token: &str) -> String {
+    let mut out = String::with_capacity(token.len());
+    for c in token.chars() {
+        match c {
+            '\\' => out.push_str("\\\\"),
+            '\t' => out.push_str("\\t"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// Splits a line into unescaped tokens.
+pub fn split_line(line: &str) -> Result<Vec<String>, &'static str> {
+    line.split('\t')
+        .map(|token| {
+            let mut out = String::with_capacity(token.len());
+            let mut chars = token.chars();
+            while let Some(c) = chars.next() {
+                if c != '\\' {
+                    out.push(c);
+                    continue;
+                }
+                out.push(match chars.next() {
+                    Some('\\') => '\\',
+                    Some('t') => '\t',
+                    Some('n') => '\n',
+                    Some('r') => '\r',
+                    _ => return Err("invalid escape"),
+                });
+            }
+            Ok(out)
+        })
+        .collect()
+}
@@ -0,0 +1,36 @@
+//! The steps the generated `Pipeline` chains in front of its tree.
+//!
+//! A pipeline fits its steps in order, each on the rows the steps before it
+//! produced, then grows the tree on the result. At prediction time only the
+//! steps whose [`Stage`] is `TrainAndServe` run again, with the parameters
+//! fitted in training, so serving prepares a point exactly like training
+//! did. Derived fields need no step: they are computed whenever a row
+//! becomes a `DataPoint`.
+
+/// When a fitted step runs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stage {
+    /// Only on the training rows, e.g. rebalancing.
+    Train,
+    /// On the training rows and again on every point predicted.
+    TrainAndServe,
+}
+
+/// A pipeline step before fitting.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StepSpec {
+    /// Fill missing values with an `Imputer` fitted on the values present.
+    Impute,
+    /// Oversample every class to the row count of the most common one,
+    /// drawing its rows with replacement from a generator seeded with `seed`.
+    Rebalance { seed: u64 },
+}
+
+impl StepSpec {
+    pub fn stage(&self) -> Stage {
+        match self {
+            StepSpec::Impute => Stage::TrainAndServe,
+            StepSpec::Rebalance { .. } => Stage::Train,
+        }
+    }
+}
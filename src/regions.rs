@@ -0,0 +1,104 @@
+//! Grids of predictions over two numeric fields and their SVG rendering.
+
+use std::fmt::Debug;
+
+use crate::labels::{label, Target};
+
+/// Values evaluated over a regular grid. `cells` is row-major: the cell for
+/// `(x_values[i], y_values[j])` is `cells[j * x_values.len() + i]`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Grid<T> {
+    pub x_values: Vec<f64>,
+    pub y_values: Vec<f64>,
+    pub cells: Vec<T>,
+}
+
+impl<T> Grid<T> {
+    pub fn get(&self, x_index: usize, y_index: usize) -> &T {
+        &self.cells[y_index * self.x_values.len() + x_index]
+    }
+}
+
+/// `resolution` evenly spaced values from `range.0` to `range.1` inclusive.
+pub fn linspace(range: (f64, f64), resolution: usize) -> Vec<f64> {
+    match resolution {
+        0 => Vec::new(),
+        1 => vec![range.0],
+        n => (0..n)
+            .map(|i| range.0 + (range.1 - range.0) * i as f64 / (n - 1) as f64)
+            .collect(),
+    }
+}
+
+const PALETTE: [&str; 10] = [
+    "#1f77b4", "#ff7f0e", "#2ca02c", "#d62728", "#9467bd", "#8c564b", "#e377c2", "#7f7f7f",
+    "#bcbd22", "#17becf",
+];
+const CELL_PX: f64 = 10.0;
+
+/// Renders the grid as an SVG heatmap, one color per class, with `points`
+/// (x, y, class) drawn on top and a legend underneath.
+pub fn render_svg<T: PartialEq + Debug>(grid: &Grid<T>, points: &[(f64, f64, T)]) -> String {
+    let mut classes: Vec<&T> = Vec::new();
+    for class in grid.cells.iter().chain(points.iter().map(|p| &p.2)) {
+        if !classes.contains(&class) {
+            classes.push(class);
+        }
+    }
+    classes.sort_by_key(|c| format!("{:?}", c));
+    let color =
+        |class: &T| PALETTE[classes.iter().position(|c| *c == class).unwrap() % PALETTE.len()];
+
+    let nx = grid.x_values.len();
+    let ny = grid.y_values.len();
+    let width = nx as f64 * CELL_PX;
+    let height = ny as f64 * CELL_PX;
+    let legend_height = 20.0 * classes.len() as f64;
+    let mut svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\">\n",
+        width,
+        height + legend_height
+    );
+    for j in 0..ny {
+        for i in 0..nx {
+            // higher y values are drawn at the top
+            svg += &format!(
+                "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"{}\" fill-opacity=\"0.4\"/>\n",
+                i as f64 * CELL_PX,
+                (ny - 1 - j) as f64 * CELL_PX,
+                CELL_PX,
+                CELL_PX,
+                color(grid.get(i, j))
+            );
+        }
+    }
+    let span = |values: &[f64]| match (values.first(), values.last()) {
+        (Some(lo), Some(hi)) if hi > lo => (*lo, hi - lo),
+        (Some(lo), _) => (*lo, 1.0),
+        _ => (0.0, 1.0),
+    };
+    let (x0, x_span) = span(&grid.x_values);
+    let (y0, y_span) = span(&grid.y_values);
+    for (x, y, class) in points {
+        let px = ((x - x0) / x_span * (nx.max(1) - 1) as f64 + 0.5) * CELL_PX;
+        let py = height - ((y - y0) / y_span * (ny.max(1) - 1) as f64 + 0.5) * CELL_PX;
+        svg += &format!(
+            "<circle cx=\"{:.1}\" cy=\"{:.1}\" r=\"3\" fill=\"{}\" stroke=\"black\"/>\n",
+            px,
+            py,
+            color(class)
+        );
+    }
+    for (k, class) in classes.iter().enumerate() {
+        let y = height + 20.0 * k as f64;
+        svg += &format!(
+            "<rect x=\"0\" y=\"{}\" width=\"12\" height=\"12\" fill=\"{}\"/><text x=\"16\" y=\"{}\" font-size=\"12\">{}</text>\n",
+            y + 4.0,
+            color(class),
+            y + 14.0,
+            label(*class, Target::Xml)
+        );
+    }
+    svg += "</svg>\n";
+    svg
+}
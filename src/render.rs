@@ -0,0 +1,64 @@
+//! Options shared by the tree renderers.
+
+/// Layout used when writing a tree as text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TreeStyle {
+    /// The `print_tree` layout: nested `--> True:` / `--> False;` blocks.
+    #[default]
+    Indent,
+    /// `├──` / `└──` / `│` connectors.
+    BoxDrawing,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct RenderOptions {
+    pub style: TreeStyle,
+    /// Color questions, leaves (by majority class) and percentages with ANSI
+    /// escapes. Off by default.
+    pub color: bool,
+    /// Format of percentages and thresholds.
+    pub number_format: crate::NumberFormat,
+    /// Subtrees to mark `[frozen]`.
+    pub frozen: crate::freeze::Frozen,
+    /// p-values from `split_pvalues`, shown next to their questions.
+    pub split_pvalues: std::collections::HashMap<usize, f32>,
+}
+
+/// Splits with a p-value above this are marked as likely spurious.
+pub const SPURIOUS_PVALUE: f32 = 0.05;
+
+const CLASS_COLORS: [&str; 6] = ["32", "33", "34", "35", "31", "36"];
+const QUESTION_COLOR: &str = "1;36";
+const DIM: &str = "2";
+
+/// Wraps `text` in the ANSI escape `code` when `enabled`.
+pub fn paint(text: &str, code: &str, enabled: bool) -> String {
+    if enabled {
+        format!("\x1b[{}m{}\x1b[0m", code, text)
+    } else {
+        text.to_owned()
+    }
+}
+
+pub fn paint_question(text: &str, enabled: bool) -> String {
+    paint(text, QUESTION_COLOR, enabled)
+}
+
+/// Colors a class label by its position among the tree's classes.
+pub fn paint_class(text: &str, index: usize, enabled: bool) -> String {
+    paint(text, CLASS_COLORS[index % CLASS_COLORS.len()], enabled)
+}
+
+pub fn paint_dim(text: &str, enabled: bool) -> String {
+    paint(text, DIM, enabled)
+}
+
+/// ` (p = 0.01)`, or ` (p = 0.40, likely spurious)` above [`SPURIOUS_PVALUE`].
+pub fn pvalue_note(pvalue: f32, format: &crate::NumberFormat) -> String {
+    let text = format.localize(&format!("{:.2}", pvalue));
+    if pvalue > SPURIOUS_PVALUE {
+        format!(" (p = {}, likely spurious)", text)
+    } else {
+        format!(" (p = {})", text)
+    }
+}
@@ -0,0 +1,147 @@
+//! Sharing trained models across threads and classifying in batches.
+//!
+//! The generated `Node` implements [`Model`] for `DataPoint`, so a tree can be
+//! wrapped in an `Arc` and handed to a [`BatchPredictor`] or served over
+//! channels with [`spawn`].
+
+use std::sync::mpsc::{self, Sender};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+
+/// Anything that maps a point to a prediction and can be shared between threads.
+pub trait Model<P>: Send + Sync {
+    type Output: Send;
+    fn predict(&self, point: &P) -> Self::Output;
+}
+
+/// Collects incoming points and classifies them `batch_size` at a time,
+/// optionally spreading each batch over several threads.
+pub struct BatchPredictor<P, M: Model<P>> {
+    model: Arc<M>,
+    batch_size: usize,
+    threads: usize,
+    pending: Vec<P>,
+}
+
+impl<P: Sync, M: Model<P>> BatchPredictor<P, M> {
+    pub fn new(model: Arc<M>, batch_size: usize) -> Self {
+        let batch_size = batch_size.max(1);
+        Self {
+            model,
+            batch_size,
+            threads: 1,
+            pending: Vec::with_capacity(batch_size),
+        }
+    }
+
+    /// Classify each batch on up to `threads` scoped threads.
+    pub fn with_threads(mut self, threads: usize) -> Self {
+        self.threads = threads.max(1);
+        self
+    }
+
+    /// Queues a point; returns the predictions of the whole batch once it is full.
+    pub fn push(&mut self, point: P) -> Option<Vec<M::Output>> {
+        self.pending.push(point);
+        if self.pending.len() >= self.batch_size {
+            Some(self.flush())
+        } else {
+            None
+        }
+    }
+
+    /// Classifies whatever is queued, in arrival order.
+    pub fn flush(&mut self) -> Vec<M::Output> {
+        let batch = std::mem::take(&mut self.pending);
+        self.predict_batch(&batch)
+    }
+
+    /// Classifies `points` in chunks of `batch_size`, preserving input order.
+    pub fn predict_batch(&self, points: &[P]) -> Vec<M::Output> {
+        let mut out = Vec::with_capacity(points.len());
+        for chunk in points.chunks(self.batch_size) {
+            if self.threads == 1 || chunk.len() < 2 {
+                out.extend(chunk.iter().map(|p| self.model.predict(p)));
+                continue;
+            }
+            let per_thread = chunk.len().div_ceil(self.threads);
+            let model = &self.model;
+            thread::scope(|scope| {
+                let handles: Vec<_> = chunk
+                    .chunks(per_thread)
+                    .map(|part| {
+                        scope.spawn(move || {
+                            part.iter().map(|p| model.predict(p)).collect::<Vec<_>>()
+                        })
+                    })
+                    .collect();
+                for handle in handles {
+                    out.extend(handle.join().expect("prediction thread panicked"));
+                }
+            });
+        }
+        out
+    }
+}
+
+struct Request<P, R> {
+    point: P,
+    reply: Sender<R>,
+}
+
+/// Handle for sending points to a model served by [`spawn`]. Clone it once per
+/// producer thread; the server stops when every client has been dropped.
+pub struct PredictionClient<P, R> {
+    sender: Sender<Request<P, R>>,
+}
+
+impl<P, R> Clone for PredictionClient<P, R> {
+    fn clone(&self) -> Self {
+        Self {
+            sender: self.sender.clone(),
+        }
+    }
+}
+
+impl<P, R> PredictionClient<P, R> {
+    /// Sends a point and blocks until its prediction comes back. Returns `None`
+    /// if the server has shut down.
+    pub fn predict(&self, point: P) -> Option<R> {
+        let (reply, response) = mpsc::channel();
+        self.sender.send(Request { point, reply }).ok()?;
+        response.recv().ok()
+    }
+}
+
+/// Starts a server thread that drains up to `batch_size` queued requests at a
+/// time and answers them from `model`.
+pub fn spawn<P, M>(
+    model: Arc<M>,
+    batch_size: usize,
+) -> (PredictionClient<P, M::Output>, JoinHandle<()>)
+where
+    P: Send + Sync + 'static,
+    M: Model<P> + 'static,
+    M::Output: 'static,
+{
+    let (sender, receiver) = mpsc::channel::<Request<P, M::Output>>();
+    let handle = thread::spawn(move || {
+        let predictor = BatchPredictor::new(model, batch_size);
+        while let Ok(first) = receiver.recv() {
+            let mut batch = vec![first];
+            while batch.len() < predictor.batch_size {
+                match receiver.try_recv() {
+                    Ok(request) => batch.push(request),
+                    Err(_) => break,
+                }
+            }
+            let (points, replies): (Vec<P>, Vec<Sender<M::Output>>) =
+                batch.into_iter().map(|r| (r.point, r.reply)).unzip();
+            for (prediction, reply) in predictor.predict_batch(&points).into_iter().zip(replies) {
+                // the client may have given up waiting; nothing to do then
+                let _ = reply.send(prediction);
+            }
+        }
+    });
+    (PredictionClient { sender }, handle)
+}
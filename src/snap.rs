@@ -0,0 +1,171 @@
+//! Reports of rounding split thresholds to readable values, of merging
+//! thresholds that differ by less than a tolerance, and of moving one
+//! threshold by hand.
+
+/// One threshold that a policy would round.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SnapChange {
+    /// Preorder id of the decision node.
+    pub node: usize,
+    pub field: String,
+    pub from: f64,
+    pub to: f64,
+    /// Training rows reaching the node.
+    pub rows: usize,
+    /// Accuracy of the node's subtree on those rows after minus before.
+    pub accuracy_delta: f64,
+    /// False if the loss exceeded the tolerance and the exact threshold was kept.
+    pub accepted: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct SnapReport {
+    /// Every threshold the policy would change, in preorder.
+    pub changes: Vec<SnapChange>,
+}
+
+impl SnapReport {
+    pub fn accepted(&self) -> impl Iterator<Item = &SnapChange> {
+        self.changes.iter().filter(|c| c.accepted)
+    }
+
+    pub fn rejected(&self) -> impl Iterator<Item = &SnapChange> {
+        self.changes.iter().filter(|c| !c.accepted)
+    }
+
+    pub fn render(&self) -> String {
+        let mut out = format!(
+            "{} thresholds snapped, {} kept exact\n",
+            self.accepted().count(),
+            self.rejected().count()
+        );
+        for c in &self.changes {
+            out += &format!(
+                "  node {:>3}  {} >= {} -> {}  ({} rows, accuracy {:+.4}){}\n",
+                c.node,
+                c.field,
+                c.from,
+                c.to,
+                c.rows,
+                c.accuracy_delta,
+                if c.accepted { "" } else { "  rejected" }
+            );
+        }
+        out
+    }
+}
+
+/// One threshold moved onto the representative of its cluster.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ThresholdMerge {
+    /// Preorder id of the decision node.
+    pub node: usize,
+    pub field: String,
+    pub from: f64,
+    pub to: f64,
+    /// False if a training row reaching the node would change sides, in
+    /// which case the threshold was kept.
+    pub accepted: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct CanonicalReport {
+    /// Every threshold with a different representative, in preorder.
+    pub merges: Vec<ThresholdMerge>,
+    /// Distinct questions asked by the tree before and after.
+    pub questions_before: usize,
+    pub questions_after: usize,
+}
+
+impl CanonicalReport {
+    /// Thresholds replaced by their representative.
+    pub fn merged(&self) -> usize {
+        self.merges.iter().filter(|m| m.accepted).count()
+    }
+
+    pub fn render(&self) -> String {
+        let mut out = format!(
+            "{} thresholds merged, {} kept; {} distinct questions -> {}\n",
+            self.merged(),
+            self.merges.len() - self.merged(),
+            self.questions_before,
+            self.questions_after
+        );
+        for m in &self.merges {
+            out += &format!(
+                "  node {:>3}  {} >= {} -> {}{}\n",
+                m.node,
+                m.field,
+                m.from,
+                m.to,
+                if m.accepted { "" } else { "  rejected" }
+            );
+        }
+        out
+    }
+}
+
+/// A leaf below a moved threshold whose rows change.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LeafShift {
+    /// Preorder id; the tree's shape, and so its ids, don't change.
+    pub leaf: usize,
+    /// (class, rows of the data) reaching the leaf, by descending rows.
+    pub before: Vec<(String, u64)>,
+    pub after: Vec<(String, u64)>,
+}
+
+/// What moving one threshold would change, from `Node::what_if_threshold`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WhatIfReport {
+    /// Preorder id of the decision node.
+    pub node: usize,
+    pub field: String,
+    pub from: f64,
+    /// The new threshold as the field's type holds it.
+    pub to: f64,
+    /// Rows of the data reaching the node.
+    pub rows: usize,
+    /// Indices into the data of the rows that would switch branches.
+    pub switched: Vec<usize>,
+    /// The leaves those rows leave or join, by id.
+    pub leaves: Vec<LeafShift>,
+    /// Accuracy on the whole data, before and after.
+    pub accuracy_before: f64,
+    pub accuracy_after: f64,
+}
+
+impl WhatIfReport {
+    pub fn accuracy_delta(&self) -> f64 {
+        self.accuracy_after - self.accuracy_before
+    }
+
+    pub fn render(&self) -> String {
+        let mut out = format!(
+            "node {}  {} >= {} -> {}: {} of {} rows switch, accuracy {:+.4}\n",
+            self.node,
+            self.field,
+            self.from,
+            self.to,
+            self.switched.len(),
+            self.rows,
+            self.accuracy_delta()
+        );
+        let counts = |counts: &[(String, u64)]| {
+            counts
+                .iter()
+                .map(|(class, n)| format!("{} {}", class, n))
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        for leaf in &self.leaves {
+            out += &format!(
+                "  leaf {:>3}  {} -> {}\n",
+                leaf.leaf,
+                counts(&leaf.before),
+                counts(&leaf.after)
+            );
+        }
+        out
+    }
+}
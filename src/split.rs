@@ -0,0 +1,150 @@
+//! Bookkeeping for the split search.
+
+use std::hash::Hash;
+
+use crate::trace::BuildObserver;
+use crate::ClassCounts;
+
+/// The impurity a split's gain is the decrease of. `TreeConfig::min_gain`
+/// is in its units.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum SplitCriterion {
+    /// Gini impurity, `1 - sum(p^2)`.
+    #[default]
+    Gini,
+    /// Shannon entropy in bits, `-sum(p log2 p)`; the gain is the
+    /// information gain.
+    Entropy,
+}
+
+impl SplitCriterion {
+    pub fn impurity<C: Eq + Hash + Clone>(self, counts: &ClassCounts<C>) -> f64 {
+        match self {
+            SplitCriterion::Gini => counts.gini(),
+            SplitCriterion::Entropy => counts.entropy(),
+        }
+    }
+}
+
+/// Counters collected while searching one node for its best split.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SplitStats {
+    /// Candidate questions generated.
+    pub candidates: usize,
+    /// Candidates whose gain was actually computed.
+    pub scored: usize,
+    /// Candidates whose partition matched one already scored at this node.
+    pub cache_hits: usize,
+    /// Candidates that left one side empty.
+    pub skipped_empty: usize,
+    /// Candidates that left fewer than `min_samples_leaf` rows on a side.
+    pub skipped_min_samples: usize,
+    /// Candidates that left less than `min_side_fraction` of the rows on a side.
+    pub skipped_min_fraction: usize,
+    /// Candidates not scored because a bound on their gain showed they
+    /// couldn't beat the best candidate so far.
+    pub skipped_bound: usize,
+    /// Fields with a single value among the node's rows, not searched.
+    pub constant_fields: usize,
+    /// Number fields with more distinct values than `max_candidates`, searched
+    /// on a sample of them.
+    pub sampled_fields: usize,
+    /// 1 when `TreeConfig::forced_fields` limited the search to the forced
+    /// fields and one of them gave the split.
+    pub forced: usize,
+    /// 1 when fields were forced but none gave a valid split, so every field
+    /// was searched.
+    pub forced_fell_through: usize,
+}
+
+/// Preorder ids of the nodes where `TreeConfig::forced_fields` applied.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ForcingReport {
+    /// A forced field gave the split.
+    pub forced: Vec<usize>,
+    /// No forced field gave a valid split; the node was searched on every
+    /// field.
+    pub fell_through: Vec<usize>,
+    nodes: usize,
+}
+
+impl<Q> BuildObserver<Q> for ForcingReport {
+    type Started = ();
+    fn start(&mut self) {}
+    // nodes are reported in preorder
+    fn node(&mut self, _: (), _: usize, _: usize, _: Option<&Q>, _: f64, stats: &SplitStats) {
+        if stats.forced > 0 {
+            self.forced.push(self.nodes);
+        }
+        if stats.forced_fell_through > 0 {
+            self.fell_through.push(self.nodes);
+        }
+        self.nodes += 1;
+    }
+}
+
+/// A decision node's question next to the runner-up kept by
+/// `TreeConfig::keep_runner_up`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Alternative {
+    /// Preorder id of the node.
+    pub node: usize,
+    pub question: String,
+    pub gain: f64,
+    pub runner_up: String,
+    pub runner_up_gain: f64,
+}
+
+impl Alternative {
+    /// `(gain - runner_up_gain) / gain`; 0 when both are 0.
+    pub fn relative_gap(&self) -> f64 {
+        if self.gain > 0.0 {
+            (self.gain - self.runner_up_gain) / self.gain
+        } else {
+            0.0
+        }
+    }
+}
+
+/// Every node with a stored runner-up, in preorder.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RobustnessReport {
+    pub alternatives: Vec<Alternative>,
+    /// Relative gap at or below which a node counts as a near tie.
+    pub tie_margin: f64,
+}
+
+impl RobustnessReport {
+    /// Nodes whose question was barely better than the runner-up, so the
+    /// structure below them is close to arbitrary.
+    pub fn near_ties(&self) -> impl Iterator<Item = &Alternative> {
+        self.alternatives
+            .iter()
+            .filter(|a| a.relative_gap() <= self.tie_margin)
+    }
+
+    pub fn render(&self) -> String {
+        let mut out = format!(
+            "{} of {} nodes within {:.1}% of their runner-up\n",
+            self.near_ties().count(),
+            self.alternatives.len(),
+            self.tie_margin * 100.0
+        );
+        for a in &self.alternatives {
+            out += &format!(
+                "  node {:>3}  {} ({:.4})  vs  {} ({:.4}){}\n",
+                a.node,
+                a.question,
+                a.gain,
+                a.runner_up,
+                a.runner_up_gain,
+                if a.relative_gap() <= self.tie_margin {
+                    "  near tie"
+                } else {
+                    ""
+                }
+            );
+        }
+        out
+    }
+}
@@ -0,0 +1,158 @@
+//! Structural stability of trees grown on resamples of the same data.
+
+use std::collections::HashMap;
+
+use crate::stats::sorted_quantile;
+
+/// The splits of one tree: (depth, field, threshold for number fields),
+/// plus its fingerprint.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TreeSplits {
+    pub fingerprint: u64,
+    pub splits: Vec<(usize, String, Option<f64>)>,
+}
+
+/// How often one field is split on at one depth.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldChoice {
+    pub field: String,
+    /// Fraction of the trees with at least one split on the field at this depth.
+    pub share: f64,
+    /// Every threshold chosen for the field here, sorted; empty for enum fields.
+    pub thresholds: Vec<f64>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LevelStability {
+    pub depth: usize,
+    /// Most common first.
+    pub choices: Vec<FieldChoice>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct StabilityReport {
+    pub trees: usize,
+    pub levels: Vec<LevelStability>,
+    /// Mean pairwise weighted Jaccard similarity of the trees' multisets of
+    /// split fields: 1 when every tree splits on the same fields equally often.
+    pub score: f64,
+    /// Number of different fingerprints among the trees.
+    pub distinct_structures: usize,
+}
+
+impl StabilityReport {
+    pub fn render(&self) -> String {
+        let mut out = format!(
+            "Stability over {} trees: score {:.3}, {} distinct structures\n",
+            self.trees, self.score, self.distinct_structures
+        );
+        for level in &self.levels {
+            out += &format!("Depth {}:\n", level.depth);
+            for choice in &level.choices {
+                out += &format!("  {:<16} {:>5.1}%", choice.field, choice.share * 100.0);
+                if !choice.thresholds.is_empty() {
+                    let t = &choice.thresholds;
+                    out += &format!(
+                        "  thresholds {} / {} / {} (min / median / max)",
+                        t[0],
+                        sorted_quantile(t, 0.5),
+                        t[t.len() - 1]
+                    );
+                }
+                out.push('\n');
+            }
+        }
+        out
+    }
+}
+
+pub fn analyze(trees: &[TreeSplits]) -> StabilityReport {
+    let levels = trees
+        .iter()
+        .flat_map(|t| t.splits.iter().map(|(depth, _, _)| depth + 1))
+        .max()
+        .unwrap_or(0);
+    let levels = (0..levels).map(|depth| level(trees, depth)).collect();
+
+    let multisets: Vec<HashMap<&str, usize>> = trees
+        .iter()
+        .map(|t| {
+            let mut counts = HashMap::new();
+            for (_, field, _) in &t.splits {
+                *counts.entry(field.as_str()).or_insert(0) += 1;
+            }
+            counts
+        })
+        .collect();
+    let mut total = 0.0;
+    let mut pairs = 0;
+    for i in 0..multisets.len() {
+        for j in i + 1..multisets.len() {
+            total += weighted_jaccard(&multisets[i], &multisets[j]);
+            pairs += 1;
+        }
+    }
+
+    let mut fingerprints: Vec<u64> = trees.iter().map(|t| t.fingerprint).collect();
+    fingerprints.sort_unstable();
+    fingerprints.dedup();
+    StabilityReport {
+        trees: trees.len(),
+        levels,
+        score: if pairs > 0 { total / pairs as f64 } else { 1.0 },
+        distinct_structures: fingerprints.len(),
+    }
+}
+
+fn level(trees: &[TreeSplits], depth: usize) -> LevelStability {
+    // (trees splitting on the field here, thresholds)
+    let mut fields: HashMap<&str, (usize, Vec<f64>)> = HashMap::new();
+    for tree in trees {
+        let mut seen: Vec<&str> = Vec::new();
+        for (_, field, threshold) in tree.splits.iter().filter(|(d, _, _)| *d == depth) {
+            let entry = fields.entry(field).or_default();
+            if !seen.contains(&field.as_str()) {
+                seen.push(field);
+                entry.0 += 1;
+            }
+            entry.1.extend(threshold);
+        }
+    }
+    let mut choices: Vec<FieldChoice> = fields
+        .into_iter()
+        .map(|(field, (n, mut thresholds))| {
+            thresholds.sort_by(|a, b| a.total_cmp(b));
+            FieldChoice {
+                field: field.to_owned(),
+                share: n as f64 / trees.len() as f64,
+                thresholds,
+            }
+        })
+        .collect();
+    choices.sort_by(|a, b| {
+        b.share
+            .total_cmp(&a.share)
+            .then_with(|| a.field.cmp(&b.field))
+    });
+    LevelStability { depth, choices }
+}
+
+// sum of the smaller counts over sum of the larger ones; 1 for two empty sets
+fn weighted_jaccard(a: &HashMap<&str, usize>, b: &HashMap<&str, usize>) -> f64 {
+    let (mut min, mut max) = (0, 0);
+    for (field, &n) in a {
+        let m = b.get(field).copied().unwrap_or(0);
+        min += n.min(m);
+        max += n.max(m);
+    }
+    max += b
+        .iter()
+        .filter(|(f, _)| !a.contains_key(*f))
+        .map(|(_, n)| n)
+        .sum::<usize>();
+    if max == 0 {
+        1.0
+    } else {
+        min as f64 / max as f64
+    }
+}
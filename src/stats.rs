@@ -0,0 +1,227 @@
+//! Layout-independent numeric helpers used by the generated code.
+
+/// Natural log of the gamma function (Lanczos approximation).
+pub fn ln_gamma(x: f64) -> f64 {
+    const COEFFS: [f64; 6] = [
+        76.180_091_729_471_46,
+        -86.505_320_329_416_77,
+        24.014_098_240_830_91,
+        -1.231_739_572_450_155,
+        0.001_208_650_973_866_179,
+        -0.000_005_395_239_384_953,
+    ];
+    let tmp = x + 5.5;
+    let tmp = tmp - (x + 0.5) * tmp.ln();
+    let mut ser = 1.000_000_000_190_015;
+    let mut y = x;
+    for c in COEFFS {
+        y += 1.0;
+        ser += c / y;
+    }
+    -tmp + (2.506_628_274_631_000_5 * ser / x).ln()
+}
+
+// Continued fraction for the incomplete beta function (modified Lentz).
+fn beta_cf(x: f64, a: f64, b: f64) -> f64 {
+    const TINY: f64 = 1e-300;
+    let qab = a + b;
+    let qap = a + 1.0;
+    let qam = a - 1.0;
+    let mut c = 1.0;
+    let mut d = 1.0 - qab * x / qap;
+    if d.abs() < TINY {
+        d = TINY;
+    }
+    d = 1.0 / d;
+    let mut h = d;
+    for m in 1..500 {
+        let m = m as f64;
+        let m2 = 2.0 * m;
+        let aa = m * (b - m) * x / ((qam + m2) * (a + m2));
+        d = 1.0 + aa * d;
+        if d.abs() < TINY {
+            d = TINY;
+        }
+        c = 1.0 + aa / c;
+        if c.abs() < TINY {
+            c = TINY;
+        }
+        d = 1.0 / d;
+        h *= d * c;
+        let aa = -(a + m) * (qab + m) * x / ((a + m2) * (qap + m2));
+        d = 1.0 + aa * d;
+        if d.abs() < TINY {
+            d = TINY;
+        }
+        c = 1.0 + aa / c;
+        if c.abs() < TINY {
+            c = TINY;
+        }
+        d = 1.0 / d;
+        let del = d * c;
+        h *= del;
+        if (del - 1.0).abs() < 1e-14 {
+            break;
+        }
+    }
+    h
+}
+
+/// Regularized incomplete beta function I_x(a, b), i.e. the Beta(a, b) CDF at `x`.
+pub fn beta_cdf(x: f64, a: f64, b: f64) -> f64 {
+    if x <= 0.0 {
+        return 0.0;
+    }
+    if x >= 1.0 {
+        return 1.0;
+    }
+    let front =
+        (ln_gamma(a + b) - ln_gamma(a) - ln_gamma(b) + a * x.ln() + b * (1.0 - x).ln()).exp();
+    if x < (a + 1.0) / (a + b + 2.0) {
+        front * beta_cf(x, a, b) / a
+    } else {
+        1.0 - front * beta_cf(1.0 - x, b, a) / b
+    }
+}
+
+/// Inverse of [`beta_cdf`]: the `p`-quantile of Beta(a, b), found by bisection.
+pub fn beta_quantile(p: f64, a: f64, b: f64) -> f64 {
+    if p <= 0.0 {
+        return 0.0;
+    }
+    if p >= 1.0 {
+        return 1.0;
+    }
+    let (mut lo, mut hi) = (0.0_f64, 1.0_f64);
+    for _ in 0..100 {
+        let mid = 0.5 * (lo + hi);
+        if beta_cdf(mid, a, b) < p {
+            lo = mid;
+        } else {
+            hi = mid;
+        }
+    }
+    0.5 * (lo + hi)
+}
+
+/// The `p`-quantile of an already sorted sample, using linear interpolation.
+pub fn sorted_quantile(sorted: &[f64], p: f64) -> f64 {
+    if sorted.is_empty() {
+        return f64::NAN;
+    }
+    let pos = p.clamp(0.0, 1.0) * (sorted.len() - 1) as f64;
+    let lower = pos.floor() as usize;
+    let upper = pos.ceil() as usize;
+    let frac = pos - lower as f64;
+    sorted[lower] + (sorted[upper] - sorted[lower]) * frac
+}
+
+/// Posterior mean and equal-tailed credible interval of one component of a
+/// Dirichlet distribution, whose marginal is Beta(`alpha`, `alpha_total - alpha`).
+pub fn dirichlet_marginal_interval(alpha: f64, alpha_total: f64, level: f64) -> (f64, f64, f64) {
+    let beta = alpha_total - alpha;
+    if beta <= 0.0 {
+        return (1.0, 1.0, 1.0);
+    }
+    let tail = (1.0 - level.clamp(0.0, 1.0)) / 2.0;
+    (
+        alpha / alpha_total,
+        beta_quantile(tail, alpha, beta),
+        beta_quantile(1.0 - tail, alpha, beta),
+    )
+}
+
+/// Weight of evidence of a cell holding `positives` of `total_positives`
+/// and `negatives` of `total_negatives`: ln of the ratio of the two shares.
+/// Infinite when either count is 0.
+pub fn weight_of_evidence(
+    positives: f64,
+    negatives: f64,
+    total_positives: f64,
+    total_negatives: f64,
+) -> f64 {
+    ((positives / total_positives) / (negatives / total_negatives)).ln()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(actual: f64, expected: f64) {
+        assert!(
+            (actual - expected).abs() < 1e-6,
+            "{} isn't {}",
+            actual,
+            expected
+        );
+    }
+
+    #[test]
+    fn ln_gamma_at_integers_is_ln_factorial() {
+        close(ln_gamma(1.0), 0.0);
+        close(ln_gamma(5.0), 24f64.ln());
+        close(ln_gamma(11.0), 3_628_800f64.ln());
+    }
+
+    // Beta(a, 1) has CDF x^a and Beta(1, b) has 1 - (1 - x)^b, so their
+    // quantiles are known in closed form.
+    #[test]
+    fn beta_quantile_matches_closed_forms() {
+        for p in [0.005, 0.025, 0.1, 0.5, 0.9, 0.975, 0.995] {
+            close(beta_quantile(p, 1.0, 1.0), p);
+            for k in [2.0, 3.0, 7.5] {
+                close(beta_quantile(p, k, 1.0), p.powf(1.0 / k));
+                close(beta_quantile(p, 1.0, k), 1.0 - (1.0 - p).powf(1.0 / k));
+            }
+        }
+    }
+
+    // For integer a and b, I_x(a, b) is P(Binomial(a + b - 1, x) >= a); these
+    // are the 2.5% and 97.5% points of that polynomial, solved to full precision.
+    #[test]
+    fn beta_quantile_matches_binomial_tails() {
+        close(beta_quantile(0.025, 2.0, 2.0), 0.094_299_324);
+        close(beta_quantile(0.975, 2.0, 2.0), 0.905_700_676);
+        close(beta_quantile(0.025, 3.0, 7.0), 0.074_854_631);
+        close(beta_quantile(0.975, 3.0, 7.0), 0.600_093_574);
+        close(beta_quantile(0.025, 5.0, 3.0), 0.290_420_864);
+        close(beta_quantile(0.975, 5.0, 3.0), 0.901_011_722);
+    }
+
+    #[test]
+    fn beta_quantile_inverts_beta_cdf() {
+        for (a, b) in [(0.5, 0.5), (2.0, 5.0), (30.0, 4.0), (101.0, 1.0)] {
+            for p in [0.01, 0.3, 0.5, 0.77, 0.99] {
+                close(beta_cdf(beta_quantile(p, a, b), a, b), p);
+            }
+        }
+    }
+
+    #[test]
+    fn dirichlet_interval_is_the_beta_marginal() {
+        // Dirichlet(1, 1): each share is uniform
+        let (mean, lower, upper) = dirichlet_marginal_interval(1.0, 2.0, 0.9);
+        close(mean, 0.5);
+        close(lower, 0.05);
+        close(upper, 0.95);
+        // Dirichlet(3, 1, 1): the first share is Beta(3, 2)
+        let (mean, lower, upper) = dirichlet_marginal_interval(3.0, 5.0, 0.95);
+        close(mean, 0.6);
+        close(lower, beta_quantile(0.025, 3.0, 2.0));
+        close(upper, beta_quantile(0.975, 3.0, 2.0));
+        // Dirichlet(1, 3): Beta(1, 3), whose quantiles are closed form
+        let (mean, lower, upper) = dirichlet_marginal_interval(1.0, 4.0, 0.8);
+        close(mean, 0.25);
+        close(lower, 1.0 - 0.9f64.powf(1.0 / 3.0));
+        close(upper, 1.0 - 0.1f64.powf(1.0 / 3.0));
+        // a lone class is certain
+        assert_eq!(dirichlet_marginal_interval(4.0, 4.0, 0.95), (1.0, 1.0, 1.0));
+    }
+
+    #[test]
+    fn wider_level_widens_the_interval() {
+        let (_, lower_80, upper_80) = dirichlet_marginal_interval(4.0, 10.0, 0.8);
+        let (_, lower_99, upper_99) = dirichlet_marginal_interval(4.0, 10.0, 0.99);
+        assert!(lower_99 < lower_80 && upper_80 < upper_99);
+    }
+}
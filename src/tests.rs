@@ -0,0 +1,1195 @@
+//! Unit tests of the generated code's private parts (the split search and
+//! tree growing), through a layout expanded inside the crate.
+
+// most of the expansion goes unused here, and the generated functions take &Vec<DataPoint>
+#![allow(dead_code, clippy::ptr_arg)]
+
+use crate::rng::Rng;
+use crate::split::SplitCriterion;
+
+crate::classification_data_layout!(
+    enum_fields = {shade: u8, shiny: bool},
+    number_fields = {weight: f64, count: u32},
+    u8
+);
+
+// rows whose class depends on the fields with some noise, so the searches
+// have real candidates to tell apart
+fn random_rows(rng: &mut Rng, rows: usize, classes: usize) -> Vec<DataPoint> {
+    (0..rows)
+        .map(|_| {
+            let shade = rng.gen_index(5) as u8;
+            let shiny = rng.gen_index(2) == 0;
+            let weight = (rng.next_f64() * 100.).round() / 4.;
+            let count = rng.gen_index(12) as u32;
+            let class = if rng.gen_index(5) == 0 {
+                rng.gen_index(classes)
+            } else {
+                (shade as usize + shiny as usize + (weight > 12.) as usize + count as usize / 5)
+                    % classes
+            };
+            DataPoint::new(shade, shiny, weight, count, class as u8)
+        })
+        .collect()
+}
+
+#[test]
+fn gain_bound_pruning_keeps_the_chosen_split() {
+    let mut rng = Rng::new(237);
+    let (mut skipped, mut saved) = (0, 0);
+    for round in 0..300 {
+        let rows = 4 + rng.gen_index(120);
+        let data = random_rows(&mut rng, rows, 2 + round % 4);
+        let rows = row_refs(&data);
+        let config = TreeConfig {
+            min_samples_leaf: 1 + rng.gen_index(4),
+            criterion: [SplitCriterion::Gini, SplitCriterion::Entropy][round % 2],
+            ..TreeConfig::default()
+        };
+        let mut pruned = SplitSearch::new(&rows, config.criterion);
+        let mut full = SplitSearch::new(&rows, config.criterion);
+        full.prune = false;
+        let chosen = search_split(&rows, &config, &mut pruned);
+        assert_eq!(
+            chosen,
+            search_split(&rows, &config, &mut full),
+            "round {}",
+            round
+        );
+        assert_eq!(full.stats.skipped_bound, 0);
+        assert!(pruned.stats.scored <= full.stats.scored, "round {}", round);
+        skipped += pruned.stats.skipped_bound;
+        saved += full.stats.scored - pruned.stats.scored;
+    }
+    // the bound did skip candidates, sparing their scoring, or this checks nothing
+    assert!(skipped > 0 && saved > 0);
+}
+
+#[test]
+fn repeated_partitions_are_scored_once() {
+    let mut rng = Rng::new(211);
+    // weight copies count, so every count threshold splits the rows as a
+    // weight threshold already did
+    let data: Vec<DataPoint> = random_rows(&mut rng, 200, 3)
+        .into_iter()
+        .map(|p| DataPoint::new(p.shade, p.shiny, p.count as f64, p.count, p.class))
+        .collect();
+    let config = TreeConfig {
+        keep_runner_up: true,
+        ..TreeConfig::default()
+    };
+    let stats = split_stats(&data, &config);
+    let mut counts: Vec<u32> = data.iter().map(|p| p.count).collect();
+    counts.sort_unstable();
+    counts.dedup();
+    assert!(stats.cache_hits >= counts.len() - 1, "{:?}", stats);
+    assert_eq!(
+        stats.candidates,
+        stats.scored
+            + stats.cache_hits
+            + stats.skipped_empty
+            + stats.skipped_min_samples
+            + stats.skipped_min_fraction
+            + stats.skipped_bound
+    );
+
+    let rows = row_refs(&data);
+    let mut search = SplitSearch::new(&rows, config.criterion);
+    let true_counts = class_counts(&rows[..50]);
+    let gain = search.score(&true_counts);
+    assert_eq!(search.score(&true_counts), gain);
+    assert_eq!((search.stats.scored, search.stats.cache_hits), (1, 1));
+}
+
+#[test]
+fn two_class_enum_splits_find_the_best_subset() {
+    let mut rng = Rng::new(224);
+    for round in 0..100 {
+        let rows = 10 + rng.gen_index(100);
+        let data = random_rows(&mut rng, rows, 2);
+        let rows = row_refs(&data);
+        let config = TreeConfig {
+            min_samples_leaf: 1 + round % 3,
+            ..TreeConfig::default()
+        };
+        let mut search = SplitSearch::new(&rows, config.criterion);
+        let (gain, question) = find_best_field_split(&rows, Field::shade, &mut search, &config);
+
+        // every proper subset of the shades present, scored directly
+        let mut shades: Vec<u8> = data.iter().map(|p| p.shade).collect();
+        shades.sort_unstable();
+        shades.dedup();
+        let parent = class_counts(&rows);
+        let mut best = 0_f64;
+        for mask in 1..(1 << shades.len()) - 1 {
+            let subset: Vec<u8> = (0..shades.len())
+                .filter(|i| mask & (1 << i) != 0)
+                .map(|i| shades[i])
+                .collect();
+            let (yes, no) = partition(&Question::shade(subset), &rows);
+            if yes.len() < config.min_samples_leaf || no.len() < config.min_samples_leaf {
+                continue;
+            }
+            let p = yes.len() as f64 / rows.len() as f64;
+            best = best.max(
+                parent.gini() - p * class_counts(&yes).gini() - (1. - p) * class_counts(&no).gini(),
+            );
+        }
+        assert!(
+            (gain - best).abs() < 1e-12,
+            "round {}: {} vs {}",
+            round,
+            gain,
+            best
+        );
+        if best > 0. {
+            let Some(Question::shade(values)) = question else {
+                panic!("round {}: no shade question", round);
+            };
+            assert!(values.windows(2).all(|pair| pair[0] < pair[1]));
+        }
+    }
+}
+
+#[test]
+fn weighted_rows_grow_the_tree_of_their_copies() {
+    let mut rng = Rng::new(202);
+    for round in 0..60 {
+        let rows = 5 + rng.gen_index(40);
+        let unique = random_rows(&mut rng, rows, 2 + round % 3);
+        let mut data = Vec::new();
+        for point in &unique {
+            for _ in 0..1 + rng.gen_index(10) {
+                data.push(point.clone());
+            }
+        }
+        // shuffled, so duplicates aren't adjacent
+        for i in (1..data.len()).rev() {
+            data.swap(i, rng.gen_index(i + 1));
+        }
+        let config = TreeConfig {
+            min_samples_leaf: 1 + rng.gen_index(6),
+            min_side_fraction: [0.0, 0.1][round % 2],
+            max_candidates: [10_000, 3][round % 3 / 2],
+            max_features: [None, Some(2)][round % 4 / 3],
+            feature_seed: round as u64,
+            ..TreeConfig::default()
+        };
+        let expected = build_tree_with(&data, &config).unwrap().fingerprint();
+        let (rows, weights) = dedupe_weighted(&data);
+        assert!(rows.len() <= unique.len());
+        let weighted = build_tree_weighted(&rows, &weights, &config).unwrap();
+        assert_eq!(weighted.fingerprint(), expected, "round {}", round);
+        let deduped = TreeConfig {
+            dedupe: true,
+            ..config
+        };
+        let deduped = build_tree_with(&data, &deduped).unwrap();
+        assert_eq!(deduped.fingerprint(), expected, "round {}", round);
+    }
+}
+
+#[test]
+fn capped_fields_sample_their_own_values() {
+    let mut rng = Rng::new(252);
+    for round in 0..20 {
+        let rows = 20 + rng.gen_index(300);
+        let data = random_rows(&mut rng, rows, 2 + round % 4);
+        let rows = row_refs(&data);
+        let config = TreeConfig {
+            max_candidates: 2 + rng.gen_index(20),
+            ..TreeConfig::default()
+        };
+        let mut distinct: Vec<f64> = data.iter().map(|p| p.weight).collect();
+        distinct.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        distinct.dedup();
+        let mut stats = crate::split::SplitStats::default();
+        let questions = candidate_questions(&rows, Field::weight, &config, &mut stats);
+        let sampled = distinct.len() > config.max_candidates;
+        assert_eq!(stats.sampled_fields, sampled as usize, "round {}", round);
+        if sampled {
+            assert!(questions.len() <= config.max_candidates, "round {}", round);
+        } else {
+            assert_eq!(questions.len(), distinct.len(), "round {}", round);
+        }
+        // thresholds are values of the rows, in order, each once
+        let thresholds: Vec<f64> = questions
+            .iter()
+            .map(|q| match q {
+                Question::weight(v) => *v,
+                other => panic!("{:?} isn't a weight question", other),
+            })
+            .collect();
+        assert!(thresholds.iter().all(|t| distinct.contains(t)));
+        assert!(
+            thresholds.windows(2).all(|w| w[0] < w[1]),
+            "round {}",
+            round
+        );
+        // and the same ones every time
+        let again = candidate_questions(&rows, Field::weight, &config, &mut stats);
+        assert_eq!(again, questions);
+    }
+}
+
+// midpoints between neighbouring values split the rows as the values
+// themselves do, so the tree only changes its thresholds, and those of the
+// integer field stay integers
+#[test]
+fn midpoint_thresholds_keep_every_partition() {
+    let mut rng = Rng::new(262);
+    for round in 0..20 {
+        let rows = 20 + rng.gen_index(200);
+        let data = random_rows(&mut rng, rows, 2 + round % 4);
+        let rows = row_refs(&data);
+        let exact = TreeConfig::default();
+        let midpoint = TreeConfig {
+            midpoint_thresholds: true,
+            ..TreeConfig::default()
+        };
+        let mut stats = crate::split::SplitStats::default();
+        for field in [Field::weight, Field::count] {
+            let values = candidate_questions(&rows, field, &exact, &mut stats);
+            let midpoints = candidate_questions(&rows, field, &midpoint, &mut stats);
+            assert_eq!(values.len(), midpoints.len(), "round {}", round);
+            for (value, midpoint) in values.iter().zip(&midpoints) {
+                let answers = |q: &Question| data.iter().map(|p| q.evaluate(p)).collect::<Vec<_>>();
+                assert_eq!(
+                    answers(value),
+                    answers(midpoint),
+                    "round {}, {:?}",
+                    round,
+                    midpoint
+                );
+            }
+        }
+        let (exact, midpoint) = (grow_tree(&data, &exact, 0), grow_tree(&data, &midpoint, 0));
+        assert_eq!(exact.node_count(), midpoint.node_count(), "round {}", round);
+        for point in &data {
+            assert_eq!(
+                classify(point, &exact).ordered(),
+                classify(point, &midpoint).ordered(),
+                "round {}",
+                round
+            );
+        }
+    }
+}
+
+#[test]
+fn weights_must_be_whole_and_one_per_row() {
+    let data = random_rows(&mut Rng::new(2), 3, 2);
+    let config = TreeConfig::default();
+    assert!(build_tree_weighted(&data, &[1., 2.], &config).is_err());
+    assert!(build_tree_weighted(&data, &[1., 0.5, 1.], &config).is_err());
+    assert!(build_tree_weighted(&data, &[1., -1., 1.], &config).is_err());
+    let dropped = build_tree_weighted(&data, &[1., 0., 1.], &config).unwrap();
+    let kept = vec![data[0].clone(), data[2].clone()];
+    assert_eq!(dropped.fingerprint(), build_tree(&kept).fingerprint());
+}
+
+// every decision node's (question, runner-up) pair
+fn runner_ups(node: &Node, out: &mut Vec<(Question, Option<RunnerUp>)>) {
+    if let Node::Decision {
+        question,
+        true_branch,
+        false_branch,
+        runner_up,
+    } = node
+    {
+        out.push((question.clone(), runner_up.as_deref().cloned()));
+        runner_ups(true_branch, out);
+        runner_ups(false_branch, out);
+    }
+}
+
+#[test]
+fn the_runner_up_is_another_question() {
+    let mut rng = Rng::new(233);
+    let mut kept = 0;
+    for round in 0..200 {
+        let rows = 10 + rng.gen_index(80);
+        let data = random_rows(&mut rng, rows, 2 + round % 3);
+        // forcing a field whose gain min_gain rejects makes the fallback search
+        // score its questions a second time
+        let config = TreeConfig {
+            keep_runner_up: true,
+            min_gain: [0.0, 0.01, 0.05][round % 3],
+            max_features: [None, Some(2)][round % 2],
+            forced_fields: vec![(Field::ALL[round % Field::ALL.len()], 1 + round % 3)],
+            ..TreeConfig::default()
+        };
+        let mut nodes = Vec::new();
+        runner_ups(&build_tree_with(&data, &config).unwrap(), &mut nodes);
+        for (question, runner_up) in nodes {
+            if let Some(runner_up) = runner_up {
+                assert!(
+                    runner_up.question != question,
+                    "round {}: {:?}",
+                    round,
+                    question
+                );
+                assert!(runner_up.gain <= runner_up.best_gain, "round {}", round);
+                kept += 1;
+            }
+        }
+    }
+    assert!(kept > 0);
+}
+
+// shiny is noise and weight decides the class, so an unforced tree asks
+// about weight first
+fn weight_decides(rows: usize, shiny: impl Fn(usize) -> bool) -> Vec<DataPoint> {
+    (0..rows)
+        .map(|i| {
+            let weight = (i % 20) as f64;
+            DataPoint::new(
+                i as u8 % 3,
+                shiny(i),
+                weight,
+                i as u32 % 7,
+                (weight >= 10.) as u8,
+            )
+        })
+        .collect()
+}
+
+fn root_field(tree: &Node) -> Option<Field> {
+    match tree {
+        Node::Decision { question, .. } => Some(question.field()),
+        Node::Leaf(_) => None,
+    }
+}
+
+#[test]
+fn a_forced_field_splits_the_root() {
+    let data = weight_decides(80, |i| i % 3 == 0);
+    assert_eq!(root_field(&build_tree(&data)), Some(Field::weight));
+    let config = TreeConfig {
+        forced_fields: vec![(Field::shiny, 1)],
+        ..TreeConfig::default()
+    };
+    let (tree, report) = build_tree_forced(&data, &config).unwrap();
+    assert_eq!(root_field(&tree), Some(Field::shiny));
+    assert_eq!(report.forced, vec![0]);
+    assert!(report.fell_through.is_empty());
+    // the forced split only reorders the tree
+    assert!(data
+        .iter()
+        .all(|point| predict(point, &tree) == point.class));
+}
+
+#[test]
+fn a_forced_field_that_cannot_split_falls_through() {
+    let config = TreeConfig {
+        forced_fields: vec![(Field::shiny, 1)],
+        keep_runner_up: true,
+        ..TreeConfig::default()
+    };
+    // constant, so it has no split at all
+    let data = weight_decides(80, |_| true);
+    let (tree, report) = build_tree_forced(&data, &config).unwrap();
+    assert_eq!(root_field(&tree), Some(Field::weight));
+    assert_eq!(report.fell_through, vec![0]);
+    assert!(report.forced.is_empty());
+
+    // splits, but gains less than min_gain: the fallback ranks only its own
+    // candidates, with no leftover shiny question as the runner-up
+    let data = weight_decides(80, |i| i % 3 == 0);
+    let config = TreeConfig {
+        min_gain: 0.05,
+        ..config
+    };
+    let (tree, report) = build_tree_forced(&data, &config).unwrap();
+    assert_eq!(root_field(&tree), Some(Field::weight));
+    assert_eq!(report.fell_through, vec![0]);
+    let mut nodes = Vec::new();
+    runner_ups(&tree, &mut nodes);
+    let root_runner_up = nodes[0].1.as_ref().expect("the root keeps a runner-up");
+    assert_eq!(root_runner_up.question.field(), Field::weight);
+}
+
+#[test]
+fn a_fallback_search_forgets_the_forced_candidates() {
+    // shiny decides the class; weight, forced, gains too little to split on
+    let data: Vec<DataPoint> = (0..80)
+        .map(|i| {
+            let shiny = i % 2 == 0;
+            DataPoint::new(0, shiny, (i / 2 % 10) as f64, 0, shiny as u8)
+        })
+        .collect();
+    let mut drawn_shiny = 0;
+    for seed in 0..20 {
+        let config = TreeConfig {
+            forced_fields: vec![(Field::weight, 1)],
+            min_gain: 0.05,
+            keep_runner_up: true,
+            max_features: Some(1),
+            feature_seed: seed,
+            ..TreeConfig::default()
+        };
+        let tree = build_tree_with(&data, &config).unwrap();
+        if let Node::Decision {
+            question,
+            runner_up,
+            ..
+        } = &tree
+        {
+            // shiny has a single question, so no other field's may follow it
+            assert_eq!(question.field(), Field::shiny);
+            assert!(runner_up.is_none(), "seed {}", seed);
+            drawn_shiny += 1;
+        }
+    }
+    assert!(drawn_shiny > 0);
+}
+
+#[test]
+fn suggested_configs_scale_with_the_data() {
+    let rows = |n: usize, minority: usize| -> Vec<DataPoint> {
+        (0..n)
+            .map(|i| DataPoint::new(0, false, i as f64, 0, (i < minority) as u8))
+            .collect()
+    };
+
+    let suggestion = suggest_config(&rows(3000, 150));
+    assert_eq!(suggestion.config.max_depth, Some(13));
+    assert_eq!(suggestion.config.min_samples_leaf, 3);
+    assert_eq!(suggestion.minority_fraction, 0.05);
+    assert!(suggestion.imbalanced);
+    // everything else is the default
+    let expected = TreeConfig {
+        max_depth: Some(13),
+        min_samples_leaf: 3,
+        ..TreeConfig::default()
+    };
+    assert_eq!(
+        format!("{:?}", suggestion.config),
+        format!("{:?}", expected)
+    );
+    assert!(suggestion.config.validate().is_ok());
+
+    let suggestion = suggest_config(&rows(100, 50));
+    assert_eq!(suggestion.config.max_depth, Some(8));
+    assert_eq!(suggestion.config.min_samples_leaf, 1);
+    assert_eq!(suggestion.minority_fraction, 0.5);
+    assert!(!suggestion.imbalanced);
+
+    // a single class is not imbalanced, and no rows still suggest a valid config
+    assert!(!suggest_config(&rows(40, 0)).imbalanced);
+    let suggestion = suggest_config(&Vec::new());
+    assert_eq!(suggestion.config.max_depth, Some(2));
+    assert_eq!(suggestion.minority_fraction, 0.0);
+    assert!(!suggestion.imbalanced);
+    assert!(suggestion.config.validate().is_ok());
+}
+
+#[test]
+fn frozen_paths_cover_their_subtrees() {
+    let mut frozen = crate::freeze::Frozen::new();
+    frozen.freeze(&[true, false]);
+    frozen.freeze(&[true, false, true]);
+    assert_eq!(frozen.paths(), [vec![true, false]]);
+    assert!(frozen.is_frozen(&[true, false, false]));
+    assert!(!frozen.is_frozen(&[true]));
+    assert!(frozen.contains_below(&[true]) && frozen.contains_below(&[]));
+    assert!(!frozen.contains_below(&[true, false]));
+    // freezing an ancestor replaces the paths below it
+    frozen.freeze(&[true]);
+    assert_eq!(frozen.paths(), [vec![true]]);
+}
+
+#[test]
+fn refits_keep_frozen_subtrees_and_regrow_the_rest() {
+    let mut rng = Rng::new(221);
+    let old = random_rows(&mut rng, 200, 2);
+    let new = random_rows(&mut rng, 300, 3);
+    let config = TreeConfig {
+        max_depth: Some(4),
+        ..TreeConfig::default()
+    };
+    let tree = build_tree_with(&old, &config).unwrap();
+    let questions = |node: &Node| -> Vec<Question> {
+        let mut nodes = Vec::new();
+        preorder(node, &mut nodes);
+        nodes.into_iter().filter_map(question_of).cloned().collect()
+    };
+
+    let mut frozen = crate::freeze::Frozen::new();
+    assert_eq!(
+        refit(&tree, &new, &config, &frozen).unwrap().fingerprint(),
+        build_tree_with(&new, &config).unwrap().fingerprint()
+    );
+
+    let path = [true, false];
+    assert!(question_of(tree.subtree(&path).unwrap()).is_some());
+    frozen.freeze(&path);
+    let refit_tree = refit(&tree, &new, &config, &frozen).unwrap();
+    assert_eq!(
+        refit_tree.subtree(&path).unwrap().fingerprint(),
+        tree.subtree(&path).unwrap().fingerprint()
+    );
+    // the nodes above it keep their questions, so it is still reached
+    assert_eq!(question_of(&refit_tree), question_of(&tree));
+    assert_eq!(
+        question_of(refit_tree.subtree(&[true]).unwrap()),
+        question_of(tree.subtree(&[true]).unwrap())
+    );
+    // and the rest fits the new rows
+    let (_, false_rows): (Vec<DataPoint>, Vec<DataPoint>) = new
+        .iter()
+        .cloned()
+        .partition(|p| question_of(&tree).unwrap().evaluate(p));
+    assert_eq!(
+        refit_tree.subtree(&[false]).unwrap().fingerprint(),
+        grow_tree(&false_rows, &config, 1).fingerprint()
+    );
+
+    // with update_counts the questions stay and the leaves count the new rows
+    frozen.update_counts = true;
+    let recounted = refit(&tree, &new, &config, &frozen).unwrap();
+    assert_eq!(
+        questions(recounted.subtree(&path).unwrap()),
+        questions(tree.subtree(&path).unwrap())
+    );
+    let routed = new
+        .iter()
+        .filter(|p| question_of(&tree).unwrap().evaluate(*p))
+        .filter(|p| {
+            !question_of(tree.subtree(&[true]).unwrap())
+                .unwrap()
+                .evaluate(*p)
+        })
+        .count();
+    let counted: u64 = recounted
+        .subtree(&path)
+        .unwrap()
+        .paths()
+        .map(|p| p.n_samples)
+        .sum();
+    assert_eq!(counted, routed as u64);
+
+    assert!(refit(&tree, &new, &config, &{
+        let mut deep = crate::freeze::Frozen::new();
+        deep.freeze(&[true; 30]);
+        deep
+    })
+    .is_err());
+}
+
+// checks the stopping rules of `config` at every node of `node`, grown from `rows`
+fn check_stopping_rules(node: &Node, rows: &[Row], config: &TreeConfig, depth: usize) {
+    let Node::Decision {
+        question,
+        true_branch,
+        false_branch,
+        ..
+    } = node
+    else {
+        return;
+    };
+    assert!(config.max_depth.is_none_or(|max| depth < max));
+    assert!(rows.len() >= config.min_samples_split);
+    let (true_rows, false_rows) = partition(question, rows);
+    let gain = SplitSearch::new(rows, config.criterion).score(&class_counts(&true_rows));
+    assert!(gain > 0.0 && gain >= config.min_gain as f64, "{}", gain);
+    check_stopping_rules(true_branch, &true_rows, config, depth + 1);
+    check_stopping_rules(false_branch, &false_rows, config, depth + 1);
+}
+
+#[test]
+fn stopping_rules_hold_at_every_split() {
+    let mut rng = Rng::new(255);
+    for round in 0..40 {
+        let rows = 2 + rng.gen_index(200);
+        let data = random_rows(&mut rng, rows, 2 + round % 4);
+        let config = TreeConfig {
+            max_depth: [None, Some(1), Some(3)][round % 3],
+            min_samples_split: 2 + rng.gen_index(20),
+            min_gain: [0.0, 0.01, 0.05, 0.1][round % 4],
+            ..TreeConfig::default()
+        };
+        let tree = build_tree_with(&data, &config).unwrap();
+        check_stopping_rules(&tree, &row_refs(&data), &config, 0);
+        if let Some(max) = config.max_depth {
+            assert!(tree.depth() <= max, "round {}", round);
+        }
+    }
+    // the defaults grow until no split gains anything
+    let data = random_rows(&mut rng, 150, 3);
+    let loose = TreeConfig {
+        min_samples_split: 2,
+        min_gain: 0.0,
+        ..TreeConfig::default()
+    };
+    assert_eq!(
+        build_tree_with(&data, &loose).unwrap().fingerprint(),
+        build_tree(&data).fingerprint()
+    );
+}
+
+// each limit cuts the unlimited tree back, and no rows grow one empty leaf
+#[test]
+fn limits_cut_the_full_tree_back() {
+    let mut rng = Rng::new(255);
+    let data = random_rows(&mut rng, 300, 3);
+    let full = build_tree(&data);
+    let limited = |config: TreeConfig| build_tree_with(&data, &config).unwrap();
+    assert!(full.depth() > 2);
+    let shallow = limited(TreeConfig {
+        max_depth: Some(2),
+        ..TreeConfig::default()
+    });
+    assert!(shallow.depth() <= 2);
+    let split_floor = limited(TreeConfig {
+        min_samples_split: 40,
+        ..TreeConfig::default()
+    });
+    assert!(split_floor.node_count() < full.node_count());
+    let too_few = limited(TreeConfig {
+        min_samples_split: data.len() + 1,
+        ..TreeConfig::default()
+    });
+    assert_eq!(too_few.node_count(), 1);
+    let gain_floor = limited(TreeConfig {
+        min_gain: 0.01,
+        ..TreeConfig::default()
+    });
+    assert!(gain_floor.node_count() < full.node_count());
+
+    let empty = build_tree(&Vec::new());
+    assert_eq!(empty.node_count(), 1);
+    assert!(classify(&data[0], &empty).is_empty());
+}
+
+fn leaf_count(node: &Node) -> usize {
+    match node {
+        Node::Leaf(_) => 1,
+        Node::Decision {
+            true_branch,
+            false_branch,
+            ..
+        } => leaf_count(true_branch) + leaf_count(false_branch),
+    }
+}
+
+#[test]
+fn paths_reach_every_leaf_once() {
+    let mut rng = Rng::new(225);
+    for round in 0..40 {
+        let rows = 2 + rng.gen_index(150);
+        let data = random_rows(&mut rng, rows, 2 + round % 4);
+        let tree = build_tree(&data);
+        let sizes = subtree_sizes(&tree);
+        let paths: Vec<PathInfo> = tree.paths().collect();
+        assert_eq!(paths.len(), leaf_count(&tree), "round {}", round);
+        assert!(paths
+            .windows(2)
+            .all(|pair| pair[0].leaf_id < pair[1].leaf_id));
+        assert_eq!(
+            paths.iter().map(|path| path.n_samples).sum::<u64>(),
+            data.len() as u64
+        );
+        // a row meets the conditions of exactly the path to its leaf
+        for point in &data {
+            let met: Vec<usize> = paths
+                .iter()
+                .filter(|path| path.conditions.iter().all(|c| c.holds(point)))
+                .map(|path| path.leaf_id)
+                .collect();
+            assert_eq!(met, vec![leaf_id(point, &tree, &sizes)], "round {}", round);
+        }
+    }
+}
+
+#[test]
+fn predictions_are_the_majority_of_the_leaf_reached() {
+    let mut rng = Rng::new(2512);
+    for round in 0..20 {
+        let rows = 2 + rng.gen_index(150);
+        let data = random_rows(&mut rng, rows, 2 + round % 4);
+        let tree = build_tree(&data);
+        let leaves: Vec<&Node> = {
+            let mut nodes = Vec::new();
+            preorder(&tree, &mut nodes);
+            nodes.retain(|node| matches!(node, Node::Leaf(_)));
+            nodes
+        };
+        for point in &data {
+            // the counts are borrowed from the tree's own leaf
+            let counts = classify(point, &tree);
+            assert!(leaves
+                .iter()
+                .any(|leaf| matches!(leaf, Node::Leaf(x) if std::ptr::eq(x, counts))));
+            let predicted = predict(point, &tree);
+            assert!(counts
+                .classes()
+                .all(|c| counts.get(c) <= counts.get(&predicted)));
+        }
+    }
+    // an even leaf goes to the label that sorts first
+    let mut even = crate::ClassCounts::new();
+    even.add(3, 4);
+    even.add(1, 4);
+    even.add(2, 1);
+    let point = DataPoint::new(0, false, 1.0, 1, 0);
+    assert_eq!(predict(&point, &Node::Leaf(even)), 1);
+}
+
+// the nodes of `node` in preorder, so position is the id the generated code gives them
+fn preorder<'a>(node: &'a Node, out: &mut Vec<&'a Node>) {
+    out.push(node);
+    if let Node::Decision {
+        true_branch,
+        false_branch,
+        ..
+    } = node
+    {
+        preorder(true_branch, out);
+        preorder(false_branch, out);
+    }
+}
+
+#[test]
+fn rendered_pvalues_sit_on_their_questions() {
+    let mut rng = Rng::new(208);
+    for round in 0..20 {
+        let rows = 20 + rng.gen_index(100);
+        let config = TreeConfig {
+            max_depth: Some(5),
+            ..TreeConfig::default()
+        };
+        let tree = build_tree_with(&random_rows(&mut rng, rows, 2 + round % 3), &config).unwrap();
+        let mut nodes = Vec::new();
+        preorder(&tree, &mut nodes);
+        // p-values that name their node, which the depth limit keeps below 1
+        let mut options = crate::render::RenderOptions::default();
+        for (id, node) in nodes.iter().enumerate() {
+            if let Node::Decision { .. } = node {
+                options.split_pvalues.insert(id, id as f32 / 100.);
+            }
+        }
+        for style in [
+            crate::render::TreeStyle::Indent,
+            crate::render::TreeStyle::BoxDrawing,
+        ] {
+            options.style = style;
+            let mut out = Vec::new();
+            tree.write_tree(&mut out, &options).unwrap();
+            let text = String::from_utf8(out).unwrap();
+            let noted: Vec<&str> = text.lines().filter(|line| line.contains("(p = ")).collect();
+            assert_eq!(noted.len(), options.split_pvalues.len(), "round {}", round);
+            for (&id, pvalue) in &options.split_pvalues {
+                let Node::Decision { question, .. } = nodes[id] else {
+                    unreachable!("only decision nodes have p-values");
+                };
+                let note = crate::render::pvalue_note(*pvalue, &Default::default());
+                let expected = question_text(question) + &note;
+                assert!(
+                    noted.iter().any(|line| line.ends_with(&expected)),
+                    "round {}: no line ends with {:?} in\n{}",
+                    round,
+                    expected,
+                    text
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn subtree_sizes_count_every_subtree() {
+    let mut rng = Rng::new(246);
+    for round in 0..20 {
+        let rows = 2 + rng.gen_index(150);
+        let tree = build_tree(&random_rows(&mut rng, rows, 2 + round % 4));
+        let mut nodes = Vec::new();
+        preorder(&tree, &mut nodes);
+        let sizes: Vec<usize> = nodes.iter().map(|node| node.node_count()).collect();
+        assert_eq!(subtree_sizes(&tree), sizes, "round {}", round);
+    }
+}
+
+#[cfg(feature = "export")]
+#[test]
+fn audit_records_name_the_leaf_and_the_way_there() {
+    let mut rng = Rng::new(210);
+    for round in 0..20 {
+        let rows = 2 + rng.gen_index(150);
+        let data = random_rows(&mut rng, rows, 2 + round % 4);
+        let tree = build_tree(&data);
+        let paths: Vec<PathInfo> = tree.paths().collect();
+        for point in &data {
+            let record = audit_record(point, &tree);
+            let path = paths
+                .iter()
+                .find(|path| path.leaf_id == record.leaf_id)
+                .expect("a leaf");
+            let steps: Vec<(String, bool)> = path
+                .conditions
+                .iter()
+                .map(|c| (question_text(&c.question), c.answer))
+                .collect();
+            let recorded: Vec<(String, bool)> = record
+                .path
+                .iter()
+                .map(|step| (step.question.clone(), step.answer))
+                .collect();
+            assert_eq!(recorded, steps, "round {}", round);
+        }
+    }
+}
+
+#[cfg(feature = "metrics")]
+#[test]
+fn snapped_thresholds_name_their_nodes() {
+    let mut rng = Rng::new(231);
+    let policy = SnapPolicy {
+        steps: vec![(Field::weight, 2.0)],
+        tolerance: 1.0,
+    };
+    let mut changes = 0;
+    for round in 0..20 {
+        let rows = 20 + rng.gen_index(150);
+        let data = random_rows(&mut rng, rows, 2 + round % 4);
+        let tree = build_tree(&data);
+        let (snapped, report) = tree.snap_thresholds(&data, &policy).unwrap();
+        let (mut before, mut after) = (Vec::new(), Vec::new());
+        preorder(&tree, &mut before);
+        preorder(&snapped, &mut after);
+        let weights = |nodes: &[&Node]| -> Vec<Option<f64>> {
+            nodes
+                .iter()
+                .map(|node| match node {
+                    Node::Decision {
+                        question: Question::weight(v),
+                        ..
+                    } => Some(*v),
+                    _ => None,
+                })
+                .collect()
+        };
+        let (before, after) = (weights(&before), weights(&after));
+        let moved: Vec<usize> = (0..before.len())
+            .filter(|&id| before[id] != after[id])
+            .collect();
+        assert_eq!(
+            report.changes.iter().map(|c| c.node).collect::<Vec<_>>(),
+            moved,
+            "round {}",
+            round
+        );
+        for change in &report.changes {
+            assert!(change.accepted);
+            assert_eq!(before[change.node], Some(change.from));
+            assert_eq!(after[change.node], Some(change.to));
+        }
+        changes += report.changes.len();
+    }
+    assert!(changes > 0);
+}
+
+#[cfg(feature = "metrics")]
+#[test]
+fn snaps_losing_more_than_the_tolerance_are_kept_exact() {
+    let mut rng = Rng::new(2310);
+    let policy = SnapPolicy {
+        steps: vec![(Field::weight, 5.0), (Field::count, 5.0)],
+        tolerance: 0.0,
+    };
+    let (mut accepted, mut rejected) = (0, 0);
+    for round in 0..30 {
+        let rows = 30 + rng.gen_index(150);
+        let data = random_rows(&mut rng, rows, 2 + round % 4);
+        let tree = build_tree(&data);
+        let (snapped, report) = tree.snap_thresholds(&data, &policy).unwrap();
+        let (mut before, mut after) = (Vec::new(), Vec::new());
+        preorder(&tree, &mut before);
+        preorder(&snapped, &mut after);
+        for change in &report.changes {
+            assert_eq!(
+                change.accepted,
+                change.accuracy_delta >= 0.0,
+                "round {}",
+                round
+            );
+            let kept = question_of(after[change.node]) == question_of(before[change.node]);
+            assert_eq!(kept, !change.accepted, "round {}", round);
+        }
+        // no node lost accuracy on its rows, so the tree as a whole didn't
+        let correct = |tree: &Node| {
+            data.iter()
+                .filter(|p| classify(*p, tree).argmax() == Some(&p.class))
+                .count()
+        };
+        assert!(correct(&snapped) >= correct(&tree), "round {}", round);
+        // and the rendered report has a line per change
+        assert_eq!(report.render().lines().count(), report.changes.len() + 1);
+        accepted += report.accepted().count();
+        rejected += report.rejected().count();
+    }
+    assert!(accepted > 0 && rejected > 0, "{} {}", accepted, rejected);
+}
+
+#[test]
+fn robustness_reports_name_the_nodes_with_runner_ups() {
+    let mut rng = Rng::new(233);
+    let config = TreeConfig {
+        keep_runner_up: true,
+        ..TreeConfig::default()
+    };
+    for round in 0..20 {
+        let rows = 10 + rng.gen_index(150);
+        let data = random_rows(&mut rng, rows, 2 + round % 4);
+        let tree = build_tree_with(&data, &config).unwrap();
+        let mut nodes = Vec::new();
+        preorder(&tree, &mut nodes);
+        let expected: Vec<(usize, String)> = nodes
+            .iter()
+            .enumerate()
+            .filter_map(|(id, node)| match node {
+                Node::Decision {
+                    question,
+                    runner_up: Some(_),
+                    ..
+                } => Some((id, question_text(question))),
+                _ => None,
+            })
+            .collect();
+        let report = robustness_report(&tree, 0.05).unwrap();
+        let reported: Vec<(usize, String)> = report
+            .alternatives
+            .iter()
+            .map(|a| (a.node, a.question.clone()))
+            .collect();
+        assert_eq!(reported, expected, "round {}", round);
+    }
+}
+
+#[cfg(feature = "metrics")]
+#[test]
+fn split_pvalues_cover_the_decision_nodes() {
+    let mut rng = Rng::new(223);
+    for round in 0..10 {
+        let rows = 10 + rng.gen_index(100);
+        let data = random_rows(&mut rng, rows, 2 + round % 3);
+        let tree = build_tree(&data);
+        let mut nodes = Vec::new();
+        preorder(&tree, &mut nodes);
+        let decisions: Vec<usize> = (0..nodes.len())
+            .filter(|&id| matches!(nodes[id], Node::Decision { .. }))
+            .collect();
+        let mut ids: Vec<usize> = split_pvalues(&tree, &data, 5, 1)
+            .unwrap()
+            .into_keys()
+            .collect();
+        ids.sort();
+        assert_eq!(ids, decisions, "round {}", round);
+    }
+}
+
+#[cfg(feature = "metrics")]
+#[test]
+fn threshold_merges_name_their_nodes() {
+    let mut rng = Rng::new(250);
+    let mut merged = 0;
+    for round in 0..20 {
+        let rows = 20 + rng.gen_index(150);
+        let data = random_rows(&mut rng, rows, 2 + round % 4);
+        let tree = build_tree(&data);
+        let (canonical, report) = tree.canonicalize_thresholds(&data, 3.0).unwrap();
+        let (mut before, mut after) = (Vec::new(), Vec::new());
+        preorder(&tree, &mut before);
+        preorder(&canonical, &mut after);
+        let threshold = |node: &Node| match node {
+            Node::Decision {
+                question: Question::weight(v),
+                ..
+            } => Some(*v),
+            Node::Decision {
+                question: Question::count(v),
+                ..
+            } => Some(*v as f64),
+            _ => None,
+        };
+        for merge in &report.merges {
+            assert_eq!(
+                threshold(before[merge.node]),
+                Some(merge.from),
+                "round {}",
+                round
+            );
+            let kept = if merge.accepted { merge.to } else { merge.from };
+            assert_eq!(threshold(after[merge.node]), Some(kept), "round {}", round);
+        }
+        merged += report.merged();
+    }
+    assert!(merged > 0);
+}
+
+#[cfg(feature = "metrics")]
+#[test]
+fn applied_what_ifs_count_the_rows_the_moved_tree_routes() {
+    let mut rng = Rng::new(258);
+    let mut moves = 0;
+    for round in 0..20 {
+        let rows = 20 + rng.gen_index(150);
+        let data = random_rows(&mut rng, rows, 2 + round % 4);
+        let tree = build_tree(&data);
+        let mut nodes = Vec::new();
+        preorder(&tree, &mut nodes);
+        for (id, node) in nodes.iter().enumerate() {
+            let Node::Decision {
+                question: Question::weight(v),
+                ..
+            } = node
+            else {
+                continue;
+            };
+            let applied = tree.apply_what_if(id, v + 3.0, &data).unwrap();
+            let routed = recount(&applied, &row_refs(&data));
+            let (mut applied_nodes, mut routed_nodes) = (Vec::new(), Vec::new());
+            preorder(&applied, &mut applied_nodes);
+            preorder(&routed, &mut routed_nodes);
+            for (a, r) in applied_nodes.iter().zip(&routed_nodes) {
+                if let (Node::Leaf(a), Node::Leaf(r)) = (a, r) {
+                    assert_eq!(a.ordered(), r.ordered(), "round {}, node {}", round, id);
+                }
+            }
+            moves += 1;
+        }
+    }
+    assert!(moves > 0);
+}
+
+#[test]
+fn alternatives_swap_in_at_their_node() {
+    let mut rng = Rng::new(233);
+    let config = TreeConfig {
+        keep_runner_up: true,
+        ..TreeConfig::default()
+    };
+    let mut swapped = 0;
+    for round in 0..10 {
+        let rows = 10 + rng.gen_index(150);
+        let data = random_rows(&mut rng, rows, 2 + round % 4);
+        let tree = build_tree_with(&data, &config).unwrap();
+        let mut nodes = Vec::new();
+        preorder(&tree, &mut nodes);
+        for (id, node) in nodes.iter().enumerate() {
+            let Node::Decision {
+                runner_up: Some(runner_up),
+                ..
+            } = node
+            else {
+                assert!(tree.with_alternative(id, &data, &config).is_err());
+                continue;
+            };
+            let alternative = tree.with_alternative(id, &data, &config).unwrap();
+            let mut changed = Vec::new();
+            preorder(&alternative, &mut changed);
+            let Node::Decision { question, .. } = changed[id] else {
+                panic!("round {}: node {} is a leaf", round, id);
+            };
+            assert!(*question == runner_up.question, "round {}", round);
+            assert!(changed[..id]
+                .iter()
+                .zip(&nodes[..id])
+                .all(|(a, b)| question_of(a) == question_of(b)));
+            swapped += 1;
+        }
+    }
+    assert!(swapped > 0);
+}
+
+fn question_of(node: &Node) -> Option<&Question> {
+    match node {
+        Node::Decision { question, .. } => Some(question),
+        Node::Leaf(_) => None,
+    }
+}
+
+// (id, true branch id, false branch id) of every decision node, numbered by preorder
+fn branch_ids(node: &Node, next_id: &mut usize, out: &mut Vec<(usize, usize, usize)>) {
+    let id = *next_id;
+    *next_id += 1;
+    if let Node::Decision {
+        true_branch,
+        false_branch,
+        ..
+    } = node
+    {
+        let true_id = *next_id;
+        branch_ids(true_branch, next_id, out);
+        out.push((id, true_id, *next_id));
+        branch_ids(false_branch, next_id, out);
+    }
+}
+
+#[cfg(feature = "export")]
+#[test]
+fn dot_edges_join_preorder_ids() {
+    let mut rng = Rng::new(508);
+    for round in 0..20 {
+        let rows = 2 + rng.gen_index(150);
+        let tree = build_tree(&random_rows(&mut rng, rows, 2 + round % 4));
+        let mut expected = Vec::new();
+        branch_ids(&tree, &mut 0, &mut expected);
+        expected.sort();
+        let dot = tree.to_dot();
+        let id = |name: &str| {
+            name.trim()
+                .trim_start_matches('n')
+                .parse::<usize>()
+                .unwrap()
+        };
+        let mut edges: HashMap<usize, (usize, usize)> = HashMap::new();
+        for line in dot.lines().filter(|line| line.contains(" -> ")) {
+            let (from, rest) = line.split_once(" -> ").unwrap();
+            let (to, label) = rest.split_once(" [").unwrap();
+            let entry = edges.entry(id(from)).or_default();
+            if label.contains("true") {
+                entry.0 = id(to);
+            } else {
+                entry.1 = id(to);
+            }
+        }
+        let mut found: Vec<(usize, usize, usize)> = edges
+            .into_iter()
+            .map(|(from, (t, f))| (from, t, f))
+            .collect();
+        found.sort();
+        assert_eq!(found, expected, "round {}", round);
+        let leaves: Vec<usize> = tree.paths().map(|path| path.leaf_id).collect();
+        let ellipses: Vec<usize> = dot
+            .lines()
+            .filter(|line| line.contains("[shape=ellipse"))
+            .map(|line| id(line.split_once(" [").unwrap().0))
+            .collect();
+        assert_eq!(ellipses, leaves, "round {}", round);
+    }
+}
+
+#[test]
+fn batches_reach_the_leaves_of_single_points() {
+    let mut rng = Rng::new(247);
+    for round in 0..20 {
+        let rows = 2 + rng.gen_index(300);
+        let data = random_rows(&mut rng, rows, 2 + round % 4);
+        let tree = build_tree(&data);
+        // batches on both sides of the size where routing by node kicks in
+        for size in [0, 1, 63, 64, 65, 500] {
+            let points = random_rows(&mut rng, size, 4);
+            let leaves = classify_batch(&points, &tree);
+            assert_eq!(leaves.len(), points.len());
+            for (point, leaf) in points.iter().zip(leaves) {
+                assert!(
+                    std::ptr::eq(leaf, classify(point, &tree)),
+                    "round {}, batch of {}",
+                    round,
+                    size
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,136 @@
+//! Where the time of a build goes, collected by the generated
+//! `build_tree_timed`.
+//!
+//! Each node's split search is timed once, and with it the search of each
+//! field, so the overhead is two `Instant` reads per field per node.
+
+use std::time::{Duration, Instant};
+
+use crate::split::SplitStats;
+use crate::trace::BuildObserver;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldTiming {
+    pub field: &'static str,
+    /// Generating and scoring the field's candidate questions, over all nodes.
+    pub elapsed: Duration,
+    /// Nodes that searched the field.
+    pub nodes: usize,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DepthTiming {
+    pub depth: usize,
+    /// Nodes at this depth, decisions and leaves.
+    pub nodes: usize,
+    /// Processing the nodes at this depth, not counting their subtrees.
+    pub elapsed: Duration,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BuildTimings {
+    /// Slowest field first.
+    pub fields: Vec<FieldTiming>,
+    /// Ordered by depth, root first.
+    pub depths: Vec<DepthTiming>,
+    /// The whole build, including partitioning rows between the nodes.
+    pub total: Duration,
+}
+
+impl BuildTimings {
+    /// One line per depth, then one per field, slowest first.
+    pub fn render(&self) -> String {
+        let ms = |d: Duration| d.as_secs_f64() * 1000.0;
+        let mut out = format!("build took {:.3} ms\n", ms(self.total));
+        for d in &self.depths {
+            out += &format!(
+                "  depth {:>3}  {:>6} nodes  {:>10.3} ms\n",
+                d.depth,
+                d.nodes,
+                ms(d.elapsed)
+            );
+        }
+        let width = self.fields.iter().map(|f| f.field.len()).max().unwrap_or(0);
+        for f in &self.fields {
+            out += &format!(
+                "  {:<width$}  {:>6} nodes  {:>10.3} ms\n",
+                f.field,
+                f.nodes,
+                ms(f.elapsed),
+                width = width
+            );
+        }
+        out
+    }
+}
+
+/// The observer behind `build_tree_timed`.
+#[doc(hidden)]
+pub struct Timer {
+    started: Instant,
+    timings: BuildTimings,
+}
+
+impl Default for Timer {
+    fn default() -> Self {
+        Self {
+            started: Instant::now(),
+            timings: BuildTimings::default(),
+        }
+    }
+}
+
+impl Timer {
+    pub fn finish(mut self) -> BuildTimings {
+        self.timings.total = self.started.elapsed();
+        self.timings
+            .fields
+            .sort_by(|a, b| b.elapsed.cmp(&a.elapsed).then_with(|| a.field.cmp(b.field)));
+        self.timings
+    }
+}
+
+impl<Q> BuildObserver<Q> for Timer {
+    type Started = Instant;
+    fn start(&mut self) -> Instant {
+        Instant::now()
+    }
+    fn node(
+        &mut self,
+        started: Instant,
+        depth: usize,
+        _: usize,
+        _: Option<&Q>,
+        _: f64,
+        _: &SplitStats,
+    ) {
+        while self.timings.depths.len() <= depth {
+            let depth = self.timings.depths.len();
+            self.timings.depths.push(DepthTiming {
+                depth,
+                ..Default::default()
+            });
+        }
+        let entry = &mut self.timings.depths[depth];
+        entry.nodes += 1;
+        entry.elapsed += started.elapsed();
+    }
+    fn times_fields(&self) -> bool {
+        true
+    }
+    fn fields(&mut self, times: &[(&'static str, Duration)]) {
+        for &(field, elapsed) in times {
+            match self.timings.fields.iter_mut().find(|f| f.field == field) {
+                Some(entry) => {
+                    entry.elapsed += elapsed;
+                    entry.nodes += 1;
+                }
+                None => self.timings.fields.push(FieldTiming {
+                    field,
+                    elapsed,
+                    nodes: 1,
+                }),
+            }
+        }
+    }
+}
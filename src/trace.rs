@@ -0,0 +1,151 @@
+//! Hooks the tree builder reports each node to, and (with the `trace`
+//! feature) a structured trace built on them.
+
+use crate::split::SplitStats;
+
+/// Called by the builder once per node. `()` ignores everything, so untraced
+/// builds compile to the same code as before.
+#[doc(hidden)]
+pub trait BuildObserver<Q> {
+    type Started;
+    fn start(&mut self) -> Self::Started;
+    /// `question` is None for a leaf.
+    fn node(
+        &mut self,
+        started: Self::Started,
+        depth: usize,
+        rows: usize,
+        question: Option<&Q>,
+        gain: f64,
+        stats: &SplitStats,
+    );
+    /// Whether the builder should time the search of each field; then
+    /// `fields` gets (field, time) for every field searched at a node,
+    /// before that node's `node` call.
+    fn times_fields(&self) -> bool {
+        false
+    }
+    fn fields(&mut self, _times: &[(&'static str, std::time::Duration)]) {}
+}
+
+impl<Q> BuildObserver<Q> for () {
+    type Started = ();
+    fn start(&mut self) {}
+    fn node(&mut self, _: (), _: usize, _: usize, _: Option<&Q>, _: f64, _: &SplitStats) {}
+}
+
+#[cfg(feature = "trace")]
+pub use traced::*;
+
+#[cfg(feature = "trace")]
+mod traced {
+    use crate::split::SplitStats;
+    use std::time::Duration;
+
+    /// One node of a build. Events arrive in preorder, so `node_id` is also
+    /// the event's index.
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct BuildTraceEvent {
+        /// Preorder index of the node, root 0, true branch before false branch.
+        pub node_id: usize,
+        pub depth: usize,
+        pub rows: usize,
+        /// Field and question text of the chosen split; None for a leaf.
+        pub field: Option<String>,
+        pub question: Option<String>,
+        pub gain: f32,
+        pub stats: SplitStats,
+        /// Time spent searching this node's split.
+        pub elapsed: Duration,
+    }
+
+    /// Receives trace events during a build.
+    pub trait TraceSink {
+        fn event(&mut self, event: BuildTraceEvent);
+    }
+
+    impl TraceSink for Vec<BuildTraceEvent> {
+        fn event(&mut self, event: BuildTraceEvent) {
+            self.push(event);
+        }
+    }
+
+    #[derive(Debug, Clone, Default, PartialEq)]
+    pub struct DepthTrace {
+        pub depth: usize,
+        pub decisions: usize,
+        pub leaves: usize,
+        pub rows: usize,
+        pub stats: SplitStats,
+        pub elapsed: Duration,
+    }
+
+    #[derive(Debug, Clone, Default, PartialEq)]
+    pub struct FieldTrace {
+        pub field: String,
+        pub splits: usize,
+        pub total_gain: f32,
+        pub rows: usize,
+    }
+
+    /// Per-depth totals (ordered by depth) and per-split-field totals
+    /// (ordered by name).
+    #[derive(Debug, Clone, Default, PartialEq)]
+    pub struct TraceSummary {
+        pub depths: Vec<DepthTrace>,
+        pub fields: Vec<FieldTrace>,
+    }
+
+    fn add_stats(total: &mut SplitStats, stats: &SplitStats) {
+        total.candidates += stats.candidates;
+        total.scored += stats.scored;
+        total.cache_hits += stats.cache_hits;
+        total.skipped_empty += stats.skipped_empty;
+        total.skipped_min_samples += stats.skipped_min_samples;
+        total.skipped_min_fraction += stats.skipped_min_fraction;
+        total.skipped_bound += stats.skipped_bound;
+        total.constant_fields += stats.constant_fields;
+        total.sampled_fields += stats.sampled_fields;
+        total.forced += stats.forced;
+        total.forced_fell_through += stats.forced_fell_through;
+    }
+
+    pub fn summarize(events: &[BuildTraceEvent]) -> TraceSummary {
+        let mut summary = TraceSummary::default();
+        for event in events {
+            while summary.depths.len() <= event.depth {
+                let depth = summary.depths.len();
+                summary.depths.push(DepthTrace {
+                    depth,
+                    ..Default::default()
+                });
+            }
+            let depth = &mut summary.depths[event.depth];
+            depth.rows += event.rows;
+            depth.elapsed += event.elapsed;
+            add_stats(&mut depth.stats, &event.stats);
+            match &event.field {
+                None => depth.leaves += 1,
+                Some(field) => {
+                    depth.decisions += 1;
+                    let index = match summary.fields.iter().position(|f| f.field == *field) {
+                        Some(index) => index,
+                        None => {
+                            summary.fields.push(FieldTrace {
+                                field: field.clone(),
+                                ..Default::default()
+                            });
+                            summary.fields.len() - 1
+                        }
+                    };
+                    let entry = &mut summary.fields[index];
+                    entry.splits += 1;
+                    entry.total_gain += event.gain;
+                    entry.rows += event.rows;
+                }
+            }
+        }
+        summary.fields.sort_by(|a, b| a.field.cmp(&b.field));
+        summary
+    }
+}
@@ -0,0 +1,146 @@
+//! CachedClassifier: keyed on the fields without the class, evicting the
+//! least recently used point, and agreeing with the model it wraps when
+//! shared between threads.
+
+#![cfg(feature = "serving")]
+// the generated functions take &Vec<DataPoint>
+#![allow(clippy::ptr_arg)]
+
+use decision_leaf::cache::{CacheKey, CachedClassifier};
+use decision_leaf::classification_data_layout;
+use decision_leaf::serving::Model;
+use decision_leaf::ClassCounts;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::thread;
+
+classification_data_layout!(
+    enum_fields = {red: bool},
+    number_fields = {size: f64},
+    bool
+);
+
+// counts the predictions that reach the tree, i.e. the cache misses
+struct Counting {
+    tree: Node,
+    calls: AtomicU64,
+}
+
+impl Model<DataPoint> for Counting {
+    type Output = ClassCounts<bool>;
+    fn predict(&self, point: &DataPoint) -> Self::Output {
+        self.calls.fetch_add(1, Ordering::Relaxed);
+        self.tree.predict(point)
+    }
+}
+
+fn tree() -> Node {
+    let data: Vec<DataPoint> = (0..40)
+        .map(|i| DataPoint::new(i % 3 == 0, i as f64, i % 3 == 0 || i > 25))
+        .collect();
+    build_tree(&data)
+}
+
+fn cached(capacity: usize) -> CachedClassifier<DataPoint, Counting> {
+    let model = Counting {
+        tree: tree(),
+        calls: AtomicU64::new(0),
+    };
+    CachedClassifier::new(model, capacity)
+}
+
+#[test]
+fn the_class_is_not_part_of_the_key() {
+    let a = DataPoint::new(true, 4.0, true);
+    let b = DataPoint::new(true, 4.0, false);
+    assert_ne!(a, b);
+    assert!(a.cache_key() == b.cache_key());
+    assert!(a.cache_key() != DataPoint::new(true, 5.0, true).cache_key());
+    assert!(a.cache_key() != DataPoint::new(false, 4.0, true).cache_key());
+
+    let cache = cached(8);
+    let first = cache.predict(&a);
+    let second = cache.predict(&b);
+    assert_eq!(first, second);
+    assert_eq!((cache.hits(), cache.misses(), cache.len()), (1, 1, 1));
+    assert_eq!(cache.model().calls.load(Ordering::Relaxed), 1);
+}
+
+#[test]
+fn hits_return_what_the_model_returns() {
+    let cache = cached(64);
+    let points: Vec<DataPoint> = (0..30)
+        .map(|i| DataPoint::new(i % 2 == 0, (i % 10) as f64 * 4.5, false))
+        .collect();
+    for _ in 0..3 {
+        for point in &points {
+            assert_eq!(cache.predict(point), cache.model().tree.predict(point));
+        }
+    }
+    // 10 distinct keys: i % 10 fixes the size and, through the parity of i, red
+    assert_eq!(cache.len(), 10);
+    assert_eq!(cache.misses(), 10);
+    assert_eq!(cache.hits(), 80);
+    assert_eq!(cache.model().calls.load(Ordering::Relaxed), 10);
+}
+
+#[test]
+fn nan_sizes_share_an_entry() {
+    let cache = cached(4);
+    cache.predict(&DataPoint::new(false, f64::NAN, false));
+    cache.predict(&DataPoint::new(false, f64::NAN, true));
+    assert_eq!((cache.hits(), cache.misses()), (1, 1));
+}
+
+#[test]
+fn evicts_the_least_recently_used() {
+    let cache = cached(2);
+    let [a, b, c] = [1.0, 2.0, 3.0].map(|size| DataPoint::new(true, size, true));
+    cache.predict(&a);
+    cache.predict(&b);
+    cache.predict(&a); // b is now the least recently used
+    cache.predict(&c);
+    assert_eq!(cache.len(), 2);
+    let misses = cache.misses();
+    cache.predict(&a);
+    cache.predict(&c);
+    assert_eq!(cache.misses(), misses);
+    cache.predict(&b);
+    assert_eq!(cache.misses(), misses + 1);
+}
+
+#[test]
+fn shared_between_threads() {
+    let cache = Arc::new(cached(16));
+    let points: Vec<DataPoint> = (0..48)
+        .map(|i| DataPoint::new(i % 4 == 0, (i % 24) as f64 * 1.7, i % 5 == 0))
+        .collect();
+    let expected: Vec<_> = points
+        .iter()
+        .map(|p| cache.model().tree.predict(p))
+        .collect();
+    let points = Arc::new(points);
+    let handles: Vec<_> = (0..8)
+        .map(|t| {
+            let cache = Arc::clone(&cache);
+            let points = Arc::clone(&points);
+            thread::spawn(move || {
+                (0..500)
+                    .map(|i| {
+                        let index = (i * 7 + t * 13) % points.len();
+                        (index, cache.predict(&points[index]))
+                    })
+                    .collect::<Vec<_>>()
+            })
+        })
+        .collect();
+    for handle in handles {
+        for (index, output) in handle.join().unwrap() {
+            assert_eq!(output, expected[index]);
+        }
+    }
+    assert_eq!(cache.hits() + cache.misses(), 8 * 500);
+    assert_eq!(cache.misses(), cache.model().calls.load(Ordering::Relaxed));
+    assert!(cache.len() <= 16);
+    assert!(cache.hits() > 0);
+}
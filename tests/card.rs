@@ -0,0 +1,134 @@
+//! ModelCard's Markdown against a golden copy, with labels that would break
+//! the tables if written as they are, and its JSON with numbers JSON can't
+//! hold.
+
+#![cfg(all(feature = "export", feature = "metrics"))]
+
+use decision_leaf::card::{ClassMetrics, Limitation, ModelCard};
+
+fn card() -> ModelCard {
+    ModelCard {
+        created: 1_700_000_000,
+        model_fingerprint: 0x0123_4567_89ab_cdef,
+        schema_fingerprint: 0xfedc_ba98_7654_3210,
+        schema: vec![
+            ("color".to_owned(), "enum Color".to_owned()),
+            ("size".to_owned(), "number u32".to_owned()),
+            ("class".to_owned(), "class Either<A|B>".to_owned()),
+        ],
+        config: vec![
+            ("max_depth".to_owned(), "Some(3)".to_owned()),
+            ("fields".to_owned(), "None".to_owned()),
+        ],
+        training_rows: 120,
+        class_distribution: vec![("A|B".to_owned(), 70), ("C\\".to_owned(), 50)],
+        node_count: 7,
+        depth: 2,
+        heap_bytes: 448,
+        test_rows: 30,
+        accuracy: 0.9,
+        class_metrics: vec![
+            ClassMetrics {
+                class: "A|B".to_owned(),
+                support: 20,
+                precision: 0.95,
+                recall: 0.9,
+            },
+            ClassMetrics {
+                class: "C\\".to_owned(),
+                support: 10,
+                precision: 0.8181818,
+                recall: 0.9,
+            },
+        ],
+        top_features: vec![("size".to_owned(), 0.75), ("color".to_owned(), 0.25)],
+        limitations: vec![Limitation {
+            source: "drift",
+            field: "color".to_owned(),
+            detail: "PSI 0.310, unseen values Teal|Blue\nand more".to_owned(),
+        }],
+        notes: Some("Trained on the *spring* sample.".to_owned()),
+    }
+}
+
+const GOLDEN: &str = "# Model card
+
+- Model: `0123456789abcdef`
+- Schema: `fedcba9876543210`
+- Created: 2023-11-14
+
+Trained on the *spring* sample.
+
+## Schema
+
+| Field | Type |
+|---|---|
+| color | enum Color |
+| size | number u32 |
+| class | class Either<A\\|B> |
+
+## Training
+
+- Rows: 120
+- Nodes: 7
+- Depth: 2
+- Size estimate: 448 bytes
+- max_depth: Some(3)
+- fields: None
+
+| Class | Rows |
+|---|---|
+| A\\|B | 70 |
+| C\\\\ | 50 |
+
+## Evaluation
+
+- Test rows: 30
+- Accuracy: 0.900
+
+| Class | Support | Precision | Recall |
+|---|---|---|---|
+| A\\|B | 20 | 0.950 | 0.900 |
+| C\\\\ | 10 | 0.818 | 0.900 |
+
+## Top features
+
+1. size (0.750)
+2. color (0.250)
+
+## Limitations
+
+- color (drift): PSI 0.310, unseen values Teal\\|Blue<br>and more
+";
+
+#[test]
+fn markdown_matches_the_golden_copy() {
+    assert_eq!(card().to_markdown(), GOLDEN);
+}
+
+#[test]
+fn json_writes_non_finite_numbers_as_null() {
+    let mut card = card();
+    card.accuracy = f32::NAN;
+    card.class_metrics[0].precision = f32::INFINITY;
+    card.class_metrics[1].recall = f32::NEG_INFINITY;
+    card.top_features[0].1 = f64::NAN;
+    let json = card.to_json();
+    assert!(json.contains("\"accuracy\":null"), "{}", json);
+    assert!(
+        json.contains("\"precision\":null,\"recall\":0.9}"),
+        "{}",
+        json
+    );
+    assert!(
+        json.contains("\"precision\":0.8181818,\"recall\":null}"),
+        "{}",
+        json
+    );
+    assert!(
+        json.contains("\"top_features\":{\"size\":null,\"color\":0.25}"),
+        "{}",
+        json
+    );
+    assert!(!json.contains("NaN") && !json.contains("inf"), "{}", json);
+}
@@ -0,0 +1,81 @@
+//! Layouts that must not compile, one per kind of bad field type, in
+//! tests/ui. Each `x.rs` there is built against this crate with rustc and
+//! the first error it gives is compared with `x.stderr`; the ones after it
+//! come from inside the expansion and aren't pinned down. Run with
+//! `UI=overwrite` to write the `.stderr` files from the current output.
+
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+#[test]
+fn bad_field_types_name_the_field() {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let out = Path::new(env!("CARGO_TARGET_TMPDIR")).join("ui");
+    fs::create_dir_all(&out).unwrap();
+    let rustc = std::env::var("RUSTC").unwrap_or_else(|_| "rustc".to_owned());
+
+    let lib = Command::new(&rustc)
+        .current_dir(root)
+        .args([
+            "--edition=2021",
+            "--crate-type=rlib",
+            "--crate-name=decision_leaf",
+        ])
+        .arg("--out-dir")
+        .arg(&out)
+        .arg("src/lib.rs")
+        .output()
+        .unwrap();
+    assert!(
+        lib.status.success(),
+        "{}",
+        String::from_utf8_lossy(&lib.stderr)
+    );
+
+    let overwrite = std::env::var("UI").is_ok_and(|v| v == "overwrite");
+    let mut cases: Vec<_> = fs::read_dir(root.join("tests/ui"))
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|e| e == "rs"))
+        .collect();
+    cases.sort();
+    assert!(!cases.is_empty());
+    let mut failed = Vec::new();
+    for case in &cases {
+        let relative = case.strip_prefix(root).unwrap();
+        let result = Command::new(&rustc)
+            .current_dir(root)
+            .args(["--edition=2021", "--crate-type=lib", "--emit=metadata"])
+            .args(["--error-format=short", "--color=never"])
+            .arg("--out-dir")
+            .arg(out.join("cases"))
+            .arg("--extern")
+            .arg(format!(
+                "decision_leaf={}",
+                out.join("libdecision_leaf.rlib").display()
+            ))
+            .arg(relative)
+            .output()
+            .unwrap();
+        let stderr = String::from_utf8_lossy(&result.stderr);
+        let first = stderr.lines().next().unwrap_or("").to_owned() + "\n";
+        let expected_path = case.with_extension("stderr");
+        if result.status.success() {
+            failed.push(format!("{} compiled", relative.display()));
+        } else if overwrite {
+            fs::write(&expected_path, &first).unwrap();
+        } else {
+            let expected = fs::read_to_string(&expected_path).unwrap_or_default();
+            if first != expected {
+                failed.push(format!(
+                    "{}:\nexpected {}got      {}",
+                    relative.display(),
+                    expected,
+                    first
+                ));
+            }
+        }
+    }
+    assert!(failed.is_empty(), "{}", failed.join("\n"));
+}
@@ -0,0 +1,140 @@
+//! Conformal prediction sets on synthetic rows with a known noise level:
+//! coverage of `1 - alpha` on fresh rows, ranks and p-values on a hand-sized
+//! calibration set, and the edge cases (ties, classes a leaf never saw, too
+//! few calibration rows).
+
+#![cfg(feature = "metrics")]
+// the generated functions take &Vec<DataPoint>
+#![allow(clippy::ptr_arg)]
+
+use decision_leaf::conformal::Calibration;
+use decision_leaf::rng::Rng;
+use decision_leaf::{classification_data_layout, ClassCounts};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Zone {
+    Low,
+    Mid,
+    High,
+}
+
+classification_data_layout!(
+    enum_fields = {},
+    number_fields = {x: f64, y: f64},
+    Zone
+);
+
+// the zone of x + y, relabeled at random for a fifth of the rows
+fn rows(n: usize, rng: &mut Rng) -> Vec<DataPoint> {
+    (0..n)
+        .map(|_| {
+            let (x, y) = (rng.next_f64(), rng.next_f64());
+            let zone = match x + y {
+                s if s < 0.7 => Zone::Low,
+                s if s < 1.3 => Zone::Mid,
+                _ => Zone::High,
+            };
+            let class = if rng.next_f64() < 0.2 {
+                [Zone::Low, Zone::Mid, Zone::High][rng.gen_index(3)]
+            } else {
+                zone
+            };
+            DataPoint::new(x, y, class)
+        })
+        .collect()
+}
+
+// (tree, calibration rows, test rows)
+fn setup() -> (Node, Vec<DataPoint>, Vec<DataPoint>) {
+    let mut rng = Rng::new(5);
+    let (train, calib, test) = (
+        rows(2000, &mut rng),
+        rows(2000, &mut rng),
+        rows(5000, &mut rng),
+    );
+    let config = TreeConfig {
+        max_depth: Some(4),
+        min_samples_leaf: 20,
+        ..TreeConfig::default()
+    };
+    (build_tree_with(&train, &config).unwrap(), calib, test)
+}
+
+// a leaf that only saw Low
+fn pure() -> Node {
+    let mut counts = ClassCounts::new();
+    counts.add(Zone::Low, 10);
+    Node::Leaf(counts)
+}
+
+#[test]
+fn sets_cover_one_minus_alpha() {
+    let (tree, calib, test) = setup();
+    for alpha in [0.05, 0.1, 0.2] {
+        let conformal = ConformalClassifier::calibrate(&tree, &calib, alpha).unwrap();
+        let exact = conformal.coverage(&test);
+        let randomized = conformal.coverage_randomized(&test, 17);
+        // ties with the threshold are kept, which can only add coverage; the
+        // randomized sets hit 1 - alpha itself
+        assert!(exact.coverage() >= 1.0 - alpha - 0.015, "alpha {}", alpha);
+        assert!(
+            (randomized.coverage() - (1.0 - alpha)).abs() < 0.015,
+            "alpha {}",
+            alpha
+        );
+        assert!(randomized.mean_set_size() <= exact.mean_set_size());
+        assert_eq!(exact.empty_sets, 0);
+        for point in test.iter().take(50) {
+            let set = conformal.predict_set(point);
+            assert!(!set.is_empty());
+            assert!(set
+                .windows(2)
+                .all(|w| format!("{:?}", w[0]) < format!("{:?}", w[1])));
+        }
+    }
+    // the leaves give few distinct probabilities, so scores tie
+    let conformal = ConformalClassifier::calibrate(&tree, &calib, 0.1).unwrap();
+    let mut distinct = conformal.calibration.scores().to_vec();
+    distinct.dedup();
+    assert!(distinct.len() < calib.len() / 10);
+}
+
+#[test]
+fn thresholds_and_p_values_follow_the_ranks() {
+    // 9 scores and alpha 0.2 take the ceil(10 * 0.8) = 8th smallest
+    let calibration = Calibration::new(vec![0.5, 0.1, 0.1, 0.2, 0.3, 0.3, 0.3, 0.4, 1.0], 0.2);
+    assert_eq!(calibration.threshold(), 0.5);
+    assert!(calibration.includes(0.5) && !calibration.includes(0.6));
+    // 0.5: 1 above, 1 tied, so p = (1 + u * 2) / 10 > 0.2 once u > 0.5
+    assert!(!calibration.includes_randomized(0.5, 0.4));
+    assert!(calibration.includes_randomized(0.5, 0.6));
+    assert!(calibration.includes_randomized(0.3, 0.0));
+    assert!(!calibration.includes_randomized(2.0, 0.99));
+}
+
+#[test]
+fn few_rows_and_unseen_classes_keep_every_class() {
+    let (tree, calib, _) = setup();
+    // too few calibration rows for alpha: the threshold is infinite
+    let few = ConformalClassifier::calibrate(&tree, &calib[..5].to_vec(), 0.1).unwrap();
+    assert_eq!(few.calibration.threshold(), f64::INFINITY);
+    // and sets hold every class, even one the leaf gives probability 0
+    let point = DataPoint::new(0.9, 0.9, Zone::High);
+    let mid = DataPoint::new(0.5, 0.5, Zone::Mid);
+    let unseen = ConformalClassifier::calibrate(&pure(), &vec![point.clone(); 3], 0.1).unwrap();
+    assert_eq!(unseen.predict_set(&mid), [Zone::High, Zone::Low]);
+    // with enough rows the true class, scoring 1 everywhere, still ties in
+    let high = ConformalClassifier::calibrate(&pure(), &vec![point.clone(); 30], 0.1).unwrap();
+    assert_eq!(high.predict_set(&mid), [Zone::High, Zone::Low]);
+    assert_eq!(high.coverage(&vec![point; 4]).coverage(), 1.0);
+}
+
+#[test]
+fn calibration_needs_rows_and_an_open_alpha() {
+    let (tree, calib, _) = setup();
+    assert!(ConformalClassifier::calibrate(&tree, &calib, 0.0).is_err());
+    assert!(ConformalClassifier::calibrate(&tree, &calib, 1.0).is_err());
+    assert!(ConformalClassifier::calibrate(&tree, &Vec::new(), 0.1).is_err());
+    let conformal = ConformalClassifier::calibrate(&tree, &calib, 0.1).unwrap();
+    assert_eq!(conformal.coverage(&Vec::new()).coverage(), 0.0);
+}
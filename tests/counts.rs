@@ -0,0 +1,76 @@
+//! ClassCounts with plain counts and with float weights: totals,
+//! probabilities and impurities against hand-computed values, and argmax
+//! and ordered breaking ties by label whatever order classes were added in.
+
+use decision_leaf::ClassCounts;
+
+fn close(actual: f64, expected: f64) {
+    assert!(
+        (actual - expected).abs() < 1e-12,
+        "{} isn't {}",
+        actual,
+        expected
+    );
+}
+
+#[test]
+fn counts_and_weights_accumulate_per_class() {
+    let mut counts: ClassCounts<&str> = ClassCounts::new();
+    counts.add("a", 3);
+    counts.add("b", 1);
+    counts.add("a", 2);
+    assert_eq!(counts.get(&"a"), 5);
+    assert_eq!(counts.get(&"c"), 0);
+    assert_eq!(counts.total(), 6);
+    assert_eq!(counts.len(), 2);
+    assert!(counts.contains(&"b") && !counts.contains(&"c"));
+    close(counts.proba(&"b"), 1. / 6.);
+
+    let weighted: ClassCounts<&str, f64> =
+        [("a", 0.5), ("b", 1.5), ("a", 0.25)].into_iter().collect();
+    close(weighted.get(&"a"), 0.75);
+    close(weighted.total_f64(), 2.25);
+    close(weighted.proba(&"b"), 1.5 / 2.25);
+
+    let empty: ClassCounts<&str, f64> = ClassCounts::new();
+    assert!(empty.is_empty());
+    assert_eq!(empty.proba(&"a"), 0.0);
+    assert_eq!(empty.gini(), 0.0);
+    assert_eq!(empty.entropy(), 0.0);
+    assert_eq!(empty.argmax(), None);
+}
+
+#[test]
+fn impurities_match_hand_computed_values() {
+    let counts: ClassCounts<u8> = [(0, 1), (1, 1), (2, 2)].into_iter().collect();
+    close(counts.gini(), 1. - (0.25 * 0.25 * 2. + 0.5 * 0.5));
+    close(counts.entropy(), 1.5);
+    // the same proportions as weights give the same impurities
+    let weighted: ClassCounts<u8, f32> = [(0, 0.5), (1, 0.5), (2, 1.0)].into_iter().collect();
+    close(weighted.gini(), counts.gini());
+    close(weighted.entropy(), counts.entropy());
+    let pure: ClassCounts<u8> = [(7, 4)].into_iter().collect();
+    assert_eq!(pure.gini(), 0.0);
+    assert_eq!(pure.entropy(), 0.0);
+}
+
+#[test]
+fn ties_go_to_the_label_that_sorts_first() {
+    let forward: ClassCounts<&str> = [("b", 2), ("a", 2), ("c", 5)].into_iter().collect();
+    let backward: ClassCounts<&str> = [("c", 5), ("a", 2), ("b", 2)].into_iter().collect();
+    for counts in [&forward, &backward] {
+        assert_eq!(counts.argmax(), Some(&"c"));
+        assert_eq!(counts.ordered(), vec![(&"c", 5), (&"a", 2), (&"b", 2)]);
+    }
+    let tied: ClassCounts<&str> = [("y", 1), ("x", 1)].into_iter().collect();
+    assert_eq!(tied.argmax(), Some(&"x"));
+}
+
+#[test]
+fn merges_add_every_class() {
+    let mut left: ClassCounts<&str> = [("a", 1), ("b", 2)].into_iter().collect();
+    let right: ClassCounts<&str> = [("b", 3), ("c", 4)].into_iter().collect();
+    left.merge(&right);
+    assert_eq!(left.ordered(), vec![(&"b", 5), (&"c", 4), (&"a", 1)]);
+    assert_eq!(left.total(), 10);
+}
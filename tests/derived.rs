@@ -0,0 +1,92 @@
+//! A derived field computed from two others: filled in by DataPoint::new and
+//! compute_derived, split on like a declared number field, and unknown on a
+//! PartialPoint until it is completed. With `csv`, it is neither read nor
+//! written as a column.
+
+// the generated functions take &Vec<DataPoint>
+#![allow(clippy::ptr_arg)]
+
+use decision_leaf::classification_data_layout;
+
+classification_data_layout!(
+    enum_fields = {married: bool},
+    number_fields = {age: u32, hours: u32},
+    derived_fields = {hours_per_decade: u32 = |p| p.hours * 10 / p.age.max(1)},
+    u8
+);
+
+// the class is set by the ratio alone, which neither input splits cleanly
+fn data() -> Vec<DataPoint> {
+    let mut data = Vec::new();
+    for age in (20..70).step_by(5) {
+        for hours in (10..70).step_by(6) {
+            let busy = hours * 10 / age >= 12;
+            data.push(DataPoint::new(age % 2 == 0, age, hours, busy as u8));
+        }
+    }
+    data
+}
+
+#[test]
+fn new_and_compute_derived_fill_the_field_in() {
+    let mut point = DataPoint::new(true, 40, 60, 1);
+    assert_eq!(point.hours_per_decade, 15);
+    point.hours = 20;
+    assert_eq!(point.hours_per_decade, 15);
+    point.compute_derived();
+    assert_eq!(point.hours_per_decade, 5);
+    // the closure guards the division
+    assert_eq!(DataPoint::new(true, 0, 7, 0).hours_per_decade, 70);
+}
+
+#[test]
+fn trees_split_on_derived_fields() {
+    let data = data();
+    let tree = build_tree(&data);
+    match &tree {
+        Node::Decision { question, .. } => {
+            assert_eq!(question.field(), Field::hours_per_decade);
+            assert_eq!(question, &Question::hours_per_decade(12));
+        }
+        Node::Leaf(_) => panic!("the tree didn't split"),
+    }
+    assert!(data.iter().all(|p| predict(p, &tree) == p.class));
+}
+
+#[test]
+fn partial_points_derive_once_complete() {
+    let tree = build_tree(&data());
+    let complete = PartialPoint {
+        married: Some(false),
+        age: Some(30),
+        hours: Some(45),
+        class: 1,
+    };
+    assert!(Question::hours_per_decade(12)
+        .evaluate_partial(&complete)
+        .is_none());
+    let point = complete.into_point().unwrap();
+    assert_eq!(point.hours_per_decade, 15);
+    assert_eq!(predict(&point, &tree), 1);
+    let missing = PartialPoint {
+        married: Some(false),
+        age: None,
+        hours: Some(45),
+        class: 1,
+    };
+    assert_eq!(missing.into_point(), None);
+}
+
+#[cfg(feature = "csv")]
+#[test]
+fn csvs_leave_derived_fields_out() {
+    let data = data();
+    let mut csv = Vec::new();
+    write_csv(&data, &mut csv).unwrap();
+    let text = String::from_utf8(csv).unwrap();
+    assert_eq!(text.lines().next(), Some("married,age,hours,class"));
+    assert_eq!(
+        load_csv_with(text.as_bytes(), &mut LoaderConfig::new()).unwrap(),
+        data
+    );
+}
@@ -0,0 +1,122 @@
+//! Exports don't depend on hash order: a leaf's classes go by descending
+//! count, then label, and trees built twice, or with their counts added in
+//! another order, print, export and save to the same text.
+
+// the generated functions take &Vec<DataPoint>
+#![allow(clippy::ptr_arg)]
+
+use decision_leaf::rng::Rng;
+use decision_leaf::{classification_data_layout, ClassCounts};
+
+classification_data_layout!(
+    enum_fields = {shade: u8},
+    number_fields = {weight: f64},
+    u8
+);
+
+// six classes and a third of the rows relabeled at random, so leaves see
+// several classes and hash order has room to show
+fn data() -> Vec<DataPoint> {
+    let mut rng = Rng::new(256);
+    (0..400)
+        .map(|_| {
+            let shade = rng.gen_index(4) as u8;
+            let weight = (rng.next_f64() * 40.).round();
+            let class = if rng.gen_index(3) == 0 {
+                rng.gen_index(6)
+            } else {
+                (shade as usize * 2 + (weight > 20.) as usize) % 6
+            };
+            DataPoint::new(shade, weight, class as u8)
+        })
+        .collect()
+}
+
+fn config() -> TreeConfig {
+    TreeConfig {
+        max_depth: Some(3),
+        ..TreeConfig::default()
+    }
+}
+
+// the tree's text as every exporter the features allow writes it
+fn exports(tree: &Node) -> Vec<String> {
+    let mut printed = Vec::new();
+    tree.write_tree(&mut printed, &Default::default()).unwrap();
+    #[allow(unused_mut)]
+    let mut texts = vec![String::from_utf8(printed).unwrap()];
+    #[cfg(feature = "export")]
+    texts.push(tree.to_dot());
+    #[cfg(feature = "persist")]
+    {
+        let mut saved = Vec::new();
+        tree.save(&mut saved).unwrap();
+        texts.push(String::from_utf8(saved).unwrap());
+    }
+    texts
+}
+
+// a copy with each leaf's counts added back in reverse order
+fn reinserted(node: &Node) -> Node {
+    match node {
+        Node::Leaf(counts) => {
+            let mut copy = ClassCounts::new();
+            for (class, n) in counts.ordered().into_iter().rev() {
+                copy.add(*class, n);
+            }
+            Node::Leaf(copy)
+        }
+        Node::Decision {
+            question,
+            true_branch,
+            false_branch,
+            runner_up,
+        } => Node::Decision {
+            question: question.clone(),
+            true_branch: Box::new(reinserted(true_branch)),
+            false_branch: Box::new(reinserted(false_branch)),
+            runner_up: runner_up.clone(),
+        },
+    }
+}
+
+#[test]
+fn distributions_go_by_count_then_label() {
+    let mut counts = ClassCounts::<u8>::new();
+    for (class, n) in [(3, 5), (1, 5), (2, 9), (0, 1)] {
+        counts.add(class, n);
+    }
+    assert_eq!(counts.ordered(), [(&2, 9), (&1, 5), (&3, 5), (&0, 1)]);
+}
+
+#[test]
+fn rebuilt_trees_export_the_same_text() {
+    let data = data();
+    let tree = build_tree_with(&data, &config()).unwrap();
+    let again = build_tree_with(&data, &config()).unwrap();
+    assert!(tree.paths().any(|path| path.distribution.len() >= 3));
+    assert_eq!(exports(&again), exports(&tree));
+    assert_eq!(exports(&reinserted(&tree)), exports(&tree));
+}
+
+#[cfg(feature = "persist")]
+#[test]
+fn saved_leaves_list_classes_by_count_then_label() {
+    use decision_leaf::persist::LoadOptions;
+
+    let mut counts = ClassCounts::new();
+    for (class, n) in [(3u8, 5), (1, 5), (2, 9)] {
+        counts.add(class, n);
+    }
+    let mut saved = Vec::new();
+    Node::Leaf(counts).save(&mut saved).unwrap();
+    let text = String::from_utf8(saved).unwrap();
+    assert_eq!(text.lines().last(), Some("leaf\t2\t9\t1\t5\t3\t5"));
+
+    // a loaded tree saves back to the same text
+    let tree = build_tree_with(&data(), &config()).unwrap();
+    let mut saved = Vec::new();
+    tree.save(&mut saved).unwrap();
+    let loaded = Node::load(&saved[..], &LoadOptions::new()).unwrap();
+    assert_eq!(exports(&loaded), exports(&tree));
+}
@@ -0,0 +1,618 @@
+//! Checks on the iris-style rows of examples/data/flowers.csv, f32 lengths
+//! and three even classes: float thresholds and NaN measurements, sampled
+//! candidates on an all-unique column, timed builds, memory estimates, split
+//! criteria, random feature draws, forests, evaluation reports, and
+//! experiments under each protocol.
+
+// the generated functions take &Vec<DataPoint>
+#![allow(clippy::ptr_arg)]
+
+use decision_leaf::classification_data_layout;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Species {
+    Setosa,
+    Versicolor,
+    Virginica,
+}
+
+impl FromStr for Species {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, String> {
+        match s {
+            "Setosa" => Ok(Species::Setosa),
+            "Versicolor" => Ok(Species::Versicolor),
+            "Virginica" => Ok(Species::Virginica),
+            _ => Err(format!("unknown species {}", s)),
+        }
+    }
+}
+
+classification_data_layout!(
+    enum_fields = {},
+    number_fields = {sepal_length: f32, sepal_width: f32, petal_length: f32, petal_width: f32},
+    Species
+);
+
+const FLOWERS: &str = include_str!("../examples/data/flowers.csv");
+
+fn rows() -> Vec<DataPoint> {
+    FLOWERS
+        .lines()
+        .skip(1)
+        .map(|line| {
+            let cells: Vec<&str> = line.split(',').collect();
+            DataPoint::new(
+                cells[0].parse().unwrap(),
+                cells[1].parse().unwrap(),
+                cells[2].parse().unwrap(),
+                cells[3].parse().unwrap(),
+                cells[4].parse().unwrap(),
+            )
+        })
+        .collect()
+}
+
+fn config() -> TreeConfig {
+    TreeConfig {
+        max_depth: Some(4),
+        min_samples_leaf: 2,
+        ..TreeConfig::default()
+    }
+}
+
+#[test]
+fn the_fixture_loads() {
+    let rows = rows();
+    assert_eq!(rows.len(), 150);
+    for species in [Species::Setosa, Species::Versicolor, Species::Virginica] {
+        assert_eq!(rows.iter().filter(|p| p.class == species).count(), 50);
+    }
+    assert!(build_tree_with(&rows, &config()).unwrap().depth() <= 4);
+}
+
+// (field, threshold) of every decision node
+fn thresholds(node: &Node) -> Vec<(Field, f32)> {
+    match node {
+        Node::Leaf(_) => Vec::new(),
+        Node::Decision {
+            question,
+            true_branch,
+            false_branch,
+            ..
+        } => {
+            let mut found = match question {
+                Question::sepal_length(t)
+                | Question::sepal_width(t)
+                | Question::petal_length(t)
+                | Question::petal_width(t) => vec![(question.field(), *t)],
+            };
+            found.extend(thresholds(true_branch));
+            found.extend(thresholds(false_branch));
+            found
+        }
+    }
+}
+
+#[test]
+fn float_thresholds_fall_on_measured_values() {
+    let data = rows();
+    let tree = build_tree(&data);
+    let found = thresholds(&tree);
+    assert!(found.iter().any(|(_, t)| t.fract() != 0.0));
+    for (field, t) in found {
+        assert!(
+            data.iter().any(|p| match field {
+                Field::sepal_length => p.sepal_length == t,
+                Field::sepal_width => p.sepal_width == t,
+                Field::petal_length => p.petal_length == t,
+                Field::petal_width => p.petal_width == t,
+            }),
+            "{:?} {}",
+            field,
+            t
+        );
+    }
+}
+
+#[test]
+fn nan_measurements_train_and_classify() {
+    let mut gaps = rows();
+    for point in gaps.iter_mut().step_by(7) {
+        point.petal_width = f32::NAN;
+    }
+    let tree = build_tree(&gaps);
+    let widths: Vec<f32> = thresholds(&tree)
+        .into_iter()
+        .filter(|(field, _)| *field == Field::petal_width)
+        .map(|(_, t)| t)
+        .collect();
+    // NaN is never a threshold, and answers every question with false
+    assert!(!widths.is_empty());
+    assert!(widths.iter().all(|w| !w.is_nan()));
+    assert!(widths
+        .iter()
+        .all(|&w| !Question::petal_width(w).evaluate(&gaps[0])));
+    assert!(gaps.iter().all(|p| predict(p, &tree) == p.class));
+    // a NaN equals itself, so points stay Eq + Hash
+    assert_eq!(gaps[0], gaps[0].clone());
+}
+
+// 20k rows with a distinct sepal length each, the class set by it
+fn unique_lengths() -> Vec<DataPoint> {
+    (0..20_000u32)
+        .map(|i| {
+            let class = if i < 12_000 {
+                Species::Setosa
+            } else {
+                Species::Virginica
+            };
+            DataPoint::new(i as f32 * 0.7, 3.0, 4.0, 1.0, class)
+        })
+        .collect()
+}
+
+#[test]
+fn all_unique_columns_are_sampled() {
+    let unique = unique_lengths();
+    let capped = TreeConfig {
+        max_candidates: 200,
+        ..TreeConfig::default()
+    };
+    let started = std::time::Instant::now();
+    let tree = build_tree_with(&unique, &capped).unwrap();
+    let elapsed = started.elapsed();
+    assert!(
+        elapsed < std::time::Duration::from_secs(10),
+        "sampled build took {:?}",
+        elapsed
+    );
+    let stats = split_stats(&unique, &capped);
+    assert_eq!(stats.sampled_fields, 1);
+    assert!(stats.candidates <= 200);
+    assert_eq!(
+        split_stats(&unique[..150].to_vec(), &capped).sampled_fields,
+        0
+    );
+    // the leaves still separate the classes, around the sampled values
+    assert!(unique.iter().all(|p| predict(p, &tree) == p.class));
+    let again = build_tree_with(&unique, &capped).unwrap();
+    assert_eq!(again.fingerprint(), tree.fingerprint());
+}
+
+#[test]
+fn timed_builds_account_for_every_node_and_field() {
+    let data = rows();
+    let (timed, timings) = build_tree_timed(&data, &config()).unwrap();
+    let tree = build_tree_with(&data, &config()).unwrap();
+    assert_eq!(timed.fingerprint(), tree.fingerprint());
+
+    let mut fields: Vec<&str> = timings.fields.iter().map(|f| f.field).collect();
+    fields.sort();
+    assert_eq!(
+        fields,
+        ["petal_length", "petal_width", "sepal_length", "sepal_width"]
+    );
+    // slowest first, and every field is searched at the same nodes
+    assert!(timings
+        .fields
+        .windows(2)
+        .all(|w| w[0].elapsed >= w[1].elapsed));
+    assert!(timings
+        .fields
+        .iter()
+        .all(|f| f.nodes == timings.fields[0].nodes));
+
+    let depths: Vec<usize> = timings.depths.iter().map(|d| d.depth).collect();
+    assert_eq!(depths, (0..=tree.depth()).collect::<Vec<_>>());
+    let nodes: usize = timings.depths.iter().map(|d| d.nodes).sum();
+    assert_eq!(nodes, tree.node_count());
+    let spent: std::time::Duration = timings.depths.iter().map(|d| d.elapsed).sum();
+    assert!(spent <= timings.total);
+    assert_eq!(
+        timings.render().lines().count(),
+        1 + timings.depths.len() + timings.fields.len()
+    );
+}
+
+// the tree with its false branch, a leaf, regrown with room for 64 classes
+fn padded(tree: &Node) -> Node {
+    let Some(Node::Leaf(leaf)) = tree.subtree(&[false]) else {
+        panic!("the first split isolates a class");
+    };
+    let mut roomy = decision_leaf::ClassCounts::with_capacity(64);
+    roomy.merge(leaf);
+    tree.graft(&[false], Node::Leaf(roomy), &Default::default())
+        .unwrap()
+}
+
+#[test]
+fn shrinking_releases_spare_capacity_only() {
+    let tree = build_tree_with(&rows(), &config()).unwrap();
+    let padded = padded(&tree);
+    assert_eq!(padded.fingerprint(), tree.fingerprint());
+    let mut shrunk = padded.clone();
+    shrunk.shrink_to_fit();
+    assert_eq!(shrunk.fingerprint(), tree.fingerprint());
+    assert!(shrunk.heap_size_estimate() < padded.heap_size_estimate());
+    assert_eq!(shrunk.heap_size_estimate(), tree.heap_size_estimate());
+    // every node costs at least its own size
+    let node = std::mem::size_of::<Node>();
+    assert!(tree.heap_size_estimate() > tree.node_count() * node);
+    let Some(Node::Leaf(leaf)) = tree.subtree(&[false]) else {
+        unreachable!();
+    };
+    assert_eq!(
+        Node::Leaf(leaf.clone()).heap_size_estimate(),
+        node + leaf.heap_size_estimate()
+    );
+}
+
+// the estimate stays within a factor of 4 of the saved text, which a
+// padded leaf doesn't change
+#[cfg(feature = "persist")]
+#[test]
+fn estimates_track_the_saved_size() {
+    let tree = build_tree_with(&rows(), &config()).unwrap();
+    let (mut saved, mut padded_saved) = (Vec::new(), Vec::new());
+    tree.save(&mut saved).unwrap();
+    padded(&tree).save(&mut padded_saved).unwrap();
+    assert_eq!(padded_saved, saved);
+    let estimate = tree.heap_size_estimate();
+    assert!(estimate <= saved.len() * 4 && saved.len() <= estimate * 4);
+}
+
+// information gain grows its own tree, as accurate on this data, and the
+// entropy of an even two-class split is one bit
+#[test]
+fn entropy_trees_fit_as_well() {
+    use decision_leaf::split::SplitCriterion;
+    use decision_leaf::ClassCounts;
+
+    let data = rows();
+    let entropy = TreeConfig {
+        criterion: SplitCriterion::Entropy,
+        ..config()
+    };
+    let informed = build_tree_with(&data, &entropy).unwrap();
+    let right = data
+        .iter()
+        .filter(|p| predict(*p, &informed) == p.class)
+        .count();
+    assert!(right >= 140, "{} of 150", right);
+    assert_eq!(
+        build_tree_with(&data, &entropy).unwrap().fingerprint(),
+        informed.fingerprint()
+    );
+    let even: ClassCounts<Species> = [(Species::Setosa, 5), (Species::Virginica, 5)]
+        .into_iter()
+        .collect();
+    assert!((SplitCriterion::Entropy.impurity(&even) - 1.0).abs() < 1e-12);
+    assert!((SplitCriterion::Gini.impurity(&even) - 0.5).abs() < 1e-12);
+}
+
+// with one field a node the root's field follows the seed, and a node
+// searching none is an error
+#[test]
+fn feature_draws_follow_their_seed() {
+    let data = rows();
+    let root_fields: std::collections::HashSet<Field> = (0..20)
+        .map(|feature_seed| {
+            let config = TreeConfig {
+                max_features: Some(1),
+                feature_seed,
+                ..config()
+            };
+            match build_tree_with(&data, &config).unwrap() {
+                Node::Decision { question, .. } => question.field(),
+                Node::Leaf(_) => panic!("the root splits"),
+            }
+        })
+        .collect();
+    assert!(root_fields.len() > 1);
+    let none = TreeConfig {
+        max_features: Some(0),
+        ..config()
+    };
+    assert!(build_tree_with(&data, &none).is_err());
+}
+
+#[cfg(feature = "ensemble")]
+mod forests {
+    use super::*;
+    use decision_leaf::ensemble::Resample;
+    use decision_leaf::DecisionLeafError;
+
+    // rows of the training data each tree's leaves hold
+    fn rows_per_tree(forest: &Forest) -> Vec<u64> {
+        forest
+            .trees
+            .iter()
+            .map(|tree| tree.paths().map(|path| path.n_samples).sum())
+            .collect()
+    }
+
+    #[test]
+    fn resampling_draws_the_rows_asked_for() {
+        let data = rows();
+        let config = config();
+        let whole = build_forest_with(&data, 3, 1, &config, Resample::None).unwrap();
+        let tree = build_tree_with(&data, &config).unwrap();
+        assert!(whole
+            .trees
+            .iter()
+            .all(|t| t.fingerprint() == tree.fingerprint()));
+        let half = build_forest_with(&data, 3, 1, &config, Resample::Fraction(0.5)).unwrap();
+        assert_eq!(rows_per_tree(&half), [75, 75, 75]);
+        let bootstrap = build_forest_with(&data, 3, 1, &config, Resample::Bootstrap).unwrap();
+        assert_eq!(rows_per_tree(&bootstrap), [150, 150, 150]);
+        let plain = build_forest(&data, 3, 1, &config).unwrap();
+        let fingerprints = |forest: &Forest| -> Vec<u64> {
+            forest.trees.iter().map(|t| t.fingerprint()).collect()
+        };
+        assert_eq!(fingerprints(&bootstrap), fingerprints(&plain));
+    }
+
+    // each tree on 80% of the rows, each node searching two of the four fields
+    #[test]
+    fn random_forests_vote_by_their_seed() {
+        let data = rows();
+        let (test, train): (Vec<_>, Vec<_>) = data
+            .iter()
+            .cloned()
+            .enumerate()
+            .partition(|(i, _)| i % 5 == 0);
+        let train: Vec<DataPoint> = train.into_iter().map(|(_, p)| p).collect();
+        let test: Vec<DataPoint> = test.into_iter().map(|(_, p)| p).collect();
+        let random = TreeConfig {
+            max_features: Some(2),
+            ..config()
+        };
+        let grow =
+            |seed| build_forest_with(&train, 25, seed, &random, Resample::Fraction(0.8)).unwrap();
+        let forest = grow(3);
+        let mut right = 0;
+        for point in &test {
+            let votes = forest.classify(point);
+            assert_eq!(votes.total(), 25);
+            // soft votes are the mean of the leaves' probabilities
+            let soft = forest.vote(point);
+            let sum: f64 = soft.classes().map(|c| soft.proba(c)).sum();
+            assert!((sum - 1.0).abs() < 1e-9);
+            assert_eq!(forest.predict(point).as_ref(), soft.argmax());
+            if votes.argmax() == Some(&point.class) {
+                right += 1;
+            }
+        }
+        assert!(
+            right * 100 >= test.len() * 85,
+            "{} of {}",
+            right,
+            test.len()
+        );
+        let again = grow(3);
+        assert!(test.iter().all(|p| forest.classify(p) == again.classify(p)));
+        let other = grow(4);
+        assert!(forest
+            .trees
+            .iter()
+            .zip(&other.trees)
+            .any(|(a, b)| a.fingerprint() != b.fingerprint()));
+    }
+
+    #[test]
+    fn forest_arguments_are_checked() {
+        let data = rows();
+        let name =
+            |n_trees, resample| match build_forest_with(&data, n_trees, 1, &config(), resample) {
+                Err(DecisionLeafError::InvalidParameter { name, .. }) => name,
+                other => panic!("built {:?} trees", other.map(|f| f.trees.len())),
+            };
+        assert_eq!(name(3, Resample::Fraction(0.0)), "fraction");
+        assert_eq!(name(3, Resample::Fraction(1.5)), "fraction");
+        assert_eq!(name(0, Resample::None), "n_trees");
+    }
+}
+
+#[cfg(feature = "metrics")]
+mod reports {
+    use super::*;
+    use decision_leaf::eval::EvaluationReport;
+
+    #[test]
+    fn reports_count_every_prediction() {
+        let data = rows();
+        let (test, train): (Vec<_>, Vec<_>) = data
+            .iter()
+            .cloned()
+            .enumerate()
+            .partition(|(i, _)| i % 5 == 0);
+        let train: Vec<DataPoint> = train.into_iter().map(|(_, p)| p).collect();
+        let test: Vec<DataPoint> = test.into_iter().map(|(_, p)| p).collect();
+        let tree = build_tree_with(&train, &config()).unwrap();
+        let report = evaluate(&test, &tree);
+        assert_eq!(report.total, 30);
+        let correct = test
+            .iter()
+            .filter(|p| predict(*p, &tree) == p.class)
+            .count();
+        assert_eq!(report.correct, correct);
+        assert_eq!(report.accuracy, correct as f32 / 30.);
+        assert!(report.accuracy >= 0.85, "accuracy {}", report.accuracy);
+        for ((actual, predicted), n) in &report.confusion {
+            let expected = test
+                .iter()
+                .filter(|p| p.class == *actual && predict(*p, &tree) == *predicted)
+                .count();
+            assert_eq!(*n, expected, "{:?} as {:?}", actual, predicted);
+        }
+        assert_eq!(report.confusion.values().sum::<usize>(), 30);
+        // run_tests prints the report's Display
+        let first = report.to_string().lines().next().unwrap().to_owned();
+        assert!(first.starts_with("accuracy: "), "{}", first);
+        assert!(first.ends_with(&format!("({}/30)", correct)), "{}", first);
+        assert_eq!(evaluate(&Vec::new(), &tree).accuracy, 0.);
+    }
+
+    #[test]
+    fn precision_and_recall_read_the_confusion_matrix() {
+        use Species::*;
+        // 4 Setosa, 3 right; 2 Versicolor, both predicted Setosa; 1 Virginica
+        let report = EvaluationReport::from_predictions(
+            [
+                (Setosa, Setosa),
+                (Setosa, Setosa),
+                (Setosa, Setosa),
+                (Setosa, Virginica),
+                (Versicolor, Setosa),
+                (Versicolor, Setosa),
+                (Virginica, Virginica),
+            ]
+            .map(|(actual, predicted)| (actual, Some(predicted))),
+        );
+        assert_eq!(report.precision(&Setosa), 3. / 5.);
+        assert_eq!(report.recall(&Setosa), 3. / 4.);
+        assert_eq!(report.precision(&Virginica), 0.5);
+        assert_eq!(report.recall(&Virginica), 1.);
+        // never predicted, so no precision to speak of
+        assert_eq!(report.precision(&Versicolor), 0.);
+        assert_eq!(report.recall(&Versicolor), 0.);
+        assert_eq!(report.support(&Versicolor), 2);
+        let shown = report.to_string();
+        assert!(shown.ends_with(&report.render_classes(&Default::default())));
+        assert_eq!(shown.lines().count(), 1 + 3 + 3);
+    }
+
+    // a class the tree never saw still counts as an actual class, and is
+    // never predicted
+    #[test]
+    fn unseen_classes_have_no_recall() {
+        let data = rows();
+        let without: Vec<DataPoint> = data
+            .iter()
+            .filter(|p| p.class != Species::Virginica)
+            .cloned()
+            .collect();
+        let tree = build_tree_with(&without, &config()).unwrap();
+        let report = evaluate(&data, &tree);
+        assert!(report.classes().contains(&Species::Virginica));
+        assert_eq!(report.recall(&Species::Virginica), 0.);
+        assert_eq!(report.support(&Species::Virginica), 50);
+        assert!(report
+            .confusion
+            .keys()
+            .all(|(_, predicted)| *predicted != Species::Virginica));
+        assert!(report.precision(&Species::Setosa) > 0.9);
+    }
+
+    #[test]
+    fn points_without_a_prediction_count_as_wrong() {
+        let report = EvaluationReport::from_predictions([
+            (Species::Setosa, Some(Species::Setosa)),
+            (Species::Setosa, None),
+            (Species::Virginica, Some(Species::Setosa)),
+        ]);
+        assert_eq!((report.total, report.correct), (3, 1));
+        assert_eq!(report.accuracy, 1. / 3.);
+        assert_eq!(report.confusion.len(), 2);
+        assert_eq!(report.confusion[&(Species::Virginica, Species::Setosa)], 1);
+    }
+}
+
+#[cfg(feature = "metrics")]
+mod experiments {
+    use super::*;
+    use decision_leaf::experiment::Protocol;
+    use decision_leaf::DecisionLeafError;
+
+    const PROTOCOLS: [Protocol; 3] = [
+        Protocol::Holdout { test_fraction: 0.2 },
+        Protocol::KFold { k: 5 },
+        Protocol::StratifiedKFold { k: 5 },
+    ];
+
+    #[test]
+    fn each_protocol_repeats_byte_for_byte() {
+        let data = rows();
+        for protocol in PROTOCOLS {
+            let result = run_experiment(&data, &config(), 7, protocol).unwrap();
+            let again = run_experiment(&data, &config(), 7, protocol).unwrap();
+            assert_eq!(result.to_json(), again.to_json(), "{}", protocol.name());
+            assert_eq!(result.seed, 7);
+            assert_eq!(result.protocol, protocol);
+            assert_eq!(
+                result.model_fingerprint,
+                build_tree_with(&data, &config()).unwrap().fingerprint()
+            );
+            assert!(result.accuracy_mean >= 0.85, "{}", protocol.name());
+        }
+    }
+
+    #[test]
+    fn folds_split_every_row() {
+        let data = rows();
+        let holdout = run_experiment(&data, &config(), 7, PROTOCOLS[0]).unwrap();
+        assert_eq!(holdout.folds.len(), 1);
+        assert_eq!(holdout.timings.folds.len(), 1);
+        assert_eq!(holdout.folds[0].test_rows, 30);
+        assert_eq!(holdout.accuracy_std, 0.);
+        for protocol in &PROTOCOLS[1..] {
+            let result = run_experiment(&data, &config(), 7, *protocol).unwrap();
+            assert_eq!(result.folds.len(), 5);
+            assert_eq!(result.timings.folds.len(), 5);
+            for fold in &result.folds {
+                assert_eq!(fold.train_rows + fold.test_rows, data.len());
+                let confused: usize = fold.confusion.iter().map(|(_, _, n)| n).sum();
+                assert_eq!(confused, fold.test_rows);
+            }
+            let tested: usize = result.folds.iter().map(|f| f.test_rows).sum();
+            assert_eq!(tested, data.len());
+        }
+    }
+
+    #[test]
+    fn stratified_folds_hold_each_species_evenly() {
+        let result = run_experiment(&rows(), &config(), 7, PROTOCOLS[2]).unwrap();
+        for fold in &result.folds {
+            for species in ["Setosa", "Versicolor", "Virginica"] {
+                let rows: usize = fold
+                    .confusion
+                    .iter()
+                    .filter(|(actual, _, _)| actual == species)
+                    .map(|(_, _, n)| n)
+                    .sum();
+                assert_eq!(rows, 10);
+            }
+        }
+    }
+
+    #[test]
+    fn the_data_hash_follows_the_rows_not_the_seed() {
+        let data = rows();
+        let kfold = PROTOCOLS[1];
+        let result = run_experiment(&data, &config(), 7, kfold).unwrap();
+        let reseeded = run_experiment(&data, &config(), 8, kfold).unwrap();
+        assert_eq!(reseeded.data_hash, result.data_hash);
+        assert_ne!(reseeded.to_json(), result.to_json());
+        let fewer = data[1..].to_vec();
+        let trimmed = run_experiment(&fewer, &config(), 7, kfold).unwrap();
+        assert_ne!(trimmed.data_hash, result.data_hash);
+        assert_eq!(trimmed.rows, 149);
+    }
+
+    #[test]
+    fn protocols_are_checked_up_front() {
+        let data = rows();
+        let name = |protocol| match run_experiment(&data, &config(), 7, protocol) {
+            Err(DecisionLeafError::InvalidParameter { name, .. }) => name,
+            other => panic!("{:?} ran: {:?}", protocol, other.map(|r| r.to_json())),
+        };
+        assert_eq!(
+            name(Protocol::Holdout { test_fraction: 1.0 }),
+            "test_fraction"
+        );
+        assert_eq!(name(Protocol::KFold { k: 1 }), "k");
+        assert_eq!(name(Protocol::StratifiedKFold { k: 151 }), "k");
+    }
+}
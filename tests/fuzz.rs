@@ -0,0 +1,62 @@
+//! The generated `fuzz_roundtrip` on hand-picked edge inputs and seeded
+//! random byte strings, over a layout with every category of field, NaN
+//! floats and an optional field included. Any input is valid, so each call
+//! passing means no invariant broke.
+
+#![cfg(feature = "fuzz")]
+// the generated functions take &Vec<DataPoint>
+#![allow(clippy::ptr_arg)]
+
+use decision_leaf::classification_data_layout;
+use decision_leaf::fuzz::{Arbitrary, Unstructured};
+use decision_leaf::rng::Rng;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Kind {
+    Spam,
+    Ham,
+    Unsure,
+}
+
+impl FromStr for Kind {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, String> {
+        match s {
+            "Spam" => Ok(Kind::Spam),
+            "Ham" => Ok(Kind::Ham),
+            "Unsure" => Ok(Kind::Unsure),
+            _ => Err(format!("unknown kind {}", s)),
+        }
+    }
+}
+
+impl Arbitrary for Kind {
+    fn arbitrary(input: &mut Unstructured) -> Self {
+        [Kind::Spam, Kind::Ham, Kind::Unsure][input.choose(3)]
+    }
+}
+
+classification_data_layout!(
+    enum_fields = {sender: Kind, flagged: bool, initial: char},
+    number_fields = {links: u16, score: f64, offset: i8, rating: Option<f32>},
+    Kind
+);
+
+#[test]
+fn edge_inputs_hold_the_invariants() {
+    let edges: [&[u8]; 4] = [&[], &[0xff; 300], &[0; 300], &[1, 2, 3]];
+    for bytes in edges {
+        fuzz_roundtrip(bytes);
+    }
+}
+
+#[test]
+fn random_inputs_hold_the_invariants() {
+    let mut rng = Rng::new(11);
+    for _ in 0..500 {
+        let len = rng.gen_index(1200);
+        let bytes: Vec<u8> = (0..len).map(|_| rng.gen_index(256) as u8).collect();
+        fuzz_roundtrip(&bytes);
+    }
+}
@@ -0,0 +1,155 @@
+//! Fields with gaps, an `Option<u32>` number field and an `Option<bool>`
+//! enum field: trained on without imputing, None never a threshold, and
+//! missing values kept through CSV files and saved trees.
+
+// the generated functions take &Vec<DataPoint>
+#![allow(clippy::ptr_arg)]
+
+use decision_leaf::classification_data_layout;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Risk {
+    Low,
+    High,
+}
+
+impl FromStr for Risk {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, String> {
+        match s {
+            "Low" => Ok(Risk::Low),
+            "High" => Ok(Risk::High),
+            _ => Err(format!("unknown risk {}", s)),
+        }
+    }
+}
+
+classification_data_layout!(
+    enum_fields = {smoker: Option<bool>},
+    number_fields = {age: u32, income: Option<u32>},
+    Risk
+);
+
+// High below an income of 30 and when the income isn't given; smokers are
+// High either way. Every fourth income and every fifth smoker is missing.
+fn data() -> Vec<DataPoint> {
+    (0..80u32)
+        .map(|i| {
+            let smoker = match i % 5 {
+                0 => None,
+                1 => Some(true),
+                _ => Some(false),
+            };
+            let income = (i % 4 != 0).then_some(10 + i * 13 % 50);
+            let high = smoker == Some(true) || income.is_none_or(|income| income < 30);
+            let class = if high { Risk::High } else { Risk::Low };
+            DataPoint::new(smoker, 20 + i % 40, income, class)
+        })
+        .collect()
+}
+
+fn questions(node: &Node, out: &mut Vec<Question>) {
+    if let Node::Decision {
+        question,
+        true_branch,
+        false_branch,
+        ..
+    } = node
+    {
+        out.push(question.clone());
+        questions(true_branch, out);
+        questions(false_branch, out);
+    }
+}
+
+#[test]
+fn missing_values_train_without_imputing() {
+    let data = data();
+    let tree = build_tree(&data);
+    for point in &data {
+        assert_eq!(predict(point, &tree), point.class);
+    }
+    // None is never a threshold, and answers `income >= t` with false
+    let mut asked = Vec::new();
+    questions(&tree, &mut asked);
+    assert!(asked
+        .iter()
+        .any(|q| matches!(q, Question::income(Some(t)) if (30..35).contains(t))));
+    assert!(asked.iter().all(|q| !matches!(q, Question::income(None))));
+    assert!(!Question::income(Some(0)).evaluate(&data[0]));
+    let unknown = DataPoint::new(Some(false), 30, None, Risk::High);
+    assert_eq!(predict(&unknown, &tree), Risk::High);
+    let known = DataPoint::new(Some(false), 30, Some(45), Risk::Low);
+    assert_eq!(predict(&known, &tree), Risk::Low);
+    // a missing smoker is a value of its own
+    let unsure = DataPoint::new(None, 30, Some(45), Risk::Low);
+    assert_eq!(predict(&unsure, &tree), Risk::Low);
+}
+
+#[cfg(feature = "persist")]
+#[test]
+fn saved_trees_route_missing_values_the_same() {
+    use decision_leaf::persist::LoadOptions;
+
+    let data = data();
+    let tree = build_tree(&data);
+    let mut saved = Vec::new();
+    tree.save(&mut saved).unwrap();
+    let reloaded = Node::load(&saved[..], &LoadOptions::new()).unwrap();
+    assert_eq!(reloaded.fingerprint(), tree.fingerprint());
+    let points = [
+        DataPoint::new(Some(false), 30, None, Risk::High),
+        DataPoint::new(None, 30, Some(45), Risk::Low),
+        DataPoint::new(None, 30, None, Risk::High),
+    ];
+    for point in points.iter().chain(&data) {
+        assert_eq!(predict(point, &reloaded), predict(point, &tree));
+    }
+}
+
+#[cfg(feature = "csv")]
+mod csv {
+    use super::*;
+
+    // the rows as a CSV of empty smoker cells and `N/A` incomes
+    fn csv() -> String {
+        let mut csv = String::from("smoker,age,income,class\n");
+        for point in data() {
+            let smoker = point.smoker.map_or(String::new(), |s| s.to_string());
+            let income = point.income.map_or("N/A".to_owned(), |i| i.to_string());
+            csv.push_str(&format!(
+                "{},{},{},{:?}\n",
+                smoker, point.age, income, point.class
+            ));
+        }
+        csv
+    }
+
+    #[test]
+    fn missing_cells_of_option_fields_load_as_none() {
+        let mut config = LoaderConfig::new().missing_markers(&["N/A"]);
+        let loaded = load_csv_with(csv().as_bytes(), &mut config).unwrap();
+        assert_eq!(loaded, data());
+        assert_eq!((loaded[0].smoker, loaded[0].income), (None, None));
+        assert_eq!((loaded[1].smoker, loaded[1].income), (Some(true), Some(23)));
+        // without the marker `N/A` isn't a number
+        assert!(load_csv_with(csv().as_bytes(), &mut LoaderConfig::new()).is_err());
+    }
+
+    #[test]
+    fn written_gaps_read_back_as_none() {
+        let data = data();
+        let mut written = Vec::new();
+        write_csv(&data, &mut written).unwrap();
+        let reread = load_csv_with(&written[..], &mut LoaderConfig::new()).unwrap();
+        assert_eq!(reread, data);
+    }
+
+    #[test]
+    fn plain_fields_still_need_a_value() {
+        let gap = "smoker,age,income,class\ntrue,N/A,20,High\n";
+        let mut config = LoaderConfig::new().missing_markers(&["N/A"]);
+        assert!(load_csv_with(gap.as_bytes(), &mut config).is_err());
+    }
+}
@@ -0,0 +1,268 @@
+//! Categorical fields that aren't enums, a `char` grade and a `[u8; 3]`
+//! course code, on rows where A and B pass and C passes the lab course only:
+//! trained on, printed and exported by their Debug form, read from CSV files
+//! and through per-column adapters, written back, and saved with trees and
+//! training profiles.
+
+// the generated functions take &Vec<DataPoint>
+#![allow(clippy::ptr_arg)]
+
+use decision_leaf::classification_data_layout;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Pass {
+    Yes,
+    No,
+}
+
+impl FromStr for Pass {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, String> {
+        match s {
+            "Yes" => Ok(Pass::Yes),
+            "No" => Ok(Pass::No),
+            _ => Err(format!("unknown outcome {}", s)),
+        }
+    }
+}
+
+classification_data_layout!(
+    enum_fields = {grade: char, code: [u8; 3]},
+    number_fields = {score: u32},
+    Pass
+);
+
+const GRADES: [char; 5] = ['A', 'B', 'C', 'F', '\''];
+const CODES: [&str; 3] = ["lab", "art", "mat"];
+
+// (grade, code, score, class) of each row; the score is noise
+fn cells() -> Vec<(char, &'static str, u32, Pass)> {
+    (0..60)
+        .map(|i| {
+            let grade = GRADES[i % 5];
+            let code = CODES[i % 3];
+            let pass = matches!(grade, 'A' | 'B') || (grade == 'C' && code == "lab");
+            let class = if pass { Pass::Yes } else { Pass::No };
+            (grade, code, 40 + i as u32 * 7 % 50, class)
+        })
+        .collect()
+}
+
+fn data() -> Vec<DataPoint> {
+    cells()
+        .into_iter()
+        .map(|(grade, code, score, class)| {
+            DataPoint::new(grade, code.as_bytes().try_into().unwrap(), score, class)
+        })
+        .collect()
+}
+
+#[test]
+fn the_rows_cover_every_grade_and_code() {
+    let data = data();
+    assert_eq!(data.len(), 60);
+    let passed = data.iter().filter(|p| p.class == Pass::Yes).count();
+    // A and B, 24 rows, and the 4 C rows of the lab course
+    assert_eq!(passed, 28);
+}
+
+#[test]
+fn char_and_array_fields_train_and_name_their_rules() {
+    let data = data();
+    let tree = build_tree(&data);
+    for point in &data {
+        assert_eq!(predict(point, &tree), point.class);
+    }
+    // rules name the values by their Debug form, and hold for the rows
+    // reaching their leaf
+    let rules: Vec<String> = tree
+        .paths()
+        .flat_map(|p| p.conditions.into_iter().map(|c| c.question_text()))
+        .collect();
+    assert!(rules.iter().any(|r| r.contains("'C'")), "{:?}", rules);
+    assert!(
+        rules.iter().any(|r| r.contains("[108, 97, 98]")),
+        "{:?}",
+        rules
+    );
+    for path in tree.paths() {
+        for point in data
+            .iter()
+            .filter(|p| path.conditions.iter().all(|c| c.holds(p)))
+        {
+            assert_eq!(point.class, *path.distribution.argmax().unwrap());
+        }
+    }
+    let mut printed = Vec::new();
+    tree.write_tree(&mut printed, &Default::default()).unwrap();
+    let printed = String::from_utf8(printed).unwrap();
+    assert!(printed.contains("'C'") && printed.contains("[108, 97, 98]"));
+}
+
+#[cfg(feature = "persist")]
+#[test]
+fn saved_trees_keep_chars_and_arrays() {
+    use decision_leaf::persist::{LoadOptions, PersistError};
+
+    let data = data();
+    let tree = build_tree(&data);
+    let mut saved = Vec::new();
+    tree.save(&mut saved).unwrap();
+    let reloaded = Node::load(&saved[..], &LoadOptions::new()).unwrap();
+    assert_eq!(reloaded.fingerprint(), tree.fingerprint());
+    for point in &data {
+        assert_eq!(classify(point, &reloaded), classify(point, &tree));
+    }
+    // a tree of another layout is an error, not a panic
+    let renamed = String::from_utf8(saved).unwrap().replace("score", "points");
+    assert!(matches!(
+        Node::load(renamed.as_bytes(), &LoadOptions::new()),
+        Err(PersistError::Schema(_))
+    ));
+}
+
+#[cfg(feature = "persist")]
+#[test]
+fn saved_profiles_keep_char_and_array_values() {
+    use decision_leaf::persist::LoadOptions;
+
+    let trained = build_trained(&data(), &TreeConfig::default()).unwrap();
+    let mut saved = Vec::new();
+    trained.save(&mut saved).unwrap();
+    let reloaded = TrainedTree::load(&saved[..], &LoadOptions::new()).unwrap();
+    let observed = reloaded.profile.observed().unwrap();
+    assert_eq!(Ok(observed), trained.profile.observed());
+    // ordered by label, and '\'' sorts after the letters
+    assert_eq!(observed.grade, ['A', 'B', 'C', 'F', '\'']);
+    // and labels of arrays compare as text: "[108, ..." before "[97, ..."
+    assert_eq!(observed.code, [*b"lab", *b"mat", *b"art"]);
+}
+
+#[cfg(feature = "csv")]
+mod csv {
+    use super::*;
+    use decision_leaf::load::LoadError;
+
+    // the rows as the CSV writes them, codes as plain text
+    fn csv() -> String {
+        let mut csv = String::from("grade,code,score,class\n");
+        for (grade, code, score, class) in cells() {
+            csv.push_str(&format!("{},{},{},{:?}\n", grade, code, score, class));
+        }
+        csv
+    }
+
+    fn code_adapter() -> LoaderConfig {
+        LoaderConfig::new().custom("code", |text| {
+            let code: [u8; 3] = text
+                .as_bytes()
+                .try_into()
+                .map_err(|_| "is not a three-letter code".to_owned())?;
+            Ok(FieldValue::code(code))
+        })
+    }
+
+    #[test]
+    fn custom_adapters_read_their_column() {
+        let loaded = load_csv_with(csv().as_bytes(), &mut code_adapter()).unwrap();
+        assert_eq!(loaded, data());
+        // without it the plain text isn't an array
+        assert!(load_csv_with(csv().as_bytes(), &mut LoaderConfig::new()).is_err());
+    }
+
+    #[test]
+    fn adapter_errors_name_the_cell() {
+        let csv = "grade,code,score,class\nA,lab,70,Yes\nB,math,60,No\n";
+        let err = load_csv_with(csv.as_bytes(), &mut code_adapter()).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "line 3, column `code`: \"math\" is not a three-letter code"
+        );
+        // a parser answering for another column is an error too
+        let mut wrong = LoaderConfig::new().custom("code", |_| Ok(FieldValue::score(1)));
+        let err = load_csv_with(csv.as_bytes(), &mut wrong).unwrap_err();
+        assert!(
+            matches!(&err, LoadError::Parse { line: 2, column, .. } if column == "code"),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn enum_maps_bool_markers_and_separators_rewrite_cells() {
+        let csv = "grade,code,score,class\ntop,\"[108, 97, 98]\",\"1,070\",pass\nB,\"[97, 114, 116]\",60,fail\n";
+        let mut config = LoaderConfig::new()
+            .map_enum("grade", &[("top", 'A')])
+            .map_enum("class", &[("pass", Pass::Yes), ("fail", Pass::No)])
+            .thousands_separator(',');
+        assert_eq!(
+            load_csv_with(csv.as_bytes(), &mut config).unwrap(),
+            [
+                DataPoint::new('A', *b"lab", 1070, Pass::Yes),
+                DataPoint::new('B', *b"art", 60, Pass::No),
+            ]
+        );
+        // a label mapped to a value of another type
+        let mut config = LoaderConfig::new().map_enum("grade", &[("top", "A")]);
+        assert!(load_csv_with(csv.as_bytes(), &mut config).is_err());
+        // number columns read bool markers as 1 and 0
+        let csv = "grade,code,score,class\nA,\"[108, 97, 98]\",high,Yes\n";
+        let mut config = LoaderConfig::new().bool_markers("score", &["high"], &["low"]);
+        assert_eq!(
+            load_csv_with(csv.as_bytes(), &mut config).unwrap()[0].score,
+            1
+        );
+    }
+
+    // from a file, with the columns in another order; a bad cell names its
+    // line and column
+    #[test]
+    fn files_load_by_header_name() {
+        let path = std::env::temp_dir().join(format!("grades-{}.csv", std::process::id()));
+        std::fs::write(
+            &path,
+            "class,score,code,grade\nYes,70,\"[108, 97, 98]\",A\n",
+        )
+        .unwrap();
+        let loaded = load_csv(&path);
+        std::fs::write(
+            &path,
+            "grade,code,score,class\nA,\"[108, 97, 98]\",70,Yes\nB,\"[97, 114, 116]\",seventy,No\n",
+        )
+        .unwrap();
+        let bad_score = load_csv(&path);
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(
+            loaded.unwrap(),
+            [DataPoint::new('A', *b"lab", 70, Pass::Yes)]
+        );
+        assert_eq!(
+            bad_score.unwrap_err().to_string(),
+            "line 3, column `score`: \"seventy\" is not a valid u32"
+        );
+        assert!(matches!(load_csv(&path), Err(LoadError::Io(_))));
+    }
+
+    // written back as CSV, the Debug forms (quotes, commas and arrays) load
+    // again without the custom adapter
+    #[test]
+    fn written_debug_forms_load_again() {
+        let mut odd = data();
+        odd.push(DataPoint::new(',', *b"a,b", 0, Pass::No));
+        odd.push(DataPoint::new('"', *b"\"\"\"", u32::MAX, Pass::Yes));
+        let mut written = Vec::new();
+        write_csv(&odd, &mut written).unwrap();
+        let reread = load_csv_with(&written[..], &mut LoaderConfig::new()).unwrap();
+        assert_eq!(reread, odd);
+    }
+
+    #[test]
+    fn options_must_name_columns_of_the_layout() {
+        let mut config = LoaderConfig::new().bool_markers("passed", &["y"], &["n"]);
+        assert!(matches!(
+            load_csv_with(csv().as_bytes(), &mut config),
+            Err(LoadError::UnknownColumn { column }) if column == "passed"
+        ));
+    }
+}
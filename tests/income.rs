@@ -0,0 +1,805 @@
+//! Checks on the adult-style rows of examples/data/income.csv, enum and
+//! number fields mixed: drift between the training rows and live batches,
+//! imputing the four missing hours, the DOT export, questions asked of every
+//! kind of point, structs of the caller's own mapped onto the layout, the
+//! training profile kept with a tree, grafting subtrees, the training rows
+//! of each leaf, tuning under nested cross-validation, merging nearby
+//! thresholds, and what moving one by hand would change.
+
+// the generated functions take &Vec<DataPoint>
+#![allow(clippy::ptr_arg)]
+
+use decision_leaf::freeze::Frozen;
+use decision_leaf::{classification_data_layout, impl_has_fields, ClassCounts, DecisionLeafError};
+use std::str::FromStr;
+
+// FromStr by variant name, as the fixture writes them
+macro_rules! labeled_enum {
+    ($name:ident { $($variant:ident),* }) => {
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+        pub enum $name {
+            $($variant),*
+        }
+
+        impl FromStr for $name {
+            type Err = String;
+            fn from_str(s: &str) -> Result<Self, String> {
+                match s {
+                    $(stringify!($variant) => Ok($name::$variant),)*
+                    _ => Err(format!("unknown {} {}", stringify!($name), s)),
+                }
+            }
+        }
+    };
+}
+
+labeled_enum!(Education {
+    HighSchool,
+    Bachelors,
+    Masters,
+    Doctorate
+});
+labeled_enum!(Occupation {
+    Clerical,
+    Sales,
+    Craft,
+    Professional,
+    Managerial,
+    Service
+});
+labeled_enum!(Income { Low, High });
+
+classification_data_layout!(
+    enum_fields = {education: Education, occupation: Occupation, married: bool},
+    number_fields = {age: u32, hours_per_week: u32},
+    Income
+);
+
+const INCOME: &str = include_str!("../examples/data/income.csv");
+
+// the fixture's rows, less the four with `?` for their hours
+fn rows() -> Vec<DataPoint> {
+    INCOME
+        .lines()
+        .skip(1)
+        .filter_map(|line| {
+            let cells: Vec<&str> = line.split(',').collect();
+            Some(DataPoint::new(
+                cells[1].parse().ok()?,
+                cells[2].parse().ok()?,
+                cells[3] == "yes",
+                cells[0].parse().ok()?,
+                cells[4].parse().ok()?,
+                cells[5].parse().ok()?,
+            ))
+        })
+        .collect()
+}
+
+// (train, test), every fourth row held out
+fn split() -> (Vec<DataPoint>, Vec<DataPoint>) {
+    let (test, train): (Vec<_>, Vec<_>) = rows()
+        .into_iter()
+        .enumerate()
+        .partition(|(i, _)| i % 4 == 0);
+    (
+        train.into_iter().map(|(_, p)| p).collect(),
+        test.into_iter().map(|(_, p)| p).collect(),
+    )
+}
+
+#[test]
+fn the_fixture_loads() {
+    let rows = rows();
+    assert_eq!(rows.len(), 396);
+    assert_eq!(
+        rows[0],
+        DataPoint::new(
+            Education::Bachelors,
+            Occupation::Clerical,
+            true,
+            28,
+            49,
+            Income::Low
+        )
+    );
+    let (train, test) = split();
+    assert_eq!((train.len(), test.len()), (297, 99));
+}
+
+#[cfg(feature = "metrics")]
+#[test]
+fn held_out_rows_dont_drift() {
+    let (train, test) = split();
+    let summary = training_summary(&train);
+    assert_eq!(summary.rows, train.len());
+    let names: Vec<&str> = summary
+        .fields
+        .iter()
+        .map(|(name, _)| name.as_str())
+        .collect();
+    assert_eq!(
+        names,
+        [
+            "education",
+            "occupation",
+            "married",
+            "age",
+            "hours_per_week"
+        ]
+    );
+    let drift = field_drift(&summary, &test, 0.25).unwrap();
+    assert!(drift.iter().all(|d| !d.flagged), "{:?}", drift);
+}
+
+#[cfg(feature = "metrics")]
+#[test]
+fn shifted_batches_flag_their_fields() {
+    let (train, test) = split();
+    let summary = training_summary(&train);
+    // everyone is thirty years older, and a third became Service workers
+    let shifted: Vec<DataPoint> = test
+        .iter()
+        .enumerate()
+        .map(|(i, p)| {
+            let mut p = p.clone();
+            p.age += 30;
+            if i % 3 == 0 {
+                p.occupation = Occupation::Service;
+            }
+            p
+        })
+        .collect();
+    let drift = field_drift(&summary, &shifted, 0.25).unwrap();
+    let flagged: Vec<&str> = drift
+        .iter()
+        .filter(|d| d.flagged)
+        .map(|d| d.field.as_str())
+        .collect();
+    assert_eq!(flagged, ["occupation", "age"]);
+    let age = &drift[3];
+    assert!(age.ks.unwrap() > 0.5, "{:?}", age);
+    assert_eq!(drift[1].ks, None);
+}
+
+#[cfg(feature = "metrics")]
+#[test]
+fn enum_drift_is_the_psi_of_value_frequencies() {
+    use decision_leaf::drift::{compare, FieldSummary};
+
+    let labels = |counts: &[(&str, usize)]| {
+        FieldSummary::categorical(
+            counts
+                .iter()
+                .flat_map(|&(label, n)| std::iter::repeat_n(label.to_owned(), n)),
+        )
+    };
+    let training = labels(&[("A", 50), ("B", 50)]);
+    let live = labels(&[("B", 1), ("A", 9)]);
+    assert_eq!(
+        live,
+        FieldSummary::Categorical(vec![("A".to_owned(), 9), ("B".to_owned(), 1)])
+    );
+    let drift = compare("f", &training, &live, 0.25);
+    let psi = 0.4 * 1.8f64.ln() - 0.4 * 0.2f64.ln();
+    assert!((drift.psi - psi).abs() < 1e-12, "{:?}", drift);
+    assert!(drift.flagged && drift.unseen_values.is_empty());
+    // a value training never saw is flagged however rare
+    let unseen = compare(
+        "f",
+        &training,
+        &labels(&[("A", 50), ("B", 49), ("C", 1)]),
+        10.,
+    );
+    assert_eq!(unseen.unseen_values, ["C"]);
+    assert!(unseen.flagged);
+    assert!(!compare("f", &training, &training, 0.25).flagged);
+}
+
+#[cfg(feature = "metrics")]
+#[test]
+fn drift_needs_rows_and_a_positive_threshold() {
+    let (train, test) = split();
+    let summary = training_summary(&train);
+    assert!(field_drift(&summary, &Vec::new(), 0.25).is_err());
+    assert!(field_drift(&summary, &test, 0.0).is_err());
+    assert!(field_drift(&summary, &test, f64::NAN).is_err());
+}
+
+#[cfg(feature = "metrics")]
+#[test]
+fn leaf_profiles_add_up_to_the_training_summary() {
+    let (train, _) = split();
+    let tree = build_tree(&train);
+    let profiles = tree.leaf_profiles(&train);
+    let rows: usize = profiles.values().map(|p| p.rows()).sum();
+    assert_eq!(rows, train.len());
+
+    let mut merged = training_summary(&Vec::new()).fields;
+    for profile in profiles.values() {
+        for class in &profile.classes {
+            for ((_, total), (_, summary)) in merged.iter_mut().zip(&class.fields) {
+                total.merge(summary);
+            }
+        }
+    }
+    assert_eq!(merged, training_summary(&train).fields);
+
+    // each leaf has the rows and classes it was trained on
+    for path in tree.paths() {
+        let profile = &profiles[&path.leaf_id];
+        assert_eq!(profile.leaf, path.leaf_id);
+        assert_eq!(profile.rows() as u64, path.n_samples);
+        for class in &profile.classes {
+            let income: Income = class.class.parse().unwrap();
+            assert_eq!(class.rows as u64, path.distribution.get(&income));
+        }
+        let rendered = profile.render();
+        assert_eq!(
+            rendered.lines().next().unwrap(),
+            format!("leaf {}: {} rows", path.leaf_id, path.n_samples)
+        );
+        // a line per class, and one per field of each class
+        assert_eq!(rendered.lines().count(), 1 + profile.classes.len() * 6);
+    }
+}
+
+#[cfg(feature = "metrics")]
+#[test]
+fn merged_thresholds_keep_every_training_row_in_its_leaf() {
+    let (train, _) = split();
+    // a fully grown tree asks about nearby ages and hours many times over
+    let deep = build_tree(&train);
+    let (canonical, report) = deep.canonicalize_thresholds(&train, 3.0).unwrap();
+    assert!(report.merged() > 0);
+    assert_eq!(report.questions_before, deep.distinct_questions().len());
+    assert_eq!(report.questions_after, canonical.distinct_questions().len());
+    assert!(report.questions_after < report.questions_before);
+    assert_eq!(canonical.node_count(), deep.node_count());
+    // the shape is the same, so each row's leaf keeps its id
+    let leaf_of = |tree: &Node, point: &DataPoint| {
+        tree.paths()
+            .find(|path| path.conditions.iter().all(|c| c.holds(point)))
+            .unwrap()
+            .leaf_id
+    };
+    for point in &train {
+        assert_eq!(leaf_of(&canonical, point), leaf_of(&deep, point));
+    }
+    // only the number fields have thresholds
+    assert!(report
+        .merges
+        .iter()
+        .all(|m| m.field == "age" || m.field == "hours_per_week"));
+    assert!(deep.canonicalize_thresholds(&train, -1.0).is_err());
+}
+
+#[cfg(feature = "metrics")]
+mod nested {
+    use super::*;
+    use decision_leaf::experiment::{NestedCvReport, OuterFold, ParamGrid};
+
+    #[test]
+    fn nested_estimates_stay_below_the_naive_one() {
+        let (train, _) = split();
+        let grid = ParamGrid {
+            max_depth: vec![Some(1), Some(2), Some(4), Some(8), None],
+            min_samples_leaf: vec![1, 10],
+        };
+        let base = TreeConfig::default();
+        let nested = nested_cv(&train, &grid, 4, 3, 11, &base).unwrap();
+        assert_eq!(nested.candidates, 10);
+        assert_eq!(nested.folds.len(), 4);
+        let tested: usize = nested.folds.iter().map(|f| f.test_rows).sum();
+        assert_eq!(tested, train.len());
+        assert!(nested.accuracy_mean <= nested.naive_accuracy);
+        assert!((1..=4).contains(&nested.distinct_configs));
+        assert_eq!(nested_cv(&train, &grid, 4, 3, 11, &base).unwrap(), nested);
+
+        // with nothing to choose, both estimates are the same k-fold accuracy
+        let fixed = nested_cv(&train, &ParamGrid::default(), 4, 3, 11, &base).unwrap();
+        assert_eq!(fixed.candidates, 1);
+        assert_eq!(fixed.distinct_configs, 1);
+        assert_eq!(fixed.accuracy_mean, fixed.naive_accuracy);
+        assert!(fixed.warnings.is_empty());
+    }
+
+    #[test]
+    fn fold_counts_are_checked() {
+        let (train, _) = split();
+        let grid = ParamGrid::default();
+        let name = |outer_k, inner_k| match nested_cv(
+            &train,
+            &grid,
+            outer_k,
+            inner_k,
+            11,
+            &TreeConfig::default(),
+        ) {
+            Err(DecisionLeafError::InvalidParameter { name, .. }) => name,
+            other => panic!("{:?}", other.map(|r| r.accuracy_mean)),
+        };
+        assert_eq!(name(1, 3), "k");
+        assert_eq!(name(4, 1), "inner_k");
+        assert_eq!(name(4, 300), "inner_k");
+    }
+
+    #[test]
+    fn unstable_choices_are_warned_about() {
+        let fold = |depth: usize| OuterFold {
+            config: vec![("max_depth".to_owned(), depth.to_string())],
+            inner_accuracy: 0.8,
+            accuracy: 0.7,
+            test_rows: 10,
+        };
+        let stable = NestedCvReport::new(vec![fold(2), fold(2), fold(3), fold(2)], 3, 0.75);
+        assert_eq!(stable.distinct_configs, 2);
+        assert!(stable.warnings.is_empty());
+        let unstable = NestedCvReport::new(vec![fold(1), fold(2), fold(3), fold(2)], 3, 0.75);
+        assert_eq!(
+            unstable.warnings,
+            ["3 different configs chosen in 4 outer folds; the tuning is unstable"]
+        );
+    }
+}
+
+#[test]
+fn imputers_fill_in_modes_and_lower_medians() {
+    let rows = rows();
+    let imputer = Imputer::fit(&rows).unwrap();
+    // the most common value of each enum field
+    let education = |e| rows.iter().filter(|p| p.education == e).count();
+    let occupation = |o| rows.iter().filter(|p| p.occupation == o).count();
+    assert!(rows
+        .iter()
+        .all(|p| education(p.education) <= education(imputer.education)));
+    assert!(rows
+        .iter()
+        .all(|p| occupation(p.occupation) <= occupation(imputer.occupation)));
+    let mut hours: Vec<u32> = rows.iter().map(|p| p.hours_per_week).collect();
+    hours.sort();
+    assert_eq!(imputer.hours_per_week, hours[(hours.len() - 1) / 2]);
+
+    // transform fills the gaps only, and counts them
+    let mut row = PartialPoint::from(&rows[0]);
+    assert_eq!(imputer.transform(&mut row), 0);
+    row.hours_per_week = None;
+    row.married = None;
+    assert_eq!(imputer.transform(&mut row), 2);
+    let filled = row.into_point().unwrap();
+    assert_eq!(filled.hours_per_week, imputer.hours_per_week);
+    assert_eq!(filled.married, imputer.married);
+    assert_eq!(filled.age, rows[0].age);
+
+    // a field no row has a value for has nothing to impute
+    let empty: Vec<PartialPoint> = rows
+        .iter()
+        .map(|p| PartialPoint {
+            age: None,
+            ..PartialPoint::from(p)
+        })
+        .collect();
+    assert_eq!(
+        Imputer::fit_partial(&empty),
+        Err(DecisionLeafError::Unavailable {
+            name: "age",
+            reason: "no row has a value for it",
+        })
+    );
+    assert!(Imputer::fit(&Vec::new()).is_err());
+}
+
+// the DOT export names every node once, draws two edges per question, and
+// asks about both kinds of field
+#[cfg(feature = "export")]
+#[test]
+fn dot_exports_draw_every_node_and_edge() {
+    let (train, _) = split();
+    let tree = build_tree(&train);
+    let dot = tree.to_dot();
+    assert!(dot.starts_with("digraph tree {\n") && dot.ends_with("}\n"));
+    let named = dot.lines().filter(|l| l.contains(" [shape=")).count();
+    let edges = dot.lines().filter(|l| l.contains(" -> ")).count();
+    assert_eq!(named, tree.node_count());
+    assert_eq!(edges, 2 * (tree.node_count() - tree.paths().count()));
+    assert!(dot.contains(" >= ") && dot.contains(" == "));
+    let path = std::env::temp_dir().join(format!("income-{}.dot", std::process::id()));
+    tree.write_dot(&path).unwrap();
+    let written = std::fs::read_to_string(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(written, dot);
+}
+
+// a point of its own that only knows the age
+struct AgeOnly(u32);
+
+impl_has_fields!(AgeOnly { age: 0 });
+
+#[test]
+fn questions_answer_every_kind_of_point() {
+    let point = rows()[0].clone();
+    let partial = PartialPoint {
+        occupation: None,
+        hours_per_week: None,
+        ..PartialPoint::from(&point)
+    };
+    let age = AgeOnly(point.age);
+    // (question, its answer for the point), one of each kind
+    let questions = [
+        (
+            Question::education(vec![Education::Masters, Education::Bachelors]),
+            true,
+        ),
+        (Question::occupation(vec![Occupation::Sales]), false),
+        (Question::married(vec![true]), true),
+        (Question::age(28), true),
+        (Question::age(29), false),
+        (Question::hours_per_week(50), false),
+    ];
+    for (question, answer) in questions {
+        assert_eq!(question.evaluate(&point), answer, "{:?}", question);
+        assert_eq!(question.evaluate_partial(&point), Some(answer));
+        let known = !matches!(
+            question,
+            Question::occupation(_) | Question::hours_per_week(_)
+        );
+        assert_eq!(
+            question.evaluate_partial(&partial),
+            known.then_some(answer),
+            "{:?}",
+            question
+        );
+        let asks_age = matches!(question, Question::age(_));
+        assert_eq!(
+            question.evaluate_partial(&age),
+            asks_age.then_some(answer),
+            "{:?}",
+            question
+        );
+    }
+
+    // a partial point with every field is classified like the full one
+    let tree = build_tree(&rows());
+    let full = PartialPoint::from(&point);
+    assert_eq!(predict(&full, &tree), predict(&point, &tree));
+}
+
+// a request as it arrives off the wire, its members named its own way
+struct Request {
+    id: u64,
+    degree: Education,
+    job: Occupation,
+    spouse: bool,
+    years: u32,
+    weekly_hours: u32,
+}
+
+impl_has_fields!(Request {
+    education: degree,
+    occupation: job,
+    married: spouse,
+    age: years,
+    hours_per_week: weekly_hours,
+});
+
+#[test]
+fn mapped_structs_reach_the_leaf_of_the_owned_point() {
+    let (train, test) = split();
+    let tree = build_tree(&train);
+    for (id, point) in test.iter().enumerate() {
+        let request = Request {
+            id: id as u64,
+            degree: point.education,
+            job: point.occupation,
+            spouse: point.married,
+            years: point.age,
+            weekly_hours: point.hours_per_week,
+        };
+        assert!(std::ptr::eq(
+            classify(&request, &tree),
+            classify(point, &tree)
+        ));
+        assert_eq!(predict(&request, &tree), predict(point, &tree));
+        assert_eq!(request.id, id as u64);
+    }
+    // a partial mapping serves trees that only ask about what it maps
+    let by_age = build_tree_with(
+        &train,
+        &TreeConfig {
+            fields: Some(vec![Field::age]),
+            ..Default::default()
+        },
+    )
+    .unwrap();
+    assert!(by_age.depth() > 0);
+    for point in &test {
+        assert_eq!(
+            predict(&AgeOnly(point.age), &by_age),
+            predict(point, &by_age)
+        );
+    }
+}
+
+#[test]
+#[should_panic(expected = "the point has no value for occupation")]
+fn evaluate_panics_on_unknown_fields() {
+    Question::occupation(vec![Occupation::Sales]).evaluate(&AgeOnly(30));
+}
+
+#[cfg(feature = "persist")]
+#[test]
+fn saved_imputers_load_back_the_same() {
+    use decision_leaf::persist::LoadOptions;
+
+    let imputer = Imputer::fit(&rows()).unwrap();
+    let mut saved = Vec::new();
+    imputer.save(&mut saved).unwrap();
+    assert_eq!(
+        Imputer::load(&saved[..], &LoadOptions::new()).unwrap(),
+        imputer
+    );
+}
+
+#[test]
+fn training_profiles_record_priors_and_observed_values() {
+    let (train, _) = split();
+    let trained = build_trained(&train, &TreeConfig::default()).unwrap();
+    assert_eq!(trained.tree.fingerprint(), build_tree(&train).fingerprint());
+    let profile = &trained.profile;
+    assert_eq!(profile.rows, 297);
+    let high = train.iter().filter(|p| p.class == Income::High).count() as f64 / 297.;
+    assert_eq!(
+        profile.priors(),
+        [(Income::High, high), (Income::Low, 1. - high)]
+    );
+    let observed = profile.observed().unwrap();
+    assert_eq!(observed.married, [false, true]);
+    assert_eq!(observed.education.len(), 4);
+    let ages = train.iter().map(|p| p.age);
+    assert_eq!(
+        observed.age,
+        (ages.clone().min().unwrap(), ages.max().unwrap())
+    );
+
+    // a profile recovered from the leaves has the counts but not the values
+    let recovered = TrainingProfile::from_tree(&trained.tree);
+    assert_eq!(recovered.rows, 297);
+    assert_eq!(recovered.priors(), profile.priors());
+    assert!(matches!(
+        recovered.observed(),
+        Err(DecisionLeafError::Unavailable {
+            name: "observed",
+            ..
+        })
+    ));
+}
+
+fn leaf(class: Income, n: u64) -> Node {
+    let mut counts = ClassCounts::new();
+    counts.add(class, n);
+    Node::Leaf(counts)
+}
+
+#[test]
+fn grafts_change_only_the_points_routed_there() {
+    let (train, test) = split();
+    let tree = build_tree(&train);
+    let Node::Decision { question, .. } = &tree else {
+        panic!("the tree splits at the root");
+    };
+    let grafted = tree
+        .graft(&[true], leaf(Income::High, 5), &Frozen::new())
+        .unwrap();
+    for point in test.iter().chain(&train) {
+        if question.evaluate(point) {
+            assert_eq!(predict(point, &grafted), Income::High);
+        } else {
+            assert_eq!(predict(point, &grafted), predict(point, &tree));
+        }
+    }
+    let replaced = tree.subtree(&[true]).unwrap().node_count();
+    assert_eq!(grafted.node_count(), tree.node_count() - replaced + 1);
+    assert_eq!(
+        grafted.subtree(&[false]).unwrap().fingerprint(),
+        tree.subtree(&[false]).unwrap().fingerprint()
+    );
+
+    // a decision node goes in as given
+    let split = Node::Decision {
+        question: Question::age(40),
+        true_branch: Box::new(leaf(Income::High, 2)),
+        false_branch: Box::new(leaf(Income::Low, 3)),
+        runner_up: None,
+    };
+    let grafted = tree
+        .graft(&[false, true], split.clone(), &Frozen::new())
+        .unwrap();
+    assert_eq!(
+        grafted.subtree(&[false, true]).unwrap().fingerprint(),
+        split.fingerprint()
+    );
+}
+
+#[test]
+fn grafts_check_the_path_replacement_and_frozen_nodes() {
+    let (train, _) = split();
+    let tree = build_tree(&train);
+    let name = |result: Result<Node, DecisionLeafError>| match result {
+        Err(DecisionLeafError::InvalidParameter { name, .. }) => name,
+        Err(other) => panic!("{}", other),
+        Ok(_) => panic!("the graft went through"),
+    };
+    let high = || leaf(Income::High, 5);
+    assert_eq!(
+        name(tree.graft(&[true; 40], high(), &Frozen::new())),
+        "path"
+    );
+    let empty = Node::Leaf(ClassCounts::new());
+    assert_eq!(
+        name(tree.graft(&[false], empty, &Frozen::new())),
+        "replacement"
+    );
+    let asks_nothing = Node::Decision {
+        question: Question::education(Vec::new()),
+        true_branch: Box::new(high()),
+        false_branch: Box::new(high()),
+        runner_up: None,
+    };
+    assert_eq!(
+        name(tree.graft(&[false], asks_nothing, &Frozen::new())),
+        "replacement"
+    );
+
+    // neither a frozen node nor an ancestor of one can be replaced
+    let mut frozen = Frozen::new();
+    frozen.freeze(&[true, false]);
+    assert_eq!(name(tree.graft(&[true], high(), &frozen)), "path");
+    assert_eq!(name(tree.graft(&[true, false], high(), &frozen)), "path");
+    assert!(tree.graft(&[false], high(), &frozen).is_ok());
+}
+
+// moving the age threshold of a hand-built tree: the married rows aged 35 to
+// 39 switch from the under-40 leaf (id 3) to the 40-and-over one (id 2)
+#[cfg(feature = "metrics")]
+#[test]
+fn what_ifs_report_the_rows_between_the_thresholds() {
+    use decision_leaf::snap::LeafShift;
+
+    let counts = |high: u64, low: u64| {
+        let mut counts = ClassCounts::new();
+        counts.add(Income::High, high);
+        counts.add(Income::Low, low);
+        Node::Leaf(counts)
+    };
+    let tree = Node::Decision {
+        question: Question::married(vec![true]),
+        true_branch: Box::new(Node::Decision {
+            question: Question::age(40),
+            true_branch: Box::new(counts(3, 1)),
+            false_branch: Box::new(counts(1, 5)),
+            runner_up: None,
+        }),
+        false_branch: Box::new(leaf(Income::Low, 4)),
+        runner_up: None,
+    };
+    let row = |married: bool, age: u32, class: Income| {
+        DataPoint::new(
+            Education::Bachelors,
+            Occupation::Craft,
+            married,
+            age,
+            40,
+            class,
+        )
+    };
+    let rows = vec![
+        row(true, 30, Income::Low),
+        row(true, 36, Income::High),
+        row(true, 38, Income::High),
+        row(true, 45, Income::High),
+        row(false, 37, Income::Low),
+    ];
+    let what_if = tree.what_if_threshold(1, 35.0, &rows).unwrap();
+    assert_eq!((what_if.from, what_if.to, what_if.rows), (40.0, 35.0, 4));
+    assert_eq!(what_if.switched, [1, 2]);
+    let shift = |counts: &[(&str, u64)]| -> Vec<(String, u64)> {
+        counts.iter().map(|&(c, n)| (c.to_owned(), n)).collect()
+    };
+    assert_eq!(
+        what_if.leaves,
+        [
+            LeafShift {
+                leaf: 2,
+                before: shift(&[("High", 1)]),
+                after: shift(&[("High", 3)]),
+            },
+            LeafShift {
+                leaf: 3,
+                before: shift(&[("High", 2), ("Low", 1)]),
+                after: shift(&[("Low", 1)]),
+            },
+        ]
+    );
+    assert!((what_if.accuracy_before - 0.6).abs() < 1e-9);
+    assert!((what_if.accuracy_delta() - 0.4).abs() < 1e-9);
+    assert!(what_if
+        .render()
+        .starts_with("node 1  age >= 40 -> 35: 2 of 4 rows switch"));
+
+    // committing moves the two High rows between the leaves' counts, and
+    // leaves the asked tree as it was
+    let applied = tree.apply_what_if(1, 35.0, &rows).unwrap();
+    let leaf_counts = |tree: &Node, path: &[bool]| match tree.subtree(path) {
+        Some(Node::Leaf(x)) => (x.get(&Income::High), x.get(&Income::Low)),
+        _ => panic!("not a leaf"),
+    };
+    assert_eq!(leaf_counts(&applied, &[true, true]), (5, 1));
+    assert_eq!(leaf_counts(&applied, &[true, false]), (0, 5));
+    assert_eq!(leaf_counts(&tree, &[true, true]), (3, 1));
+    assert!(matches!(
+        applied.subtree(&[true]),
+        Some(Node::Decision {
+            question: Question::age(35),
+            ..
+        })
+    ));
+    // only number nodes move: 0 asks an enum, 2 is a leaf, 99 isn't there
+    for bad in [0, 2, 99] {
+        assert!(tree.what_if_threshold(bad, 35.0, &rows).is_err());
+    }
+    assert!(tree.apply_what_if(1, f64::NAN, &rows).is_err());
+}
+
+#[cfg(feature = "persist")]
+#[test]
+fn trained_trees_save_their_profile() {
+    use decision_leaf::persist::LoadOptions;
+
+    let (train, _) = split();
+    let trained = build_trained(&train, &TreeConfig::default()).unwrap();
+    let mut saved = Vec::new();
+    trained.save(&mut saved).unwrap();
+    let reloaded = TrainedTree::load(&saved[..], &LoadOptions::new()).unwrap();
+    assert_eq!(reloaded.tree.fingerprint(), trained.tree.fingerprint());
+    assert_eq!(reloaded.profile.rows, 297);
+    assert_eq!(reloaded.profile.priors(), trained.profile.priors());
+    assert_eq!(reloaded.profile.observed(), trained.profile.observed());
+    // Node::load skips the profile
+    let node = Node::load(&saved[..], &LoadOptions::new()).unwrap();
+    assert_eq!(node.fingerprint(), trained.tree.fingerprint());
+
+    // a tree saved without one loads with the profile of its leaves
+    let mut plain = Vec::new();
+    trained.tree.save(&mut plain).unwrap();
+    let legacy = TrainedTree::load(&plain[..], &LoadOptions::new()).unwrap();
+    assert_eq!(legacy.profile.priors(), trained.profile.priors());
+    assert!(legacy.profile.observed().is_err());
+}
+
+#[cfg(feature = "csv")]
+mod csv {
+    use super::*;
+
+    fn config() -> LoaderConfig {
+        LoaderConfig::new()
+            .missing_markers(&["?"])
+            .bool_markers("married", &["yes"], &["no"])
+    }
+
+    #[test]
+    fn imputing_loaders_count_what_they_filled() {
+        let complete = load_csv_with(INCOME.as_bytes(), &mut config().skip_missing_rows()).unwrap();
+        assert_eq!(complete, rows());
+        let imputer = Imputer::fit(&complete).unwrap();
+        let mut imputing = config().impute(imputer.clone());
+        let data = load_csv_with(INCOME.as_bytes(), &mut imputing).unwrap();
+        assert_eq!(data.len(), 400);
+        assert_eq!(imputing.imputed(), [("hours_per_week", 4)]);
+        let filled: Vec<&DataPoint> = data.iter().filter(|p| !complete.contains(p)).collect();
+        assert!(filled
+            .iter()
+            .all(|p| p.hours_per_week == imputer.hours_per_week));
+        // without an imputer or skipping, a missing value is an error
+        assert!(load_csv_with(INCOME.as_bytes(), &mut config()).is_err());
+    }
+}
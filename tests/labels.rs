@@ -0,0 +1,116 @@
+//! Labels whose `Debug` form would break the formats they're written into:
+//! quotes, markup, `#`, `|`, backslashes and newlines, escaped once per
+//! target, and exported trees and region SVGs that carry them escaped.
+
+// the generated functions take &Vec<DataPoint>
+#![allow(clippy::ptr_arg)]
+
+use decision_leaf::classification_data_layout;
+use decision_leaf::labels::{escape, label, Target};
+use std::fmt;
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Tag {
+    Plain,
+    Odd,
+}
+
+const ODD: &str = "Odd \"<b>\" & #1 | a\\b\nc";
+
+impl fmt::Debug for Tag {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Tag::Plain => f.write_str("Plain"),
+            Tag::Odd => f.write_str(ODD),
+        }
+    }
+}
+
+classification_data_layout!(
+    enum_fields = {tag: Tag},
+    number_fields = {size: u32},
+    Tag
+);
+
+// the class is the tag, so the root asks about it
+fn data() -> Vec<DataPoint> {
+    (0..20)
+        .map(|i| {
+            let tag = if i % 2 == 0 { Tag::Plain } else { Tag::Odd };
+            DataPoint::new(tag, i, tag)
+        })
+        .collect()
+}
+
+#[test]
+fn each_target_escapes_its_own_characters() {
+    assert_eq!(escape(ODD, Target::Text), ODD);
+    assert_eq!(
+        escape(ODD, Target::Dot),
+        "Odd \\\"<b>\\\" & #1 | a\\\\b\\nc"
+    );
+    assert_eq!(
+        escape(ODD, Target::Xml),
+        "Odd &quot;&lt;b&gt;&quot; &amp; #1 | a\\b\nc"
+    );
+    assert_eq!(escape("it's", Target::Xml), "it&apos;s");
+    assert_eq!(escape("it's", Target::Html), "it&#39;s");
+    assert_eq!(
+        escape(ODD, Target::Mermaid),
+        "Odd #quot;#lt;b#gt;#quot; & #35;1 | a\\b<br/>c"
+    );
+    assert_eq!(
+        escape(ODD, Target::Json),
+        "Odd \\\"<b>\\\" & #1 | a\\\\b\\nc"
+    );
+    assert_eq!(escape("\t\u{1}", Target::Json), "\\t\\u0001");
+    assert_eq!(
+        escape(ODD, Target::Markdown),
+        "Odd \"<b>\" & #1 \\| a\\\\b<br>c"
+    );
+    assert_eq!(label(&Tag::Odd, Target::Xml), escape(ODD, Target::Xml));
+    assert_eq!(label("quoted", Target::Dot), "\\\"quoted\\\"");
+}
+
+#[test]
+fn printed_trees_keep_labels_as_they_are() {
+    let tree = build_tree(&data());
+    let mut out = Vec::new();
+    tree.write_tree(&mut out, &Default::default()).unwrap();
+    let text = String::from_utf8(out).unwrap();
+    assert!(text.contains(ODD), "{}", text);
+}
+
+#[cfg(feature = "export")]
+#[test]
+fn dot_labels_stay_inside_their_quotes() {
+    let dot = build_tree(&data()).to_dot();
+    assert!(dot.contains(&escape(ODD, Target::Dot)), "{}", dot);
+    assert!(!dot.contains(ODD), "{}", dot);
+    // every label opens and closes on one line
+    for line in dot.lines().filter(|line| line.contains("label=")) {
+        let unescaped_quotes = line.replace("\\\\", "").replace("\\\"", "");
+        assert_eq!(unescaped_quotes.matches('"').count() % 2, 0, "{}", line);
+    }
+}
+
+#[cfg(feature = "export")]
+#[test]
+fn region_svgs_escape_class_labels() {
+    let data = data();
+    let tree = build_tree(&data);
+    let fixed = DataPoint::new(Tag::Odd, 0, Tag::Odd);
+    let grid = decision_regions(
+        &tree,
+        Field::size,
+        Field::size,
+        (0., 20.),
+        (0., 20.),
+        4,
+        &fixed,
+    )
+    .unwrap();
+    let svg = decision_regions_svg(&grid, Field::size, Field::size, &data).unwrap();
+    assert!(svg.contains(&escape(ODD, Target::Xml)), "{}", svg);
+    assert!(!svg.contains("<b>"), "{}", svg);
+}
@@ -0,0 +1,154 @@
+//! Label noise on clean synthetic rows: flips at the asked rate, repeatable
+//! and nested as the rate grows, cross-validated accuracy that falls as the
+//! training labels get noisier, and an estimate that tells clean labels from
+//! noisy ones.
+
+#![cfg(feature = "metrics")]
+// the generated functions take &Vec<DataPoint>
+#![allow(clippy::ptr_arg)]
+
+use decision_leaf::rng::Rng;
+use decision_leaf::{classification_data_layout, DecisionLeafError};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Zone {
+    Low,
+    Mid,
+    High,
+}
+
+classification_data_layout!(
+    enum_fields = {},
+    number_fields = {x: f64, y: f64},
+    Zone
+);
+
+// the zone of x + y, without noise
+fn rows(n: usize, seed: u64) -> Vec<DataPoint> {
+    let mut rng = Rng::new(seed);
+    (0..n)
+        .map(|_| {
+            let (x, y) = (rng.next_f64(), rng.next_f64());
+            let class = match x + y {
+                s if s < 0.7 => Zone::Low,
+                s if s < 1.3 => Zone::Mid,
+                _ => Zone::High,
+            };
+            DataPoint::new(x, y, class)
+        })
+        .collect()
+}
+
+fn config() -> TreeConfig {
+    TreeConfig {
+        max_depth: Some(6),
+        min_samples_leaf: 10,
+        max_candidates: 32,
+        ..Default::default()
+    }
+}
+
+fn flipped(data: &[DataPoint], noisy: &[DataPoint]) -> Vec<usize> {
+    (0..data.len())
+        .filter(|&i| noisy[i].class != data[i].class)
+        .collect()
+}
+
+fn parameter(result: Result<Vec<DataPoint>, DecisionLeafError>) -> &'static str {
+    match result {
+        Err(DecisionLeafError::InvalidParameter { name, .. }) => name,
+        other => panic!("{:?}", other.map(|rows| rows.len())),
+    }
+}
+
+#[test]
+fn flips_hit_their_rate_and_repeat() {
+    let data = rows(2000, 9);
+    let noisy = inject_label_noise(&data, 0.2, 4).unwrap();
+    let rate = flipped(&data, &noisy).len() as f64 / data.len() as f64;
+    assert!((rate - 0.2).abs() < 0.02, "{}", rate);
+    // only the labels change
+    assert!(data
+        .iter()
+        .zip(&noisy)
+        .all(|(a, b)| (a.x, a.y) == (b.x, b.y)));
+    assert_eq!(inject_label_noise(&data, 0.2, 4).unwrap(), noisy);
+    assert_ne!(inject_label_noise(&data, 0.2, 5).unwrap(), noisy);
+    assert_eq!(inject_label_noise(&data, 0.0, 4).unwrap(), data);
+    assert_eq!(
+        flipped(&data, &inject_label_noise(&data, 1.0, 4).unwrap()).len(),
+        2000
+    );
+}
+
+#[test]
+fn higher_rates_keep_every_earlier_flip() {
+    let data = rows(1000, 10);
+    let noisy = inject_label_noise(&data, 0.2, 4).unwrap();
+    let noisier = inject_label_noise(&data, 0.3, 4).unwrap();
+    let earlier = flipped(&data, &noisy);
+    assert!(earlier.iter().all(|&i| noisier[i].class == noisy[i].class));
+    assert!(flipped(&data, &noisier).len() > earlier.len());
+}
+
+#[test]
+fn rates_are_probabilities_of_data_with_two_classes() {
+    let data = rows(100, 11);
+    assert_eq!(parameter(inject_label_noise(&data, 1.5, 4)), "rate");
+    assert_eq!(parameter(inject_label_noise(&data, -0.1, 4)), "rate");
+    assert_eq!(parameter(inject_label_noise(&data, f64::NAN, 4)), "rate");
+    assert_eq!(
+        parameter(inject_label_noise(&data[..1].to_vec(), 0.5, 4)),
+        "data"
+    );
+    assert_eq!(inject_label_noise(&Vec::new(), 0.5, 4).unwrap(), []);
+}
+
+#[test]
+fn accuracy_falls_as_the_rate_rises() {
+    let data = rows(2000, 9);
+    let rates = [0.0, 0.1, 0.2, 0.3, 0.4];
+    let curve = noise_sensitivity_curve(&data, &rates, 5, 2, &config()).unwrap();
+    assert_eq!(curve.iter().map(|p| p.rate).collect::<Vec<_>>(), rates);
+    assert_eq!(curve[0].flipped, 0);
+    // each row is a training row of 4 of the 5 folds
+    let expected = flipped(&data, &inject_label_noise(&data, 0.2, 2).unwrap()).len() * 4;
+    assert_eq!(curve[2].flipped, expected);
+    assert!(curve
+        .windows(2)
+        .all(|w| w[1].accuracy_mean <= w[0].accuracy_mean));
+    assert!(curve[0].accuracy_mean > 0.9);
+    assert_eq!(
+        noise_sensitivity_curve(&data, &rates, 5, 2, &config()).unwrap(),
+        curve
+    );
+    assert!(noise_sensitivity_curve(&data, &rates, 1, 2, &config()).is_err());
+}
+
+// confident out-of-fold disagreements: rare on clean labels, and mostly the
+// flipped rows once noise is injected
+#[test]
+fn estimates_tell_clean_labels_from_noisy_ones() {
+    let data = rows(2000, 9);
+    let noisy = inject_label_noise(&data, 0.2, 4).unwrap();
+    let flipped = flipped(&data, &noisy);
+    let clean = estimate_label_noise(&data, 5, 2, &config(), 0.7).unwrap();
+    let estimate = estimate_label_noise(&noisy, 5, 2, &config(), 0.7).unwrap();
+    assert_eq!((estimate.rows, estimate.confidence), (2000, 0.7));
+    assert!(clean.rate() < 0.05, "{}", clean.rate());
+    assert!(
+        estimate.rate() > 0.1 && estimate.rate() <= 0.22,
+        "{}",
+        estimate.rate()
+    );
+    assert!(estimate.suspects.windows(2).all(|w| w[0] < w[1]));
+    let caught = estimate
+        .suspects
+        .iter()
+        .filter(|i| flipped.binary_search(i).is_ok())
+        .count();
+    assert!(caught * 5 >= estimate.suspects.len() * 4);
+    // at 0.5 two classes could both be confident
+    assert!(estimate_label_noise(&data, 5, 2, &config(), 0.5).is_err());
+    assert!(estimate_label_noise(&data, 5, 2, &config(), 1.1).is_err());
+}
@@ -0,0 +1,254 @@
+//! save_tree and load_tree through a file: the reloaded tree is the same
+//! tree, and a file that doesn't match the layout is an error unless
+//! LoadOptions maps the renamed fields and values. With `csv`,
+//! the rows round-trip through write_csv too.
+
+#![cfg(feature = "persist")]
+// the generated functions take &Vec<DataPoint>
+#![allow(clippy::ptr_arg)]
+
+use decision_leaf::classification_data_layout;
+use decision_leaf::persist::{LoadOptions, PersistError};
+use decision_leaf::rng::Rng;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Grade {
+    Low,
+    Mid,
+    High,
+}
+
+impl FromStr for Grade {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, String> {
+        match s {
+            "Low" => Ok(Grade::Low),
+            "Mid" => Ok(Grade::Mid),
+            "High" => Ok(Grade::High),
+            _ => Err(format!("unknown grade {}", s)),
+        }
+    }
+}
+
+// a field of each kind persist writes differently
+classification_data_layout!(
+    enum_fields = {city: String, initial: char, code: [u8; 2]},
+    number_fields = {score: f64, age: Option<u32>},
+    Grade
+);
+
+// a layout with another field set, for files that don't match
+mod other {
+    use super::Grade;
+    decision_leaf::classification_data_layout!(
+        enum_fields = {city: String},
+        number_fields = {height: f32},
+        Grade
+    );
+}
+
+// `other` after renaming city to town and the Mid grade to Middle
+mod renamed {
+    use std::str::FromStr;
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    pub enum Level {
+        Low,
+        Middle,
+        High,
+    }
+
+    impl FromStr for Level {
+        type Err = String;
+        fn from_str(s: &str) -> Result<Self, String> {
+            match s {
+                "Low" => Ok(Level::Low),
+                "Middle" => Ok(Level::Middle),
+                "High" => Ok(Level::High),
+                _ => Err(format!("unknown level {}", s)),
+            }
+        }
+    }
+
+    decision_leaf::classification_data_layout!(
+        enum_fields = {town: String},
+        number_fields = {height: f32},
+        Level
+    );
+
+    // and with height widened to f64
+    pub mod widened {
+        use super::Level;
+        decision_leaf::classification_data_layout!(
+            enum_fields = {town: String},
+            number_fields = {height: f64},
+            Level
+        );
+    }
+}
+
+fn path(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join(name)
+}
+
+fn random_rows(seed: u64, rows: usize) -> Vec<DataPoint> {
+    let mut rng = Rng::new(seed);
+    let cities = ["Oslo", "São Paulo", "a \"quoted\" city", "tab\there"];
+    (0..rows)
+        .map(|_| {
+            let city = cities[rng.gen_index(cities.len())].to_owned();
+            let initial = ['x', '\'', ',', 'é'][rng.gen_index(4)];
+            let code = [rng.gen_index(3) as u8, 7];
+            let score = (rng.next_f64() * 200. - 100.).round() / 8.;
+            let age = (rng.gen_index(5) > 0).then(|| 18 + rng.gen_index(60) as u32);
+            let grade = match (score > 0., age.is_some_and(|a| a > 40), code[0]) {
+                (true, true, _) => Grade::High,
+                (true, false, 0) | (false, true, _) => Grade::Mid,
+                _ => [Grade::Low, Grade::Mid][rng.gen_index(2)],
+            };
+            DataPoint::new(city, initial, code, score, age, grade)
+        })
+        .collect()
+}
+
+#[test]
+fn round_trip_through_a_file() {
+    let data = random_rows(254, 300);
+    let tree = build_tree(&data);
+    let file = path("round_trip.tree");
+    save_tree(&tree, &file).unwrap();
+    let loaded = load_tree(&file).unwrap();
+
+    assert_eq!(loaded.fingerprint(), tree.fingerprint());
+    for point in data.iter().chain(&random_rows(255, 100)) {
+        assert_eq!(classify(point, &loaded), classify(point, &tree));
+    }
+    // and saves back to the same bytes
+    let resaved = path("round_trip_again.tree");
+    save_tree(&loaded, &resaved).unwrap();
+    assert_eq!(
+        std::fs::read(&file).unwrap(),
+        std::fs::read(&resaved).unwrap()
+    );
+}
+
+#[test]
+fn another_layouts_tree_is_an_error() {
+    let data = vec![
+        other::DataPoint::new("Oslo".to_owned(), 1.5, Grade::Low),
+        other::DataPoint::new("Lima".to_owned(), 2.5, Grade::High),
+    ];
+    let file = path("other_layout.tree");
+    other::save_tree(&other::build_tree(&data), &file).unwrap();
+    assert!(matches!(load_tree(&file), Err(PersistError::Schema(_))));
+}
+
+// a tree saved under `other`'s names: Lima is Mid, and Oslo splits on height
+fn old_tree() -> Vec<u8> {
+    let data: Vec<other::DataPoint> = (0..12)
+        .map(|i| match i % 4 {
+            0 => other::DataPoint::new("Oslo".to_owned(), 1.0, Grade::Low),
+            1 => other::DataPoint::new("Oslo".to_owned(), 3.0, Grade::High),
+            _ => other::DataPoint::new("Lima".to_owned(), 1.0 + i as f32 / 6., Grade::Mid),
+        })
+        .collect();
+    let mut saved = Vec::new();
+    other::build_tree(&data).save(&mut saved).unwrap();
+    saved
+}
+
+#[test]
+fn renamed_fields_and_values_load_with_options() {
+    let saved = old_tree();
+    let options = LoadOptions::new()
+        .rename_field("city", "town")
+        .map_enum_value("class", "Mid", "Middle");
+    let tree = renamed::Node::load(&saved[..], &options).unwrap();
+    let ask = |town: &str, height| {
+        renamed::predict(
+            &renamed::DataPoint::new(town.to_owned(), height, renamed::Level::Low),
+            &tree,
+        )
+    };
+    assert_eq!(ask("Lima", 1.0), renamed::Level::Middle);
+    assert_eq!(ask("Oslo", 1.0), renamed::Level::Low);
+    assert_eq!(ask("Oslo", 3.0), renamed::Level::High);
+
+    // string values are saved in their quoted Debug form
+    let options = options.map_enum_value("town", "\"Oslo\"", "\"Kristiania\"");
+    let tree = renamed::Node::load(&saved[..], &options).unwrap();
+    let resaved = {
+        let mut out = Vec::new();
+        tree.save(&mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    };
+    assert!(
+        resaved.contains("split\ttown\t\"Kristiania\""),
+        "{}",
+        resaved
+    );
+}
+
+#[test]
+fn unmapped_names_and_changed_types_list_their_nodes() {
+    let saved = old_tree();
+    // without the shim every node naming city, and every leaf with Mid, fails
+    let problems = match renamed::Node::load(&saved[..], &LoadOptions::new()) {
+        Err(PersistError::Schema(problems)) => problems,
+        other => panic!("expected a schema error, got {:?}", other.err()),
+    };
+    assert_eq!(problems[0].node, 0);
+    assert_eq!(problems[0].message, "field `city` is not in the layout");
+    assert!(problems
+        .iter()
+        .any(|p| p.message == "class \"Mid\" is not a Level"));
+
+    // renaming doesn't cover a type change
+    let options = LoadOptions::new()
+        .rename_field("city", "town")
+        .map_enum_value("class", "Mid", "Middle");
+    let problems = match renamed::widened::Node::load(&saved[..], &options) {
+        Err(PersistError::Schema(problems)) => problems,
+        other => panic!("expected a schema error, got {:?}", other.err()),
+    };
+    assert_eq!(problems.len(), 1);
+    assert_eq!(
+        problems[0].message,
+        "field `height` was number f32, is number f64"
+    );
+}
+
+#[test]
+fn truncated_and_missing_files_are_errors() {
+    let tree = build_tree(&random_rows(256, 80));
+    let file = path("truncated.tree");
+    save_tree(&tree, &file).unwrap();
+    let text = std::fs::read_to_string(&file).unwrap();
+    let cut = text.lines().count() - 1;
+    std::fs::write(&file, text.lines().take(cut).collect::<Vec<_>>().join("\n")).unwrap();
+    assert!(matches!(load_tree(&file), Err(PersistError::Format { .. })));
+
+    std::fs::write(&file, "not a tree\n").unwrap();
+    assert!(matches!(
+        load_tree(&file),
+        Err(PersistError::Format { line: 1, .. })
+    ));
+
+    assert!(matches!(
+        load_tree(&path("no_such.tree")),
+        Err(PersistError::Io(_))
+    ));
+}
+// the training data goes along with the tree as CSV; strings are written in
+// their quoted Debug form and must come back without the quotes
+#[cfg(feature = "csv")]
+#[test]
+fn data_round_trips_through_write_csv() {
+    let data = random_rows(9, 50);
+    let mut out = Vec::new();
+    write_csv(&data, &mut out).unwrap();
+    let back = load_csv_with(&out[..], &mut LoaderConfig::new()).unwrap();
+    assert_eq!(back, data);
+}
@@ -0,0 +1,197 @@
+//! A Pipeline of imputation and rebalancing in front of a tree, on the
+//! income rows with a derived field: the steps fit on the raw rows, serving
+//! replays the imputation and derives the field as training did, and with
+//! `persist` the pipeline saves as one artifact.
+
+// the generated functions take &Vec<DataPoint>
+#![allow(clippy::ptr_arg)]
+
+use decision_leaf::pipeline::{Stage, StepSpec};
+use decision_leaf::{classification_data_layout, ClassCounts, DecisionLeafError};
+use std::str::FromStr;
+
+// FromStr by variant name, as the fixture writes them
+macro_rules! labeled_enum {
+    ($name:ident { $($variant:ident),* }) => {
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+        pub enum $name {
+            $($variant),*
+        }
+
+        impl FromStr for $name {
+            type Err = String;
+            fn from_str(s: &str) -> Result<Self, String> {
+                match s {
+                    $(stringify!($variant) => Ok($name::$variant),)*
+                    _ => Err(format!("unknown {} {}", stringify!($name), s)),
+                }
+            }
+        }
+    };
+}
+
+labeled_enum!(Education {
+    HighSchool,
+    Bachelors,
+    Masters,
+    Doctorate
+});
+labeled_enum!(Occupation {
+    Clerical,
+    Sales,
+    Craft,
+    Professional,
+    Managerial,
+    Service
+});
+labeled_enum!(Income { Low, High });
+
+classification_data_layout!(
+    enum_fields = {education: Education, occupation: Occupation, married: bool},
+    number_fields = {age: u32, hours_per_week: u32},
+    derived_fields = {hours_per_decade: u32 = |p| p.hours_per_week * 100 / p.age.max(1)},
+    Income
+);
+
+const INCOME: &str = include_str!("../examples/data/income.csv");
+
+// the raw rows, `?` hours left missing
+fn raw_rows() -> Vec<PartialPoint> {
+    INCOME
+        .lines()
+        .skip(1)
+        .map(|line| {
+            let cells: Vec<&str> = line.split(',').collect();
+            PartialPoint {
+                age: cells[0].parse().ok(),
+                education: cells[1].parse().ok(),
+                occupation: cells[2].parse().ok(),
+                married: Some(cells[3] == "yes"),
+                hours_per_week: cells[4].parse().ok(),
+                class: cells[5].parse().unwrap(),
+            }
+        })
+        .collect()
+}
+
+fn complete(rows: &[PartialPoint]) -> Vec<DataPoint> {
+    rows.iter()
+        .cloned()
+        .filter_map(PartialPoint::into_point)
+        .collect()
+}
+
+fn config() -> TreeConfig {
+    TreeConfig {
+        max_depth: Some(5),
+        min_samples_leaf: 3,
+        ..TreeConfig::default()
+    }
+}
+
+const STEPS: [StepSpec; 2] = [StepSpec::Impute, StepSpec::Rebalance { seed: 3 }];
+
+fn fitted() -> Pipeline {
+    Pipeline::fit(raw_rows(), &STEPS, &config()).unwrap()
+}
+
+#[test]
+fn steps_fit_on_the_rows_reaching_them() {
+    let rows = raw_rows();
+    assert_eq!(
+        rows.iter().filter(|r| r.hours_per_week.is_none()).count(),
+        4
+    );
+    assert_eq!(STEPS[0].stage(), Stage::TrainAndServe);
+    assert_eq!(STEPS[1].stage(), Stage::Train);
+    let pipeline = fitted();
+    let FittedStep::Impute(imputer) = &pipeline.steps[0] else {
+        panic!("imputation comes first");
+    };
+    assert_eq!(*imputer, Imputer::fit(&complete(&rows)).unwrap());
+    // rebalancing ran in training only: the tree saw both classes equally
+    // often, and more rows than there are
+    let counts = pipeline
+        .tree
+        .paths()
+        .fold(ClassCounts::<Income>::new(), |mut total, path| {
+            total.merge(path.distribution);
+            total
+        });
+    assert_eq!(counts.get(&Income::Low), counts.get(&Income::High));
+    assert!(counts.total() > rows.len() as u64);
+}
+
+#[test]
+fn serving_prepares_points_as_training_did() {
+    let pipeline = fitted();
+    let FittedStep::Impute(imputer) = &pipeline.steps[0] else {
+        panic!("imputation comes first");
+    };
+    let rows = raw_rows();
+    let raw = rows
+        .iter()
+        .find(|r| r.hours_per_week.is_none())
+        .unwrap()
+        .clone();
+    let prepared = pipeline.prepare(raw.clone()).unwrap();
+    assert_eq!(prepared.hours_per_week, imputer.hours_per_week);
+    assert_eq!(
+        prepared.hours_per_decade,
+        imputer.hours_per_week * 100 / prepared.age
+    );
+    assert_eq!(
+        pipeline.predict(raw).unwrap(),
+        predict(&prepared, &pipeline.tree)
+    );
+    // complete points pass through unchanged
+    for row in &rows {
+        if let Some(point) = row.clone().into_point() {
+            assert_eq!(pipeline.prepare(row.clone()).unwrap(), point);
+        }
+    }
+}
+
+#[test]
+fn missing_values_need_an_imputer() {
+    let rows = raw_rows();
+    assert!(matches!(
+        Pipeline::fit(rows.clone(), &STEPS[1..], &config()),
+        Err(DecisionLeafError::Unavailable { name: "rows", .. })
+    ));
+    let plain = Pipeline::fit(
+        complete(&rows).iter().map(PartialPoint::from).collect(),
+        &[],
+        &config(),
+    )
+    .unwrap();
+    let raw = rows.iter().find(|r| r.hours_per_week.is_none()).unwrap();
+    assert!(matches!(
+        plain.prepare(raw.clone()),
+        Err(DecisionLeafError::Unavailable { name: "point", .. })
+    ));
+}
+
+#[cfg(feature = "persist")]
+#[test]
+fn saved_pipelines_predict_the_same() {
+    use decision_leaf::persist::LoadOptions;
+
+    let pipeline = fitted();
+    let mut saved = Vec::new();
+    pipeline.save(&mut saved).unwrap();
+    let reloaded = Pipeline::load(&saved[..], &LoadOptions::new()).unwrap();
+    assert_eq!(reloaded.steps, pipeline.steps);
+    assert_eq!(reloaded.tree.fingerprint(), pipeline.tree.fingerprint());
+    for row in raw_rows() {
+        assert_eq!(
+            reloaded.predict(row.clone()).unwrap(),
+            pipeline.predict(row).unwrap()
+        );
+    }
+    let mut resaved = Vec::new();
+    reloaded.save(&mut resaved).unwrap();
+    assert_eq!(resaved, saved);
+    let truncated = &saved[..saved.len() / 2];
+    assert!(Pipeline::load(truncated, &LoadOptions::new()).is_err());
+}
@@ -0,0 +1,111 @@
+//! Seeded builds grow the same tree every time: twice from one seed they
+//! match node for node, and another seed draws other fields. Weighted
+//! counts are summed exactly enough that large and small weights don't
+//! reorder splits.
+
+// the generated functions take &Vec<DataPoint>
+#![allow(clippy::ptr_arg)]
+
+use decision_leaf::rng::Rng;
+use decision_leaf::{classification_data_layout, ClassCounts};
+
+classification_data_layout!(
+    enum_fields = {shade: u8, shiny: bool},
+    number_fields = {weight: f64, count: u32, length: f32},
+    u8
+);
+
+// every field says something about the class, so the fields a seed draws
+// decide the splits
+fn data() -> Vec<DataPoint> {
+    let mut rng = Rng::new(226);
+    (0..600)
+        .map(|_| {
+            let shade = rng.gen_index(6) as u8;
+            let shiny = rng.gen_index(2) == 0;
+            let weight = (rng.next_f64() * 400.).round() / 4.;
+            let count = rng.gen_index(30) as u32;
+            let length = rng.normal() as f32;
+            let votes = (shade > 2) as usize
+                + shiny as usize
+                + (weight > 50.) as usize
+                + (count > 15) as usize
+                + (length > 0.) as usize;
+            let class = if rng.gen_index(8) == 0 {
+                rng.gen_index(3)
+            } else {
+                votes % 3
+            };
+            DataPoint::new(shade, shiny, weight, count, length, class as u8)
+        })
+        .collect()
+}
+
+fn seeded(feature_seed: u64) -> TreeConfig {
+    TreeConfig {
+        max_features: Some(2),
+        feature_seed,
+        ..TreeConfig::default()
+    }
+}
+
+#[test]
+fn one_seed_grows_one_tree() {
+    let data = data();
+    for seed in [0, 1, 226, u64::MAX] {
+        let first = build_tree_with(&data, &seeded(seed)).unwrap();
+        let second = build_tree_with(&data, &seeded(seed)).unwrap();
+        assert_eq!(first.fingerprint(), second.fingerprint(), "seed {}", seed);
+        assert_eq!(first.node_count(), second.node_count(), "seed {}", seed);
+    }
+}
+
+#[test]
+fn other_seeds_grow_other_trees() {
+    let data = data();
+    let first = build_tree_with(&data, &seeded(1)).unwrap().fingerprint();
+    let others = (2..10)
+        .filter(|&seed| build_tree_with(&data, &seeded(seed)).unwrap().fingerprint() != first)
+        .count();
+    assert!(others > 0);
+    // without max_features every field is searched and the seed is unused
+    let all_fields = |feature_seed| TreeConfig {
+        feature_seed,
+        ..TreeConfig::default()
+    };
+    assert_eq!(
+        build_tree_with(&data, &all_fields(1))
+            .unwrap()
+            .fingerprint(),
+        build_tree_with(&data, &all_fields(2))
+            .unwrap()
+            .fingerprint()
+    );
+}
+
+#[cfg(feature = "ensemble")]
+#[test]
+fn one_seed_grows_one_forest() {
+    let data = data();
+    let config = seeded(0);
+    let fingerprints = |seed| -> Vec<u64> {
+        build_forest(&data, 5, seed, &config)
+            .unwrap()
+            .trees
+            .iter()
+            .map(|tree| tree.fingerprint())
+            .collect()
+    };
+    assert_eq!(fingerprints(226), fingerprints(226));
+    assert_ne!(fingerprints(226), fingerprints(227));
+}
+
+#[test]
+fn weighted_totals_keep_small_weights() {
+    let mut counts: ClassCounts<u8, f64> = ClassCounts::new();
+    counts.add(0, 1e16);
+    for class in 1..=10 {
+        counts.add(class, 1.0);
+    }
+    assert_eq!(counts.total_f64(), 1e16 + 10.0);
+}
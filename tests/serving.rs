@@ -0,0 +1,99 @@
+//! BatchPredictor and the channel server against classify: batches come back
+//! full and in arrival order, threaded or not, and a served tree answers
+//! every client until the last one is dropped.
+
+#![cfg(feature = "serving")]
+// the generated functions take &Vec<DataPoint>
+#![allow(clippy::ptr_arg)]
+
+use decision_leaf::rng::Rng;
+use decision_leaf::serving::{spawn, BatchPredictor, Model};
+use decision_leaf::{classification_data_layout, ClassCounts};
+use std::sync::Arc;
+use std::thread;
+
+classification_data_layout!(
+    enum_fields = {red: bool},
+    number_fields = {size: f64},
+    u8
+);
+
+fn rows(seed: u64, rows: usize) -> Vec<DataPoint> {
+    let mut rng = Rng::new(seed);
+    (0..rows)
+        .map(|_| {
+            let red = rng.gen_index(2) == 0;
+            let size = (rng.next_f64() * 100.).round();
+            let class = (red as u8) + (size > 40.) as u8 + (size > 80.) as u8;
+            DataPoint::new(red, size, class)
+        })
+        .collect()
+}
+
+fn expected(points: &[DataPoint], tree: &Node) -> Vec<ClassCounts<u8>> {
+    points.iter().map(|p| classify(p, tree).clone()).collect()
+}
+
+#[test]
+fn batches_keep_arrival_order() {
+    let tree = Arc::new(build_tree(&rows(205, 300)));
+    let points = rows(206, 101);
+    for threads in [1, 3, 8] {
+        let predictor = BatchPredictor::new(tree.clone(), 16).with_threads(threads);
+        assert_eq!(
+            predictor.predict_batch(&points),
+            expected(&points, &tree),
+            "{} threads",
+            threads
+        );
+    }
+}
+
+#[test]
+fn push_returns_full_batches_and_flush_the_rest() {
+    let tree = Arc::new(build_tree(&rows(205, 300)));
+    let points = rows(207, 10);
+    let mut predictor = BatchPredictor::new(tree.clone(), 4);
+    let mut out = Vec::new();
+    for (i, point) in points.iter().enumerate() {
+        match predictor.push(point.clone()) {
+            Some(batch) => {
+                assert_eq!(i % 4, 3);
+                assert_eq!(batch.len(), 4);
+                out.extend(batch);
+            }
+            None => assert_ne!(i % 4, 3),
+        }
+    }
+    let rest = predictor.flush();
+    assert_eq!(rest.len(), 2);
+    out.extend(rest);
+    assert_eq!(out, expected(&points, &tree));
+    assert!(predictor.flush().is_empty());
+    // a batch size of 0 is taken as 1
+    let mut single = BatchPredictor::new(tree, 0);
+    assert_eq!(single.push(points[0].clone()).map(|b| b.len()), Some(1));
+}
+
+#[test]
+fn served_trees_answer_every_client() {
+    let tree = Arc::new(build_tree(&rows(205, 300)));
+    let (client, server) = spawn(tree.clone(), 8);
+    let producers: Vec<_> = (0..4)
+        .map(|seed| {
+            let client = client.clone();
+            let tree = tree.clone();
+            thread::spawn(move || {
+                for point in rows(seed, 50) {
+                    assert_eq!(client.predict(point.clone()), Some(tree.predict(&point)));
+                }
+            })
+        })
+        .collect();
+    for producer in producers {
+        producer.join().unwrap();
+    }
+    // the server stops once the last client is gone
+    drop(client);
+    server.join().unwrap();
+}
@@ -0,0 +1,76 @@
+//! The generated types are Send + Sync: one trained tree behind an Arc, or
+//! a forest borrowed by scoped threads, answers threads classifying at the
+//! same time, each getting what a single thread gets.
+
+// the generated functions take &Vec<DataPoint>
+#![allow(clippy::ptr_arg)]
+
+use decision_leaf::classification_data_layout;
+use decision_leaf::rng::Rng;
+use std::sync::Arc;
+use std::thread;
+
+classification_data_layout!(
+    enum_fields = {colour: u8},
+    number_fields = {length: f64},
+    bool
+);
+
+fn rows(seed: u64, n: usize) -> Vec<DataPoint> {
+    let mut rng = Rng::new(seed);
+    (0..n)
+        .map(|_| {
+            let colour = rng.gen_index(4) as u8;
+            let length = rng.next_f64() * 10.;
+            let long = length > 4. + colour as f64;
+            DataPoint::new(colour, length, long != (rng.gen_index(10) == 0))
+        })
+        .collect()
+}
+
+#[test]
+fn threads_share_one_tree() {
+    let tree = Arc::new(build_tree(&rows(236, 400)));
+    let points = Arc::new(rows(237, 800));
+    let expected: Vec<bool> = points.iter().map(|p| predict(p, &tree)).collect();
+    let handles: Vec<_> = (0..4)
+        .map(|t| {
+            let tree = Arc::clone(&tree);
+            let points = Arc::clone(&points);
+            thread::spawn(move || {
+                points
+                    .iter()
+                    .skip(t)
+                    .step_by(4)
+                    .map(|p| predict(p, &tree))
+                    .collect::<Vec<_>>()
+            })
+        })
+        .collect();
+    for (t, handle) in handles.into_iter().enumerate() {
+        let answers = handle.join().unwrap();
+        let own: Vec<bool> = expected.iter().skip(t).step_by(4).copied().collect();
+        assert_eq!(answers, own, "thread {}", t);
+    }
+}
+
+#[cfg(feature = "ensemble")]
+#[test]
+fn threads_share_one_forest() {
+    let forest = build_forest(&rows(238, 300), 8, 1, &TreeConfig::default()).unwrap();
+    let points = rows(239, 200);
+    let expected: Vec<Option<bool>> = points.iter().map(|p| forest.predict(p)).collect();
+    let answers: Vec<Option<bool>> = thread::scope(|scope| {
+        let handles: Vec<_> = points
+            .chunks(50)
+            .map(|chunk| {
+                scope.spawn(|| chunk.iter().map(|p| forest.predict(p)).collect::<Vec<_>>())
+            })
+            .collect();
+        handles
+            .into_iter()
+            .flat_map(|handle| handle.join().unwrap())
+            .collect()
+    });
+    assert_eq!(answers, expected);
+}
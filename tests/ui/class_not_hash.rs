@@ -0,0 +1,7 @@
+use decision_leaf::classification_data_layout;
+
+classification_data_layout!(
+    enum_fields = {color: bool},
+    number_fields = {age: u32},
+    f32
+);
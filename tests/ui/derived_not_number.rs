@@ -0,0 +1,8 @@
+use decision_leaf::classification_data_layout;
+
+classification_data_layout!(
+    enum_fields = {color: bool},
+    number_fields = {age: u32},
+    derived_fields = {label: String = |p| p.age.to_string()},
+    bool
+);
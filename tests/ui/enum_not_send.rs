@@ -0,0 +1,8 @@
+use decision_leaf::classification_data_layout;
+use std::rc::Rc;
+
+classification_data_layout!(
+    enum_fields = {name: Rc<str>},
+    number_fields = {age: u32},
+    bool
+);
@@ -0,0 +1,92 @@
+//! classify_with_uncertainty's intervals against the Beta quantiles they
+//! should be: a leaf with three `true` rows of two classes has the
+//! Dirichlet(4, 1) posterior, whose marginals are Beta(4, 1) and Beta(1, 4)
+//! with quantiles in closed form.
+
+// the generated functions take &Vec<DataPoint>
+#![allow(clippy::ptr_arg)]
+
+use decision_leaf::classification_data_layout;
+
+classification_data_layout!(
+    enum_fields = {red: bool},
+    number_fields = {size: u32},
+    bool
+);
+
+fn tree() -> Node {
+    let data = vec![
+        DataPoint::new(true, 5, true),
+        DataPoint::new(true, 6, true),
+        DataPoint::new(true, 7, true),
+        DataPoint::new(false, 5, false),
+    ];
+    build_tree(&data)
+}
+
+fn close(actual: f32, expected: f64) {
+    assert!(
+        (actual as f64 - expected).abs() < 1e-5,
+        "{} isn't {}",
+        actual,
+        expected
+    );
+}
+
+#[test]
+fn intervals_are_beta_marginal_quantiles() {
+    let tree = tree();
+    let point = DataPoint::new(true, 6, true);
+    let intervals = classify_with_uncertainty(&point, &tree, 0.95).unwrap();
+    assert_eq!(intervals.len(), 2);
+
+    let (mean, lower, upper) = intervals[&true];
+    close(mean, 0.8);
+    close(lower, 0.025f64.powf(0.25));
+    close(upper, 0.975f64.powf(0.25));
+
+    let (mean, lower, upper) = intervals[&false];
+    close(mean, 0.2);
+    close(lower, 1.0 - 0.975f64.powf(0.25));
+    close(upper, 1.0 - 0.025f64.powf(0.25));
+}
+
+#[test]
+fn monte_carlo_intervals_approach_the_exact_ones() {
+    let tree = tree();
+    let point = DataPoint::new(true, 6, true);
+    let exact = classify_with_uncertainty(&point, &tree, 0.9).unwrap();
+    let sampled = classify_with_uncertainty_mc(&point, &tree, 0.9, 20_000, 7).unwrap();
+    for (label, (mean, lower, upper)) in exact {
+        let (mc_mean, mc_lower, mc_upper) = sampled[&label];
+        assert!(
+            (mean - mc_mean).abs() < 0.01,
+            "{}: {} vs {}",
+            label,
+            mean,
+            mc_mean
+        );
+        assert!(
+            (lower - mc_lower).abs() < 0.02,
+            "{}: {} vs {}",
+            label,
+            lower,
+            mc_lower
+        );
+        assert!(
+            (upper - mc_upper).abs() < 0.02,
+            "{}: {} vs {}",
+            label,
+            upper,
+            mc_upper
+        );
+    }
+}
+
+#[test]
+fn level_must_be_inside_the_unit_interval() {
+    let tree = tree();
+    let point = DataPoint::new(true, 6, true);
+    assert!(classify_with_uncertainty(&point, &tree, 0.0).is_err());
+    assert!(classify_with_uncertainty(&point, &tree, 1.0).is_err());
+}
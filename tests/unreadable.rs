@@ -0,0 +1,72 @@
+//! A field type without FromStr can be trained on, but its values can't be
+//! read back from text: loading fails up front naming the field, unless a
+//! CSV column has an adapter.
+
+#![cfg(any(feature = "csv", feature = "persist"))]
+// the generated functions take &Vec<DataPoint>
+#![allow(clippy::ptr_arg)]
+
+use decision_leaf::classification_data_layout;
+
+// no FromStr
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Shade {
+    Light,
+    Dark,
+}
+
+classification_data_layout!(
+    enum_fields = {shade: Shade},
+    number_fields = {size: u32},
+    bool
+);
+
+#[cfg(feature = "csv")]
+#[test]
+fn csv_column_needs_an_adapter() {
+    use decision_leaf::load::LoadError;
+    let csv = "shade,size,class\nLight,3,true\nDark,4,false\n";
+    match load_csv_with(csv.as_bytes(), &mut LoaderConfig::new()) {
+        Err(LoadError::Unreadable { column, type_name }) => {
+            assert_eq!((column.as_str(), type_name.as_str()), ("shade", "Shade"));
+        }
+        other => panic!("expected an unreadable column, got {:?}", other),
+    }
+
+    let mut config =
+        LoaderConfig::new().map_enum("shade", &[("Light", Shade::Light), ("Dark", Shade::Dark)]);
+    let data = load_csv_with(csv.as_bytes(), &mut config).unwrap();
+    assert_eq!(
+        data,
+        vec![
+            DataPoint::new(Shade::Light, 3, true),
+            DataPoint::new(Shade::Dark, 4, false)
+        ]
+    );
+
+    let mut config = LoaderConfig::new().custom("shade", |text| match text {
+        "Light" => Ok(FieldValue::shade(Shade::Light)),
+        "Dark" => Ok(FieldValue::shade(Shade::Dark)),
+        _ => Err(format!("unknown shade {}", text)),
+    });
+    assert_eq!(load_csv_with(csv.as_bytes(), &mut config).unwrap(), data);
+}
+
+#[cfg(feature = "persist")]
+#[test]
+fn saved_tree_doesnt_load() {
+    use decision_leaf::persist::{LoadOptions, PersistError};
+    let data = vec![
+        DataPoint::new(Shade::Light, 3, true),
+        DataPoint::new(Shade::Dark, 4, false),
+    ];
+    let mut saved = Vec::new();
+    build_tree(&data).save(&mut saved).unwrap();
+    match Node::load(&saved[..], &LoadOptions::new()) {
+        Err(PersistError::Unreadable { field, type_name }) => {
+            assert_eq!((field.as_str(), type_name.as_str()), ("shade", "Shade"));
+        }
+        Err(other) => panic!("expected an unreadable field, got {}", other),
+        Ok(_) => panic!("a tree over Shade loaded"),
+    }
+}
@@ -0,0 +1,164 @@
+//! Knobs out of range fail up front with an error naming them and the value
+//! given, before any work: every TreeConfig check, and the arguments of
+//! memorization_report and decision_regions.
+
+// the generated functions take &Vec<DataPoint>
+#![allow(clippy::ptr_arg)]
+
+use decision_leaf::{classification_data_layout, DecisionLeafError};
+
+classification_data_layout!(
+    enum_fields = {shiny: bool},
+    number_fields = {weight: f64, count: u32},
+    u8
+);
+
+fn data() -> Vec<DataPoint> {
+    (0..40)
+        .map(|i| DataPoint::new(i % 3 == 0, i as f64 / 2., i % 7, (i > 20) as u8))
+        .collect()
+}
+
+// the name an InvalidParameter error gives
+fn invalid(err: DecisionLeafError) -> &'static str {
+    match err {
+        DecisionLeafError::InvalidParameter { name, .. } => name,
+        other => panic!("{} isn't an InvalidParameter", other),
+    }
+}
+
+#[test]
+fn each_config_value_is_checked() {
+    let data = data();
+    let cases: Vec<(&str, TreeConfig)> = vec![
+        (
+            "min_samples_leaf",
+            TreeConfig {
+                min_samples_leaf: 0,
+                ..TreeConfig::default()
+            },
+        ),
+        (
+            "min_side_fraction",
+            TreeConfig {
+                min_side_fraction: 0.6,
+                ..TreeConfig::default()
+            },
+        ),
+        (
+            "max_candidates",
+            TreeConfig {
+                max_candidates: 1,
+                ..TreeConfig::default()
+            },
+        ),
+        (
+            "min_samples_split",
+            TreeConfig {
+                min_samples_split: 1,
+                ..TreeConfig::default()
+            },
+        ),
+        (
+            "min_gain",
+            TreeConfig {
+                min_gain: f32::NAN,
+                ..TreeConfig::default()
+            },
+        ),
+        (
+            "forced_fields",
+            TreeConfig {
+                forced_fields: vec![(Field::weight, 0)],
+                ..TreeConfig::default()
+            },
+        ),
+        (
+            "max_features",
+            TreeConfig {
+                max_features: Some(0),
+                ..TreeConfig::default()
+            },
+        ),
+        (
+            "fields",
+            TreeConfig {
+                fields: Some(Vec::new()),
+                ..TreeConfig::default()
+            },
+        ),
+    ];
+    for (name, config) in cases {
+        assert_eq!(invalid(config.validate().unwrap_err()), name);
+        assert_eq!(
+            invalid(build_tree_with(&data, &config).err().unwrap()),
+            name
+        );
+    }
+    assert_eq!(TreeConfig::default().validate(), Ok(()));
+}
+
+#[test]
+fn errors_name_the_value_given() {
+    let config = TreeConfig {
+        min_side_fraction: -0.25,
+        ..TreeConfig::default()
+    };
+    assert_eq!(
+        config.validate().unwrap_err().to_string(),
+        "invalid `min_side_fraction`: got -0.25, expected a fraction between 0 and 0.5"
+    );
+}
+
+#[cfg(feature = "metrics")]
+#[test]
+fn memorization_reports_check_their_arguments() {
+    let data = data();
+    let tree = build_tree(&data);
+    let err = memorization_report(&data, &tree, 1.5, 10, 0).unwrap_err();
+    assert_eq!(invalid(err), "distinct_fraction");
+    let err = memorization_report(&data, &tree, 0.5, 0, 0).unwrap_err();
+    assert_eq!(invalid(err), "permutations");
+    assert!(memorization_report(&data, &tree, 0.5, 10, 0).is_ok());
+}
+
+#[cfg(feature = "export")]
+#[test]
+fn decision_regions_need_number_fields_and_ranges() {
+    let data = data();
+    let tree = build_tree(&data);
+    let fixed = data[0].clone();
+    let regions = |x: Field, x_range: (f64, f64), resolution: usize| {
+        decision_regions(
+            &tree,
+            x,
+            Field::count,
+            x_range,
+            (0., 6.),
+            resolution,
+            &fixed,
+        )
+    };
+    assert_eq!(
+        regions(Field::shiny, (0., 20.), 5),
+        Err(DecisionLeafError::InvalidField {
+            name: "field_x",
+            field: "shiny".to_owned(),
+            expected: "a number field",
+        })
+    );
+    assert_eq!(
+        invalid(regions(Field::weight, (20., 0.), 5).unwrap_err()),
+        "x_range"
+    );
+    assert_eq!(
+        invalid(regions(Field::weight, (0., f64::INFINITY), 5).unwrap_err()),
+        "x_range"
+    );
+    assert_eq!(
+        invalid(regions(Field::weight, (0., 20.), 0).unwrap_err()),
+        "resolution"
+    );
+    let grid = regions(Field::weight, (0., 20.), 5).unwrap();
+    assert!(decision_regions_svg(&grid, Field::weight, Field::shiny, &data).is_err());
+}
@@ -0,0 +1,91 @@
+//! A class enum far wider than any tree it trains: leaves store only the
+//! classes that reach them, and evaluation reports collapse rare classes and
+//! long confusion rows for display while the metrics keep every cell.
+
+// the generated functions take &Vec<DataPoint>
+#![allow(clippy::ptr_arg)]
+
+use decision_leaf::classification_data_layout;
+
+classification_data_layout!(
+    enum_fields = {},
+    number_fields = {code: u32},
+    u16
+);
+
+// 200 classes, two rows each, one class per value of code
+fn data() -> Vec<DataPoint> {
+    (0..400)
+        .map(|i| DataPoint::new(i / 2, (i / 2) as u16))
+        .collect()
+}
+
+#[test]
+fn leaves_hold_only_the_classes_that_reach_them() {
+    let data = data();
+    let tree = build_tree(&data);
+    assert_eq!(tree.classes().len(), 200);
+    let leaves: Vec<_> = tree.paths().collect();
+    assert_eq!(leaves.len(), 200);
+    assert!(leaves.iter().all(|path| path.distribution.len() == 1));
+    for point in data.iter().step_by(37) {
+        assert_eq!(predict(point, &tree), point.class);
+    }
+}
+
+#[cfg(feature = "metrics")]
+mod reports {
+    use decision_leaf::eval::{EvaluationReport, ReportOptions};
+
+    // a with 6 points, 4 right; b with 3, all predicted c; c with 1 and d with 1
+    fn report() -> EvaluationReport<char> {
+        let pairs = [
+            ('a', 'a'),
+            ('a', 'a'),
+            ('a', 'a'),
+            ('a', 'a'),
+            ('a', 'b'),
+            ('a', 'd'),
+            ('b', 'c'),
+            ('b', 'c'),
+            ('b', 'c'),
+            ('c', 'c'),
+            ('d', 'a'),
+        ];
+        EvaluationReport::from_predictions(pairs.into_iter().map(|(a, p)| (a, Some(p))))
+    }
+
+    #[test]
+    fn full_reports_show_every_cell() {
+        assert_eq!(
+            report().render(&ReportOptions::default()),
+            "accuracy: 45% (5/11)\n\
+             'a' (6): 'a' 4, 'b' 1, 'd' 1\n\
+             'b' (3): 'c' 3\n\
+             'c' (1): 'c' 1\n\
+             'd' (1): 'a' 1\n"
+        );
+    }
+
+    #[test]
+    fn collapsing_changes_the_text_only() {
+        let report = report();
+        let options = ReportOptions {
+            top_k: Some(1),
+            min_support: 2,
+            ..ReportOptions::default()
+        };
+        // c and d have one point each and pool into `other`
+        assert_eq!(
+            report.render(&options),
+            "accuracy: 45% (5/11)\n\
+             'a' (6): 'a' 4, +2 more 2\n\
+             'b' (3): other 3\n\
+             other (2): 'a' 1, +1 more 1\n"
+        );
+        assert_eq!(report.classes(), ['a', 'b', 'c', 'd']);
+        assert_eq!(report.support(&'c'), 1);
+        assert_eq!(report.recall(&'a'), 4. / 6.);
+        assert_eq!(report.precision(&'c'), 0.25);
+    }
+}
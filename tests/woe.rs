@@ -0,0 +1,137 @@
+//! leaf_woe and scorecards against hand-computed weights of evidence, on
+//! stumps whose leaf counts are set by hand, and the trees they refuse:
+//! anything but two classes, or a positive class the tree doesn't have.
+
+#![cfg(all(feature = "ensemble", feature = "export"))]
+// the generated functions take &Vec<DataPoint>
+#![allow(clippy::ptr_arg)]
+
+use decision_leaf::{classification_data_layout, ClassCounts, DecisionLeafError};
+
+classification_data_layout!(
+    enum_fields = {married: bool},
+    number_fields = {age: u32},
+    u8
+);
+
+const HIGH: u8 = 1;
+const LOW: u8 = 0;
+
+fn leaf(counts: &[(u8, u64)]) -> Node {
+    let mut leaf = ClassCounts::new();
+    for &(label, count) in counts {
+        leaf.add(label, count);
+    }
+    Node::Leaf(leaf)
+}
+
+fn stump(true_leaf: Node, false_leaf: Node) -> Node {
+    Node::Decision {
+        question: Question::age(40),
+        true_branch: Box::new(true_leaf),
+        false_branch: Box::new(false_leaf),
+        runner_up: None,
+    }
+}
+
+fn close(actual: f32, expected: f64) {
+    assert!(
+        (actual as f64 - expected).abs() < 1e-6,
+        "{} isn't {}",
+        actual,
+        expected
+    );
+}
+
+// 4 High and 6 Low rows, split 3/1 and 1/5
+fn split() -> Node {
+    stump(leaf(&[(HIGH, 3), (LOW, 1)]), leaf(&[(HIGH, 1), (LOW, 5)]))
+}
+
+// 4 High and 4 Low rows, split 2/0 and 2/4
+fn pure() -> Node {
+    stump(leaf(&[(HIGH, 2)]), leaf(&[(HIGH, 2), (LOW, 4)]))
+}
+
+#[test]
+fn woe_matches_the_hand_computed_values() {
+    let woe = split().leaf_woe(&HIGH, 0.0).unwrap();
+    close(woe[&1], 4.5f64.ln());
+    close(woe[&2], 0.3f64.ln());
+    // the other class is the mirror image
+    let low = split().leaf_woe(&LOW, 0.0).unwrap();
+    close(low[&1], -4.5f64.ln());
+    close(low[&2], -0.3f64.ln());
+}
+
+#[test]
+fn smoothing_keeps_empty_cells_finite() {
+    assert_eq!(pure().leaf_woe(&HIGH, 0.0).unwrap()[&1], f32::INFINITY);
+    // the counts become 2.5/0.5 and 2.5/4.5 of totals 5 and 5
+    let smoothed = pure().leaf_woe(&HIGH, 0.5).unwrap();
+    close(smoothed[&1], 5.0f64.ln());
+    close(smoothed[&2], (2.5f64 / 4.5).ln());
+    assert!(pure().leaf_woe(&HIGH, -0.5).is_err());
+    assert!(pure().leaf_woe(&HIGH, f64::NAN).is_err());
+}
+
+#[test]
+fn scorecards_sum_the_leaf_woe_of_every_tree() {
+    let older = DataPoint::new(true, 41, HIGH);
+    let card = split().scorecard(&HIGH, 0.0).unwrap();
+    close(card.score(&older), 4.5f64.ln());
+
+    let forest = Forest {
+        trees: vec![split(), pure()],
+    };
+    let card = forest.scorecard(&HIGH, 0.5).unwrap();
+    let expected =
+        split().leaf_woe(&HIGH, 0.5).unwrap()[&1] + pure().leaf_woe(&HIGH, 0.5).unwrap()[&1];
+    close(card.score(&older), expected as f64);
+    for age in [20, 39, 40, 70] {
+        let point = DataPoint::new(false, age, LOW);
+        let sum: f32 = card
+            .leaf_woe()
+            .zip(&forest.trees)
+            .map(|(woe, tree)| woe[&audit_record(&point, tree).leaf_id])
+            .sum();
+        assert_eq!(card.score(&point), sum);
+    }
+}
+
+#[test]
+fn the_positive_class_must_be_in_the_tree() {
+    let err = split().leaf_woe(&7, 0.0).unwrap_err();
+    assert!(
+        matches!(err, DecisionLeafError::InvalidParameter { .. }),
+        "{}",
+        err
+    );
+    assert!(split().scorecard(&7, 0.0).is_err());
+}
+
+#[test]
+fn trees_of_one_class_have_no_woe() {
+    let one_class = stump(leaf(&[(HIGH, 3)]), leaf(&[(HIGH, 1)]));
+    let err = one_class.leaf_woe(&HIGH, 0.5).unwrap_err();
+    assert!(
+        matches!(err, DecisionLeafError::InvalidParameter { .. }),
+        "{}",
+        err
+    );
+}
+
+#[test]
+fn trees_of_three_classes_have_no_woe() {
+    let three_classes = stump(leaf(&[(HIGH, 3), (LOW, 1)]), leaf(&[(2, 4)]));
+    let err = three_classes.leaf_woe(&HIGH, 0.5).unwrap_err();
+    assert!(
+        matches!(err, DecisionLeafError::InvalidParameter { .. }),
+        "{}",
+        err
+    );
+    let forest = Forest {
+        trees: vec![split(), three_classes],
+    };
+    assert!(forest.scorecard(&HIGH, 0.5).is_err());
+}